@@ -2,16 +2,12 @@ use crate::agents::command_generator::CommandGeneratorTrait;
 use crate::agents::external_commands_generator::ExternalCommandsGenerator;
 use crate::agents::external_skills_generator::ExternalSkillsGenerator;
 use crate::agents::rule_generator::AgentRuleGenerator;
-use crate::agents::single_file_based::{
-    check_in_sync, clean_generated_files, generate_agent_file_contents,
-};
+use crate::agents::single_file_based::{clean_generated_files, generate_agent_file_contents};
 use crate::agents::skills_generator::SkillsGeneratorTrait;
 use crate::constants::{AGENTS_MD_FILENAME, AMP_COMMANDS_DIR, AMP_SKILLS_DIR};
 use crate::models::SourceFile;
-use crate::utils::file_utils::{
-    check_agents_md_symlink, check_inlined_file_symlink, create_symlink_to_agents_md,
-    create_symlink_to_inlined_file,
-};
+use crate::utils::file_utils::{symlink_to_agents_md, symlink_to_inlined_file};
+use crate::utils::plan::Operation;
 use anyhow::Result;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -23,7 +19,7 @@ impl AgentRuleGenerator for AmpGenerator {
         "amp"
     }
 
-    fn clean(&self, current_dir: &Path) -> Result<()> {
+    fn clean(&self, current_dir: &Path) -> Result<Vec<Operation>> {
         clean_generated_files(current_dir, AGENTS_MD_FILENAME)
     }
 
@@ -35,48 +31,29 @@ impl AgentRuleGenerator for AmpGenerator {
         generate_agent_file_contents(source_files, current_dir, AGENTS_MD_FILENAME)
     }
 
-    fn check_agent_contents(
-        &self,
-        source_files: &[SourceFile],
-        current_dir: &Path,
-    ) -> Result<bool> {
-        check_in_sync(source_files, current_dir, AGENTS_MD_FILENAME)
-    }
-
-    fn check_symlink(&self, current_dir: &Path) -> Result<bool> {
-        let output_file = current_dir.join(AGENTS_MD_FILENAME);
-        check_agents_md_symlink(current_dir, &output_file)
-    }
-
     fn gitignore_patterns(&self) -> Vec<String> {
         vec![AGENTS_MD_FILENAME.to_string()]
     }
 
-    fn generate_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        let success = create_symlink_to_agents_md(current_dir, Path::new(AGENTS_MD_FILENAME))?;
-        if success {
-            Ok(vec![current_dir.join(AGENTS_MD_FILENAME)])
-        } else {
-            Ok(vec![])
-        }
+    fn generate_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        symlink_to_agents_md(current_dir, Path::new(AGENTS_MD_FILENAME))
+            .into_iter()
+            .collect()
     }
 
     fn uses_inlined_symlink(&self) -> bool {
         true
     }
 
-    fn generate_inlined_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        let success = create_symlink_to_inlined_file(current_dir, Path::new(AGENTS_MD_FILENAME))?;
-        if success {
-            Ok(vec![current_dir.join(AGENTS_MD_FILENAME)])
-        } else {
-            Ok(vec![])
-        }
+    fn managed_block_file(&self) -> Option<&str> {
+        Some(AGENTS_MD_FILENAME)
     }
 
-    fn check_inlined_symlink(&self, current_dir: &Path) -> Result<bool> {
-        let output_file = current_dir.join(AGENTS_MD_FILENAME);
-        check_inlined_file_symlink(current_dir, &output_file)
+    fn generate_inlined_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        vec![symlink_to_inlined_file(
+            current_dir,
+            Path::new(AGENTS_MD_FILENAME),
+        )]
     }
 
     fn command_generator(&self) -> Option<Box<dyn CommandGeneratorTrait>> {
@@ -92,6 +69,7 @@ impl AgentRuleGenerator for AmpGenerator {
 mod tests {
     use super::*;
     use crate::constants::{AI_RULE_SOURCE_DIR, COMMANDS_DIR};
+    use crate::utils::test_utils::helpers::apply_operations;
     use std::fs;
     use tempfile::TempDir;
 
@@ -129,6 +107,7 @@ mod tests {
         // generate_command_symlinks creates symlinks (flat structure with -ai-rules.md suffix)
         let symlinks = cmd_gen.generate_command_symlinks(temp_dir.path()).unwrap();
         assert_eq!(symlinks.len(), 1);
+        apply_operations(temp_dir.path(), symlinks);
 
         // Verify symlink was created with correct naming
         let symlink_path = temp_dir.path().join(".agents/commands/test-ai-rules.md");
@@ -2,19 +2,16 @@ use crate::agents::command_generator::CommandGeneratorTrait;
 use crate::agents::external_commands_generator::ExternalCommandsGenerator;
 use crate::agents::external_skills_generator::ExternalSkillsGenerator;
 use crate::agents::mcp_generator::{ExternalMcpGenerator, McpGeneratorTrait};
-use crate::agents::rule_generator::AgentRuleGenerator;
-use crate::agents::single_file_based::{
-    check_in_sync, clean_generated_files, generate_agent_file_contents,
-};
+use crate::agents::rule_generator::{AgentRuleGenerator, LocalRulesOutput, UserScopeTarget};
+use crate::agents::single_file_based::{clean_generated_files, generate_agent_file_contents};
 use crate::agents::skills_generator::SkillsGeneratorTrait;
 use crate::constants::{
-    CLAUDE_COMMANDS_DIR, CLAUDE_COMMANDS_SUBDIR, CLAUDE_MCP_JSON, CLAUDE_SKILLS_DIR,
+    CLAUDE_COMMANDS_DIR, CLAUDE_COMMANDS_SUBDIR, CLAUDE_LOCAL_MD, CLAUDE_MCP_JSON,
+    CLAUDE_SKILLS_DIR, CLAUDE_USER_COMMANDS_DIR, CLAUDE_USER_RULES_FILE,
 };
 use crate::models::source_file::SourceFile;
-use crate::utils::file_utils::{
-    check_agents_md_symlink, check_inlined_file_symlink, create_symlink_to_agents_md,
-    create_symlink_to_inlined_file,
-};
+use crate::utils::file_utils::{symlink_to_agents_md, symlink_to_inlined_file};
+use crate::utils::plan::Operation;
 use anyhow::Result;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -38,7 +35,7 @@ impl AgentRuleGenerator for ClaudeGenerator {
         &self.name
     }
 
-    fn clean(&self, current_dir: &Path) -> Result<()> {
+    fn clean(&self, current_dir: &Path) -> Result<Vec<Operation>> {
         clean_generated_files(current_dir, &self.output_filename)
     }
 
@@ -50,49 +47,29 @@ impl AgentRuleGenerator for ClaudeGenerator {
         generate_agent_file_contents(source_files, current_dir, &self.output_filename)
     }
 
-    fn check_agent_contents(
-        &self,
-        source_files: &[SourceFile],
-        current_dir: &Path,
-    ) -> Result<bool> {
-        check_in_sync(source_files, current_dir, &self.output_filename)
-    }
-
-    fn check_symlink(&self, current_dir: &Path) -> Result<bool> {
-        let output_file = current_dir.join(&self.output_filename);
-        check_agents_md_symlink(current_dir, &output_file)
-    }
-
     fn gitignore_patterns(&self) -> Vec<String> {
         vec![self.output_filename.clone()]
     }
 
-    fn generate_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        let success = create_symlink_to_agents_md(current_dir, Path::new(&self.output_filename))?;
-        if success {
-            Ok(vec![current_dir.join(&self.output_filename)])
-        } else {
-            Ok(vec![])
-        }
+    fn generate_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        symlink_to_agents_md(current_dir, Path::new(&self.output_filename))
+            .into_iter()
+            .collect()
     }
 
     fn uses_inlined_symlink(&self) -> bool {
         true
     }
 
-    fn generate_inlined_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        let success =
-            create_symlink_to_inlined_file(current_dir, Path::new(&self.output_filename))?;
-        if success {
-            Ok(vec![current_dir.join(&self.output_filename)])
-        } else {
-            Ok(vec![])
-        }
+    fn managed_block_file(&self) -> Option<&str> {
+        Some(&self.output_filename)
     }
 
-    fn check_inlined_symlink(&self, current_dir: &Path) -> Result<bool> {
-        let output_file = current_dir.join(&self.output_filename);
-        check_inlined_file_symlink(current_dir, &output_file)
+    fn generate_inlined_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        vec![symlink_to_inlined_file(
+            current_dir,
+            Path::new(&self.output_filename),
+        )]
     }
 
     fn mcp_generator(&self) -> Option<Box<dyn McpGeneratorTrait>> {
@@ -111,6 +88,21 @@ impl AgentRuleGenerator for ClaudeGenerator {
     fn skills_generator(&self) -> Option<Box<dyn SkillsGeneratorTrait>> {
         Some(Box::new(ExternalSkillsGenerator::new(CLAUDE_SKILLS_DIR)))
     }
+
+    fn user_scope(&self) -> Option<UserScopeTarget> {
+        Some(UserScopeTarget {
+            rules_file: CLAUDE_USER_RULES_FILE,
+            commands_dir: Some(CLAUDE_USER_COMMANDS_DIR),
+            skills_dir: Some(CLAUDE_SKILLS_DIR),
+        })
+    }
+
+    fn local_rules_output(&self) -> Option<LocalRulesOutput> {
+        Some(LocalRulesOutput {
+            file_name: CLAUDE_LOCAL_MD,
+            includes_shared_rules: false,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -126,7 +118,7 @@ mod tests {
 
         create_file(temp_dir.path(), "CLAUDE.md", "content");
 
-        generator.clean(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), generator.clean(temp_dir.path()).unwrap());
 
         assert!(!temp_dir.path().join("CLAUDE.md").exists());
     }
@@ -1,15 +1,13 @@
 use crate::agents::external_skills_generator::ExternalSkillsGenerator;
-use crate::agents::rule_generator::AgentRuleGenerator;
-use crate::agents::single_file_based::{
-    check_in_sync, clean_generated_files, generate_agent_file_contents,
-};
+use crate::agents::rule_generator::{AgentRuleGenerator, LocalRulesOutput, UserScopeTarget};
+use crate::agents::single_file_based::{clean_generated_files, generate_agent_file_contents};
 use crate::agents::skills_generator::SkillsGeneratorTrait;
-use crate::constants::{AGENTS_MD_FILENAME, CODEX_SKILLS_DIR};
-use crate::models::SourceFile;
-use crate::utils::file_utils::{
-    check_agents_md_symlink, check_inlined_file_symlink, create_symlink_to_agents_md,
-    create_symlink_to_inlined_file,
+use crate::constants::{
+    AGENTS_MD_FILENAME, CODEX_OVERRIDE_MD, CODEX_SKILLS_DIR, CODEX_USER_RULES_FILE,
 };
+use crate::models::SourceFile;
+use crate::utils::file_utils::{symlink_to_agents_md, symlink_to_inlined_file};
+use crate::utils::plan::Operation;
 use anyhow::Result;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -39,7 +37,7 @@ impl AgentRuleGenerator for CodexGenerator {
         &self.name
     }
 
-    fn clean(&self, current_dir: &Path) -> Result<()> {
+    fn clean(&self, current_dir: &Path) -> Result<Vec<Operation>> {
         clean_generated_files(current_dir, &self.output_filename)
     }
 
@@ -51,59 +49,55 @@ impl AgentRuleGenerator for CodexGenerator {
         generate_agent_file_contents(source_files, current_dir, &self.output_filename)
     }
 
-    fn check_agent_contents(
-        &self,
-        source_files: &[SourceFile],
-        current_dir: &Path,
-    ) -> Result<bool> {
-        check_in_sync(source_files, current_dir, &self.output_filename)
-    }
-
-    fn check_symlink(&self, current_dir: &Path) -> Result<bool> {
-        let output_file = current_dir.join(&self.output_filename);
-        check_agents_md_symlink(current_dir, &output_file)
-    }
-
     fn gitignore_patterns(&self) -> Vec<String> {
         vec![self.output_filename.clone()]
     }
 
-    fn generate_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        let success = create_symlink_to_agents_md(current_dir, Path::new(&self.output_filename))?;
-        if success {
-            Ok(vec![current_dir.join(&self.output_filename)])
-        } else {
-            Ok(vec![])
-        }
+    fn generate_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        symlink_to_agents_md(current_dir, Path::new(&self.output_filename))
+            .into_iter()
+            .collect()
     }
 
     fn uses_inlined_symlink(&self) -> bool {
         true
     }
 
-    fn generate_inlined_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        let success =
-            create_symlink_to_inlined_file(current_dir, Path::new(&self.output_filename))?;
-        if success {
-            Ok(vec![current_dir.join(&self.output_filename)])
-        } else {
-            Ok(vec![])
-        }
+    fn managed_block_file(&self) -> Option<&str> {
+        Some(&self.output_filename)
     }
 
-    fn check_inlined_symlink(&self, current_dir: &Path) -> Result<bool> {
-        let output_file = current_dir.join(&self.output_filename);
-        check_inlined_file_symlink(current_dir, &output_file)
+    fn generate_inlined_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        vec![symlink_to_inlined_file(
+            current_dir,
+            Path::new(&self.output_filename),
+        )]
     }
 
     fn skills_generator(&self) -> Option<Box<dyn SkillsGeneratorTrait>> {
         Some(Box::new(ExternalSkillsGenerator::new(CODEX_SKILLS_DIR)))
     }
+
+    fn user_scope(&self) -> Option<UserScopeTarget> {
+        Some(UserScopeTarget {
+            rules_file: CODEX_USER_RULES_FILE,
+            commands_dir: None,
+            skills_dir: Some(CODEX_SKILLS_DIR),
+        })
+    }
+
+    fn local_rules_output(&self) -> Option<LocalRulesOutput> {
+        Some(LocalRulesOutput {
+            file_name: CODEX_OVERRIDE_MD,
+            includes_shared_rules: true,
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::file_utils::write_operations;
     use crate::utils::test_utils::helpers::*;
     use tempfile::TempDir;
 
@@ -130,8 +124,8 @@ mod tests {
         assert_file_exists(temp_dir.path(), "AGENTS.md");
 
         // Clean should remove it
-        let result = generator.clean(temp_dir.path());
-        assert!(result.is_ok());
+        let removals = generator.clean(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), removals);
         assert_file_not_exists(temp_dir.path(), "AGENTS.md");
     }
 
@@ -155,6 +149,19 @@ mod tests {
         assert!(content.contains("@ai-rules/.generated-ai-rules/ai-rules-generated-rule1.md"));
     }
 
+    #[test]
+    fn test_codex_generator_generate_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let generator = CodexGenerator::new();
+
+        // Create the source file for symlinking
+        create_file(temp_dir.path(), "ai-rules/AGENTS.md", "# Source content");
+
+        let symlinks = generator.generate_symlink(temp_dir.path());
+        assert_eq!(symlinks.len(), 1);
+        assert!(symlinks[0].path().ends_with("AGENTS.md"));
+    }
+
     #[test]
     fn test_codex_generator_check_agent_contents_in_sync() {
         let temp_dir = TempDir::new().unwrap();
@@ -171,10 +178,11 @@ mod tests {
         let expected_content = "@ai-rules/.generated-ai-rules/ai-rules-generated-rule1.md\n";
         create_file(temp_dir.path(), "AGENTS.md", expected_content);
 
-        let result = generator
-            .check_agent_contents(&source_files, temp_dir.path())
-            .unwrap();
-        assert!(result);
+        let mut operations = generator.clean(temp_dir.path()).unwrap();
+        operations.extend(write_operations(
+            generator.generate_agent_contents(&source_files, temp_dir.path()),
+        ));
+        assert!(planned_changes(operations).is_empty());
     }
 
     #[test]
@@ -192,26 +200,11 @@ mod tests {
         // Write wrong content
         create_file(temp_dir.path(), "AGENTS.md", "wrong content");
 
-        let result = generator
-            .check_agent_contents(&source_files, temp_dir.path())
-            .unwrap();
-        assert!(!result);
-    }
-
-    #[test]
-    fn test_codex_generator_generate_symlink() {
-        let temp_dir = TempDir::new().unwrap();
-        let generator = CodexGenerator::new();
-
-        // Create the source file for symlinking
-        create_file(temp_dir.path(), "ai-rules/AGENTS.md", "# Source content");
-
-        let result = generator.generate_symlink(temp_dir.path());
-        assert!(result.is_ok());
-
-        let paths = result.unwrap();
-        assert_eq!(paths.len(), 1);
-        assert!(paths[0].ends_with("AGENTS.md"));
+        let mut operations = generator.clean(temp_dir.path()).unwrap();
+        operations.extend(write_operations(
+            generator.generate_agent_contents(&source_files, temp_dir.path()),
+        ));
+        assert!(!planned_changes(operations).is_empty());
     }
 
     #[test]
@@ -221,9 +214,10 @@ mod tests {
 
         // Create source and symlink
         create_file(temp_dir.path(), "ai-rules/AGENTS.md", "# Source content");
-        generator.generate_symlink(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), generator.generate_symlink(temp_dir.path()));
 
-        let result = generator.check_symlink(temp_dir.path()).unwrap();
-        assert!(result);
+        let mut operations = generator.clean(temp_dir.path()).unwrap();
+        operations.extend(generator.generate_symlink(temp_dir.path()));
+        assert!(planned_changes(operations).is_empty());
     }
 }
@@ -1,16 +1,13 @@
+use crate::utils::plan::Operation;
 use anyhow::Result;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 pub trait CommandGeneratorTrait {
     /// Generate command symlinks for this agent
-    /// Returns Vec of created symlink paths
-    fn generate_command_symlinks(&self, current_dir: &Path) -> Result<Vec<PathBuf>>;
+    fn generate_command_symlinks(&self, current_dir: &Path) -> Result<Vec<Operation>>;
 
     /// Clean generated command files/symlinks
-    fn clean_commands(&self, current_dir: &Path) -> Result<()>;
-
-    /// Check if command files/symlinks are in sync
-    fn check_commands(&self, current_dir: &Path) -> Result<bool>;
+    fn clean_commands(&self, current_dir: &Path) -> Result<Vec<Operation>>;
 
     /// Get gitignore patterns for generated commands
     fn command_gitignore_patterns(&self) -> Vec<String>;
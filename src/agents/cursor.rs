@@ -9,9 +9,9 @@ use crate::constants::{
     AGENTS_MD_FILENAME, CURSOR_COMMANDS_DIR, CURSOR_COMMANDS_SUBDIR, CURSOR_SKILLS_DIR, MCP_JSON,
 };
 use crate::models::SourceFile;
+use crate::utils::plan::Operation;
 use anyhow::Result;
 use std::collections::HashMap;
-use std::fs;
 use std::path::{Path, PathBuf};
 
 pub struct CursorGenerator {
@@ -36,12 +36,12 @@ fn get_cursor_rules_dir(current_dir: &Path) -> PathBuf {
     current_dir.join(".cursor").join("rules")
 }
 
-fn clean_cursor_rules_dir(current_dir: &Path) -> Result<()> {
+fn clean_cursor_rules_dir(current_dir: &Path) -> Vec<Operation> {
     let cursor_rules_dir = get_cursor_rules_dir(current_dir);
     if cursor_rules_dir.exists() {
-        fs::remove_dir_all(cursor_rules_dir)?;
+        return vec![Operation::delete(cursor_rules_dir)];
     }
-    Ok(())
+    Vec::new()
 }
 
 impl AgentRuleGenerator for CursorGenerator {
@@ -49,9 +49,10 @@ impl AgentRuleGenerator for CursorGenerator {
         "cursor"
     }
 
-    fn clean(&self, current_dir: &Path) -> Result<()> {
-        clean_cursor_rules_dir(current_dir)?;
-        self.inner.clean(current_dir)
+    fn clean(&self, current_dir: &Path) -> Result<Vec<Operation>> {
+        let mut removals = clean_cursor_rules_dir(current_dir);
+        removals.extend(self.inner.clean(current_dir)?);
+        Ok(removals)
     }
 
     fn generate_agent_contents(
@@ -63,40 +64,28 @@ impl AgentRuleGenerator for CursorGenerator {
             .generate_agent_contents(source_files, current_dir)
     }
 
-    fn check_agent_contents(
-        &self,
-        source_files: &[SourceFile],
-        current_dir: &Path,
-    ) -> Result<bool> {
-        Ok(self.inner.check_agent_contents(source_files, current_dir)?
-            && !get_cursor_rules_dir(current_dir).exists())
-    }
-
-    fn check_symlink(&self, current_dir: &Path) -> Result<bool> {
-        Ok(self.inner.check_symlink(current_dir)? && !get_cursor_rules_dir(current_dir).exists())
-    }
-
     fn gitignore_patterns(&self) -> Vec<String> {
         self.inner.gitignore_patterns()
     }
 
-    fn generate_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        clean_cursor_rules_dir(current_dir)?;
-        self.inner.generate_symlink(current_dir)
+    fn generate_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        let mut operations = clean_cursor_rules_dir(current_dir);
+        operations.extend(self.inner.generate_symlink(current_dir));
+        operations
     }
 
     fn uses_inlined_symlink(&self) -> bool {
         true
     }
 
-    fn generate_inlined_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        clean_cursor_rules_dir(current_dir)?;
-        self.inner.generate_inlined_symlink(current_dir)
+    fn generate_inlined_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        let mut operations = clean_cursor_rules_dir(current_dir);
+        operations.extend(self.inner.generate_inlined_symlink(current_dir));
+        operations
     }
 
-    fn check_inlined_symlink(&self, current_dir: &Path) -> Result<bool> {
-        Ok(self.inner.check_inlined_symlink(current_dir)?
-            && !get_cursor_rules_dir(current_dir).exists())
+    fn managed_block_file(&self) -> Option<&str> {
+        self.inner.managed_block_file()
     }
 
     fn mcp_generator(&self) -> Option<Box<dyn McpGeneratorTrait>> {
@@ -120,6 +109,7 @@ impl AgentRuleGenerator for CursorGenerator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::file_utils::write_operations;
     use crate::utils::test_utils::helpers::*;
     use tempfile::TempDir;
 
@@ -170,9 +160,9 @@ mod tests {
         let generator = CursorGenerator::default();
         let temp_dir = TempDir::new().unwrap();
 
-        let result = generator.clean(temp_dir.path());
+        let removals = generator.clean(temp_dir.path()).unwrap();
 
-        assert!(result.is_ok());
+        assert!(removals.is_empty());
         assert_file_not_exists(temp_dir.path(), ".cursor/rules");
     }
 
@@ -197,9 +187,8 @@ mod tests {
         );
         assert_file_exists(temp_dir.path(), ".cursor/rules/ai-rules-generated-test.mdc");
 
-        let result = generator.clean(temp_dir.path());
+        apply_operations(temp_dir.path(), generator.clean(temp_dir.path()).unwrap());
 
-        assert!(result.is_ok());
         assert_file_not_exists(temp_dir.path(), ".cursor/rules");
         assert_file_exists(temp_dir.path(), ".cursor/settings.json");
     }
@@ -211,14 +200,12 @@ mod tests {
 
         create_file(temp_dir.path(), "ai-rules/AGENTS.md", "# Source content");
 
-        let result = generator.generate_symlink(temp_dir.path());
-        assert!(result.is_ok());
+        apply_operations(temp_dir.path(), generator.generate_symlink(temp_dir.path()));
 
         let agents_md_path = temp_dir.path().join(AGENTS_MD_FILENAME);
         assert!(agents_md_path.is_symlink());
 
-        let result = generator.clean(temp_dir.path());
-        assert!(result.is_ok());
+        apply_operations(temp_dir.path(), generator.clean(temp_dir.path()).unwrap());
 
         assert!(!agents_md_path.exists());
 
@@ -237,12 +224,55 @@ mod tests {
             "stale content",
         );
 
-        generator.clean(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), generator.clean(temp_dir.path()).unwrap());
 
         assert_file_not_exists(temp_dir.path(), AGENTS_MD_FILENAME);
         assert_file_not_exists(temp_dir.path(), ".cursor/rules");
     }
 
+    #[test]
+    fn test_generate_symlink_removes_stale_cursor_rules_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let generator = CursorGenerator::default();
+
+        create_file(temp_dir.path(), "ai-rules/AGENTS.md", "# Source content");
+        create_file(
+            temp_dir.path(),
+            ".cursor/rules/ai-rules-generated-stale.mdc",
+            "stale content",
+        );
+
+        apply_operations(temp_dir.path(), generator.generate_symlink(temp_dir.path()));
+
+        assert!(temp_dir.path().join(AGENTS_MD_FILENAME).is_symlink());
+        assert_file_not_exists(temp_dir.path(), ".cursor/rules");
+    }
+
+    #[test]
+    fn test_generate_inlined_symlink_removes_stale_cursor_rules_dir() {
+        let generator = CursorGenerator::default();
+        let temp_dir = TempDir::new().unwrap();
+
+        create_file(
+            temp_dir.path(),
+            ".cursor/rules/ai-rules-generated-stale.mdc",
+            "stale content",
+        );
+
+        let operations = generator.generate_inlined_symlink(temp_dir.path());
+
+        assert_eq!(
+            operations,
+            vec![
+                Operation::delete(temp_dir.path().join(".cursor/rules")),
+                Operation::symlink(
+                    temp_dir.path().join(AGENTS_MD_FILENAME),
+                    "ai-rules/.generated-ai-rules/ai-rules-generated-AGENTS.md"
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn test_check_empty_source_files_with_stale_cursor_rules_dir() {
         let generator = CursorGenerator::default();
@@ -254,11 +284,12 @@ mod tests {
             "stale content",
         );
 
-        let result = generator
-            .check_agent_contents(&[], temp_dir.path())
-            .unwrap();
+        let mut operations = generator.clean(temp_dir.path()).unwrap();
+        operations.extend(write_operations(
+            generator.generate_agent_contents(&[], temp_dir.path()),
+        ));
 
-        assert!(!result);
+        assert!(!planned_changes(operations).is_empty());
     }
 
     #[test]
@@ -267,12 +298,11 @@ mod tests {
         let generator = CursorGenerator::default();
 
         create_file(temp_dir.path(), "ai-rules/AGENTS.md", "# Source content");
+        apply_operations(temp_dir.path(), generator.generate_symlink(temp_dir.path()));
 
-        let result = generator.generate_symlink(temp_dir.path());
-        assert!(result.is_ok());
-
-        let result = generator.check_symlink(temp_dir.path()).unwrap();
-        assert!(result);
+        let mut operations = generator.clean(temp_dir.path()).unwrap();
+        operations.extend(generator.generate_symlink(temp_dir.path()));
+        assert!(planned_changes(operations).is_empty());
     }
 
     #[test]
@@ -281,15 +311,16 @@ mod tests {
         let generator = CursorGenerator::default();
 
         create_file(temp_dir.path(), "ai-rules/AGENTS.md", "# Source content");
-        generator.generate_symlink(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), generator.generate_symlink(temp_dir.path()));
         create_file(
             temp_dir.path(),
             ".cursor/rules/ai-rules-generated-stale.mdc",
             "stale content",
         );
 
-        let result = generator.check_symlink(temp_dir.path()).unwrap();
-        assert!(!result);
+        let mut operations = generator.clean(temp_dir.path()).unwrap();
+        operations.extend(generator.generate_symlink(temp_dir.path()));
+        assert!(!planned_changes(operations).is_empty());
     }
 
     #[test]
@@ -309,11 +340,12 @@ mod tests {
             "stale content",
         );
 
-        let result = generator
-            .check_agent_contents(&[source_file], temp_dir.path())
-            .unwrap();
+        let mut operations = generator.clean(temp_dir.path()).unwrap();
+        operations.extend(write_operations(
+            generator.generate_agent_contents(&[source_file], temp_dir.path()),
+        ));
 
-        assert!(!result);
+        assert!(!planned_changes(operations).is_empty());
     }
 
     #[test]
@@ -326,14 +358,18 @@ mod tests {
             "ai-rules/.generated-ai-rules/ai-rules-generated-AGENTS.md",
             "# Inlined content\n",
         );
-        generator.generate_inlined_symlink(temp_dir.path()).unwrap();
+        apply_operations(
+            temp_dir.path(),
+            generator.generate_inlined_symlink(temp_dir.path()),
+        );
         create_file(
             temp_dir.path(),
             ".cursor/rules/ai-rules-generated-stale.mdc",
             "stale content",
         );
 
-        let result = generator.check_inlined_symlink(temp_dir.path()).unwrap();
-        assert!(!result);
+        let mut operations = generator.clean(temp_dir.path()).unwrap();
+        operations.extend(generator.generate_inlined_symlink(temp_dir.path()));
+        assert!(!planned_changes(operations).is_empty());
     }
 }
@@ -1,12 +1,12 @@
 use crate::agents::command_generator::CommandGeneratorTrait;
 use crate::operations::command_reader::{
-    check_command_symlinks_in_subdir_in_sync, check_command_symlinks_in_sync_with_extension,
     create_command_symlinks_in_subdir, create_command_symlinks_with_extension,
     get_command_gitignore_patterns_subdir, get_command_gitignore_patterns_with_extension,
     remove_command_symlinks_in_subdir, remove_generated_command_symlinks_with_extension,
 };
+use crate::utils::plan::Operation;
 use anyhow::Result;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 pub struct ExternalCommandsGenerator {
     target_dir: String,
@@ -46,7 +46,7 @@ impl ExternalCommandsGenerator {
 }
 
 impl CommandGeneratorTrait for ExternalCommandsGenerator {
-    fn generate_command_symlinks(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
+    fn generate_command_symlinks(&self, current_dir: &Path) -> Result<Vec<Operation>> {
         match &self.subdir {
             Some(subdir) => {
                 create_command_symlinks_in_subdir(current_dir, &self.target_dir, subdir)
@@ -59,7 +59,7 @@ impl CommandGeneratorTrait for ExternalCommandsGenerator {
         }
     }
 
-    fn clean_commands(&self, current_dir: &Path) -> Result<()> {
+    fn clean_commands(&self, current_dir: &Path) -> Result<Vec<Operation>> {
         match &self.subdir {
             Some(subdir) => {
                 remove_command_symlinks_in_subdir(current_dir, &self.target_dir, subdir)
@@ -72,19 +72,6 @@ impl CommandGeneratorTrait for ExternalCommandsGenerator {
         }
     }
 
-    fn check_commands(&self, current_dir: &Path) -> Result<bool> {
-        match &self.subdir {
-            Some(subdir) => {
-                check_command_symlinks_in_subdir_in_sync(current_dir, &self.target_dir, subdir)
-            }
-            None => check_command_symlinks_in_sync_with_extension(
-                current_dir,
-                &self.target_dir,
-                &self.extension,
-            ),
-        }
-    }
-
     fn command_gitignore_patterns(&self) -> Vec<String> {
         match &self.subdir {
             Some(subdir) => get_command_gitignore_patterns_subdir(&self.target_dir, subdir),
@@ -99,7 +86,10 @@ impl CommandGeneratorTrait for ExternalCommandsGenerator {
 mod tests {
     use super::*;
     use crate::constants::{AI_RULE_SOURCE_DIR, COMMANDS_DIR, GENERATED_COMMAND_SUFFIX};
+    use crate::utils::plan::PlannedChange;
+    use crate::utils::test_utils::helpers::{apply_operations, planned_changes};
     use std::fs;
+    use std::path::PathBuf;
     use tempfile::TempDir;
 
     fn create_command_file(temp_dir: &Path, command_name: &str, content: &str) -> PathBuf {
@@ -127,11 +117,11 @@ mod tests {
 
         create_command_file(temp_dir.path(), "my-command", "command content");
 
-        let result = generator.generate_command_symlinks(temp_dir.path());
-        assert!(result.is_ok());
-
-        let symlinks = result.unwrap();
+        let symlinks = generator
+            .generate_command_symlinks(temp_dir.path())
+            .unwrap();
         assert_eq!(symlinks.len(), 1);
+        apply_operations(temp_dir.path(), symlinks);
 
         let symlink_path = temp_dir
             .path()
@@ -146,15 +136,17 @@ mod tests {
         let generator = ExternalCommandsGenerator::new(".agents/commands");
 
         create_command_file(temp_dir.path(), "my-command", "command content");
-        generator
+        let symlinks = generator
             .generate_command_symlinks(temp_dir.path())
             .unwrap();
+        apply_operations(temp_dir.path(), symlinks);
 
         // Create user command (real file, not symlink)
         let user_command = temp_dir.path().join(".agents/commands/custom.md");
         fs::write(&user_command, "user content").unwrap();
 
-        generator.clean_commands(temp_dir.path()).unwrap();
+        let removals = generator.clean_commands(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), removals);
 
         // Generated symlink should be gone
         let generated = temp_dir
@@ -167,24 +159,6 @@ mod tests {
         assert!(user_command.exists());
     }
 
-    #[test]
-    fn test_flat_generator_check_in_sync() {
-        let temp_dir = TempDir::new().unwrap();
-        let generator = ExternalCommandsGenerator::new(".agents/commands");
-
-        create_command_file(temp_dir.path(), "my-command", "command content");
-
-        // Not in sync before generating
-        assert!(!generator.check_commands(temp_dir.path()).unwrap());
-
-        generator
-            .generate_command_symlinks(temp_dir.path())
-            .unwrap();
-
-        // Now in sync
-        assert!(generator.check_commands(temp_dir.path()).unwrap());
-    }
-
     #[test]
     fn test_flat_generator_gitignore_patterns() {
         let generator = ExternalCommandsGenerator::new(".agents/commands");
@@ -215,11 +189,11 @@ mod tests {
 
         create_command_file(temp_dir.path(), "my-command", "command content");
 
-        let result = generator.generate_command_symlinks(temp_dir.path());
-        assert!(result.is_ok());
-
-        let symlinks = result.unwrap();
+        let symlinks = generator
+            .generate_command_symlinks(temp_dir.path())
+            .unwrap();
         assert_eq!(symlinks.len(), 1);
+        apply_operations(temp_dir.path(), symlinks);
 
         // Subfolder uses original name without suffix
         let symlink_path = temp_dir
@@ -238,15 +212,17 @@ mod tests {
         let generator = ExternalCommandsGenerator::with_subdir(".claude/commands", "ai-rules");
 
         create_command_file(temp_dir.path(), "my-command", "command content");
-        generator
+        let symlinks = generator
             .generate_command_symlinks(temp_dir.path())
             .unwrap();
+        apply_operations(temp_dir.path(), symlinks);
 
         // Create user command in parent directory (should be preserved)
         let user_command = temp_dir.path().join(".claude/commands/custom.md");
         fs::write(&user_command, "user content").unwrap();
 
-        generator.clean_commands(temp_dir.path()).unwrap();
+        let removals = generator.clean_commands(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), removals);
 
         // Subfolder should be removed entirely
         let subdir_path = temp_dir.path().join(".claude/commands/ai-rules");
@@ -256,33 +232,6 @@ mod tests {
         assert!(user_command.exists());
     }
 
-    #[test]
-    fn test_subdir_generator_check_in_sync() {
-        let temp_dir = TempDir::new().unwrap();
-        let generator = ExternalCommandsGenerator::with_subdir(".claude/commands", "ai-rules");
-
-        create_command_file(temp_dir.path(), "my-command", "command content");
-
-        // Not in sync before generating
-        assert!(!generator.check_commands(temp_dir.path()).unwrap());
-
-        generator
-            .generate_command_symlinks(temp_dir.path())
-            .unwrap();
-
-        // Now in sync
-        assert!(generator.check_commands(temp_dir.path()).unwrap());
-    }
-
-    #[test]
-    fn test_subdir_generator_check_no_commands() {
-        let temp_dir = TempDir::new().unwrap();
-        let generator = ExternalCommandsGenerator::with_subdir(".claude/commands", "ai-rules");
-
-        // No commands - should be in sync
-        assert!(generator.check_commands(temp_dir.path()).unwrap());
-    }
-
     #[test]
     fn test_subdir_generator_gitignore_patterns() {
         let generator = ExternalCommandsGenerator::with_subdir(".claude/commands", "ai-rules");
@@ -327,4 +276,61 @@ mod tests {
             )]
         );
     }
+
+    /// What regenerating the commands would change: their cleanup, then their links.
+    fn command_changes(
+        generator: &ExternalCommandsGenerator,
+        current_dir: &Path,
+    ) -> Vec<PlannedChange> {
+        let mut operations = generator.clean_commands(current_dir).unwrap();
+        operations.extend(generator.generate_command_symlinks(current_dir).unwrap());
+        planned_changes(operations)
+    }
+
+    #[test]
+    fn test_flat_generator_check_in_sync() {
+        let temp_dir = TempDir::new().unwrap();
+        let generator = ExternalCommandsGenerator::new(".agents/commands");
+
+        create_command_file(temp_dir.path(), "my-command", "command content");
+
+        // Not in sync before generating
+        assert!(!command_changes(&generator, temp_dir.path()).is_empty());
+
+        let symlinks = generator
+            .generate_command_symlinks(temp_dir.path())
+            .unwrap();
+        apply_operations(temp_dir.path(), symlinks);
+
+        // Now in sync
+        assert!(command_changes(&generator, temp_dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_subdir_generator_check_in_sync() {
+        let temp_dir = TempDir::new().unwrap();
+        let generator = ExternalCommandsGenerator::with_subdir(".claude/commands", "ai-rules");
+
+        create_command_file(temp_dir.path(), "my-command", "command content");
+
+        // Not in sync before generating
+        assert!(!command_changes(&generator, temp_dir.path()).is_empty());
+
+        let symlinks = generator
+            .generate_command_symlinks(temp_dir.path())
+            .unwrap();
+        apply_operations(temp_dir.path(), symlinks);
+
+        // Now in sync
+        assert!(command_changes(&generator, temp_dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_subdir_generator_check_no_commands() {
+        let temp_dir = TempDir::new().unwrap();
+        let generator = ExternalCommandsGenerator::with_subdir(".claude/commands", "ai-rules");
+
+        // No commands - should be in sync
+        assert!(command_changes(&generator, temp_dir.path()).is_empty());
+    }
 }
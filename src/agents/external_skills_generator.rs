@@ -1,10 +1,10 @@
 use crate::agents::skills_generator::SkillsGeneratorTrait;
 use crate::operations::skills_reader::{
-    check_skill_symlinks_in_sync, create_skill_symlinks, get_skill_gitignore_patterns,
-    remove_generated_skills,
+    create_skill_symlinks, get_skill_gitignore_patterns, remove_generated_skills,
 };
+use crate::utils::plan::Operation;
 use anyhow::Result;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 pub struct ExternalSkillsGenerator {
     target_dir: String,
@@ -19,18 +19,14 @@ impl ExternalSkillsGenerator {
 }
 
 impl SkillsGeneratorTrait for ExternalSkillsGenerator {
-    fn generate_skills(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
+    fn generate_skills(&self, current_dir: &Path) -> Result<Vec<Operation>> {
         create_skill_symlinks(current_dir, &self.target_dir)
     }
 
-    fn clean_skills(&self, current_dir: &Path) -> Result<()> {
+    fn clean_skills(&self, current_dir: &Path) -> Result<Vec<Operation>> {
         remove_generated_skills(current_dir, &self.target_dir)
     }
 
-    fn check_skills(&self, current_dir: &Path) -> Result<bool> {
-        check_skill_symlinks_in_sync(current_dir, &self.target_dir)
-    }
-
     fn skills_gitignore_patterns(&self) -> Vec<String> {
         get_skill_gitignore_patterns(&self.target_dir)
     }
@@ -40,7 +36,10 @@ impl SkillsGeneratorTrait for ExternalSkillsGenerator {
 mod tests {
     use super::*;
     use crate::constants::{AI_RULE_SOURCE_DIR, GENERATED_FILE_PREFIX, SKILLS_DIR, SKILL_FILENAME};
+    use crate::utils::plan::PlannedChange;
+    use crate::utils::test_utils::helpers::{apply_operations, planned_changes};
     use std::fs;
+    use std::path::PathBuf;
     use tempfile::TempDir;
 
     fn create_skill_folder(temp_dir: &Path, skill_name: &str, content: &str) -> PathBuf {
@@ -60,11 +59,9 @@ mod tests {
 
         create_skill_folder(temp_dir.path(), "my-skill", "skill content");
 
-        let result = generator.generate_skills(temp_dir.path());
-        assert!(result.is_ok());
-
-        let symlinks = result.unwrap();
+        let symlinks = generator.generate_skills(temp_dir.path()).unwrap();
         assert_eq!(symlinks.len(), 1);
+        apply_operations(temp_dir.path(), symlinks);
 
         let symlink_path = temp_dir
             .path()
@@ -80,7 +77,10 @@ mod tests {
 
         // Create skill and generate symlink
         create_skill_folder(temp_dir.path(), "my-skill", "skill content");
-        generator.generate_skills(temp_dir.path()).unwrap();
+        apply_operations(
+            temp_dir.path(),
+            generator.generate_skills(temp_dir.path()).unwrap(),
+        );
 
         // Create user skill (real folder, not symlink)
         let user_skill = temp_dir.path().join(".claude/skills/user-skill");
@@ -88,7 +88,10 @@ mod tests {
         fs::write(user_skill.join(SKILL_FILENAME), "user content").unwrap();
 
         // Clean
-        generator.clean_skills(temp_dir.path()).unwrap();
+        apply_operations(
+            temp_dir.path(),
+            generator.clean_skills(temp_dir.path()).unwrap(),
+        );
 
         // Generated symlink should be gone
         let generated = temp_dir
@@ -101,36 +104,6 @@ mod tests {
         assert!(user_skill.exists());
     }
 
-    #[test]
-    fn test_external_skills_generator_check_in_sync() {
-        let temp_dir = TempDir::new().unwrap();
-        let generator = ExternalSkillsGenerator::new(".claude/skills");
-
-        // Create skill
-        create_skill_folder(temp_dir.path(), "my-skill", "skill content");
-
-        // Not in sync before generating
-        let result = generator.check_skills(temp_dir.path()).unwrap();
-        assert!(!result);
-
-        // Generate symlinks
-        generator.generate_skills(temp_dir.path()).unwrap();
-
-        // Now in sync
-        let result = generator.check_skills(temp_dir.path()).unwrap();
-        assert!(result);
-    }
-
-    #[test]
-    fn test_external_skills_generator_check_no_skills() {
-        let temp_dir = TempDir::new().unwrap();
-        let generator = ExternalSkillsGenerator::new(".claude/skills");
-
-        // No skills directory - should be in sync (nothing to do)
-        let result = generator.check_skills(temp_dir.path()).unwrap();
-        assert!(result);
-    }
-
     #[test]
     fn test_external_skills_generator_gitignore_patterns() {
         let generator = ExternalSkillsGenerator::new(".claude/skills");
@@ -161,4 +134,42 @@ mod tests {
             vec![".agents/skills/ai-rules-generated-*"]
         );
     }
+
+    /// What regenerating the skills would change: their cleanup, then their links.
+    fn skill_changes(
+        generator: &ExternalSkillsGenerator,
+        current_dir: &Path,
+    ) -> Vec<PlannedChange> {
+        let mut operations = generator.clean_skills(current_dir).unwrap();
+        operations.extend(generator.generate_skills(current_dir).unwrap());
+        planned_changes(operations)
+    }
+
+    #[test]
+    fn test_external_skills_generator_check_in_sync() {
+        let temp_dir = TempDir::new().unwrap();
+        let generator = ExternalSkillsGenerator::new(".claude/skills");
+
+        // Create skill
+        create_skill_folder(temp_dir.path(), "my-skill", "skill content");
+
+        // Not in sync before generating
+        assert!(!skill_changes(&generator, temp_dir.path()).is_empty());
+
+        // Generate symlinks
+        let symlinks = generator.generate_skills(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), symlinks);
+
+        // Now in sync
+        assert!(skill_changes(&generator, temp_dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_external_skills_generator_check_no_skills() {
+        let temp_dir = TempDir::new().unwrap();
+        let generator = ExternalSkillsGenerator::new(".claude/skills");
+
+        // No skills directory - should be in sync (nothing to do)
+        assert!(skill_changes(&generator, temp_dir.path()).is_empty());
+    }
 }
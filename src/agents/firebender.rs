@@ -5,9 +5,7 @@ use crate::agents::external_commands_generator::ExternalCommandsGenerator;
 use crate::agents::external_skills_generator::ExternalSkillsGenerator;
 use crate::agents::mcp_generator::McpGeneratorTrait;
 use crate::agents::rule_generator::AgentRuleGenerator;
-use crate::agents::single_file_based::{
-    check_in_sync, clean_generated_files, generate_agent_file_contents,
-};
+use crate::agents::single_file_based::{clean_generated_files, generate_agent_file_contents};
 use crate::agents::skills_generator::SkillsGeneratorTrait;
 use crate::constants::{
     AGENTS_MD_FILENAME, AI_RULE_SOURCE_DIR, AMP_SKILLS_DIR, FIREBENDER_COMMANDS_DIR,
@@ -16,9 +14,9 @@ use crate::constants::{
 use crate::models::SourceFile;
 use crate::operations::mcp_reader::extract_mcp_servers_for_firebender;
 use crate::utils::file_utils::{
-    check_agents_md_symlink, check_inlined_file_symlink, create_symlink_to_agents_md,
-    create_symlink_to_inlined_file, ensure_trailing_newline,
+    ensure_trailing_newline, symlink_to_agents_md, symlink_to_inlined_file,
 };
+use crate::utils::plan::Operation;
 use anyhow::{Context, Result};
 use serde_json::{json, Value};
 use std::collections::HashMap;
@@ -35,7 +33,7 @@ impl AgentRuleGenerator for FirebenderGenerator {
         "firebender"
     }
 
-    fn clean(&self, current_dir: &Path) -> Result<()> {
+    fn clean(&self, current_dir: &Path) -> Result<Vec<Operation>> {
         clean_generated_files(current_dir, AGENTS_MD_FILENAME)
     }
 
@@ -47,48 +45,29 @@ impl AgentRuleGenerator for FirebenderGenerator {
         generate_agent_file_contents(source_files, current_dir, AGENTS_MD_FILENAME)
     }
 
-    fn check_agent_contents(
-        &self,
-        source_files: &[SourceFile],
-        current_dir: &Path,
-    ) -> Result<bool> {
-        check_in_sync(source_files, current_dir, AGENTS_MD_FILENAME)
-    }
-
-    fn check_symlink(&self, current_dir: &Path) -> Result<bool> {
-        let output_file = current_dir.join(AGENTS_MD_FILENAME);
-        check_agents_md_symlink(current_dir, &output_file)
-    }
-
     fn gitignore_patterns(&self) -> Vec<String> {
         vec![AGENTS_MD_FILENAME.to_string()]
     }
 
-    fn generate_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        let success = create_symlink_to_agents_md(current_dir, Path::new(AGENTS_MD_FILENAME))?;
-        if success {
-            Ok(vec![current_dir.join(AGENTS_MD_FILENAME)])
-        } else {
-            Ok(vec![])
-        }
+    fn generate_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        symlink_to_agents_md(current_dir, Path::new(AGENTS_MD_FILENAME))
+            .into_iter()
+            .collect()
     }
 
     fn uses_inlined_symlink(&self) -> bool {
         true
     }
 
-    fn generate_inlined_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        let success = create_symlink_to_inlined_file(current_dir, Path::new(AGENTS_MD_FILENAME))?;
-        if success {
-            Ok(vec![current_dir.join(AGENTS_MD_FILENAME)])
-        } else {
-            Ok(vec![])
-        }
+    fn managed_block_file(&self) -> Option<&str> {
+        Some(AGENTS_MD_FILENAME)
     }
 
-    fn check_inlined_symlink(&self, current_dir: &Path) -> Result<bool> {
-        let output_file = current_dir.join(AGENTS_MD_FILENAME);
-        check_inlined_file_symlink(current_dir, &output_file)
+    fn generate_inlined_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        vec![symlink_to_inlined_file(
+            current_dir,
+            Path::new(AGENTS_MD_FILENAME),
+        )]
     }
 
     fn mcp_generator(&self) -> Option<Box<dyn McpGeneratorTrait>> {
@@ -108,36 +87,45 @@ impl AgentRuleGenerator for FirebenderGenerator {
 }
 
 impl McpGeneratorTrait for FirebenderConfigGenerator {
-    fn generate_mcp(&self, current_dir: &Path) -> HashMap<PathBuf, String> {
-        let mut files = HashMap::new();
-
-        if let Ok(Some(config)) = generate_firebender_config(current_dir) {
-            files.insert(current_dir.join(FIREBENDER_JSON), config);
+    fn generate_mcp(&self, current_dir: &Path) -> Vec<Operation> {
+        match generate_firebender_config(current_dir) {
+            Ok(Some(config)) => vec![Operation::write(current_dir.join(FIREBENDER_JSON), config)],
+            _ => Vec::new(),
         }
-
-        files
     }
 
-    fn clean_mcp(&self, current_dir: &Path) -> Result<()> {
+    fn clean_mcp(&self, current_dir: &Path) -> Result<Vec<Operation>> {
         let firebender_file = current_dir.join(FIREBENDER_JSON);
         if firebender_file.exists() {
-            fs::remove_file(&firebender_file)
-                .with_context(|| format!("Failed to remove {}", firebender_file.display()))?;
+            return Ok(vec![Operation::delete(firebender_file)]);
         }
-        Ok(())
+        Ok(Vec::new())
     }
 
-    fn check_mcp(&self, current_dir: &Path) -> Result<bool> {
-        let firebender_file = current_dir.join(FIREBENDER_JSON);
+    fn mcp_gitignore_patterns(&self) -> Vec<String> {
+        vec![FIREBENDER_JSON.to_string()]
+    }
 
-        match generate_firebender_config(current_dir)? {
-            Some(expected_content) => file_matches_expected(&firebender_file, &expected_content),
-            None => Ok(!firebender_file.exists()),
+    fn adopt_mcp(&self, current_dir: &Path, edited: &str) -> Result<Option<Operation>> {
+        let edited: Value = serde_json::from_str(edited)
+            .with_context(|| format!("Invalid JSON in {FIREBENDER_JSON}"))?;
+        let mut base = json!({});
+        if let Some(mcp_servers) = extract_mcp_servers_for_firebender(current_dir)? {
+            base[MCP_SERVERS_FIELD] = mcp_servers;
         }
-    }
+        let Some(overlay) = json_overlay(&base, &edited) else {
+            anyhow::bail!(
+                "Can't adopt {FIREBENDER_JSON}: it removes settings generated from ai-rules/mcp.json. Remove them there instead"
+            );
+        };
 
-    fn mcp_gitignore_patterns(&self) -> Vec<String> {
-        vec![FIREBENDER_JSON.to_string()]
+        let overlay_path = current_dir
+            .join(AI_RULE_SOURCE_DIR)
+            .join(FIREBENDER_OVERLAY_JSON);
+        Ok(Some(Operation::write(
+            overlay_path,
+            ensure_trailing_newline(serde_json::to_string_pretty(&overlay)?),
+        )))
     }
 
     fn box_clone(&self) -> Box<dyn McpGeneratorTrait> {
@@ -194,15 +182,28 @@ fn merge_json_objects(base: &mut Value, overlay: &Value) {
     }
 }
 
-fn file_matches_expected(file_path: &Path, expected_content: &str) -> Result<bool> {
-    if !file_path.exists() {
-        return Ok(false);
+/// Returns the overlay that `merge_json_objects` needs to turn `base` into `edited`, or
+/// `None` if `edited` drops a key of `base`, which an overlay can't express.
+fn json_overlay(base: &Value, edited: &Value) -> Option<Value> {
+    let (Some(base_obj), Some(edited_obj)) = (base.as_object(), edited.as_object()) else {
+        return Some(edited.clone());
+    };
+    if base_obj.keys().any(|key| !edited_obj.contains_key(key)) {
+        return None;
+    }
+    let mut overlay = serde_json::Map::new();
+    for (key, value) in edited_obj {
+        match base_obj.get(key) {
+            Some(base_value) if base_value == value => {}
+            Some(base_value) if base_value.is_object() && value.is_object() => {
+                overlay.insert(key.clone(), json_overlay(base_value, value)?);
+            }
+            _ => {
+                overlay.insert(key.clone(), value.clone());
+            }
+        }
     }
-
-    let actual_content = fs::read_to_string(file_path)
-        .with_context(|| format!("Failed to read {}", file_path.display()))?;
-
-    Ok(actual_content == expected_content)
+    Some(Value::Object(overlay))
 }
 
 #[cfg(test)]
@@ -258,10 +259,15 @@ mod tests {
 
         create_file(temp_dir.path(), "ai-rules/AGENTS.md", "# Shared rules");
 
-        let result = generator.generate_symlink(temp_dir.path()).unwrap();
+        let result = generator.generate_symlink(temp_dir.path());
 
-        assert_eq!(result, vec![temp_dir.path().join(AGENTS_MD_FILENAME)]);
-        assert!(temp_dir.path().join(AGENTS_MD_FILENAME).is_symlink());
+        assert_eq!(
+            result,
+            vec![Operation::symlink(
+                temp_dir.path().join(AGENTS_MD_FILENAME),
+                "ai-rules/AGENTS.md"
+            )]
+        );
     }
 
     #[test]
@@ -270,7 +276,7 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
 
         create_file(temp_dir.path(), AGENTS_MD_FILENAME, "generated content");
-        generator.clean(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), generator.clean(temp_dir.path()).unwrap());
 
         assert_file_not_exists(temp_dir.path(), AGENTS_MD_FILENAME);
     }
@@ -382,11 +388,10 @@ mod tests {
 
         create_file(temp_dir.path(), "ai-rules/mcp.json", TEST_MCP_CONFIG);
 
-        let files = mcp_gen.generate_mcp(temp_dir.path());
+        let operations = mcp_gen.generate_mcp(temp_dir.path());
 
-        assert_eq!(files.len(), 1);
-        let expected_path = temp_dir.path().join(FIREBENDER_JSON);
-        assert!(files.contains_key(&expected_path));
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].path(), temp_dir.path().join(FIREBENDER_JSON));
     }
 
     #[test]
@@ -395,9 +400,9 @@ mod tests {
         let generator = FirebenderGenerator;
         let mcp_gen = generator.mcp_generator().unwrap();
 
-        let files = mcp_gen.generate_mcp(temp_dir.path());
+        let operations = mcp_gen.generate_mcp(temp_dir.path());
 
-        assert!(files.is_empty());
+        assert!(operations.is_empty());
     }
 
     #[test]
@@ -412,7 +417,9 @@ mod tests {
             .unwrap();
         create_file(temp_dir.path(), FIREBENDER_JSON, &expected);
 
-        assert!(mcp_gen.check_mcp(temp_dir.path()).unwrap());
+        let mut operations = mcp_gen.clean_mcp(temp_dir.path()).unwrap();
+        operations.extend(mcp_gen.generate_mcp(temp_dir.path()));
+        assert!(planned_changes(operations).is_empty());
     }
 
     #[test]
@@ -441,6 +448,7 @@ mod tests {
         let symlinks = cmd_gen.generate_command_symlinks(temp_dir.path()).unwrap();
 
         assert_eq!(symlinks.len(), 1);
+        apply_operations(temp_dir.path(), symlinks);
         assert!(temp_dir
             .path()
             .join(".firebender/commands/commit-ai-rules.mdc")
@@ -480,6 +488,7 @@ mod tests {
         let symlinks = skills_gen.generate_skills(temp_dir.path()).unwrap();
 
         assert_eq!(symlinks.len(), 1);
+        apply_operations(temp_dir.path(), symlinks);
         assert!(temp_dir
             .path()
             .join(".agents/skills")
@@ -521,4 +530,26 @@ mod tests {
         );
         assert_eq!(base["backgroundAgent"]["otherSetting"], true);
     }
+
+    #[test]
+    fn test_json_overlay_inverts_merge() {
+        let base = json!({
+            "mcpServers": { "server-a": { "command": "npx" } }
+        });
+        let edited = json!({
+            "mcpServers": {
+                "server-a": { "command": "npx" },
+                "server-b": { "command": "python" }
+            },
+            "backgroundAgent": { "copyFiles": ["a"] }
+        });
+
+        let overlay = json_overlay(&base, &edited).unwrap();
+        let mut merged = base.clone();
+        merge_json_objects(&mut merged, &overlay);
+
+        assert_eq!(merged, edited);
+        assert!(overlay["mcpServers"].get("server-a").is_none());
+        assert_eq!(json_overlay(&base, &json!({})), None);
+    }
 }
@@ -1,15 +1,11 @@
 use crate::agents::mcp_generator::McpGeneratorTrait;
-use crate::agents::rule_generator::AgentRuleGenerator;
-use crate::agents::single_file_based::{
-    check_in_sync, clean_generated_files, generate_agent_file_contents,
-};
+use crate::agents::rule_generator::{AgentRuleGenerator, UserScopeTarget};
+use crate::agents::single_file_based::{clean_generated_files, generate_agent_file_contents};
 use crate::constants::GENERATED_FILE_PREFIX;
 use crate::models::SourceFile;
 use crate::operations::mcp_reader::read_mcp_config;
-use crate::utils::file_utils::{
-    check_agents_md_symlink, check_inlined_file_symlink, create_symlink_to_agents_md,
-    create_symlink_to_inlined_file,
-};
+use crate::utils::file_utils::{symlink_to_agents_md, symlink_to_inlined_file};
+use crate::utils::plan::Operation;
 use anyhow::Result;
 use serde_json::{json, Map, Value};
 use std::collections::HashMap;
@@ -18,6 +14,7 @@ use std::path::{Path, PathBuf};
 
 const GEMINI_SETTINGS_JSON: &str = ".gemini/settings.json";
 const GEMINI_AGENT_FILE: &str = "GEMINI.md";
+const GEMINI_USER_RULES_FILE: &str = ".gemini/GEMINI.md";
 
 pub struct GeminiGenerator;
 
@@ -26,12 +23,12 @@ impl AgentRuleGenerator for GeminiGenerator {
         "gemini"
     }
 
-    fn clean(&self, current_dir: &Path) -> Result<()> {
-        clean_generated_files(current_dir, GEMINI_AGENT_FILE)?;
+    fn clean(&self, current_dir: &Path) -> Result<Vec<Operation>> {
+        let mut removals = clean_generated_files(current_dir, GEMINI_AGENT_FILE)?;
         if let Some(mcp) = self.mcp_generator() {
-            mcp.clean_mcp(current_dir)?;
+            removals.extend(mcp.clean_mcp(current_dir)?);
         }
-        Ok(())
+        Ok(removals)
     }
 
     fn generate_agent_contents(
@@ -42,19 +39,6 @@ impl AgentRuleGenerator for GeminiGenerator {
         generate_agent_file_contents(source_files, current_dir, GEMINI_AGENT_FILE)
     }
 
-    fn check_agent_contents(
-        &self,
-        source_files: &[SourceFile],
-        current_dir: &Path,
-    ) -> Result<bool> {
-        check_in_sync(source_files, current_dir, GEMINI_AGENT_FILE)
-    }
-
-    fn check_symlink(&self, current_dir: &Path) -> Result<bool> {
-        let output_file = current_dir.join(GEMINI_AGENT_FILE);
-        check_agents_md_symlink(current_dir, &output_file)
-    }
-
     fn gitignore_patterns(&self) -> Vec<String> {
         let mut patterns = vec![GEMINI_AGENT_FILE.to_string()];
         if let Some(mcp) = self.mcp_generator() {
@@ -63,48 +47,48 @@ impl AgentRuleGenerator for GeminiGenerator {
         patterns
     }
 
-    fn generate_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        let success = create_symlink_to_agents_md(current_dir, Path::new(GEMINI_AGENT_FILE))?;
-        if success {
-            Ok(vec![current_dir.join(GEMINI_AGENT_FILE)])
-        } else {
-            Ok(vec![])
-        }
+    fn generate_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        symlink_to_agents_md(current_dir, Path::new(GEMINI_AGENT_FILE))
+            .into_iter()
+            .collect()
     }
 
     fn uses_inlined_symlink(&self) -> bool {
         true
     }
 
-    fn generate_inlined_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        let success = create_symlink_to_inlined_file(current_dir, Path::new(GEMINI_AGENT_FILE))?;
-        if success {
-            Ok(vec![current_dir.join(GEMINI_AGENT_FILE)])
-        } else {
-            Ok(vec![])
-        }
+    fn managed_block_file(&self) -> Option<&str> {
+        Some(GEMINI_AGENT_FILE)
     }
 
-    fn check_inlined_symlink(&self, current_dir: &Path) -> Result<bool> {
-        let output_file = current_dir.join(GEMINI_AGENT_FILE);
-        check_inlined_file_symlink(current_dir, &output_file)
+    fn generate_inlined_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        vec![symlink_to_inlined_file(
+            current_dir,
+            Path::new(GEMINI_AGENT_FILE),
+        )]
     }
 
     fn mcp_generator(&self) -> Option<Box<dyn McpGeneratorTrait>> {
         Some(Box::new(GeminiMcpGenerator))
     }
+
+    fn user_scope(&self) -> Option<UserScopeTarget> {
+        Some(UserScopeTarget {
+            rules_file: GEMINI_USER_RULES_FILE,
+            commands_dir: None,
+            skills_dir: None,
+        })
+    }
 }
 
 struct GeminiMcpGenerator;
 
 impl McpGeneratorTrait for GeminiMcpGenerator {
-    fn generate_mcp(&self, current_dir: &Path) -> HashMap<PathBuf, String> {
-        let mut files = HashMap::new();
-
+    fn generate_mcp(&self, current_dir: &Path) -> Vec<Operation> {
         // 1. Read source MCP config (ai-rules/mcp.json)
         let source_mcp_content = match read_mcp_config(current_dir) {
             Ok(Some(c)) => c,
-            _ => return files, // No source config, nothing to generate
+            _ => return Vec::new(), // No source config, nothing to generate
         };
         let source_json: Value = serde_json::from_str(&source_mcp_content).unwrap_or(json!({}));
         let mut source_servers = source_json.get("mcpServers").unwrap_or(&json!({})).clone();
@@ -115,130 +99,75 @@ impl McpGeneratorTrait for GeminiMcpGenerator {
         // Prefix source server names with GENERATED_FILE_PREFIX
         let prefixed_servers = self.prefix_server_names(&source_servers);
 
-        // 2. Read existing target config (.gemini/settings.json)
+        // 2. Replace the generated servers in .gemini/settings.json, keeping the user's
+        // servers and other settings
         let target_path = current_dir.join(GEMINI_SETTINGS_JSON);
-        let mut target_json = if target_path.exists() {
-            let content = fs::read_to_string(&target_path).unwrap_or_else(|_| "{}".to_string());
-            serde_json::from_str(&content).unwrap_or(json!({}))
-        } else {
-            json!({})
-        };
-
-        // 3. Merge mcpServers: preserve user servers, replace generated ones
-        let existing_servers = target_json
-            .get("mcpServers")
-            .and_then(|v| v.as_object())
-            .cloned()
+        let remove = fs::read_to_string(&target_path)
+            .ok()
+            .and_then(|content| self.generated_json_keys(&content))
             .unwrap_or_default();
-
-        // Keep only user-configured servers (those without the prefix)
-        let mut merged_servers: Map<String, Value> = existing_servers
+        let set = prefixed_servers
+            .as_object()
             .into_iter()
-            .filter(|(name, _)| !name.starts_with(GENERATED_FILE_PREFIX))
+            .flatten()
+            .map(|(name, config)| (server_pointer(name), config.clone()))
             .collect();
 
-        // Add new prefixed servers
-        if let Some(prefixed_obj) = prefixed_servers.as_object() {
-            for (name, config) in prefixed_obj {
-                merged_servers.insert(name.clone(), config.clone());
-            }
-        }
-
-        target_json["mcpServers"] = Value::Object(merged_servers);
-
-        // 4. Format and return
-        // Use pretty print
-        if let Ok(content) = serde_json::to_string_pretty(&target_json) {
-            files.insert(target_path, content);
-        }
-
-        files
+        vec![Operation::MergeJson {
+            path: target_path,
+            remove,
+            set,
+        }]
     }
 
-    fn clean_mcp(&self, current_dir: &Path) -> Result<()> {
+    fn clean_mcp(&self, current_dir: &Path) -> Result<Vec<Operation>> {
         let target_path = current_dir.join(GEMINI_SETTINGS_JSON);
-        if target_path.exists() {
-            let content = fs::read_to_string(&target_path)?;
-            let mut json: Value = serde_json::from_str(&content)?;
-
-            if let Some(obj) = json.as_object_mut() {
-                // Only remove servers with the generated prefix, preserve user servers
-                if let Some(mcp_servers) = obj.get_mut("mcpServers") {
-                    if let Some(servers_obj) = mcp_servers.as_object_mut() {
-                        servers_obj.retain(|name, _| !name.starts_with(GENERATED_FILE_PREFIX));
-                    }
-                }
-
-                let new_content = serde_json::to_string_pretty(&json)?;
-                fs::write(&target_path, new_content)?;
-            }
-        }
-        Ok(())
-    }
-
-    fn check_mcp(&self, current_dir: &Path) -> Result<bool> {
-        let target_path = current_dir.join(GEMINI_SETTINGS_JSON);
-
-        let source_mcp_content = match read_mcp_config(current_dir)? {
-            Some(c) => c,
-            None => {
-                // If no source, check target doesn't have any generated (prefixed) servers
-                if !target_path.exists() {
-                    return Ok(true);
-                }
-                let target_content = fs::read_to_string(&target_path)?;
-                let target_json: Value = serde_json::from_str(&target_content)?;
-                let mcp_servers = target_json.get("mcpServers");
-                let has_no_generated = match mcp_servers {
-                    None => true,
-                    Some(val) => val
-                        .as_object()
-                        .is_none_or(|o| !o.keys().any(|k| k.starts_with(GENERATED_FILE_PREFIX))),
-                };
-                return Ok(has_no_generated);
-            }
-        };
-
         if !target_path.exists() {
-            return Ok(false);
+            return Ok(Vec::new());
         }
+        let content = fs::read_to_string(&target_path)?;
+        let json: Value = serde_json::from_str(&content)?;
 
-        let source_json: Value = serde_json::from_str(&source_mcp_content)?;
-        let empty_obj = json!({});
-        let mut source_servers = source_json.get("mcpServers").unwrap_or(&empty_obj).clone();
-        // Transform source before comparison
-        self.transform_mcp_servers(&mut source_servers);
-        // Prefix source server names for comparison
-        let expected_servers = self.prefix_server_names(&source_servers);
-
-        let target_content = fs::read_to_string(&target_path)?;
-        let target_json: Value = serde_json::from_str(&target_content)?;
-
-        // Extract only generated (prefixed) servers from target for comparison
-        let target_generated_servers: Map<String, Value> = target_json
-            .get("mcpServers")
-            .and_then(|v| v.as_object())
-            .map(|obj| {
-                obj.iter()
-                    .filter(|(name, _)| name.starts_with(GENERATED_FILE_PREFIX))
-                    .map(|(k, v)| (k.clone(), v.clone()))
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        let expected_obj = expected_servers.as_object().cloned().unwrap_or_default();
-        Ok(target_generated_servers == expected_obj)
+        // Only remove servers with the generated prefix, preserve user servers
+        Ok(vec![Operation::MergeJson {
+            path: target_path,
+            remove: generated_server_pointers(&json),
+            set: Vec::new(),
+        }])
     }
 
     fn mcp_gitignore_patterns(&self) -> Vec<String> {
         vec![GEMINI_SETTINGS_JSON.to_string()]
     }
 
+    fn generated_json_keys(&self, content: &str) -> Option<Vec<String>> {
+        let json: Value = serde_json::from_str(content).ok()?;
+        Some(generated_server_pointers(&json))
+    }
+
     fn box_clone(&self) -> Box<dyn McpGeneratorTrait> {
         Box::new(Self)
     }
 }
 
+/// The JSON pointer to the server `name` in `mcpServers`.
+fn server_pointer(name: &str) -> String {
+    // JSON pointer escaping
+    let name = name.replace('~', "~0").replace('/', "~1");
+    format!("/mcpServers/{name}")
+}
+
+/// Pointers to the servers in `json` that ai-rules generated.
+fn generated_server_pointers(json: &Value) -> Vec<String> {
+    json.get("mcpServers")
+        .and_then(|v| v.as_object())
+        .into_iter()
+        .flat_map(|servers| servers.keys())
+        .filter(|name| name.starts_with(GENERATED_FILE_PREFIX))
+        .map(|name| server_pointer(name))
+        .collect()
+}
+
 impl GeminiMcpGenerator {
     /// Prefixes all server names with GENERATED_FILE_PREFIX
     fn prefix_server_names(&self, servers: &Value) -> Value {
@@ -288,9 +217,17 @@ impl GeminiMcpGenerator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::plan::PlannedChange;
     use crate::utils::test_utils::helpers::*;
     use tempfile::TempDir;
 
+    /// What regenerating the MCP servers would change: their cleanup, then the merge.
+    fn mcp_changes(generator: &GeminiMcpGenerator, current_dir: &Path) -> Vec<PlannedChange> {
+        let mut operations = generator.clean_mcp(current_dir).unwrap();
+        operations.extend(generator.generate_mcp(current_dir));
+        planned_changes(operations)
+    }
+
     #[test]
     fn test_gemini_mcp_transformation() {
         let mut servers = json!({
@@ -375,9 +312,8 @@ mod tests {
 }"#;
         create_file(temp_dir.path(), ".gemini/settings.json", target_config);
 
-        // Check should pass because source is transformed and prefixed before comparison
-        let result = generator.check_mcp(temp_dir.path()).unwrap();
-        assert!(result);
+        // Nothing changes because source is transformed and prefixed before comparison
+        assert!(mcp_changes(&generator, temp_dir.path()).is_empty());
     }
 
     #[test]
@@ -419,12 +355,12 @@ mod tests {
         create_file(temp_dir.path(), ".gemini/settings.json", existing_target);
 
         // Generate MCP
-        let files = generator.generate_mcp(temp_dir.path());
+        let operations = generator.generate_mcp(temp_dir.path());
+        assert_eq!(operations.len(), 1);
+        apply_operations(temp_dir.path(), operations);
 
-        // Verify the result - get the first (and only) file
-        assert_eq!(files.len(), 1);
-        let content = files.values().next().unwrap();
-        let json: Value = serde_json::from_str(content).unwrap();
+        let content = fs::read_to_string(temp_dir.path().join(".gemini/settings.json")).unwrap();
+        let json: Value = serde_json::from_str(&content).unwrap();
 
         // Other settings should be preserved
         assert_eq!(json.get("otherSetting").unwrap(), "preserved");
@@ -454,10 +390,10 @@ mod tests {
         // Create invalid JSON source
         create_file(temp_dir.path(), "ai-rules/mcp.json", "{ invalid json }");
 
-        // Should return empty map - read_mcp_config returns Err for invalid JSON,
-        // which is caught by the wildcard pattern and returns empty files
-        let files = generator.generate_mcp(temp_dir.path());
-        assert!(files.is_empty());
+        // Should return no operations - read_mcp_config returns Err for invalid JSON,
+        // which is caught by the wildcard pattern
+        let operations = generator.generate_mcp(temp_dir.path());
+        assert!(operations.is_empty());
     }
 
     #[test]
@@ -477,7 +413,10 @@ mod tests {
         create_file(temp_dir.path(), ".gemini/settings.json", target_config);
 
         // Clean MCP
-        generator.clean_mcp(temp_dir.path()).unwrap();
+        apply_operations(
+            temp_dir.path(),
+            generator.clean_mcp(temp_dir.path()).unwrap(),
+        );
 
         // Verify the result
         let target_path = temp_dir.path().join(".gemini/settings.json");
@@ -499,8 +438,8 @@ mod tests {
         let generator = GeminiMcpGenerator;
 
         // Should not error when file doesn't exist
-        let result = generator.clean_mcp(temp_dir.path());
-        assert!(result.is_ok());
+        let removals = generator.clean_mcp(temp_dir.path()).unwrap();
+        assert!(removals.is_empty());
     }
 
     #[test]
@@ -531,9 +470,8 @@ mod tests {
 }"#;
         create_file(temp_dir.path(), ".gemini/settings.json", target_config);
 
-        // Should report in sync (true) because user servers are allowed without source
-        let result = generator.check_mcp(temp_dir.path()).unwrap();
-        assert!(result);
+        // Nothing changes because user servers are allowed without source
+        assert!(mcp_changes(&generator, temp_dir.path()).is_empty());
     }
 
     #[test]
@@ -551,9 +489,8 @@ mod tests {
 }"#;
         create_file(temp_dir.path(), ".gemini/settings.json", target_config);
 
-        // Should report out of sync (false) because target has generated servers but no source
-        let result = generator.check_mcp(temp_dir.path()).unwrap();
-        assert!(!result);
+        // The generated servers are removed because there is no source
+        assert!(!mcp_changes(&generator, temp_dir.path()).is_empty());
     }
 
     #[test]
@@ -562,9 +499,7 @@ mod tests {
         let generator = GeminiMcpGenerator;
 
         // No source config, no target file
-        // Should report in sync (true)
-        let result = generator.check_mcp(temp_dir.path()).unwrap();
-        assert!(result);
+        assert!(mcp_changes(&generator, temp_dir.path()).is_empty());
     }
 
     #[test]
@@ -578,9 +513,8 @@ mod tests {
         let target_config = r#"{"otherSetting": "value"}"#;
         create_file(temp_dir.path(), ".gemini/settings.json", target_config);
 
-        // Should report in sync (true) because target has no mcpServers
-        let result = generator.check_mcp(temp_dir.path()).unwrap();
-        assert!(result);
+        // Nothing changes because target has no mcpServers
+        assert!(mcp_changes(&generator, temp_dir.path()).is_empty());
     }
 
     #[test]
@@ -594,9 +528,8 @@ mod tests {
         let target_config = r#"{"mcpServers": {}}"#;
         create_file(temp_dir.path(), ".gemini/settings.json", target_config);
 
-        // Should report in sync (true) because mcpServers is empty
-        let result = generator.check_mcp(temp_dir.path()).unwrap();
-        assert!(result);
+        // Nothing changes because mcpServers is empty
+        assert!(mcp_changes(&generator, temp_dir.path()).is_empty());
     }
 
     #[test]
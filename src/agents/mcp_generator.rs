@@ -1,18 +1,31 @@
-use crate::operations::mcp_reader::read_mcp_config;
+use crate::constants::MCP_SERVERS_FIELD;
+use crate::operations::mcp_reader::{adopt_mcp_servers, read_mcp_config};
+use crate::utils::plan::Operation;
 use anyhow::Result;
-use std::collections::HashMap;
-use std::fs;
+use serde_json::{json, Value};
 use std::path::{Path, PathBuf};
 
 pub trait McpGeneratorTrait {
-    fn generate_mcp(&self, current_dir: &Path) -> HashMap<PathBuf, String>;
+    fn generate_mcp(&self, current_dir: &Path) -> Vec<Operation>;
 
-    fn clean_mcp(&self, current_dir: &Path) -> Result<()>;
-
-    fn check_mcp(&self, current_dir: &Path) -> Result<bool>;
+    fn clean_mcp(&self, current_dir: &Path) -> Result<Vec<Operation>>;
 
     fn mcp_gitignore_patterns(&self) -> Vec<String>;
 
+    /// For generators that merge into a file the user also edits, returns the JSON
+    /// pointers (e.g. `/mcpServers/ai-rules-generated-db`) that ai-rules owns in
+    /// `content`. Returns `None` when the whole file is generated or `content` isn't the
+    /// generator's JSON file.
+    fn generated_json_keys(&self, _content: &str) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Returns the write that moves hand edits of the generated file back into the
+    /// source files, or `None` if edits to this generator's output can't be adopted.
+    fn adopt_mcp(&self, _current_dir: &Path, _edited: &str) -> Result<Option<Operation>> {
+        Ok(None)
+    }
+
     fn box_clone(&self) -> Box<dyn McpGeneratorTrait>;
 }
 
@@ -33,43 +46,34 @@ impl ExternalMcpGenerator {
 }
 
 impl McpGeneratorTrait for ExternalMcpGenerator {
-    fn generate_mcp(&self, current_dir: &Path) -> HashMap<PathBuf, String> {
-        let mut files = HashMap::new();
-
-        if let Ok(Some(mcp_content)) = read_mcp_config(current_dir) {
-            files.insert(current_dir.join(&self.output_path), mcp_content);
-        }
-
-        files
-    }
-
-    fn clean_mcp(&self, current_dir: &Path) -> Result<()> {
-        let mcp_file = current_dir.join(&self.output_path);
-        if mcp_file.exists() {
-            fs::remove_file(mcp_file)?;
+    fn generate_mcp(&self, current_dir: &Path) -> Vec<Operation> {
+        match read_mcp_config(current_dir) {
+            Ok(Some(mcp_content)) => vec![Operation::write(
+                current_dir.join(&self.output_path),
+                mcp_content,
+            )],
+            _ => Vec::new(),
         }
-        Ok(())
     }
 
-    fn check_mcp(&self, current_dir: &Path) -> Result<bool> {
+    fn clean_mcp(&self, current_dir: &Path) -> Result<Vec<Operation>> {
         let mcp_file = current_dir.join(&self.output_path);
-
-        match read_mcp_config(current_dir)? {
-            Some(expected) => {
-                if !mcp_file.exists() {
-                    return Ok(false);
-                }
-                let actual = fs::read_to_string(mcp_file)?;
-                Ok(actual == expected)
-            }
-            None => Ok(!mcp_file.exists()),
+        if mcp_file.exists() || mcp_file.is_symlink() {
+            return Ok(vec![Operation::delete(mcp_file)]);
         }
+        Ok(Vec::new())
     }
 
     fn mcp_gitignore_patterns(&self) -> Vec<String> {
         vec![self.output_path.display().to_string()]
     }
 
+    fn adopt_mcp(&self, current_dir: &Path, edited: &str) -> Result<Option<Operation>> {
+        let edited: Value = serde_json::from_str(edited)?;
+        let servers = edited.get(MCP_SERVERS_FIELD).cloned().unwrap_or(json!({}));
+        adopt_mcp_servers(current_dir, &servers).map(Some)
+    }
+
     fn box_clone(&self) -> Box<dyn McpGeneratorTrait> {
         Box::new(Self {
             output_path: self.output_path.clone(),
@@ -99,11 +103,10 @@ mod tests {
 
         create_file(temp_dir.path(), "ai-rules/mcp.json", TEST_MCP_CONFIG);
 
-        let files = generator.generate_mcp(temp_dir.path());
+        let operations = generator.generate_mcp(temp_dir.path());
 
-        assert_eq!(files.len(), 1);
-        let expected_path = temp_dir.path().join(".mcp.json");
-        assert!(files.contains_key(&expected_path));
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].path(), temp_dir.path().join(".mcp.json"));
     }
 
     #[test]
@@ -111,9 +114,9 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let generator = ExternalMcpGenerator::new(PathBuf::from(".mcp.json"));
 
-        let files = generator.generate_mcp(temp_dir.path());
+        let operations = generator.generate_mcp(temp_dir.path());
 
-        assert_eq!(files.len(), 0);
+        assert_eq!(operations.len(), 0);
     }
 
     #[test]
@@ -124,8 +127,8 @@ mod tests {
         create_file(temp_dir.path(), ".mcp.json", "test content");
         assert_file_exists(temp_dir.path(), ".mcp.json");
 
-        let result = generator.clean_mcp(temp_dir.path());
-        assert!(result.is_ok());
+        let removals = generator.clean_mcp(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), removals);
         assert_file_not_exists(temp_dir.path(), ".mcp.json");
     }
 
@@ -138,8 +141,9 @@ mod tests {
         let expected = read_mcp_config(temp_dir.path()).unwrap().unwrap();
         create_file(temp_dir.path(), ".mcp.json", &expected);
 
-        let result = generator.check_mcp(temp_dir.path()).unwrap();
-        assert!(result);
+        let mut operations = generator.clean_mcp(temp_dir.path()).unwrap();
+        operations.extend(generator.generate_mcp(temp_dir.path()));
+        assert!(planned_changes(operations).is_empty());
     }
 
     #[test]
@@ -150,8 +154,9 @@ mod tests {
         create_file(temp_dir.path(), "ai-rules/mcp.json", TEST_MCP_CONFIG);
         create_file(temp_dir.path(), ".mcp.json", "wrong content");
 
-        let result = generator.check_mcp(temp_dir.path()).unwrap();
-        assert!(!result);
+        let mut operations = generator.clean_mcp(temp_dir.path()).unwrap();
+        operations.extend(generator.generate_mcp(temp_dir.path()));
+        assert!(!planned_changes(operations).is_empty());
     }
 
     #[test]
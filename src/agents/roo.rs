@@ -3,6 +3,7 @@ use crate::agents::rule_generator::AgentRuleGenerator;
 use crate::agents::single_file_based::SingleFileBasedGenerator;
 use crate::constants::{AGENTS_MD_FILENAME, MCP_JSON};
 use crate::models::SourceFile;
+use crate::utils::plan::Operation;
 use anyhow::Result;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -33,7 +34,7 @@ impl AgentRuleGenerator for RooGenerator {
         self.inner.name()
     }
 
-    fn clean(&self, current_dir: &Path) -> Result<()> {
+    fn clean(&self, current_dir: &Path) -> Result<Vec<Operation>> {
         self.inner.clean(current_dir)
     }
 
@@ -46,23 +47,11 @@ impl AgentRuleGenerator for RooGenerator {
             .generate_agent_contents(source_files, current_dir)
     }
 
-    fn check_agent_contents(
-        &self,
-        source_files: &[SourceFile],
-        current_dir: &Path,
-    ) -> Result<bool> {
-        self.inner.check_agent_contents(source_files, current_dir)
-    }
-
-    fn check_symlink(&self, current_dir: &Path) -> Result<bool> {
-        self.inner.check_symlink(current_dir)
-    }
-
     fn gitignore_patterns(&self) -> Vec<String> {
         self.inner.gitignore_patterns()
     }
 
-    fn generate_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
+    fn generate_symlink(&self, current_dir: &Path) -> Vec<Operation> {
         self.inner.generate_symlink(current_dir)
     }
 
@@ -70,12 +59,12 @@ impl AgentRuleGenerator for RooGenerator {
         self.inner.uses_inlined_symlink()
     }
 
-    fn generate_inlined_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
+    fn generate_inlined_symlink(&self, current_dir: &Path) -> Vec<Operation> {
         self.inner.generate_inlined_symlink(current_dir)
     }
 
-    fn check_inlined_symlink(&self, current_dir: &Path) -> Result<bool> {
-        self.inner.check_inlined_symlink(current_dir)
+    fn managed_block_file(&self) -> Option<&str> {
+        self.inner.managed_block_file()
     }
 
     fn mcp_generator(&self) -> Option<Box<dyn McpGeneratorTrait>> {
@@ -120,11 +109,10 @@ mod tests {
         create_file(temp_dir.path(), "ai-rules/mcp.json", TEST_MCP_CONFIG);
 
         let mcp_gen = generator.mcp_generator().unwrap();
-        let files = mcp_gen.generate_mcp(temp_dir.path());
+        let operations = mcp_gen.generate_mcp(temp_dir.path());
 
-        assert_eq!(files.len(), 1);
-        let expected_path = temp_dir.path().join(".roo/mcp.json");
-        assert!(files.contains_key(&expected_path));
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].path(), temp_dir.path().join(".roo/mcp.json"));
     }
 
     #[test]
@@ -2,14 +2,33 @@ use crate::agents::command_generator::CommandGeneratorTrait;
 use crate::agents::mcp_generator::McpGeneratorTrait;
 use crate::agents::skills_generator::SkillsGeneratorTrait;
 use crate::models::SourceFile;
+use crate::utils::plan::Operation;
 use anyhow::Result;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+/// Home-directory locations an agent reads user-level instructions from,
+/// relative to the home directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserScopeTarget {
+    pub rules_file: &'static str,
+    /// Directory owned by ai-rules for user-level commands
+    pub commands_dir: Option<&'static str>,
+    pub skills_dir: Option<&'static str>,
+}
+
+/// A tool-native file for personal rules, written when `local_rules: native` is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalRulesOutput {
+    pub file_name: &'static str,
+    /// The agent reads this file instead of its regular one, so it repeats the shared rules
+    pub includes_shared_rules: bool,
+}
+
 pub trait AgentRuleGenerator {
     fn name(&self) -> &str;
 
-    fn clean(&self, current_dir: &Path) -> Result<()>;
+    fn clean(&self, current_dir: &Path) -> Result<Vec<Operation>>;
 
     fn generate_agent_contents(
         &self,
@@ -17,25 +36,23 @@ pub trait AgentRuleGenerator {
         current_dir: &Path,
     ) -> HashMap<PathBuf, String>;
 
-    fn check_agent_contents(&self, source_files: &[SourceFile], current_dir: &Path)
-        -> Result<bool>;
-
-    fn check_symlink(&self, current_dir: &Path) -> Result<bool>;
-
     fn gitignore_patterns(&self) -> Vec<String>;
 
-    fn generate_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>>;
+    fn generate_symlink(&self, current_dir: &Path) -> Vec<Operation>;
 
     fn uses_inlined_symlink(&self) -> bool {
         false
     }
 
-    fn generate_inlined_symlink(&self, _current_dir: &Path) -> Result<Vec<PathBuf>> {
-        Ok(vec![])
+    fn generate_inlined_symlink(&self, _current_dir: &Path) -> Vec<Operation> {
+        vec![]
     }
 
-    fn check_inlined_symlink(&self, _current_dir: &Path) -> Result<bool> {
-        Ok(true)
+    /// Returns the instruction file that holds the managed block when
+    /// `output_mode: managed-block` is set. The default returns `None` (the agent's
+    /// files are always owned by ai-rules).
+    fn managed_block_file(&self) -> Option<&str> {
+        None
     }
 
     fn mcp_generator(&self) -> Option<Box<dyn McpGeneratorTrait>> {
@@ -53,4 +70,15 @@ pub trait AgentRuleGenerator {
     fn skills_generator(&self) -> Option<Box<dyn SkillsGeneratorTrait>> {
         None
     }
+
+    /// Returns where `ai-rules generate --user` writes this agent's user-level files.
+    /// The default returns `None` (no user-level support).
+    fn user_scope(&self) -> Option<UserScopeTarget> {
+        None
+    }
+
+    /// Returns the agent's native file for personal rules, if it has one.
+    fn local_rules_output(&self) -> Option<LocalRulesOutput> {
+        None
+    }
 }
@@ -1,10 +1,9 @@
 use crate::agents::rule_generator::AgentRuleGenerator;
 use crate::models::SourceFile;
 use crate::operations::generate_all_rule_references;
-use crate::utils::file_utils::{
-    check_agents_md_symlink, check_inlined_file_symlink, create_symlink_to_agents_md,
-    create_symlink_to_inlined_file,
-};
+use crate::operations::managed_block::{has_managed_block, remove_managed_block};
+use crate::utils::file_utils::{symlink_to_agents_md, symlink_to_inlined_file};
+use crate::utils::plan::Operation;
 use anyhow::Result;
 use std::collections::HashMap;
 use std::fs;
@@ -29,7 +28,7 @@ impl AgentRuleGenerator for SingleFileBasedGenerator {
         &self.name
     }
 
-    fn clean(&self, current_dir: &Path) -> Result<()> {
+    fn clean(&self, current_dir: &Path) -> Result<Vec<Operation>> {
         clean_generated_files(current_dir, &self.output_filename)
     }
 
@@ -41,59 +40,51 @@ impl AgentRuleGenerator for SingleFileBasedGenerator {
         generate_agent_file_contents(source_files, current_dir, &self.output_filename)
     }
 
-    fn check_agent_contents(
-        &self,
-        source_files: &[SourceFile],
-        current_dir: &Path,
-    ) -> Result<bool> {
-        check_in_sync(source_files, current_dir, &self.output_filename)
-    }
-
-    fn check_symlink(&self, current_dir: &Path) -> Result<bool> {
-        let output_file = current_dir.join(&self.output_filename);
-        check_agents_md_symlink(current_dir, &output_file)
-    }
-
     fn gitignore_patterns(&self) -> Vec<String> {
         vec![self.output_filename.clone()]
     }
 
-    fn generate_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        let success = create_symlink_to_agents_md(current_dir, Path::new(&self.output_filename))?;
-        if success {
-            Ok(vec![current_dir.join(&self.output_filename)])
-        } else {
-            Ok(vec![])
-        }
+    fn generate_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        symlink_to_agents_md(current_dir, Path::new(&self.output_filename))
+            .into_iter()
+            .collect()
     }
 
     fn uses_inlined_symlink(&self) -> bool {
         true
     }
 
-    fn generate_inlined_symlink(&self, current_dir: &Path) -> Result<Vec<PathBuf>> {
-        let success =
-            create_symlink_to_inlined_file(current_dir, Path::new(&self.output_filename))?;
-        if success {
-            Ok(vec![current_dir.join(&self.output_filename)])
-        } else {
-            Ok(vec![])
-        }
+    fn managed_block_file(&self) -> Option<&str> {
+        Some(&self.output_filename)
     }
 
-    fn check_inlined_symlink(&self, current_dir: &Path) -> Result<bool> {
-        let output_file = current_dir.join(&self.output_filename);
-        check_inlined_file_symlink(current_dir, &output_file)
+    fn generate_inlined_symlink(&self, current_dir: &Path) -> Vec<Operation> {
+        vec![symlink_to_inlined_file(
+            current_dir,
+            Path::new(&self.output_filename),
+        )]
     }
 }
 
-pub fn clean_generated_files(current_dir: &Path, output_filename: &str) -> Result<()> {
+/// Removes the agent file. A regular file with a managed block only loses the block,
+/// so hand-written text around it survives.
+pub fn clean_generated_files(current_dir: &Path, output_filename: &str) -> Result<Vec<Operation>> {
     let output_file = current_dir.join(output_filename);
+    if !output_file.is_symlink() && output_file.is_file() {
+        let content = fs::read_to_string(&output_file)?;
+        if has_managed_block(&content) {
+            let rest = remove_managed_block(&content);
+            if rest.trim().is_empty() {
+                return Ok(vec![Operation::delete(output_file)]);
+            }
+            return Ok(vec![Operation::write(output_file, rest)]);
+        }
+    }
     // Check if file exists OR if it's a symlink (even if broken)
     if output_file.exists() || output_file.is_symlink() {
-        fs::remove_file(&output_file)?;
+        return Ok(vec![Operation::delete(output_file)]);
     }
-    Ok(())
+    Ok(Vec::new())
 }
 
 pub fn generate_agent_file_contents(
@@ -112,30 +103,11 @@ pub fn generate_agent_file_contents(
     agent_files
 }
 
-pub fn check_in_sync(
-    source_files: &[SourceFile],
-    current_dir: &Path,
-    output_filename: &str,
-) -> Result<bool> {
-    let file_path = current_dir.join(output_filename);
-
-    if source_files.is_empty() {
-        return Ok(!file_path.exists());
-    }
-    if !file_path.exists() {
-        return Ok(false);
-    }
-    let expected_files = generate_agent_file_contents(source_files, current_dir, output_filename);
-    let empty_string = String::new();
-    let expected_content = expected_files.get(&file_path).unwrap_or(&empty_string);
-    let actual_content = fs::read_to_string(&file_path)?;
-
-    Ok(actual_content == *expected_content)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::file_utils::write_operations;
+    use crate::utils::plan::PlannedChange;
     use crate::utils::test_utils::helpers::*;
     use tempfile::TempDir;
 
@@ -143,9 +115,9 @@ mod tests {
     fn test_clean_generated_files_non_existing() {
         let temp_dir = TempDir::new().unwrap();
 
-        let result = clean_generated_files(temp_dir.path(), "CLAUDE.md");
+        let removals = clean_generated_files(temp_dir.path(), "CLAUDE.md").unwrap();
+        apply_operations(temp_dir.path(), removals);
 
-        assert!(result.is_ok());
         assert_file_not_exists(temp_dir.path(), "CLAUDE.md");
     }
 
@@ -156,12 +128,28 @@ mod tests {
         create_file(temp_dir.path(), "CLAUDE.md", "existing content");
         assert_file_exists(temp_dir.path(), "CLAUDE.md");
 
-        let result = clean_generated_files(temp_dir.path(), "CLAUDE.md");
+        let removals = clean_generated_files(temp_dir.path(), "CLAUDE.md").unwrap();
+        apply_operations(temp_dir.path(), removals);
 
-        assert!(result.is_ok());
         assert_file_not_exists(temp_dir.path(), "CLAUDE.md");
     }
 
+    #[test]
+    fn test_clean_generated_files_keeps_text_around_managed_block() {
+        let temp_dir = TempDir::new().unwrap();
+
+        create_file(
+            temp_dir.path(),
+            "CLAUDE.md",
+            "# Notes\n\n<!-- ai-rules:begin -->\nRule\n<!-- ai-rules:end -->\n",
+        );
+
+        let removals = clean_generated_files(temp_dir.path(), "CLAUDE.md").unwrap();
+        apply_operations(temp_dir.path(), removals);
+
+        assert_file_content(temp_dir.path(), "CLAUDE.md", "# Notes\n");
+    }
+
     #[test]
     fn test_generate_agent_file_contents_empty() {
         let temp_dir = TempDir::new().unwrap();
@@ -275,13 +263,28 @@ mod tests {
         );
     }
 
+    /// What regenerating `output_filename` would change: its cleanup, then its contents.
+    fn agent_file_changes(
+        source_files: &[SourceFile],
+        current_dir: &Path,
+        output_filename: &str,
+    ) -> Vec<PlannedChange> {
+        let mut operations = clean_generated_files(current_dir, output_filename).unwrap();
+        operations.extend(write_operations(generate_agent_file_contents(
+            source_files,
+            current_dir,
+            output_filename,
+        )));
+        planned_changes(operations)
+    }
+
     #[test]
     fn test_check_in_sync_empty_source_files_no_file() {
         let temp_dir = TempDir::new().unwrap();
 
-        let result = check_in_sync(&[], temp_dir.path(), "CLAUDE.md").unwrap();
+        let changes = agent_file_changes(&[], temp_dir.path(), "CLAUDE.md");
 
-        assert!(result);
+        assert!(changes.is_empty());
     }
 
     #[test]
@@ -290,9 +293,9 @@ mod tests {
 
         create_file(temp_dir.path(), "CLAUDE.md", "stale content");
 
-        let result = check_in_sync(&[], temp_dir.path(), "CLAUDE.md").unwrap();
+        let changes = agent_file_changes(&[], temp_dir.path(), "CLAUDE.md");
 
-        assert!(!result);
+        assert!(!changes.is_empty());
     }
 
     #[test]
@@ -306,9 +309,9 @@ mod tests {
             "rule1 body",
         )];
 
-        let result = check_in_sync(&source_files, temp_dir.path(), "CLAUDE.md").unwrap();
+        let changes = agent_file_changes(&source_files, temp_dir.path(), "CLAUDE.md");
 
-        assert!(!result)
+        assert!(!changes.is_empty())
     }
 
     #[test]
@@ -324,9 +327,9 @@ mod tests {
 
         create_file(temp_dir.path(), "CLAUDE.md", "wrong content");
 
-        let result = check_in_sync(&source_files, temp_dir.path(), "CLAUDE.md").unwrap();
+        let changes = agent_file_changes(&source_files, temp_dir.path(), "CLAUDE.md");
 
-        assert!(!result);
+        assert!(!changes.is_empty());
     }
 
     #[test]
@@ -352,22 +355,39 @@ mod tests {
         let expected_content = "@ai-rules/.generated-ai-rules/ai-rules-generated-always1.md\n\n@ai-rules/.generated-ai-rules/ai-rules-generated-optional.md\n";
         create_file(temp_dir.path(), "CLAUDE.md", expected_content);
 
-        let result = check_in_sync(&source_files, temp_dir.path(), "CLAUDE.md").unwrap();
+        let changes = agent_file_changes(&source_files, temp_dir.path(), "CLAUDE.md");
 
-        assert!(result);
+        assert!(changes.is_empty());
     }
 
     #[test]
-    fn test_single_file_generator_check_symlink_with_correct_symlink() {
+    fn test_single_file_generator_generate_symlink_to_agents_md() {
         let generator = SingleFileBasedGenerator::new("test", "CLAUDE.md");
         let temp_dir = TempDir::new().unwrap();
 
+        assert!(generator.generate_symlink(temp_dir.path()).is_empty());
+
         create_file(temp_dir.path(), "ai-rules/AGENTS.md", "# Source content");
 
-        let result = generator.generate_symlink(temp_dir.path());
-        assert!(result.is_ok());
+        assert_eq!(
+            generator.generate_symlink(temp_dir.path()),
+            vec![Operation::symlink(
+                temp_dir.path().join("CLAUDE.md"),
+                "ai-rules/AGENTS.md"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_single_file_generator_check_symlink_with_correct_symlink() {
+        let generator = SingleFileBasedGenerator::new("test", "CLAUDE.md");
+        let temp_dir = TempDir::new().unwrap();
+
+        create_file(temp_dir.path(), "ai-rules/AGENTS.md", "# Source content");
+        apply_operations(temp_dir.path(), generator.generate_symlink(temp_dir.path()));
 
-        let result = generator.check_symlink(temp_dir.path()).unwrap();
-        assert!(result);
+        let mut operations = generator.clean(temp_dir.path()).unwrap();
+        operations.extend(generator.generate_symlink(temp_dir.path()));
+        assert!(planned_changes(operations).is_empty());
     }
 }
@@ -1,9 +1,9 @@
+use crate::utils::plan::Operation;
 use anyhow::Result;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 pub trait SkillsGeneratorTrait {
-    fn generate_skills(&self, current_dir: &Path) -> Result<Vec<PathBuf>>;
-    fn clean_skills(&self, current_dir: &Path) -> Result<()>;
-    fn check_skills(&self, current_dir: &Path) -> Result<bool>;
+    fn generate_skills(&self, current_dir: &Path) -> Result<Vec<Operation>>;
+    fn clean_skills(&self, current_dir: &Path) -> Result<Vec<Operation>>;
     fn skills_gitignore_patterns(&self) -> Vec<String>;
 }
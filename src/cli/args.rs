@@ -1,4 +1,8 @@
-use clap::{Args, Parser, Subcommand};
+use crate::config::{LinkModes, LocalRulesMode, OutputMode};
+use crate::operations::local_edits::LocalEditPolicy;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(
@@ -30,22 +34,41 @@ pub enum Commands {
     Generate(GenerateArgs),
     /// Show status of AI rules (i.e. if they are in sync)
     Status(StatusArgs),
+    /// Show a diff of what generate would change
+    Diff(DiffArgs),
+    /// Regenerate whenever the files in ai-rules/ change
+    Watch(WatchArgs),
+    /// Check every source file for mistakes without writing anything
+    Validate(ValidateArgs),
+    /// Check rule content for broken links, duplicates and other quality problems
+    Lint(LintArgs),
     /// Clean up generated files
     Clean(CleanArgs),
+    /// Move git imports to the latest commit of their ref and update ai-rules.lock
+    Update(UpdateArgs),
+    /// Put back files that generate or clean removed or overwrote
+    Restore(RestoreArgs),
     /// List all supported coding agents
     ListAgents,
 }
 
-#[derive(Args)]
+#[derive(Args, Default)]
 #[command(after_help = "Examples:
   ai-rules generate                           # Generate using config file settings (or all default values if no config file)
   ai-rules generate --agents claude,cursor    # Generate for specific agents only
   ai-rules generate --agents claude,cursor --nested-depth 5        # Specific agents in nested directories
+  ai-rules generate --var test_command=\"pnpm test\"                # Override a template variable
+  ai-rules generate --profile backend                               # Use the 'backend' profile from the config file
+  ai-rules generate --user                                          # Generate personal rules into ~/.claude, ~/.codex and ~/.gemini
+  ai-rules generate --force                                         # Overwrite generated files that were edited by hand
+  ai-rules generate --adopt                                         # Move hand edits of MCP files into ai-rules/ first
+  ai-rules generate --dry-run                                       # Show what would be written and removed
 
 Configuration Precedence (highest to lowest):
   1. CLI options (--agents, --nested-depth, --gitignore)
-  2. Config file: ai-rules/ai-rules-config.yaml
-  3. Default values (all agents, depth 0, generated files are NOT git ignored)
+  2. Selected profile (--profile)
+  3. Config file: ai-rules/ai-rules-config.yaml
+  4. Default values (all agents, depth 0, generated files are NOT git ignored)
 
 💡 Tip: Run 'ai-rules status' first to check sync status")]
 pub struct GenerateArgs {
@@ -67,9 +90,40 @@ pub struct GenerateArgs {
         help = "Maximum nested directory depth to traverse (0 = current directory only)"
     )]
     pub nested_depth: Option<usize>,
+    #[arg(
+        long = "var",
+        value_name = "key=value",
+        help = "Set a template variable, overriding the config file (repeatable)"
+    )]
+    pub vars: Vec<String>,
+    #[arg(long, help = "Use a named profile from the config file")]
+    pub profile: Option<String>,
+    #[arg(
+        long,
+        conflicts_with_all = ["gitignore", "no_gitignore", "nested_depth", "vars", "profile", "force", "adopt"],
+        help = "Generate personal rules from $XDG_CONFIG_HOME/ai-rules into user-level agent files"
+    )]
+    pub user: bool,
+    #[arg(
+        long,
+        conflicts_with = "adopt",
+        help = "Overwrite generated files that were edited since the last generate"
+    )]
+    pub force: bool,
+    #[arg(
+        long,
+        help = "Move edits of generated MCP and config files into ai-rules/ before generating"
+    )]
+    pub adopt: bool,
+    #[arg(
+        long,
+        conflicts_with = "adopt",
+        help = "Show the files generate would create, update and remove without changing anything"
+    )]
+    pub dry_run: bool,
 }
 
-#[derive(Args)]
+#[derive(Args, Default, Clone)]
 pub struct NestedDepthArgs {
     #[arg(
         long,
@@ -78,16 +132,23 @@ pub struct NestedDepthArgs {
     pub nested_depth: Option<usize>,
 }
 
-#[derive(Args)]
+#[derive(Args, Default)]
 #[command(after_help = "Examples:
   ai-rules status                             # Check status using config file settings (or default values if no config file)
   ai-rules status --agents claude,cursor     # Check status for specific agents only
   ai-rules status --nested-depth 2           # Check status in nested directories
+  ai-rules status --var test_command=\"pnpm test\"  # Check against overridden template variables
+  ai-rules status --profile backend          # Check the files generated with the 'backend' profile
+  ai-rules status --user                     # Check user-level files generated with 'generate --user'
+  ai-rules status --format json              # Print which files are out of sync, and why, as JSON
+  ai-rules status --format github            # Annotate the files to fix in a GitHub Actions run
+  ai-rules status --fix                      # Regenerate only what is out of sync
 
 Configuration Precedence (highest to lowest):
   1. CLI options (--agents, --nested-depth)
-  2. Config file: ai-rules/ai-rules-config.yaml
-  3. Default values (all agents, depth 0)")]
+  2. Selected profile (--profile)
+  3. Config file: ai-rules/ai-rules-config.yaml
+  4. Default values (all agents, depth 0)")]
 pub struct StatusArgs {
     #[arg(
         long,
@@ -97,12 +158,161 @@ pub struct StatusArgs {
     pub agents: Option<Vec<String>>,
     #[command(flatten)]
     pub nested_depth_args: NestedDepthArgs,
+    #[arg(
+        long = "var",
+        value_name = "key=value",
+        help = "Set a template variable, overriding the config file (repeatable)"
+    )]
+    pub vars: Vec<String>,
+    #[arg(long, help = "Use a named profile from the config file")]
+    pub profile: Option<String>,
+    #[arg(
+        long,
+        conflicts_with_all = ["nested_depth", "vars", "profile"],
+        help = "Check user-level agent files instead of the project"
+    )]
+    pub user: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "human",
+        conflicts_with = "user",
+        help = "Output format of the report"
+    )]
+    pub format: ReportFormat,
+    #[arg(
+        long,
+        conflicts_with_all = ["user", "format"],
+        help = "Regenerate only the directories and agents that are out of sync"
+    )]
+    pub fix: bool,
+}
+
+#[derive(Args, Default)]
+#[command(after_help = "Examples:
+  ai-rules diff                               # Diff using config file settings (or default values if no config file)
+  ai-rules diff --agents claude,cursor       # Diff the files of specific agents only
+  ai-rules diff --nested-depth 2             # Diff nested directories too
+  ai-rules diff --profile backend            # Diff against the 'backend' profile
+
+Exits with status 1 if generate would change anything.
+
+Configuration Precedence (highest to lowest):
+  1. CLI options (--agents, --nested-depth)
+  2. Selected profile (--profile)
+  3. Config file: ai-rules/ai-rules-config.yaml
+  4. Default values (all agents, depth 0)")]
+pub struct DiffArgs {
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated list of agents to diff"
+    )]
+    pub agents: Option<Vec<String>>,
+    #[command(flatten)]
+    pub nested_depth_args: NestedDepthArgs,
+    #[arg(
+        long = "var",
+        value_name = "key=value",
+        help = "Set a template variable, overriding the config file (repeatable)"
+    )]
+    pub vars: Vec<String>,
+    #[arg(long, help = "Use a named profile from the config file")]
+    pub profile: Option<String>,
+}
+
+#[derive(Args, Default, Clone)]
+#[command(after_help = "Examples:
+  ai-rules watch                              # Watch using config file settings (or default values if no config file)
+  ai-rules watch --agents claude,cursor      # Regenerate for specific agents only
+  ai-rules watch --nested-depth 2            # Watch nested ai-rules/ folders too
+  ai-rules watch --poll                      # Check for changes every second instead of using file notifications
+
+Only the folder whose ai-rules/ changed is regenerated, with the nested folders below it.
+Changes to the config file are picked up without restarting.
+
+Configuration Precedence (highest to lowest):
+  1. CLI options (--agents, --nested-depth, --gitignore)
+  2. Selected profile (--profile)
+  3. Config file: ai-rules/ai-rules-config.yaml
+  4. Default values (all agents, depth 0, generated files are NOT git ignored)")]
+pub struct WatchArgs {
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated list of agents to generate rules for"
+    )]
+    pub agents: Option<Vec<String>>,
+    #[arg(long, help = "Add generated file patterns to .gitignore")]
+    pub gitignore: bool,
+    #[command(flatten)]
+    pub nested_depth_args: NestedDepthArgs,
+    #[arg(
+        long = "var",
+        value_name = "key=value",
+        help = "Set a template variable, overriding the config file (repeatable)"
+    )]
+    pub vars: Vec<String>,
+    #[arg(long, help = "Use a named profile from the config file")]
+    pub profile: Option<String>,
+    #[arg(
+        long,
+        help = "Overwrite generated files that were edited since the last generate"
+    )]
+    pub force: bool,
+    #[arg(
+        long,
+        help = "Poll for changes instead of using native file notifications"
+    )]
+    pub poll: bool,
+}
+
+#[derive(Args, Default)]
+#[command(after_help = "Examples:
+  ai-rules validate                           # Validate using config file settings (or default values if no config file)
+  ai-rules validate --nested-depth 2         # Validate nested ai-rules/ folders too
+  ai-rules validate --format github          # Annotate the files to fix in GitHub Actions
+
+Checks rules, commands, skills, mcp.json, the Firebender overlay and the config file.
+Exits with status 1 if there are errors; warnings don't fail.")]
+pub struct ValidateArgs {
+    #[command(flatten)]
+    pub nested_depth_args: NestedDepthArgs,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "human",
+        help = "Output format of the report"
+    )]
+    pub format: ReportFormat,
+}
+
+#[derive(Args, Default)]
+#[command(after_help = "Examples:
+  ai-rules lint                               # Lint using config file settings (or default values if no config file)
+  ai-rules lint --nested-depth 2             # Lint nested ai-rules/ folders too
+  ai-rules lint --format sarif               # Report the problems for code scanning
+
+Checks: broken-link, duplicate-heading, duplicate-paragraph, unmatched-glob,
+vague-description and oversize-rule. Turn them off or change their severity under
+`lint.checks` in ai-rules/ai-rules-config.yaml. Exits with status 1 if there are errors.")]
+pub struct LintArgs {
+    #[command(flatten)]
+    pub nested_depth_args: NestedDepthArgs,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "human",
+        help = "Output format of the report"
+    )]
+    pub format: ReportFormat,
 }
 
 #[derive(Args)]
 #[command(after_help = "Examples:
   ai-rules clean                              # Clean using config file settings (or default values if no config file)
   ai-rules clean --nested-depth 2            # Clean generated files in nested directories
+  ai-rules clean --user                      # Remove user-level files generated with 'generate --user'
 
 Configuration Precedence (highest to lowest):
   1. CLI options (--nested-depth)
@@ -111,19 +321,111 @@ Configuration Precedence (highest to lowest):
 pub struct CleanArgs {
     #[command(flatten)]
     pub nested_depth_args: NestedDepthArgs,
+    #[arg(
+        long,
+        conflicts_with = "nested_depth",
+        help = "Remove user-level agent files generated with --user"
+    )]
+    pub user: bool,
+}
+
+#[derive(Args)]
+#[command(after_help = "Examples:
+  ai-rules update                             # Update the git imports of the current directory
+  ai-rules update --nested-depth 2           # Also update nested ai-rules/ folders
+
+'ai-rules generate' only fetches imports that are missing from ai-rules.lock or the local cache.")]
+pub struct UpdateArgs {
+    #[command(flatten)]
+    pub nested_depth_args: NestedDepthArgs,
+}
+
+#[derive(Args)]
+#[command(after_help = "Examples:
+  ai-rules restore --list                     # List backups, newest last
+  ai-rules restore 20261018T093000Z           # Put back the files of a backup
+  ai-rules restore --user --list              # List backups of the user-level files
+
+'ai-rules generate' and 'ai-rules clean' back up hand-written or edited files they remove or
+overwrite to ai-rules/.generated-ai-rules/backups/.")]
+pub struct RestoreArgs {
+    #[arg(long, conflicts_with = "id", help = "List the backups")]
+    pub list: bool,
+    #[arg(
+        long,
+        help = "Restore user-level files backed up by 'generate --user' or 'clean --user'"
+    )]
+    pub user: bool,
+    #[arg(help = "Backup to restore; lists the backups when omitted")]
+    pub id: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ResolvedGenerateArgs {
     pub agents: Option<Vec<String>>,
     pub command_agents: Option<Vec<String>>,
     pub gitignore: bool,
     pub nested_depth: usize,
+    /// Template variables from the root config file
+    pub variables: BTreeMap<String, String>,
+    /// Template variables passed with `--var`
+    pub var_overrides: BTreeMap<String, String>,
+    /// Rule names to place first in generated output
+    pub rule_order: Vec<String>,
+    /// Tags selected by the active profile
+    pub include_tags: Option<Vec<String>>,
+    /// Whether nested projects inherit ancestor rules by default
+    pub inherit: bool,
+    /// Where personal `*.local.md` rules are written
+    pub local_rules: LocalRulesMode,
+    /// Whether agent files are owned by ai-rules or only hold a managed block
+    pub output_mode: OutputMode,
+    /// Whether outputs are symlinks, copies or hard links, per artifact type
+    pub link_modes: LinkModes,
+    /// What to do with generated files that were edited by hand
+    pub local_edits: LocalEditPolicy,
+    /// Print the planned changes instead of making them
+    pub dry_run: bool,
+    /// Only these directories are generated, each for only the listed agents; all
+    /// when `None`. Used by `status --fix`
+    pub targets: Option<BTreeMap<PathBuf, Vec<String>>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ResolvedStatusArgs {
     pub agents: Option<Vec<String>>,
     pub command_agents: Option<Vec<String>>,
     pub nested_depth: usize,
+    /// Template variables from the root config file
+    pub variables: BTreeMap<String, String>,
+    /// Template variables passed with `--var`
+    pub var_overrides: BTreeMap<String, String>,
+    /// Rule names to place first in generated output
+    pub rule_order: Vec<String>,
+    /// Tags selected by the active profile
+    pub include_tags: Option<Vec<String>>,
+    /// Whether nested projects inherit ancestor rules by default
+    pub inherit: bool,
+    /// Where personal `*.local.md` rules are written
+    pub local_rules: LocalRulesMode,
+    /// Whether agent files are owned by ai-rules or only hold a managed block
+    pub output_mode: OutputMode,
+    /// Whether outputs are symlinks, copies or hard links, per artifact type
+    pub link_modes: LinkModes,
+    pub format: ReportFormat,
+    /// Whether `--fix` regenerates the out of sync outputs, updating .gitignore
+    pub gitignore: bool,
+    pub fix: bool,
+}
+
+/// How `status` prints its report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    #[default]
+    Human,
+    Json,
+    /// Workflow commands that GitHub Actions shows as annotations
+    Github,
+    /// SARIF 2.1.0, for code scanning
+    Sarif,
 }
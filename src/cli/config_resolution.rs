@@ -1,13 +1,48 @@
 use super::args::{
-    GenerateArgs, NestedDepthArgs, ResolvedGenerateArgs, ResolvedStatusArgs, StatusArgs,
+    DiffArgs, GenerateArgs, NestedDepthArgs, ResolvedGenerateArgs, ResolvedStatusArgs, StatusArgs,
+    WatchArgs,
 };
 use crate::config;
+use crate::operations::local_edits::LocalEditPolicy;
+use crate::operations::template_variables::parse_variable_overrides;
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
 
 fn resolve_agents(
     agents: Option<Vec<String>>,
+    profile: Option<&config::Profile>,
     config: Option<&config::Config>,
 ) -> Option<Vec<String>> {
-    agents.or_else(|| config?.agents.clone())
+    agents
+        .or_else(|| profile?.agents.clone())
+        .or_else(|| config?.agents.clone())
+}
+
+fn resolve_profile<'a>(
+    name: Option<&str>,
+    config: Option<&'a config::Config>,
+) -> Result<Option<&'a config::Profile>> {
+    let Some(name) = name else {
+        return Ok(None);
+    };
+    let profiles = config.and_then(|config| config.profiles.as_ref());
+    if let Some(profile) = profiles.and_then(|profiles| profiles.get(name)) {
+        return Ok(Some(profile));
+    }
+
+    let available: Vec<&str> = profiles
+        .map(|profiles| profiles.keys().map(String::as_str).collect())
+        .unwrap_or_default();
+    if available.is_empty() {
+        Err(anyhow!(
+            "Unknown profile '{name}': no profiles are defined in ai-rules-config.yaml"
+        ))
+    } else {
+        Err(anyhow!(
+            "Unknown profile '{name}'. Available profiles: {}",
+            available.join(", ")
+        ))
+    }
 }
 
 fn resolve_nested_depth(
@@ -21,55 +56,147 @@ fn resolve_command_agents(config: Option<&config::Config>) -> Option<Vec<String>
     config?.command_agents.clone()
 }
 
+fn resolve_rule_order(config: Option<&config::Config>) -> Vec<String> {
+    config
+        .and_then(|config| config.rule_order.clone())
+        .unwrap_or_default()
+}
+
+fn resolve_variables(config: Option<&config::Config>) -> BTreeMap<String, String> {
+    config
+        .and_then(|config| config.variables.clone())
+        .unwrap_or_default()
+}
+
+fn resolve_gitignore(gitignore: bool, no_gitignore: bool, config: Option<&config::Config>) -> bool {
+    // Handle gitignore resolution with backward compatibility
+    // Priority: CLI flags > Config file > Default (false)
+    if gitignore {
+        // New --gitignore flag was set
+        true
+    } else if no_gitignore {
+        // Deprecated --no-gitignore flag was set, invert it
+        false
+    } else if let Some(config) = config {
+        // Check config file - new field takes precedence
+        if let Some(gitignore_value) = config.gitignore {
+            gitignore_value
+        } else if let Some(no_gitignore_value) = config.no_gitignore {
+            // Deprecated config field, invert it
+            !no_gitignore_value
+        } else {
+            // No config set, use default
+            false
+        }
+    } else {
+        // No CLI flags and no config, use default
+        false
+    }
+}
+
 impl GenerateArgs {
-    pub fn with_config(self, config: Option<&config::Config>) -> ResolvedGenerateArgs {
-        let agents = resolve_agents(self.agents, config);
+    pub fn with_config(self, config: Option<&config::Config>) -> Result<ResolvedGenerateArgs> {
+        let profile = resolve_profile(self.profile.as_deref(), config)?;
+        let agents = resolve_agents(self.agents, profile, config);
         let command_agents = resolve_command_agents(config);
         let nested_depth = resolve_nested_depth(self.nested_depth, config);
 
-        // Handle gitignore resolution with backward compatibility
-        // Priority: CLI flags > Config file > Default (false)
-        let gitignore = if self.gitignore {
-            // New --gitignore flag was set
-            true
-        } else if self.no_gitignore {
-            // Deprecated --no-gitignore flag was set, invert it
-            false
-        } else if let Some(config) = config {
-            // Check config file - new field takes precedence
-            if let Some(gitignore_value) = config.gitignore {
-                gitignore_value
-            } else if let Some(no_gitignore_value) = config.no_gitignore {
-                // Deprecated config field, invert it
-                !no_gitignore_value
-            } else {
-                // No config set, use default
-                false
-            }
-        } else {
-            // No CLI flags and no config, use default
-            false
-        };
+        let gitignore = resolve_gitignore(self.gitignore, self.no_gitignore, config);
 
-        ResolvedGenerateArgs {
+        Ok(ResolvedGenerateArgs {
             agents,
             command_agents,
             gitignore,
             nested_depth: nested_depth.unwrap_or(0),
+            variables: resolve_variables(config),
+            var_overrides: parse_variable_overrides(&self.vars)?,
+            rule_order: resolve_rule_order(config),
+            include_tags: profile.and_then(|profile| profile.include_tags.clone()),
+            inherit: config.and_then(|config| config.inherit).unwrap_or(false),
+            local_rules: config
+                .and_then(|config| config.local_rules)
+                .unwrap_or_default(),
+            output_mode: config
+                .and_then(|config| config.output_mode)
+                .unwrap_or_default(),
+            link_modes: config
+                .and_then(|config| config.link_mode.as_ref())
+                .map(|link_mode| link_mode.resolve())
+                .unwrap_or_default(),
+            local_edits: if self.force {
+                LocalEditPolicy::Force
+            } else if self.adopt {
+                LocalEditPolicy::Adopt
+            } else {
+                LocalEditPolicy::Refuse
+            },
+            dry_run: self.dry_run,
+            targets: None,
+        })
+    }
+}
+
+impl DiffArgs {
+    /// Resolves like `generate --force --dry-run` with the same options.
+    pub fn with_config(self, config: Option<&config::Config>) -> Result<ResolvedGenerateArgs> {
+        GenerateArgs {
+            agents: self.agents,
+            nested_depth: self.nested_depth_args.nested_depth,
+            vars: self.vars,
+            profile: self.profile,
+            force: true,
+            dry_run: true,
+            ..Default::default()
         }
+        .with_config(config)
+    }
+}
+
+impl WatchArgs {
+    /// Resolves like `generate` with the same options.
+    pub fn with_config(self, config: Option<&config::Config>) -> Result<ResolvedGenerateArgs> {
+        GenerateArgs {
+            agents: self.agents,
+            gitignore: self.gitignore,
+            nested_depth: self.nested_depth_args.nested_depth,
+            vars: self.vars,
+            profile: self.profile,
+            force: self.force,
+            ..Default::default()
+        }
+        .with_config(config)
     }
 }
 
 impl StatusArgs {
-    pub fn with_config(self, config: Option<&config::Config>) -> ResolvedStatusArgs {
-        let agents = resolve_agents(self.agents, config);
+    pub fn with_config(self, config: Option<&config::Config>) -> Result<ResolvedStatusArgs> {
+        let profile = resolve_profile(self.profile.as_deref(), config)?;
+        let agents = resolve_agents(self.agents, profile, config);
         let command_agents = resolve_command_agents(config);
         let nested_depth = self.nested_depth_args.with_config(config);
-        ResolvedStatusArgs {
+        Ok(ResolvedStatusArgs {
             agents,
             command_agents,
             nested_depth,
-        }
+            variables: resolve_variables(config),
+            var_overrides: parse_variable_overrides(&self.vars)?,
+            rule_order: resolve_rule_order(config),
+            include_tags: profile.and_then(|profile| profile.include_tags.clone()),
+            inherit: config.and_then(|config| config.inherit).unwrap_or(false),
+            local_rules: config
+                .and_then(|config| config.local_rules)
+                .unwrap_or_default(),
+            output_mode: config
+                .and_then(|config| config.output_mode)
+                .unwrap_or_default(),
+            link_modes: config
+                .and_then(|config| config.link_mode.as_ref())
+                .map(|link_mode| link_mode.resolve())
+                .unwrap_or_default(),
+            format: self.format,
+            gitignore: resolve_gitignore(false, false, config),
+            fix: self.fix,
+        })
     }
 }
 
@@ -6,8 +6,13 @@ mod tests;
 
 pub use args::*;
 
-use crate::commands::{run_clean, run_generate, run_init, run_list_agents, run_status};
+use crate::commands::{
+    run_clean, run_clean_user, run_diff, run_generate, run_generate_user, run_init, run_lint,
+    run_list_agents, run_restore, run_restore_user, run_status, run_status_user, run_update,
+    run_validate, run_watch,
+};
 use crate::config;
+use crate::operations::user_scope::UserScope;
 use clap::Parser;
 
 const SUMMARY: &str = "Manage AI context rules across different AI coding agents";
@@ -22,22 +27,56 @@ pub fn run_cli() -> anyhow::Result<()> {
 
     let current_dir = std::env::current_dir()?;
 
-    let config = config::load_config(&current_dir)?;
+    // Each command loads the config itself, so commands that don't need it still run
+    // when it is invalid
+    let load_config = || config::load_config(&current_dir);
 
     match cli.command {
         Some(Commands::Init(init_args)) => run_init(&current_dir, init_args),
+        Some(Commands::Generate(args)) if args.user => {
+            run_generate_user(&UserScope::from_env()?, args.agents, args.dry_run)
+        }
         Some(Commands::Generate(args)) => {
-            let final_args = args.with_config(config.as_ref());
+            let final_args = args.with_config(load_config()?.as_ref())?;
             run_generate(&current_dir, final_args)
         }
+        Some(Commands::Status(args)) if args.user => {
+            run_status_user(&UserScope::from_env()?, args.agents)
+        }
         Some(Commands::Status(args)) => {
-            let final_args = args.with_config(config.as_ref());
+            let final_args = args.with_config(load_config()?.as_ref())?;
             run_status(&current_dir, final_args)
         }
-        Some(Commands::Clean(args)) => {
+        Some(Commands::Diff(args)) => {
+            let final_args = args.with_config(load_config()?.as_ref())?;
+            run_diff(&current_dir, final_args)
+        }
+        Some(Commands::Watch(args)) => run_watch(&current_dir, args),
+        Some(Commands::Lint(args)) => {
+            let config = load_config()?;
+            let nested_depth = args.nested_depth_args.with_config(config.as_ref());
+            let lint_config = config.as_ref().and_then(|config| config.lint.as_ref());
+            run_lint(&current_dir, nested_depth, lint_config, args.format)
+        }
+        Some(Commands::Validate(args)) => {
+            // Validate reports an invalid config instead of failing on it
+            let config = load_config().unwrap_or_default();
             let nested_depth = args.nested_depth_args.with_config(config.as_ref());
+            run_validate(&current_dir, nested_depth, args.format)
+        }
+        Some(Commands::Clean(args)) if args.user => run_clean_user(&UserScope::from_env()?),
+        Some(Commands::Clean(args)) => {
+            let nested_depth = args.nested_depth_args.with_config(load_config()?.as_ref());
             run_clean(&current_dir, nested_depth)
         }
+        Some(Commands::Update(args)) => {
+            let nested_depth = args.nested_depth_args.with_config(load_config()?.as_ref());
+            run_update(&current_dir, nested_depth)
+        }
+        Some(Commands::Restore(args)) if args.user => {
+            run_restore_user(&UserScope::from_env()?, args)
+        }
+        Some(Commands::Restore(args)) => run_restore(&current_dir, args),
         Some(Commands::ListAgents) => run_list_agents(),
         None => {
             // If no command is provided and --summary is not used, show help
@@ -11,6 +11,7 @@ fn test_generate_args_with_config_cli_priority() {
         gitignore: Some(false),
         no_gitignore: None,
         nested_depth: Some(5),
+        ..Default::default()
     };
 
     let args = GenerateArgs {
@@ -18,9 +19,10 @@ fn test_generate_args_with_config_cli_priority() {
         gitignore: true,
         no_gitignore: false,
         nested_depth: Some(2),
+        ..Default::default()
     };
 
-    let resolved = args.with_config(Some(&config));
+    let resolved = args.with_config(Some(&config)).unwrap();
 
     assert_eq!(resolved.agents, Some(vec!["claude".to_string()]));
     assert!(resolved.gitignore);
@@ -35,6 +37,7 @@ fn test_generate_args_with_config_uses_config_when_cli_missing() {
         gitignore: Some(true),
         no_gitignore: None,
         nested_depth: Some(3),
+        ..Default::default()
     };
 
     let args = GenerateArgs {
@@ -42,9 +45,10 @@ fn test_generate_args_with_config_uses_config_when_cli_missing() {
         gitignore: false,
         no_gitignore: false,
         nested_depth: None,
+        ..Default::default()
     };
 
-    let resolved = args.with_config(Some(&config));
+    let resolved = args.with_config(Some(&config)).unwrap();
 
     assert_eq!(resolved.agents, Some(vec!["cursor".to_string()]));
     assert!(resolved.gitignore);
@@ -58,9 +62,10 @@ fn test_generate_args_with_config_defaults() {
         gitignore: false,
         no_gitignore: false,
         nested_depth: None,
+        ..Default::default()
     };
 
-    let resolved = args.with_config(None);
+    let resolved = args.with_config(None).unwrap();
 
     assert_eq!(resolved.agents, None);
     assert!(!resolved.gitignore);
@@ -75,6 +80,7 @@ fn test_generate_args_with_config_partial_config() {
         gitignore: None,
         no_gitignore: None,
         nested_depth: None,
+        ..Default::default()
     };
 
     let args = GenerateArgs {
@@ -82,9 +88,10 @@ fn test_generate_args_with_config_partial_config() {
         gitignore: false,
         no_gitignore: false,
         nested_depth: None,
+        ..Default::default()
     };
 
-    let resolved = args.with_config(Some(&config));
+    let resolved = args.with_config(Some(&config)).unwrap();
 
     assert_eq!(resolved.agents, Some(vec!["claude".to_string()]));
     assert!(!resolved.gitignore);
@@ -99,6 +106,7 @@ fn test_nested_depth_args_with_config() {
         gitignore: None,
         no_gitignore: None,
         nested_depth: Some(4),
+        ..Default::default()
     };
 
     let args_with_cli = NestedDepthArgs {
@@ -121,6 +129,7 @@ fn test_nested_depth_explicit_zero_overrides_config() {
         gitignore: None,
         no_gitignore: None,
         nested_depth: Some(5),
+        ..Default::default()
     };
 
     let args = NestedDepthArgs {
@@ -138,6 +147,7 @@ fn test_status_args_with_config_cli_priority() {
         gitignore: None,
         no_gitignore: None,
         nested_depth: Some(5),
+        ..Default::default()
     };
 
     let args = StatusArgs {
@@ -145,9 +155,10 @@ fn test_status_args_with_config_cli_priority() {
         nested_depth_args: NestedDepthArgs {
             nested_depth: Some(2),
         },
+        ..Default::default()
     };
 
-    let resolved = args.with_config(Some(&config));
+    let resolved = args.with_config(Some(&config)).unwrap();
 
     assert_eq!(resolved.agents, Some(vec!["claude".to_string()]));
     assert_eq!(resolved.nested_depth, 2);
@@ -161,14 +172,16 @@ fn test_status_args_with_config_uses_config_when_cli_missing() {
         gitignore: None,
         no_gitignore: None,
         nested_depth: Some(3),
+        ..Default::default()
     };
 
     let args = StatusArgs {
         agents: None,
         nested_depth_args: NestedDepthArgs { nested_depth: None },
+        ..Default::default()
     };
 
-    let resolved = args.with_config(Some(&config));
+    let resolved = args.with_config(Some(&config)).unwrap();
 
     assert_eq!(resolved.agents, Some(vec!["cursor".to_string()]));
     assert_eq!(resolved.nested_depth, 3);
@@ -179,9 +192,10 @@ fn test_status_args_with_config_defaults() {
     let args = StatusArgs {
         agents: None,
         nested_depth_args: NestedDepthArgs { nested_depth: None },
+        ..Default::default()
     };
 
-    let resolved = args.with_config(None);
+    let resolved = args.with_config(None).unwrap();
 
     assert_eq!(resolved.agents, None);
     assert_eq!(resolved.nested_depth, 0);
@@ -195,6 +209,7 @@ fn test_generate_args_backward_compat_no_gitignore_config() {
         gitignore: None,
         no_gitignore: Some(true),
         nested_depth: None,
+        ..Default::default()
     };
 
     let args = GenerateArgs {
@@ -202,9 +217,10 @@ fn test_generate_args_backward_compat_no_gitignore_config() {
         gitignore: false,
         no_gitignore: false,
         nested_depth: None,
+        ..Default::default()
     };
 
-    let resolved = args.with_config(Some(&config));
+    let resolved = args.with_config(Some(&config)).unwrap();
 
     assert!(!resolved.gitignore);
 }
@@ -217,6 +233,7 @@ fn test_generate_args_backward_compat_no_gitignore_cli() {
         gitignore: Some(true),
         no_gitignore: None,
         nested_depth: None,
+        ..Default::default()
     };
 
     let args = GenerateArgs {
@@ -224,9 +241,10 @@ fn test_generate_args_backward_compat_no_gitignore_cli() {
         gitignore: false,
         no_gitignore: true,
         nested_depth: None,
+        ..Default::default()
     };
 
-    let resolved = args.with_config(Some(&config));
+    let resolved = args.with_config(Some(&config)).unwrap();
 
     assert!(!resolved.gitignore);
 }
@@ -239,6 +257,7 @@ fn test_generate_args_new_gitignore_flag_overrides_old() {
         gitignore: None,
         no_gitignore: None,
         nested_depth: None,
+        ..Default::default()
     };
 
     let args = GenerateArgs {
@@ -246,9 +265,104 @@ fn test_generate_args_new_gitignore_flag_overrides_old() {
         gitignore: true,
         no_gitignore: true,
         nested_depth: None,
+        ..Default::default()
     };
 
-    let resolved = args.with_config(Some(&config));
+    let resolved = args.with_config(Some(&config)).unwrap();
 
     assert!(resolved.gitignore);
 }
+
+#[test]
+fn test_generate_args_resolves_template_variables() {
+    let config = config::Config {
+        variables: Some(
+            [("test_command".to_string(), "cargo test".to_string())]
+                .into_iter()
+                .collect(),
+        ),
+        ..Default::default()
+    };
+
+    let args = GenerateArgs {
+        vars: vec!["lint=clippy".to_string()],
+        ..Default::default()
+    };
+
+    let resolved = args.with_config(Some(&config)).unwrap();
+
+    assert_eq!(resolved.variables["test_command"], "cargo test");
+    assert_eq!(resolved.var_overrides["lint"], "clippy");
+}
+
+#[test]
+fn test_status_args_rejects_invalid_var() {
+    let args = StatusArgs {
+        vars: vec!["missing-equals".to_string()],
+        ..Default::default()
+    };
+
+    assert!(args.with_config(None).is_err());
+}
+
+#[test]
+fn test_generate_args_with_profile() {
+    let config = config::Config {
+        agents: Some(vec!["cursor".to_string()]),
+        profiles: Some(
+            [(
+                "backend".to_string(),
+                config::Profile {
+                    include_tags: Some(vec!["rust".to_string()]),
+                    agents: Some(vec!["claude".to_string(), "codex".to_string()]),
+                },
+            )]
+            .into_iter()
+            .collect(),
+        ),
+        ..Default::default()
+    };
+
+    let args = GenerateArgs {
+        profile: Some("backend".to_string()),
+        ..Default::default()
+    };
+    let resolved = args.with_config(Some(&config)).unwrap();
+    assert_eq!(
+        resolved.agents,
+        Some(vec!["claude".to_string(), "codex".to_string()])
+    );
+    assert_eq!(resolved.include_tags, Some(vec!["rust".to_string()]));
+
+    let args = GenerateArgs {
+        agents: Some(vec!["gemini".to_string()]),
+        profile: Some("backend".to_string()),
+        ..Default::default()
+    };
+    let resolved = args.with_config(Some(&config)).unwrap();
+    assert_eq!(resolved.agents, Some(vec!["gemini".to_string()]));
+
+    let args = StatusArgs {
+        profile: Some("mobile".to_string()),
+        ..Default::default()
+    };
+    let error = args.with_config(Some(&config)).unwrap_err().to_string();
+    assert!(error.contains("Available profiles: backend"), "{error}");
+}
+
+#[test]
+fn test_user_rejects_project_only_flags() {
+    use clap::Parser;
+
+    for args in [
+        &["ai-rules", "generate", "--user", "--var", "a=b"][..],
+        &["ai-rules", "generate", "--user", "--profile", "backend"],
+        &["ai-rules", "generate", "--user", "--force"],
+        &["ai-rules", "generate", "--user", "--nested-depth", "2"],
+        &["ai-rules", "status", "--user", "--profile", "backend"],
+        &["ai-rules", "clean", "--user", "--nested-depth", "2"],
+    ] {
+        assert!(Cli::try_parse_from(args).is_err(), "{args:?}");
+    }
+    assert!(Cli::try_parse_from(["ai-rules", "generate", "--user", "--agents", "claude"]).is_ok());
+}
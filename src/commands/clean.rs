@@ -1,6 +1,7 @@
 use crate::agents::AgentToolRegistry;
 use crate::operations;
-use crate::utils::file_utils;
+use crate::utils::plan::Plan;
+use crate::utils::{file_utils, transaction};
 use anyhow::Result;
 use std::path::Path;
 
@@ -11,11 +12,12 @@ pub fn run_clean(current_dir: &Path, nested_depth: usize) -> Result<()> {
     let agents: Vec<String> = registry.get_all_tool_names();
     let filter = file_utils::DirectoryFilter::from_project_root(current_dir);
 
+    let mut plan = Plan::default();
     file_utils::traverse_project_directories(current_dir, nested_depth, 0, &filter, &mut |dir| {
-        operations::clean_generated_files(dir, &agents, &registry)
+        operations::clean_generated_files(dir, &agents, &registry, &mut plan)
     })?;
 
-    Ok(())
+    transaction::apply_with_backup(current_dir, &plan, "clean")
 }
 
 #[cfg(test)]
@@ -162,6 +164,7 @@ Test rule content"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: 2,
+                ..Default::default()
             },
         );
         assert!(generate_result.is_ok());
@@ -211,6 +214,7 @@ Test rule content"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: CLEAN_NESTED_DEPTH,
+                ..Default::default()
             },
         );
         assert!(generate_result.is_ok());
@@ -332,6 +336,7 @@ Test rule content"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: CLEAN_NESTED_DEPTH,
+                ..Default::default()
             },
         );
         assert!(generate_result.is_ok());
@@ -378,6 +383,7 @@ Test rule content"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: CLEAN_NESTED_DEPTH,
+                ..Default::default()
             },
         );
         assert!(generate_result.is_ok());
@@ -0,0 +1,302 @@
+use crate::cli::ResolvedGenerateArgs;
+use crate::commands::generate::plan_generate;
+use crate::commands::ExitStatus;
+use crate::constants::{AI_RULE_SOURCE_DIR, GENERATED_RULE_BODY_DIR, MANIFEST_FILENAME};
+use crate::operations::manifest::{read_manifest, Manifest};
+use crate::utils::diff_utils::{json_key_diff, unified_diff};
+use crate::utils::file_utils::{traverse_project_directories, DirectoryFilter};
+use crate::utils::plan::{PlannedChange, Snapshot};
+use crate::utils::print_utils::print_success;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Heading for outputs no single agent owns, like rule bodies and `.gitignore`.
+const SHARED_GROUP: &str = "shared";
+
+pub fn run_diff(current_dir: &Path, args: ResolvedGenerateArgs) -> Result<()> {
+    let diff = diff_project(current_dir, args)?;
+    if diff.is_empty() {
+        print_success("No differences: the generated files are up to date");
+        return Ok(());
+    }
+    print!("{diff}");
+    println!("\n💡 Run 'ai-rules generate' to apply these changes");
+    Err(ExitStatus(1).into())
+}
+
+/// A change `generate` would make, with the project directory it belongs to and the
+/// agents whose manifest entries cover it (none for shared outputs).
+pub struct OwnedChange {
+    pub dir: PathBuf,
+    pub agents: Vec<String>,
+    pub change: PlannedChange,
+}
+
+/// Works out what `generate` would change and who owns each change. The manifests
+/// themselves are left out.
+pub fn plan_owned_changes(
+    current_dir: &Path,
+    args: ResolvedGenerateArgs,
+) -> Result<Vec<OwnedChange>> {
+    let nested_depth = args.nested_depth;
+    let changes = plan_generate(current_dir, args)?;
+
+    let mut dirs = Vec::new();
+    let filter = DirectoryFilter::from_project_root(current_dir);
+    traverse_project_directories(current_dir, nested_depth, 0, &filter, &mut |dir| {
+        dirs.push(dir.to_path_buf());
+        Ok(())
+    })?;
+
+    // Owners come from the manifest on disk and the one generate would write
+    let manifest_path = Path::new(AI_RULE_SOURCE_DIR)
+        .join(GENERATED_RULE_BODY_DIR)
+        .join(MANIFEST_FILENAME);
+    let mut manifests: BTreeMap<&Path, Vec<Manifest>> = BTreeMap::new();
+    for dir in &dirs {
+        manifests
+            .entry(dir)
+            .or_default()
+            .extend(read_manifest(dir)?);
+    }
+    for change in &changes {
+        let Some(Snapshot::File(contents)) = &change.after else {
+            continue;
+        };
+        if !change.path.ends_with(&manifest_path) {
+            continue;
+        }
+        let dir = change
+            .path
+            .ancestors()
+            .nth(manifest_path.components().count());
+        if let Some(existing) = dir.and_then(|dir| manifests.get_mut(dir)) {
+            let manifest: Manifest = serde_json::from_slice(contents)
+                .with_context(|| format!("Failed to parse {}", change.path.display()))?;
+            existing.push(manifest);
+        }
+    }
+
+    let mut owned = Vec::new();
+    for change in changes {
+        if change.path.ends_with(&manifest_path) {
+            continue;
+        }
+        let dir = dirs
+            .iter()
+            .filter(|dir| change.path.starts_with(dir))
+            .max_by_key(|dir| dir.components().count())
+            .map_or(current_dir, PathBuf::as_path);
+        let agents: BTreeSet<String> = manifests
+            .get(dir)
+            .into_iter()
+            .flatten()
+            .flat_map(|manifest| &manifest.agents)
+            .filter(|(_, entries)| {
+                entries
+                    .iter()
+                    .any(|entry| change.path.starts_with(dir.join(entry.path())))
+            })
+            .map(|(agent, _)| agent.clone())
+            .collect();
+        owned.push(OwnedChange {
+            dir: dir.to_path_buf(),
+            agents: agents.into_iter().collect(),
+            change,
+        });
+    }
+    Ok(owned)
+}
+
+/// Renders what `generate` would change, grouped by directory and agent. Empty if
+/// nothing would change.
+pub fn diff_project(current_dir: &Path, args: ResolvedGenerateArgs) -> Result<String> {
+    let changes = plan_owned_changes(current_dir, args)?;
+    let mut sections: BTreeMap<&Path, BTreeMap<String, Vec<&PlannedChange>>> = BTreeMap::new();
+    for owned in &changes {
+        let group = match owned.agents.is_empty() {
+            true => SHARED_GROUP.to_string(),
+            false => owned.agents.join(", "),
+        };
+        sections
+            .entry(&owned.dir)
+            .or_default()
+            .entry(group)
+            .or_default()
+            .push(&owned.change);
+    }
+
+    let mut output = String::new();
+    for (dir, groups) in sections {
+        let dir_label = match dir.strip_prefix(current_dir) {
+            Ok(relative) if relative != Path::new("") => relative.display().to_string(),
+            _ => ".".to_string(),
+        };
+        for (group, changes) in groups {
+            output.push_str(&format!("=== {dir_label}: {group} ===\n"));
+            for change in changes {
+                let label = change
+                    .path
+                    .strip_prefix(current_dir)
+                    .unwrap_or(&change.path);
+                output.push_str(&render_change(label, change));
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Files and symlinks inside `snapshot`, keyed by path relative to it.
+fn leaves<'a>(snapshot: &'a Snapshot, path: PathBuf, leaves: &mut BTreeMap<PathBuf, &'a Snapshot>) {
+    match snapshot {
+        Snapshot::Directory(entries) => {
+            for (name, entry) in entries {
+                self::leaves(entry, path.join(name), leaves);
+            }
+        }
+        _ => {
+            leaves.insert(path, snapshot);
+        }
+    }
+}
+
+fn render_change(label: &Path, change: &PlannedChange) -> String {
+    let (mut before, mut after) = (BTreeMap::new(), BTreeMap::new());
+    if let Some(snapshot) = &change.before {
+        leaves(snapshot, PathBuf::new(), &mut before);
+    }
+    if let Some(snapshot) = &change.after {
+        leaves(snapshot, PathBuf::new(), &mut after);
+    }
+    let paths: BTreeSet<&PathBuf> = before.keys().chain(after.keys()).collect();
+
+    let mut output = String::new();
+    for path in paths {
+        let (old, new) = (before.get(path).copied(), after.get(path).copied());
+        if old == new {
+            continue;
+        }
+        let label = match path.as_os_str().is_empty() {
+            true => label.to_path_buf(),
+            false => label.join(path),
+        };
+        let old_label = match old {
+            Some(_) => format!("{} (current)", label.display()),
+            None => "/dev/null".to_string(),
+        };
+        let new_label = match new {
+            Some(_) => format!("{} (generated)", label.display()),
+            None => "/dev/null".to_string(),
+        };
+        // Merged JSON files are compared key by key, as their formatting is generate's
+        if let (Some(old_json), Some(new_json)) = (as_json(old), as_json(new)) {
+            let lines = json_key_diff(&old_json, &new_json);
+            if !lines.is_empty() {
+                output.push_str(&format!("--- {old_label}\n+++ {new_label}\n"));
+                output.push_str(&(lines.join("\n") + "\n"));
+            }
+            continue;
+        }
+        output.push_str(&unified_diff(
+            &as_text(old),
+            &as_text(new),
+            &old_label,
+            &new_label,
+        ));
+    }
+    output
+}
+
+fn as_json(snapshot: Option<&Snapshot>) -> Option<serde_json::Value> {
+    match snapshot {
+        Some(Snapshot::File(contents)) => serde_json::from_slice(contents).ok(),
+        _ => None,
+    }
+}
+
+fn as_text(snapshot: Option<&Snapshot>) -> String {
+    match snapshot {
+        Some(Snapshot::File(contents)) => String::from_utf8_lossy(contents).into_owned(),
+        Some(Snapshot::Symlink(target)) => format!("symlink -> {}\n", target.display()),
+        Some(Snapshot::Directory(_)) | None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::run_generate;
+    use crate::operations::local_edits::LocalEditPolicy;
+    use crate::utils::test_utils::helpers::*;
+    use tempfile::TempDir;
+
+    /// Sets the permissions of every folder under `dir`, without following symlinks.
+    fn diff_args(agents: &[&str]) -> ResolvedGenerateArgs {
+        ResolvedGenerateArgs {
+            agents: Some(agents.iter().map(|agent| agent.to_string()).collect()),
+            local_edits: LocalEditPolicy::Force,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_project_shows_changes_per_agent() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        create_file(project, "ai-rules/test.md", "Shared rule");
+        create_file(
+            project,
+            "ai-rules/mcp.json",
+            r#"{"mcpServers": {"db": {"command": "db"}}}"#,
+        );
+        run_generate(project, diff_args(&["claude", "gemini"])).unwrap();
+        assert_eq!(
+            diff_project(project, diff_args(&["claude", "gemini"])).unwrap(),
+            ""
+        );
+
+        create_file(project, "ai-rules/test.md", "Changed rule");
+        create_file(
+            project,
+            "ai-rules/mcp.json",
+            r#"{"mcpServers": {"db": {"command": "db2"}}}"#,
+        );
+        let diff = diff_project(project, diff_args(&["claude", "gemini"])).unwrap();
+
+        assert!(diff.contains("=== .: shared ===\n"));
+        assert!(diff.contains("-Shared rule\n+Changed rule\n"));
+        assert!(diff.contains("=== .: gemini ===\n"));
+        assert!(diff.contains("~ /mcpServers/db/command: \"db\" -> \"db2\"\n"));
+        assert_file_content(
+            project,
+            "ai-rules/.generated-ai-rules/ai-rules-generated-test.md",
+            "Shared rule\n",
+        );
+    }
+
+    #[test]
+    fn test_diff_and_status_leave_the_project_untouched() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("project");
+        create_file(&project, "ai-rules/test.md", "Shared rule");
+        create_file(&project, "ai-rules/commands/review.md", "Review");
+        run_generate(&project, diff_args(&["claude", "cursor"])).unwrap();
+        create_file(&project, "ai-rules/test.md", "Changed rule");
+        create_file(&project, "ai-rules/commands/plan.md", "Plan");
+        let before = Snapshot::read(&project).unwrap();
+
+        let error = run_diff(&project, diff_args(&["claude", "cursor"])).unwrap_err();
+        let status = crate::commands::status::check_project_status(
+            &project,
+            crate::cli::ResolvedStatusArgs {
+                agents: Some(vec!["claude".to_string(), "cursor".to_string()]),
+                ..Default::default()
+            },
+        );
+
+        assert!(matches!(error.downcast_ref(), Some(ExitStatus(1))));
+        assert!(!status.unwrap().agent_statuses["claude"]);
+        assert_eq!(Snapshot::read(&project).unwrap(), before);
+    }
+}
@@ -1,13 +1,36 @@
+use crate::agents::rule_generator::AgentRuleGenerator;
 use crate::agents::AgentToolRegistry;
 use crate::cli::ResolvedGenerateArgs;
-use crate::operations::source_reader::detect_symlink_mode;
+use crate::config::{LinkMode, LinkModes, LocalRulesMode, OutputMode};
+use crate::constants::{GENERATED_RULE_BODY_DIR, MANIFEST_FILENAME};
+use crate::operations::command_reader::generate_rendered_command_contents;
+use crate::operations::git_imports::sync_git_imports;
+use crate::operations::local_edits::{
+    adopt_local_edit, describe_local_edit, find_local_edits, LocalEditPolicy,
+};
+use crate::operations::local_rules::generate_local_rules_file;
+use crate::operations::managed_block::{
+    has_managed_block, managed_block_body, managed_file_operations, plan_managed_files,
+};
+use crate::operations::manifest::{
+    build_manifest, count_changes, inputs_hash, manifest_operation, read_manifest, read_outputs,
+};
+use crate::operations::skills_reader::{
+    generate_rendered_skill_contents, link_rendered_skill_resources,
+};
+use crate::operations::source_reader::{
+    find_personal_source_files, find_source_files_with_options, uses_symlink_mode, SourceOptions,
+};
+use crate::operations::template_variables::TemplateVariables;
 use crate::operations::{self, GenerationResult};
 use crate::utils::file_utils::{
-    traverse_project_directories, write_directory_files, DirectoryFilter,
+    materialize_link, traverse_project_directories, write_operations, DirectoryFilter,
 };
+use crate::utils::plan::{Operation, Plan, PlannedChange, Snapshot};
 use crate::utils::print_utils::print_success;
-use anyhow::Result;
-use std::collections::HashMap;
+use crate::utils::transaction;
+use anyhow::{bail, Result};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 pub fn run_generate(current_dir: &Path, args: ResolvedGenerateArgs) -> Result<()> {
@@ -20,108 +43,416 @@ pub fn run_generate(current_dir: &Path, args: ResolvedGenerateArgs) -> Result<()
         args.nested_depth,
         args.gitignore
     );
+    if args.dry_run {
+        print_planned_changes(current_dir, plan_generate(current_dir, args)?);
+        return Ok(());
+    }
+
+    let gitignore = args.gitignore;
+    let generation_result = generate(current_dir, args)?;
+    generation_result.display(current_dir);
+    if gitignore {
+        print_success("Updated .gitignore with generated file patterns");
+    }
+
+    Ok(())
+}
+
+/// Generates like [`run_generate`] without printing the generated files.
+pub fn generate_quietly(
+    current_dir: &Path,
+    args: ResolvedGenerateArgs,
+) -> Result<GenerationResult> {
+    generate(current_dir, args)
+}
+
+/// Works out what `generate` would change, without changing anything. Imports are
+/// read from the checkouts already there and hand edits are left out.
+pub fn plan_generate(current_dir: &Path, args: ResolvedGenerateArgs) -> Result<Vec<PlannedChange>> {
     let registry = AgentToolRegistry::new();
-    let agents = args.agents.unwrap_or_else(|| registry.get_all_tool_names());
+    let (plan, _) = plan_project(current_dir, &args, &registry)?;
+    plan.changes()
+}
+
+/// Fetches imports and handles hand edits in every directory, then applies the plan.
+fn generate(current_dir: &Path, args: ResolvedGenerateArgs) -> Result<GenerationResult> {
+    let registry = AgentToolRegistry::new();
+    let agents = args
+        .agents
+        .clone()
+        .unwrap_or_else(|| registry.get_all_tool_names());
+    let command_agents = args
+        .command_agents
+        .clone()
+        .unwrap_or_else(|| agents.clone());
+    let filter = DirectoryFilter::from_project_root(current_dir);
+    let root_source_options = source_options(&args);
+
+    // Check every directory for hand edits before anything is overwritten
+    let mut edit_agents = agents.clone();
+    edit_agents.extend(command_agents.iter().cloned());
+    let mut refused_edits = Vec::new();
+    let mut lock_files = Vec::new();
+    traverse_project_directories(current_dir, args.nested_depth, 0, &filter, &mut |dir| {
+        let Some(edit_agents) = targeted(&args.targets, dir, &edit_agents) else {
+            return Ok(());
+        };
+        lock_files.extend(sync_git_imports(dir, false)?);
+        let Some(manifest) = read_manifest(dir)? else {
+            return Ok(());
+        };
+        let edits = find_local_edits(dir, &manifest, &edit_agents)?;
+        match args.local_edits {
+            LocalEditPolicy::Force => {}
+            LocalEditPolicy::Adopt => {
+                for edit in &edits {
+                    let source = adopt_local_edit(dir, edit, &registry)?;
+                    print_success(&format!(
+                        "Adopted the edits to {} into {}",
+                        edit.path
+                            .strip_prefix(current_dir)
+                            .unwrap_or(&edit.path)
+                            .display(),
+                        source
+                            .strip_prefix(current_dir)
+                            .unwrap_or(&source)
+                            .display()
+                    ));
+                }
+            }
+            LocalEditPolicy::Refuse if !edits.is_empty() => {
+                let variables =
+                    TemplateVariables::for_directory(dir, &args.variables, &args.var_overrides)?;
+                let source_options = root_source_options.for_directory(dir, variables)?;
+                let source_files = find_source_files_with_options(dir, &source_options)?;
+                for edit in &edits {
+                    refused_edits.push(describe_local_edit(dir, edit, &source_files, &registry));
+                }
+            }
+            LocalEditPolicy::Refuse => {}
+        }
+        Ok(())
+    })?;
+    if !refused_edits.is_empty() {
+        for description in &refused_edits {
+            print!("{description}");
+        }
+        bail!(
+            "{} generated file(s) were edited since the last generate. Rerun with --force to overwrite them or --adopt to move the edits into ai-rules/",
+            refused_edits.len()
+        );
+    }
 
-    let command_agents = args.command_agents.unwrap_or_else(|| agents.clone());
+    let (mut plan, generation_result) = plan_project(current_dir, &args, &registry)?;
+    plan.extend(lock_files)?;
+    // Nothing is written if any change fails, so a failed run leaves the project as it was
+    transaction::apply_with_backup(current_dir, &plan, "generate")?;
+    if !args.gitignore {
+        operations::report_parent_gitignores(current_dir, &registry)?;
+    }
+    Ok(generation_result)
+}
 
+fn source_options(args: &ResolvedGenerateArgs) -> SourceOptions {
+    SourceOptions {
+        rule_order: args.rule_order.clone(),
+        include_tags: args.include_tags.clone(),
+        inherit: args.inherit,
+        local_rules: args.local_rules,
+        ..Default::default()
+    }
+}
+
+/// Plans generating every directory: each one's outputs are cleaned, then the ones
+/// still generated are planned over them.
+fn plan_project(
+    current_dir: &Path,
+    args: &ResolvedGenerateArgs,
+    registry: &AgentToolRegistry,
+) -> Result<(Plan, GenerationResult)> {
+    let agents = args
+        .agents
+        .clone()
+        .unwrap_or_else(|| registry.get_all_tool_names());
+    let command_agents = args
+        .command_agents
+        .clone()
+        .unwrap_or_else(|| agents.clone());
+    let mut edit_agents = agents.clone();
+    edit_agents.extend(command_agents.iter().cloned());
+
+    let mut plan = Plan::default();
     let mut generation_result = GenerationResult::default();
     let filter = DirectoryFilter::from_project_root(current_dir);
+    let root_source_options = source_options(args);
+    let output_options = OutputOptions {
+        mode: args.output_mode,
+        link_modes: args.link_modes,
+    };
 
     traverse_project_directories(current_dir, args.nested_depth, 0, &filter, &mut |dir| {
-        generate_files(
+        let (Some(agents), Some(command_agents), Some(edit_agents)) = (
+            targeted(&args.targets, dir, &agents),
+            targeted(&args.targets, dir, &command_agents),
+            targeted(&args.targets, dir, &edit_agents),
+        ) else {
+            return Ok(());
+        };
+        let variables =
+            TemplateVariables::for_directory(dir, &args.variables, &args.var_overrides)?;
+        let source_options = root_source_options.for_directory(dir, variables)?;
+        let previous_manifest = read_manifest(dir)?;
+        let outputs_before = match &previous_manifest {
+            Some(manifest) => read_outputs(dir, manifest, &edit_agents)?,
+            None => BTreeMap::new(),
+        };
+        let mut dir_result = GenerationResult::default();
+        plan_files(
             dir,
             &agents,
             &command_agents,
-            &registry,
-            &mut generation_result,
-        )
+            registry,
+            &source_options,
+            &output_options,
+            &mut plan,
+            &mut dir_result,
+        )?;
+        let mut manifest = build_manifest(dir, &dir_result, previous_manifest, registry, &plan)?;
+        manifest.inputs = inputs_hash(
+            dir,
+            &agents,
+            &command_agents,
+            &source_options,
+            output_options.mode,
+            output_options.link_modes,
+        )?;
+        plan.push(manifest_operation(dir, &manifest)?)?;
+        dir_result.changes = count_changes(&outputs_before, &manifest, &edit_agents);
+        generation_result.merge(dir_result);
+        Ok(())
     })?;
 
-    generation_result.display(current_dir);
-
-    if args.gitignore {
-        operations::update_project_gitignore(current_dir, &registry, args.nested_depth)?;
-        print_success("Updated .gitignore with generated file patterns");
+    let gitignore = if args.gitignore {
+        Some(operations::update_project_gitignore(
+            current_dir,
+            registry,
+            args.nested_depth,
+            args.output_mode,
+        ))
     } else {
-        operations::remove_gitignore_section(current_dir, &registry)?;
+        operations::remove_gitignore_section(current_dir)?
+    };
+    plan.extend(gitignore)?;
+    Ok((plan, generation_result))
+}
+
+/// The `agents` to generate in `dir`, or `None` if `targets` leaves the directory out.
+fn targeted(
+    targets: &Option<BTreeMap<PathBuf, Vec<String>>>,
+    dir: &Path,
+    agents: &[String],
+) -> Option<Vec<String>> {
+    let Some(targets) = targets else {
+        return Some(agents.to_vec());
+    };
+    let wanted = targets.get(dir)?;
+    Some(
+        agents
+            .iter()
+            .filter(|agent| wanted.contains(agent))
+            .cloned()
+            .collect(),
+    )
+}
+
+/// Prints the changes `generate --dry-run` found, leaving out the manifest.
+pub fn print_planned_changes(current_dir: &Path, mut changes: Vec<PlannedChange>) {
+    let manifest = Path::new(GENERATED_RULE_BODY_DIR).join(MANIFEST_FILENAME);
+    changes.retain(|change| !change.path.ends_with(&manifest));
+    if changes.is_empty() {
+        print_success("Nothing to change: the generated files are up to date");
+        return;
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    println!("Planned changes (dry run, nothing was written):");
+    let relative = |path: &Path| {
+        path.strip_prefix(current_dir)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    for change in &changes {
+        let sign = match (&change.before, &change.after) {
+            (None, _) => '+',
+            (_, None) => '-',
+            _ => '~',
+        };
+        match &change.after {
+            Some(Snapshot::Symlink(target)) => println!(
+                "  {sign} {} -> {}",
+                relative(&change.path),
+                target.display()
+            ),
+            _ => println!("  {sign} {}", relative(&change.path)),
+        }
     }
+}
 
+/// How agent files are written.
+#[derive(Debug, Clone, Copy, Default)]
+struct OutputOptions {
+    mode: OutputMode,
+    link_modes: LinkModes,
+}
+
+/// Plans the links in `operations` and records them, replacing them with copies or hard
+/// links if `mode` asks for it. Removals are planned without being recorded.
+fn add_links(
+    plan: &mut Plan,
+    result: &mut GenerationResult,
+    agent: &str,
+    operations: Vec<Operation>,
+    mode: LinkMode,
+) -> Result<()> {
+    for operation in operations {
+        if let Operation::Delete { .. } = operation {
+            plan.push(operation)?;
+            continue;
+        }
+        let link = operation.path().to_path_buf();
+        plan.push(operation)?;
+        materialize_link(plan, &link, mode)?;
+        result.add_file(agent, link);
+    }
     Ok(())
 }
 
-fn generate_files(
+/// Plans the writes in `operations` and records them as outputs of `agent`.
+fn add_files(
+    plan: &mut Plan,
+    result: &mut GenerationResult,
+    agent: &str,
+    operations: Vec<Operation>,
+) -> Result<()> {
+    for operation in operations {
+        result.add_file(agent, operation.path().to_path_buf());
+        plan.push(operation)?;
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn plan_files(
     current_dir: &Path,
     agents: &[String],
     command_agents: &[String],
     registry: &AgentToolRegistry,
+    source_options: &SourceOptions,
+    output_options: &OutputOptions,
+    plan: &mut Plan,
     result: &mut GenerationResult,
 ) -> Result<()> {
-    operations::clean_generated_files(current_dir, agents, registry)?;
+    let output_mode = output_options.mode;
+    let link_modes = output_options.link_modes;
+    let is_symlink_mode = uses_symlink_mode(current_dir, source_options)?;
+    let source_files = if is_symlink_mode {
+        Vec::new()
+    } else {
+        find_source_files_with_options(current_dir, source_options)?
+    };
+
+    // Managed blocks are placed by the files as they are on disk, before cleaning
+    let managed_files = match output_mode {
+        OutputMode::ManagedBlock => {
+            let body = managed_block_body(current_dir, &source_files, is_symlink_mode)?;
+            plan_managed_files(current_dir, agents, registry, body.as_deref())?
+        }
+        OutputMode::File => Vec::new(),
+    };
+    let is_managed = |tool: &dyn AgentRuleGenerator| {
+        output_mode == OutputMode::ManagedBlock && tool.managed_block_file().is_some()
+    };
+
+    // Earlier outputs are removed unless they are generated again below; only the
+    // files whose content or link target changed are written
+    operations::clean_generated_files(current_dir, agents, registry, plan)?;
+
+    plan.extend(managed_file_operations(&managed_files))?;
+    for file in &managed_files {
+        if file.content.as_deref().is_some_and(has_managed_block) {
+            for agent in &file.agents {
+                result.add_file(agent, file.path.clone());
+            }
+        }
+    }
 
-    if detect_symlink_mode(current_dir) {
+    if is_symlink_mode {
         for agent in agents {
-            if let Some(tool) = registry.get_tool(agent) {
-                let created_symlinks = tool.generate_symlink(current_dir)?;
-                for symlink_path in created_symlinks {
-                    result.add_file(agent, symlink_path);
-                }
+            if let Some(tool) = registry.get_tool(agent).filter(|tool| !is_managed(*tool)) {
+                let symlinks = tool.generate_symlink(current_dir);
+                add_links(plan, result, agent, symlinks, link_modes.rules)?;
             }
         }
-    } else {
-        let source_files = operations::find_source_files(current_dir)?;
-
-        if !source_files.is_empty() {
-            // Generate and write body files first (includes inlined file)
-            let body_files = operations::generate_body_contents(&source_files, current_dir);
-            write_directory_files(&body_files)?;
-
-            // Process agents: symlink-based agents get symlinks, content-based agents get files
-            let mut content_files: HashMap<PathBuf, String> = HashMap::new();
-
-            for agent in agents {
-                if let Some(tool) = registry.get_tool(agent) {
-                    if tool.uses_inlined_symlink() {
-                        let created_symlinks = tool.generate_inlined_symlink(current_dir)?;
-                        for symlink_path in created_symlinks {
-                            result.add_file(agent, symlink_path);
-                        }
-                    } else {
-                        let agent_files = tool.generate_agent_contents(&source_files, current_dir);
-                        for file_path in agent_files.keys() {
-                            result.add_file(agent, file_path.clone());
-                        }
-                        content_files.extend(agent_files);
-                    }
+    } else if !source_files.is_empty() {
+        // Body files first (includes inlined file), as agent symlinks point at them
+        let body_files = operations::generate_body_contents(&source_files, current_dir);
+        plan.extend(write_operations(body_files))?;
+
+        // Process agents: symlink-based agents get symlinks, content-based agents get files
+        for agent in agents {
+            if let Some(tool) = registry.get_tool(agent) {
+                if is_managed(tool) {
+                    continue;
+                }
+                if tool.uses_inlined_symlink() {
+                    let symlinks = tool.generate_inlined_symlink(current_dir);
+                    add_links(plan, result, agent, symlinks, link_modes.rules)?;
+                } else {
+                    let agent_files = tool.generate_agent_contents(&source_files, current_dir);
+                    add_files(plan, result, agent, write_operations(agent_files))?;
                 }
             }
+        }
+    }
 
-            write_directory_files(&content_files)?;
+    if source_options.local_rules == LocalRulesMode::Native {
+        let personal_files = find_personal_source_files(current_dir, source_options)?;
+        for agent in agents {
+            let Some(output) = registry
+                .get_tool(agent)
+                .and_then(|tool| tool.local_rules_output())
+            else {
+                continue;
+            };
+            let local_rules =
+                generate_local_rules_file(current_dir, &output, &source_files, &personal_files);
+            add_files(plan, result, agent, local_rules.into_iter().collect())?;
         }
     }
 
-    let mut mcp_files_to_write: HashMap<PathBuf, String> = HashMap::new();
     for agent in agents {
         if let Some(tool) = registry.get_tool(agent) {
             if let Some(mcp_gen) = tool.mcp_generator() {
-                let mcp_files = mcp_gen.generate_mcp(current_dir);
-                for path in mcp_files.keys() {
-                    result.add_file(agent, path.clone());
-                }
-                mcp_files_to_write.extend(mcp_files);
+                add_files(plan, result, agent, mcp_gen.generate_mcp(current_dir))?;
             }
         }
     }
-    write_directory_files(&mcp_files_to_write)?;
+
+    // Commands and skills that use template variables are rendered into the generated
+    // directory; their symlinks below point at the rendered copies.
+    let variables = source_options.variables.clone().unwrap_or_default();
+    let mut rendered_files: HashMap<PathBuf, String> =
+        generate_rendered_command_contents(current_dir, &variables)?;
+    rendered_files.extend(generate_rendered_skill_contents(current_dir, &variables)?);
+    plan.extend(write_operations(rendered_files))?;
+    plan.extend(link_rendered_skill_resources(current_dir)?)?;
 
     // Generate command symlinks - use command_agents instead of agents
     for agent in command_agents {
         if let Some(tool) = registry.get_tool(agent) {
             if let Some(cmd_gen) = tool.command_generator() {
                 let command_symlinks = cmd_gen.generate_command_symlinks(current_dir)?;
-                for symlink_path in command_symlinks {
-                    result.add_file(agent, symlink_path);
-                }
+                add_links(plan, result, agent, command_symlinks, link_modes.commands)?;
             }
         }
     }
@@ -131,9 +462,7 @@ fn generate_files(
         if let Some(tool) = registry.get_tool(agent) {
             if let Some(skills_gen) = tool.skills_generator() {
                 let skill_symlinks = skills_gen.generate_skills(current_dir)?;
-                for symlink_path in skill_symlinks {
-                    result.add_file(agent, symlink_path);
-                }
+                add_links(plan, result, agent, skill_symlinks, link_modes.skills)?;
             }
         }
     }
@@ -145,7 +474,10 @@ fn generate_files(
 mod tests {
     use super::*;
     use crate::constants::AGENTS_MD_FILENAME;
+    use crate::operations::git_imports::test_helpers::*;
     use crate::utils::test_utils::helpers::*;
+    use std::collections::BTreeMap;
+    use std::os::unix::fs::MetadataExt;
     use tempfile::TempDir;
 
     const NESTED_DEPTH: usize = 6;
@@ -155,8 +487,47 @@ mod tests {
         command_agents: None,
         gitignore: true,
         nested_depth: NESTED_DEPTH,
+        variables: BTreeMap::new(),
+        var_overrides: BTreeMap::new(),
+        rule_order: Vec::new(),
+        include_tags: None,
+        inherit: false,
+        local_rules: LocalRulesMode::Merged,
+        output_mode: OutputMode::File,
+        link_modes: LinkModes {
+            rules: LinkMode::Symlink,
+            commands: LinkMode::Symlink,
+            skills: LinkMode::Symlink,
+        },
+        local_edits: LocalEditPolicy::Refuse,
+        dry_run: false,
+        targets: None,
     };
 
+    /// Plans one directory's files and applies them.
+    fn generate_files(
+        current_dir: &Path,
+        agents: &[String],
+        command_agents: &[String],
+        registry: &AgentToolRegistry,
+        source_options: &SourceOptions,
+        output_options: &OutputOptions,
+        result: &mut GenerationResult,
+    ) -> Result<()> {
+        let mut plan = Plan::default();
+        plan_files(
+            current_dir,
+            agents,
+            command_agents,
+            registry,
+            source_options,
+            output_options,
+            &mut plan,
+            result,
+        )?;
+        transaction::apply(current_dir, &plan, None)
+    }
+
     const TEST_RULE_CONTENT: &str = r#"---
 description: Test rule
 alwaysApply: true
@@ -240,6 +611,7 @@ Test rule content"#;
             command_agents: None,
             gitignore: false,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = run_generate(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -263,6 +635,7 @@ Test rule content"#;
             command_agents: None,
             gitignore: true,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = run_generate(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -366,6 +739,7 @@ Test rule content"#;
             command_agents: None,
             gitignore: true,
             nested_depth: 0,
+            ..Default::default()
         };
         let result = run_generate(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -408,6 +782,7 @@ Test rule content"#;
             command_agents: None,
             gitignore: false,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = run_generate(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -448,6 +823,8 @@ Test rule content"#;
             &agents,
             &agents,
             &registry,
+            &SourceOptions::default(),
+            &OutputOptions::default(),
             &mut generation_result,
         );
         assert!(result.is_ok());
@@ -493,6 +870,8 @@ Test rule content"#;
             &agents,
             &agents,
             &registry,
+            &SourceOptions::default(),
+            &OutputOptions::default(),
             &mut generation_result,
         );
         assert!(result.is_ok());
@@ -523,6 +902,8 @@ Test rule content"#;
             &agents,
             &agents,
             &registry,
+            &SourceOptions::default(),
+            &OutputOptions::default(),
             &mut generation_result,
         );
         assert!(result.is_ok());
@@ -554,6 +935,8 @@ Test rule content"#;
             &agents,
             &agents,
             &registry,
+            &SourceOptions::default(),
+            &OutputOptions::default(),
             &mut generation_result,
         );
         assert!(result.is_ok());
@@ -583,6 +966,8 @@ Test rule content"#;
             &agents,
             &agents,
             &registry,
+            &SourceOptions::default(),
+            &OutputOptions::default(),
             &mut generation_result,
         );
         assert!(result1.is_ok());
@@ -605,6 +990,8 @@ New body content"#;
             &agents,
             &agents,
             &registry,
+            &SourceOptions::default(),
+            &OutputOptions::default(),
             &mut generation_result2,
         );
         assert!(result2.is_ok());
@@ -648,6 +1035,7 @@ New body content"#;
             command_agents: None,
             gitignore: false,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = run_generate(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -684,6 +1072,7 @@ New body content"#;
             command_agents: None,
             gitignore: false,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = run_generate(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -709,6 +1098,7 @@ New body content"#;
             command_agents: None,
             gitignore: false,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = run_generate(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -737,6 +1127,7 @@ New body content"#;
             command_agents: Some(vec!["claude".to_string(), "amp".to_string()]),
             gitignore: false,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = run_generate(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -776,6 +1167,7 @@ New body content"#;
             command_agents: None,
             gitignore: false,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = run_generate(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -812,6 +1204,7 @@ New body content"#;
             command_agents: None,
             gitignore: false,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = run_generate(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -846,6 +1239,7 @@ New body content"#;
             command_agents: None,
             gitignore: false,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = run_generate(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -870,6 +1264,7 @@ New body content"#;
             command_agents: None,
             gitignore: false,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = run_generate(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -877,4 +1272,770 @@ New body content"#;
         // Verify no skill symlinks created (skills directory shouldn't exist)
         assert_file_not_exists(temp_dir.path(), ".claude/skills/");
     }
+
+    #[test]
+    fn test_run_generate_renders_template_variables() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join(".git")).unwrap();
+        create_file(
+            temp_dir.path(),
+            "ai-rules/test.md",
+            "---\ndescription: Testing\nalwaysApply: true\n---\nRun `{{test_command}}` in {{relative_dir}}",
+        );
+        create_file(
+            temp_dir.path(),
+            "service/ai-rules/test.md",
+            "---\ndescription: Testing\nalwaysApply: true\n---\nRun `{{test_command}}` in {{relative_dir}}",
+        );
+        create_file(
+            temp_dir.path(),
+            "service/ai-rules/ai-rules-config.yaml",
+            "variables:\n  test_command: go test ./...\n",
+        );
+
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            nested_depth: NESTED_DEPTH,
+            variables: BTreeMap::from([("test_command".to_string(), "cargo test".to_string())]),
+            ..Default::default()
+        };
+        run_generate(temp_dir.path(), args).unwrap();
+
+        assert_file_content(
+            temp_dir.path(),
+            "CLAUDE.md",
+            "# Testing\n\nRun `cargo test` in .\n",
+        );
+        assert_file_content(
+            temp_dir.path(),
+            "service/CLAUDE.md",
+            "# Testing\n\nRun `go test ./...` in service\n",
+        );
+
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            var_overrides: BTreeMap::from([("test_command".to_string(), "make test".to_string())]),
+            ..Default::default()
+        };
+        run_generate(temp_dir.path(), args).unwrap();
+        assert_file_content(
+            temp_dir.path(),
+            "CLAUDE.md",
+            "# Testing\n\nRun `make test` in .\n",
+        );
+    }
+
+    #[test]
+    fn test_run_generate_undefined_template_variable_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "ai-rules/test.md", "Run {{missing}}");
+
+        let error = run_generate(temp_dir.path(), GENERATE_ARGS)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("undefined template variable 'missing'"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_run_generate_rolls_back_when_a_directory_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "ai-rules/test.md", "Old rule");
+        run_generate(temp_dir.path(), GENERATE_ARGS).unwrap();
+        let gitignore = std::fs::read_to_string(temp_dir.path().join(".gitignore")).unwrap();
+
+        create_file(temp_dir.path(), "ai-rules/test.md", "New rule");
+        create_file(
+            temp_dir.path(),
+            "nested/ai-rules/test.md",
+            "Run {{missing}}",
+        );
+        assert!(run_generate(temp_dir.path(), GENERATE_ARGS).is_err());
+
+        assert_file_content(
+            temp_dir.path(),
+            "ai-rules/.generated-ai-rules/ai-rules-generated-test.md",
+            "Old rule\n",
+        );
+        assert_file_content(temp_dir.path(), ".gitignore", &gitignore);
+        assert_file_not_exists(temp_dir.path(), "nested/CLAUDE.md");
+        assert_file_not_exists(temp_dir.path(), "ai-rules/.generated-ai-rules/staging");
+    }
+
+    #[test]
+    fn test_run_generate_dry_run_changes_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "ai-rules/test.md", "Old rule");
+        create_file(temp_dir.path(), "CLAUDE.md", "Hand written");
+        let dry_run = ResolvedGenerateArgs {
+            dry_run: true,
+            ..GENERATE_ARGS
+        };
+        run_generate(temp_dir.path(), dry_run.clone()).unwrap();
+        assert_file_content(temp_dir.path(), "CLAUDE.md", "Hand written");
+        assert_file_not_exists(temp_dir.path(), "ai-rules/.generated-ai-rules");
+        assert_file_not_exists(temp_dir.path(), ".gitignore");
+
+        run_generate(temp_dir.path(), GENERATE_ARGS).unwrap();
+        create_file(temp_dir.path(), "ai-rules/test.md", "New rule");
+        run_generate(temp_dir.path(), dry_run).unwrap();
+        assert_file_content(
+            temp_dir.path(),
+            "ai-rules/.generated-ai-rules/ai-rules-generated-test.md",
+            "Old rule\n",
+        );
+    }
+
+    #[test]
+    fn test_run_generate_dry_run_leaves_git_imports_alone() {
+        let temp_dir = TempDir::new().unwrap();
+        let pack = temp_dir.path().join("pack");
+        let url = create_git_pack(&pack, &[("ai-rules/security.md", "Old rule")]);
+        let project = temp_dir.path().join("project");
+        create_file(
+            &project,
+            "ai-rules/ai-rules-config.yaml",
+            &format!("imports:\n  - url: {url}\n    ref: main\n    path: ai-rules\n"),
+        );
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            ..Default::default()
+        };
+        let dry_run = ResolvedGenerateArgs {
+            dry_run: true,
+            ..args.clone()
+        };
+
+        // Nothing is fetched, so the import can't be read
+        let error = run_generate(&project, dry_run.clone()).unwrap_err();
+        assert!(
+            error.to_string().contains("has not been fetched"),
+            "{error}"
+        );
+        assert_file_not_exists(&project, "ai-rules/ai-rules.lock");
+        assert_file_not_exists(&project, "ai-rules/.cache");
+
+        run_generate(&project, args).unwrap();
+        let lock = std::fs::read_to_string(project.join("ai-rules/ai-rules.lock")).unwrap();
+        commit_files(&pack, &[("ai-rules/security.md", "New rule")]);
+        run_generate(&project, dry_run).unwrap();
+        assert_file_content(&project, "ai-rules/ai-rules.lock", &lock);
+    }
+
+    #[test]
+    fn test_run_generate_renders_templated_commands_and_skills() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(
+            temp_dir.path(),
+            "ai-rules/commands/test.md",
+            "Run {{test_command}}",
+        );
+        create_file(
+            temp_dir.path(),
+            "ai-rules/commands/plain.md",
+            "No variables",
+        );
+        create_file(
+            temp_dir.path(),
+            "ai-rules/skills/testing/SKILL.md",
+            "---\nname: testing\ndescription: Testing\n---\nRun {{test_command}}",
+        );
+        create_file(
+            temp_dir.path(),
+            "ai-rules/skills/testing/scripts/run.sh",
+            "echo run",
+        );
+
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            var_overrides: BTreeMap::from([("test_command".to_string(), "cargo test".to_string())]),
+            ..Default::default()
+        };
+        run_generate(temp_dir.path(), args).unwrap();
+
+        assert_file_content(
+            temp_dir.path(),
+            ".claude/commands/ai-rules/test.md",
+            "Run cargo test",
+        );
+        assert_file_content(
+            temp_dir.path(),
+            ".claude/commands/ai-rules/plain.md",
+            "No variables",
+        );
+        assert_file_content(
+            temp_dir.path(),
+            ".claude/skills/ai-rules-generated-testing/SKILL.md",
+            "---\nname: testing\ndescription: Testing\n---\nRun cargo test",
+        );
+        assert_file_content(
+            temp_dir.path(),
+            ".claude/skills/ai-rules-generated-testing/scripts/run.sh",
+            "echo run",
+        );
+    }
+
+    #[test]
+    fn test_run_generate_orders_inlined_rules() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(
+            temp_dir.path(),
+            "ai-rules/a-style.md",
+            "---\ndescription: Style\nalwaysApply: true\n---\nStyle body",
+        );
+        create_file(
+            temp_dir.path(),
+            "ai-rules/z-safety.md",
+            "---\ndescription: Safety\nalwaysApply: true\norder: 1\n---\nSafety body",
+        );
+        create_file(
+            temp_dir.path(),
+            "ai-rules/m-testing.md",
+            "---\ndescription: Testing\nalwaysApply: true\n---\nTesting body",
+        );
+
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            rule_order: vec!["m-testing".to_string()],
+            ..Default::default()
+        };
+        run_generate(temp_dir.path(), args).unwrap();
+
+        assert_file_content(
+            temp_dir.path(),
+            "CLAUDE.md",
+            "# Testing\n\nTesting body\n\n# Safety\n\nSafety body\n\n# Style\n\nStyle body\n",
+        );
+    }
+
+    #[test]
+    fn test_run_generate_with_profile_tags() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(
+            temp_dir.path(),
+            "ai-rules/general.md",
+            "---\ndescription: General\nalwaysApply: true\n---\nGeneral body",
+        );
+        create_file(
+            temp_dir.path(),
+            "ai-rules/db.md",
+            "---\ndescription: Db\nalwaysApply: true\ntags: [db]\n---\nDb body",
+        );
+        create_file(
+            temp_dir.path(),
+            "ai-rules/ios.md",
+            "---\ndescription: Ios\nalwaysApply: true\ntags: [mobile]\n---\nIos body",
+        );
+
+        run_generate(temp_dir.path(), GENERATE_ARGS).unwrap();
+        let gitignore_without_profile =
+            std::fs::read_to_string(temp_dir.path().join(".gitignore")).unwrap();
+
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            gitignore: true,
+            nested_depth: NESTED_DEPTH,
+            include_tags: Some(vec!["db".to_string()]),
+            ..Default::default()
+        };
+        run_generate(temp_dir.path(), args).unwrap();
+
+        assert_file_content(
+            temp_dir.path(),
+            "CLAUDE.md",
+            "# Db\n\nDb body\n\n# General\n\nGeneral body\n",
+        );
+        assert_file_not_exists(
+            temp_dir.path(),
+            "ai-rules/.generated-ai-rules/ai-rules-generated-ios.md",
+        );
+        // Switching profiles must not churn the committed .gitignore
+        assert_file_content(temp_dir.path(), ".gitignore", &gitignore_without_profile);
+    }
+
+    #[test]
+    fn test_run_generate_nested_inherits_ancestor_rules() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join(".git")).unwrap();
+        let rule = |description: &str, extra: &str| {
+            format!("---\ndescription: {description}\nalwaysApply: true\n{extra}---\n{description} body")
+        };
+        create_file(
+            temp_dir.path(),
+            "ai-rules/security.md",
+            &rule("Security", "order: 1\n"),
+        );
+        create_file(
+            temp_dir.path(),
+            "ai-rules/style.md",
+            &rule("Root style", ""),
+        );
+        create_file(temp_dir.path(), "ai-rules/legacy.md", &rule("Legacy", ""));
+        create_file(
+            temp_dir.path(),
+            "ai-rules/root-only.md",
+            &rule("Root only", "inherit: false\n"),
+        );
+        create_file(
+            temp_dir.path(),
+            "service/ai-rules/ai-rules-config.yaml",
+            "inherit: true\ndisable_inherited: [legacy]\n",
+        );
+        create_file(
+            temp_dir.path(),
+            "service/ai-rules/style.md",
+            &rule("Service style", ""),
+        );
+        // Directories without their own ai-rules/ never inherit
+        std::fs::create_dir_all(temp_dir.path().join("docs")).unwrap();
+
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            nested_depth: NESTED_DEPTH,
+            ..Default::default()
+        };
+        run_generate(temp_dir.path(), args).unwrap();
+
+        assert_file_content(
+            temp_dir.path(),
+            "service/CLAUDE.md",
+            "# Security\n\nSecurity body\n\n# Service style\n\nService style body\n",
+        );
+        assert_file_not_exists(temp_dir.path(), "docs/CLAUDE.md");
+    }
+
+    #[test]
+    fn test_run_generate_inherits_rules_marked_in_frontmatter() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join(".git")).unwrap();
+        create_file(
+            temp_dir.path(),
+            "ai-rules/org.md",
+            "---\ndescription: Org\nalwaysApply: true\ninherit: true\n---\nOrg body",
+        );
+        create_file(temp_dir.path(), "ai-rules/root.md", TEST_RULE_CONTENT);
+        // A symlink-mode nested project switches to standard mode once it inherits rules
+        create_file(temp_dir.path(), "app/ai-rules/AGENTS.md", "# App rules\n");
+
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            nested_depth: NESTED_DEPTH,
+            ..Default::default()
+        };
+        run_generate(temp_dir.path(), args).unwrap();
+
+        assert_file_content(
+            temp_dir.path(),
+            "app/CLAUDE.md",
+            "# AGENTS\n\n# App rules\n\n# Org\n\nOrg body\n",
+        );
+    }
+
+    #[test]
+    fn test_run_generate_merges_imported_rule_pack() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("project");
+        let rule = |description: &str| {
+            format!("---\ndescription: {description}\nalwaysApply: true\n---\n{description} body")
+        };
+        create_file(temp_dir.path(), "pack/style.md", &rule("Pack style"));
+        create_file(temp_dir.path(), "pack/security.md", &rule("Security"));
+        create_file(
+            temp_dir.path(),
+            "pack/commands/review.md",
+            "Review the diff",
+        );
+        create_file(
+            temp_dir.path(),
+            "pack/skills/deploy/SKILL.md",
+            "---\nname: deploy\ndescription: Deploy\n---\nDeploy it",
+        );
+        create_file(
+            temp_dir.path(),
+            "pack/skills/deploy/scripts/deploy.sh",
+            "echo deploy",
+        );
+        create_file(&project, "ai-rules/style.md", &rule("Local style"));
+        create_file(
+            &project,
+            "ai-rules/ai-rules-config.yaml",
+            "imports:\n  - path: ../pack\n    on_conflict: local-wins\n",
+        );
+
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            ..Default::default()
+        };
+        run_generate(&project, args).unwrap();
+
+        assert_file_content(
+            &project,
+            "CLAUDE.md",
+            "# Security\n\nSecurity body\n\n# Local style\n\nLocal style body\n",
+        );
+        assert_file_content(
+            &project,
+            ".claude/commands/ai-rules/review.md",
+            "Review the diff",
+        );
+        assert_file_content(
+            &project,
+            ".claude/skills/ai-rules-generated-deploy/scripts/deploy.sh",
+            "echo deploy",
+        );
+    }
+
+    #[test]
+    fn test_run_generate_import_conflict_fails_by_default() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "pack/test.md", TEST_RULE_CONTENT);
+        create_file(temp_dir.path(), "ai-rules/test.md", TEST_RULE_CONTENT);
+        create_file(
+            temp_dir.path(),
+            "ai-rules/ai-rules-config.yaml",
+            "imports:\n  - pack\n",
+        );
+
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            ..Default::default()
+        };
+        let error = run_generate(temp_dir.path(), args).unwrap_err().to_string();
+
+        assert!(error.contains("rule 'test' from import 'pack'"), "{error}");
+    }
+
+    #[test]
+    fn test_run_generate_git_import_uses_lock_without_fetching() {
+        let temp_dir = TempDir::new().unwrap();
+        let pack = temp_dir.path().join("pack");
+        let url = create_git_pack(
+            &pack,
+            &[
+                ("ai-rules/security.md", TEST_RULE_CONTENT),
+                ("ai-rules/commands/review.md", "Review the diff"),
+            ],
+        );
+        let project = temp_dir.path().join("project");
+        create_file(
+            &project,
+            "ai-rules/ai-rules-config.yaml",
+            &format!("imports:\n  - url: {url}\n    ref: main\n    path: ai-rules\n"),
+        );
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            ..Default::default()
+        };
+
+        run_generate(&project, args.clone()).unwrap();
+        assert_file_exists(&project, "ai-rules/ai-rules.lock");
+        assert_file_content(
+            &project,
+            ".claude/commands/ai-rules/review.md",
+            "Review the diff",
+        );
+        let claude_md = std::fs::read_to_string(project.join("CLAUDE.md")).unwrap();
+
+        // The lock is satisfied by the cache, so the repository isn't needed anymore
+        std::fs::remove_dir_all(&pack).unwrap();
+        run_generate(&project, args).unwrap();
+        assert_file_content(&project, "CLAUDE.md", &claude_md);
+    }
+
+    #[test]
+    fn test_run_generate_writes_the_lock_file_with_the_outputs() {
+        let temp_dir = TempDir::new().unwrap();
+        let url = create_git_pack(
+            &temp_dir.path().join("pack"),
+            &[("ai-rules/security.md", TEST_RULE_CONTENT)],
+        );
+        let project = temp_dir.path().join("project");
+        create_file(&project, "ai-rules/mcp.json", r#"{"mcpServers": {}}"#);
+        let args = |local_edits| ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            local_edits,
+            ..Default::default()
+        };
+        run_generate(&project, args(LocalEditPolicy::Refuse)).unwrap();
+
+        // The new import is fetched, but refusing the edit leaves the lock file alone
+        create_file(
+            &project,
+            "ai-rules/ai-rules-config.yaml",
+            &format!("imports:\n  - url: {url}\n    ref: main\n    path: ai-rules\n"),
+        );
+        create_file(&project, ".mcp.json", r#"{"mcpServers": {"db": {}}}"#);
+        assert!(run_generate(&project, args(LocalEditPolicy::Refuse)).is_err());
+        assert_file_not_exists(&project, "ai-rules/ai-rules.lock");
+
+        run_generate(&project, args(LocalEditPolicy::Force)).unwrap();
+        assert_file_exists(&project, "ai-rules/ai-rules.lock");
+    }
+
+    #[test]
+    fn test_run_generate_merges_personal_rules() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "ai-rules/test.md", TEST_RULE_CONTENT);
+        create_file(
+            temp_dir.path(),
+            "ai-rules/local/notes.md",
+            "---\ndescription: My notes\nalwaysApply: true\n---\nUse my fork",
+        );
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            gitignore: true,
+            ..Default::default()
+        };
+
+        run_generate(temp_dir.path(), args).unwrap();
+
+        let claude_md = std::fs::read_to_string(temp_dir.path().join("CLAUDE.md")).unwrap();
+        assert!(claude_md.contains("Use my fork"));
+        assert!(claude_md.contains("Test rule content"));
+        assert_file_not_exists(temp_dir.path(), "CLAUDE.local.md");
+        let gitignore = std::fs::read_to_string(temp_dir.path().join(".gitignore")).unwrap();
+        assert!(gitignore.contains("/ai-rules/*.local.md"));
+        assert!(gitignore.contains("/ai-rules/local"));
+    }
+
+    #[test]
+    fn test_run_generate_native_personal_rules() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "ai-rules/test.md", TEST_RULE_CONTENT);
+        create_file(
+            temp_dir.path(),
+            "ai-rules/notes.local.md",
+            "---\ndescription: My notes\nalwaysApply: false\n---\nUse my fork",
+        );
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string(), "codex".to_string()]),
+            local_rules: LocalRulesMode::Native,
+            ..Default::default()
+        };
+
+        run_generate(temp_dir.path(), args.clone()).unwrap();
+
+        let claude_md = std::fs::read_to_string(temp_dir.path().join("CLAUDE.md")).unwrap();
+        assert!(!claude_md.contains("Use my fork"));
+        let claude_local =
+            std::fs::read_to_string(temp_dir.path().join("CLAUDE.local.md")).unwrap();
+        assert!(claude_local.ends_with("# My notes\n\nUse my fork\n"));
+        assert!(!claude_local.contains("Test rule content"));
+        let codex_override =
+            std::fs::read_to_string(temp_dir.path().join("AGENTS.override.md")).unwrap();
+        assert!(codex_override.contains("Test rule content"));
+        assert!(codex_override.ends_with("# My notes\n\nUse my fork\n"));
+
+        std::fs::remove_file(temp_dir.path().join("ai-rules/notes.local.md")).unwrap();
+        run_generate(temp_dir.path(), args).unwrap();
+        assert_file_not_exists(temp_dir.path(), "CLAUDE.local.md");
+        assert_file_not_exists(temp_dir.path(), "AGENTS.override.md");
+    }
+
+    #[test]
+    fn test_run_generate_managed_block_keeps_hand_written_text() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "ai-rules/test.md", TEST_RULE_CONTENT);
+        create_file(
+            temp_dir.path(),
+            "CLAUDE.md",
+            "# Team notes\n\nAsk before deploying.\n",
+        );
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            gitignore: true,
+            output_mode: OutputMode::ManagedBlock,
+            ..Default::default()
+        };
+
+        run_generate(temp_dir.path(), args.clone()).unwrap();
+
+        let claude_path = temp_dir.path().join("CLAUDE.md");
+        assert!(!claude_path.is_symlink());
+        assert_file_content(
+            temp_dir.path(),
+            "CLAUDE.md",
+            "# Team notes\n\nAsk before deploying.\n\n<!-- ai-rules:begin -->\n# Test rule\n\nTest rule content\n<!-- ai-rules:end -->\n",
+        );
+        let gitignore = std::fs::read_to_string(temp_dir.path().join(".gitignore")).unwrap();
+        assert!(!gitignore.contains("/CLAUDE.md"));
+
+        // The block is updated where the user moved it
+        let moved = std::fs::read_to_string(&claude_path)
+            .unwrap()
+            .replace("# Team notes\n\nAsk before deploying.\n\n", "")
+            + "\nAsk before deploying.\n";
+        std::fs::write(&claude_path, moved).unwrap();
+        create_file(temp_dir.path(), "ai-rules/test.md", "Updated rule");
+        run_generate(temp_dir.path(), args).unwrap();
+        assert_file_content(
+            temp_dir.path(),
+            "CLAUDE.md",
+            "<!-- ai-rules:begin -->\n# test\n\nUpdated rule\n<!-- ai-rules:end -->\n\nAsk before deploying.\n",
+        );
+
+        let mut plan = Plan::default();
+        operations::clean_generated_files(
+            temp_dir.path(),
+            &["claude".to_string()],
+            &AgentToolRegistry::new(),
+            &mut plan,
+        )
+        .unwrap();
+        transaction::apply(temp_dir.path(), &plan, None).unwrap();
+        assert_file_content(temp_dir.path(), "CLAUDE.md", "Ask before deploying.\n");
+    }
+
+    #[test]
+    fn test_run_generate_copy_link_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        create_file(project, "ai-rules/test.md", TEST_RULE_CONTENT);
+        create_file(project, "ai-rules/commands/review.md", "Review the diff");
+        create_file(
+            project,
+            "ai-rules/skills/notes/SKILL.md",
+            "---\nname: notes\ndescription: Notes\n---\nTake notes",
+        );
+        create_file(project, "ai-rules/skills/notes/template.md", "Template");
+        let args = ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            link_modes: LinkModes {
+                rules: LinkMode::Copy,
+                commands: LinkMode::Hardlink,
+                skills: LinkMode::Copy,
+            },
+            ..Default::default()
+        };
+
+        run_generate(project, args.clone()).unwrap();
+
+        let claude_md = project.join("CLAUDE.md");
+        assert!(!claude_md.is_symlink());
+        assert_file_content(project, "CLAUDE.md", "# Test rule\n\nTest rule content\n");
+        let command = project.join(".claude/commands/ai-rules/review.md");
+        assert!(!command.is_symlink());
+        assert!(std::fs::metadata(&command).unwrap().nlink() > 1);
+        let skill = project.join(".claude/skills/ai-rules-generated-notes");
+        assert!(!skill.is_symlink());
+        assert_file_content(&skill, "template.md", "Template");
+        assert_file_exists(&skill, ".ai-rules-generated");
+
+        let status_args = || crate::cli::ResolvedStatusArgs {
+            agents: Some(vec!["claude".to_string()]),
+            link_modes: args.link_modes,
+            ..Default::default()
+        };
+        let status = crate::commands::status::check_project_status(project, status_args()).unwrap();
+        assert!(status.agent_statuses["claude"]);
+
+        create_file(project, "ai-rules/skills/notes/template.md", "Changed");
+        let status = crate::commands::status::check_project_status(project, status_args()).unwrap();
+        assert!(!status.agent_statuses["claude"]);
+
+        // Regenerating replaces the copies, and clean removes them
+        run_generate(project, args).unwrap();
+        assert_file_content(&skill, "template.md", "Changed");
+        crate::commands::run_clean(project, 0).unwrap();
+        assert_file_not_exists(project, "CLAUDE.md");
+        assert_file_not_exists(project, ".claude/skills/ai-rules-generated-notes");
+        assert_file_not_exists(project, ".claude/commands/ai-rules/review.md");
+    }
+
+    #[test]
+    fn test_run_generate_protects_edited_outputs() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        create_file(
+            project,
+            "ai-rules/mcp.json",
+            r#"{"mcpServers": {"db": {"command": "db-mcp"}}}"#,
+        );
+        let args = |local_edits| ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string(), "firebender".to_string()]),
+            local_edits,
+            ..Default::default()
+        };
+        run_generate(project, args(LocalEditPolicy::Refuse)).unwrap();
+
+        let edited_mcp = r#"{"mcpServers": {"docs": {"command": "docs-mcp"}}}"#;
+        create_file(project, ".mcp.json", edited_mcp);
+        let error = run_generate(project, args(LocalEditPolicy::Refuse)).unwrap_err();
+        assert!(error.to_string().contains("--force"));
+        assert_file_content(project, ".mcp.json", edited_mcp);
+
+        run_generate(project, args(LocalEditPolicy::Force)).unwrap();
+        assert_file_content(
+            project,
+            ".mcp.json",
+            "{\"mcpServers\": {\"db\": {\"command\": \"db-mcp\"}}}\n",
+        );
+
+        create_file(project, ".mcp.json", edited_mcp);
+        run_generate(project, args(LocalEditPolicy::Adopt)).unwrap();
+        let source = std::fs::read_to_string(project.join("ai-rules/mcp.json")).unwrap();
+        assert!(source.contains("docs-mcp") && !source.contains("db-mcp"));
+
+        let firebender = std::fs::read_to_string(project.join("firebender.json")).unwrap();
+        let firebender = firebender.replacen('{', "{\n  \"theme\": \"dark\",", 1);
+        create_file(project, "firebender.json", &firebender);
+        run_generate(project, args(LocalEditPolicy::Adopt)).unwrap();
+        assert_file_content(
+            project,
+            "ai-rules/firebender-overlay.json",
+            "{\n  \"theme\": \"dark\"\n}\n",
+        );
+        let firebender = std::fs::read_to_string(project.join("firebender.json")).unwrap();
+        assert!(firebender.contains("\"theme\": \"dark\""));
+        assert!(firebender.contains("docs-mcp"));
+    }
+
+    #[test]
+    fn test_run_generate_only_touches_changed_outputs() {
+        use std::os::unix::fs::MetadataExt;
+        use std::time::{Duration, SystemTime};
+
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        create_file(project, "ai-rules/kept.md", TEST_RULE_CONTENT);
+        create_file(project, "ai-rules/stale.md", TEST_RULE_CONTENT);
+        create_file(project, "ai-rules/commands/commit.md", "Commit");
+        let args = || ResolvedGenerateArgs {
+            agents: Some(vec!["claude".to_string()]),
+            link_modes: LinkModes {
+                commands: LinkMode::Copy,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        run_generate(project, args()).unwrap();
+
+        let old_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let kept_rule = project.join("ai-rules/.generated-ai-rules/ai-rules-generated-kept.md");
+        std::fs::File::options()
+            .write(true)
+            .open(&kept_rule)
+            .unwrap()
+            .set_modified(old_time)
+            .unwrap();
+        let inode = |path: &str| project.join(path).symlink_metadata().unwrap().ino();
+        let claude_md = inode("CLAUDE.md");
+        let command_copy = inode(".claude/commands/ai-rules/commit.md");
+
+        std::fs::remove_file(project.join("ai-rules/stale.md")).unwrap();
+        run_generate(project, args()).unwrap();
+
+        assert_eq!(
+            std::fs::metadata(&kept_rule).unwrap().modified().unwrap(),
+            old_time
+        );
+        assert_eq!(inode("CLAUDE.md"), claude_md);
+        assert_eq!(inode(".claude/commands/ai-rules/commit.md"), command_copy);
+        assert_file_not_exists(
+            project,
+            "ai-rules/.generated-ai-rules/ai-rules-generated-stale.md",
+        );
+    }
 }
@@ -0,0 +1,36 @@
+use crate::cli::ReportFormat;
+use crate::commands::validate::print_report;
+use crate::config::LintConfig;
+use crate::operations::rule_linter::{lint_directory, LintSettings};
+use crate::utils::annotations::Annotation;
+use crate::utils::file_utils::{traverse_project_directories, DirectoryFilter};
+use anyhow::Result;
+use std::path::Path;
+
+pub fn run_lint(
+    current_dir: &Path,
+    nested_depth: usize,
+    config: Option<&LintConfig>,
+    format: ReportFormat,
+) -> Result<()> {
+    let settings = LintSettings::from_config(config)?;
+    if format == ReportFormat::Human {
+        println!("🔍 Linting ai-rules rules, nested_depth: {nested_depth}");
+    }
+    let annotations = lint_project(current_dir, nested_depth, &settings)?;
+    print_report(format, &annotations, "No problems found in the rules")
+}
+
+fn lint_project(
+    current_dir: &Path,
+    nested_depth: usize,
+    settings: &LintSettings,
+) -> Result<Vec<Annotation>> {
+    let filter = DirectoryFilter::from_project_root(current_dir);
+    let mut annotations = Vec::new();
+    traverse_project_directories(current_dir, nested_depth, 0, &filter, &mut |dir| {
+        annotations.extend(lint_directory(current_dir, dir, settings)?);
+        Ok(())
+    })?;
+    Ok(annotations)
+}
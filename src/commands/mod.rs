@@ -1,14 +1,41 @@
 mod clean;
+mod diff;
 mod generate;
 mod init;
+mod lint;
 mod list_agents;
+mod restore;
 mod status;
+mod update;
+mod user;
+mod validate;
+mod watch;
 
 pub use clean::run_clean;
+pub use diff::run_diff;
 pub use generate::run_generate;
 pub use init::run_init;
+pub use lint::run_lint;
 pub use list_agents::run_list_agents;
+pub use restore::{run_restore, run_restore_user};
 pub use status::run_status;
+pub use update::run_update;
+pub use user::{run_clean_user, run_generate_user, run_status_user};
+pub use validate::run_validate;
+pub use watch::run_watch;
+
+/// Returned by a command that ran to completion but must exit with a non-zero status,
+/// like `status` when something is out of sync. `main` exits with it silently.
+#[derive(Debug)]
+pub struct ExitStatus(pub i32);
+
+impl std::fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "exit status {}", self.0)
+    }
+}
+
+impl std::error::Error for ExitStatus {}
 
 #[cfg(test)]
 mod tests {
@@ -71,6 +98,7 @@ mod tests {
             command_agents: None,
             gitignore: true,
             nested_depth,
+            ..Default::default()
         };
         let generate_result = run_generate(project_path, generate_args);
         if let Err(e) = &generate_result {
@@ -90,6 +118,7 @@ mod tests {
             agents: None,
             command_agents: None,
             nested_depth,
+            ..Default::default()
         };
         let status_result = check_project_status(project_path, status_args).unwrap();
         assert!(status_result.has_ai_rules);
@@ -107,6 +136,7 @@ mod tests {
             agents: None,
             command_agents: None,
             nested_depth,
+            ..Default::default()
         };
         let status_after_change = check_project_status(project_path, status_args).unwrap();
         assert!(status_after_change.has_ai_rules);
@@ -129,11 +159,16 @@ mod tests {
         let clean_result = run_clean(project_path, nested_depth);
         assert!(clean_result.is_ok());
 
-        assert_file_not_exists(project_path, "ai-rules/.generated-ai-rules");
+        assert_file_not_exists(project_path, "ai-rules/.generated-ai-rules/manifest.json");
         assert_file_not_exists(project_path, "CLAUDE.md");
         assert_file_not_exists(project_path, ".cursor/rules");
         assert_file_not_exists(project_path, AGENTS_MD_FILENAME);
 
+        // Only the hand-modified CLAUDE.md was backed up
+        let backups = crate::utils::backup::list_backups(project_path).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].paths, vec![Path::new("CLAUDE.md")]);
+
         assert_file_exists(project_path, starter_rule_path);
         assert_file_exists(project_path, ".gitignore"); // Gitignore remains
     }
@@ -152,6 +187,7 @@ mod tests {
             command_agents: None,
             gitignore: true,
             nested_depth,
+            ..Default::default()
         };
         let generate_result = run_generate(project_path, generate_args);
         assert!(generate_result.is_ok());
@@ -164,8 +200,12 @@ mod tests {
 
         verify_symlinks(project_path, &expected_targets, symlink_content);
 
-        // Verify no normal generated files exist
-        assert_file_not_exists(project_path, "ai-rules/.generated-ai-rules");
+        // Verify no normal generated files exist; only the manifest is written
+        let generated: Vec<_> = fs::read_dir(project_path.join("ai-rules/.generated-ai-rules"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(generated, vec!["manifest.json"]);
         assert_file_not_exists(project_path, ".cursor/rules");
         assert_file_exists(project_path, ".gitignore");
 
@@ -177,6 +217,7 @@ mod tests {
             agents: None,
             command_agents: None,
             nested_depth,
+            ..Default::default()
         };
         let status_after_change = check_project_status(project_path, status_args).unwrap();
         assert!(status_after_change.has_ai_rules);
@@ -0,0 +1,94 @@
+use crate::cli::RestoreArgs;
+use crate::operations::user_scope::UserScope;
+use crate::utils::backup::{list_backups, restore_backup};
+use crate::utils::print_utils::{print_info, print_success};
+use anyhow::Result;
+use std::path::Path;
+
+pub fn run_restore(current_dir: &Path, args: RestoreArgs) -> Result<()> {
+    restore_or_list(current_dir, current_dir, args)
+}
+
+/// Restores or lists the backups of user-level files, kept in the user's `ai-rules/`.
+pub fn run_restore_user(scope: &UserScope, args: RestoreArgs) -> Result<()> {
+    restore_or_list(&scope.config_dir, &scope.home_dir, args)
+}
+
+fn restore_or_list(store: &Path, root: &Path, args: RestoreArgs) -> Result<()> {
+    match args.id {
+        Some(id) if !args.list => restore(store, root, &id),
+        _ => print_backups(store),
+    }
+}
+
+fn print_backups(store: &Path) -> Result<()> {
+    let backups = list_backups(store)?;
+    if backups.is_empty() {
+        print_info("No backups found");
+        return Ok(());
+    }
+    println!("📋 Backups (newest last):");
+    for backup in backups {
+        println!(
+            "    {}  {}  {} file(s)",
+            backup.id,
+            backup.command,
+            backup.paths.len()
+        );
+    }
+    println!("\n💡 Run 'ai-rules restore <id>' to put the files of a backup back");
+    Ok(())
+}
+
+fn restore(store: &Path, root: &Path, id: &str) -> Result<()> {
+    let backup = restore_backup(store, root, id)?;
+    for path in &backup.paths {
+        println!("    {}", path.display());
+    }
+    print_success(&format!(
+        "Restored {} file(s) from backup {id}",
+        backup.paths.len()
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::{run_clean, run_generate};
+    use crate::utils::test_utils::helpers::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_restore_hand_written_files_removed_by_generate_and_clean() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        create_file(project, "ai-rules/test.md", "Shared rule");
+        create_file(project, ".cursor/rules/team.mdc", "Hand written");
+
+        run_generate(project, Default::default()).unwrap();
+        assert_file_not_exists(project, ".cursor/rules");
+        run_clean(project, 0).unwrap();
+
+        // Generated outputs are recreated by generate, so only the hand-written tree is kept
+        let backups = list_backups(project).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].command, "generate");
+        assert_eq!(
+            backups[0].paths,
+            vec![std::path::PathBuf::from(".cursor/rules")]
+        );
+
+        let restore = |id: &str| {
+            let args = RestoreArgs {
+                list: false,
+                user: false,
+                id: Some(id.to_string()),
+            };
+            run_restore(project, args)
+        };
+        restore(&backups[0].id).unwrap();
+        assert_file_content(project, ".cursor/rules/team.mdc", "Hand written");
+        assert!(restore("missing").is_err());
+    }
+}
@@ -1,50 +1,283 @@
 use crate::agents::AgentToolRegistry;
-use crate::cli::ResolvedStatusArgs;
-use crate::models::SourceFile;
-use crate::operations;
-use crate::operations::body_generator::generated_body_file_dir;
-use crate::operations::source_reader::detect_symlink_mode;
+use crate::cli::{ReportFormat, ResolvedGenerateArgs, ResolvedStatusArgs};
+use crate::commands;
+use crate::commands::diff::{plan_owned_changes, OwnedChange};
+use crate::commands::ExitStatus;
+use crate::config::LocalRulesMode;
+use crate::constants::{
+    AI_RULE_CONFIG_FILENAME, AI_RULE_SOURCE_DIR, GENERATED_FILE_PREFIX, LOCK_FILENAME, MCP_JSON,
+};
+use crate::operations::git_imports::check_git_imports;
+use crate::operations::local_edits::LocalEditPolicy;
+use crate::operations::manifest::{inputs_hash, read_manifest};
+use crate::operations::mcp_reader::read_mcp_config;
+use crate::operations::source_reader::{
+    find_personal_source_files, find_source_files_with_options, uses_symlink_mode, SourceOptions,
+};
+use crate::operations::template_variables::TemplateVariables;
+use crate::utils::annotations::{github_commands, sarif_log, Annotation, Level};
 use crate::utils::file_utils;
+use crate::utils::plan::Snapshot;
+use crate::utils::print_utils::print_success;
 use anyhow::Result;
-use std::collections::HashMap;
-use std::path::Path;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, PartialEq)]
 pub struct ProjectStatus {
     pub body_files_out_of_sync: bool,
     pub agent_statuses: HashMap<String, bool>,
     pub has_ai_rules: bool,
+    /// Git imports whose cached checkout doesn't match `ai-rules.lock`
+    pub import_problems: Vec<String>,
+    /// Agents with generated outputs on disk that aren't in the checked agents
+    pub stale_agents: Vec<String>,
+    /// Generated outputs edited by hand since the last generate, per agent
+    pub locally_modified: BTreeMap<String, Vec<PathBuf>>,
+    /// The outputs that are out of sync and why, when anything is
+    pub files: Vec<FileStatus>,
+    /// Source files that can't be used, as `file:line:column: message` where known
+    pub invalid_sources: Vec<String>,
+    /// Out of sync artifacts per directory (relative to the project root) and agent
+    pub out_of_sync: OutOfSync,
 }
 
-#[derive(Debug)]
-struct BodyFilesOutOfSync;
+pub type OutOfSync = BTreeMap<PathBuf, BTreeMap<String, BTreeSet<Artifact>>>;
 
-impl std::fmt::Display for BodyFilesOutOfSync {
+/// `path` relative to the project root, with `.` for the root itself.
+fn relative_to(current_dir: &Path, path: &Path) -> PathBuf {
+    match path.strip_prefix(current_dir) {
+        Ok(relative) if relative != Path::new("") => relative.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// The kind of output a generated file is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Artifact {
+    Rules,
+    Mcp,
+    Commands,
+    Skills,
+}
+
+/// Why a generated output doesn't match what generate would write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Reason {
+    Missing,
+    StaleContent,
+    WrongSymlinkTarget,
+    /// Left over from an earlier generate, and no longer generated
+    Unexpected,
+    LocallyModified,
+}
+
+impl std::fmt::Display for Artifact {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Body files are out of sync")
+        let artifact = match self {
+            Artifact::Rules => "rules",
+            Artifact::Mcp => "MCP",
+            Artifact::Commands => "commands",
+            Artifact::Skills => "skills",
+        };
+        write!(f, "{artifact}")
     }
 }
 
-impl std::error::Error for BodyFilesOutOfSync {}
+impl std::fmt::Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let reason = match self {
+            Reason::Missing => "missing",
+            Reason::StaleContent => "stale content",
+            Reason::WrongSymlinkTarget => "wrong symlink target",
+            Reason::Unexpected => "unexpected file",
+            Reason::LocallyModified => "locally modified",
+        };
+        write!(f, "{reason}")
+    }
+}
+
+/// One out of sync output. Paths are relative to the project root; `agent` is `None`
+/// for outputs shared by every agent, like the rule bodies.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileStatus {
+    pub directory: PathBuf,
+    pub agent: Option<String>,
+    pub artifact: Artifact,
+    pub path: PathBuf,
+    pub reason: Reason,
+}
 
 pub fn run_status(current_dir: &Path, args: ResolvedStatusArgs) -> Result<()> {
-    println!(
-        "🔍 AI Rules Status for agents: {}, nested_depth: {}",
-        args.agents
-            .as_ref()
-            .map(|a| a.join(","))
-            .unwrap_or_else(|| "all".to_string()),
-        args.nested_depth
-    );
-
-    let status = check_project_status(current_dir, args)?;
-    print_status_results(&status);
+    if args.format == ReportFormat::Human {
+        println!(
+            "🔍 AI Rules Status for agents: {}, nested_depth: {}",
+            args.agents
+                .as_ref()
+                .map(|a| a.join(","))
+                .unwrap_or_else(|| "all".to_string()),
+            args.nested_depth
+        );
+    }
 
+    let (format, fix) = (args.format, args.fix);
+    if fix {
+        let generate_args = generate_args(&args);
+        let status = check_project_status(current_dir, args)?;
+        if !status.has_ai_rules {
+            print_status_results(&status);
+            return Err(ExitStatus(2).into());
+        }
+        if status.is_in_sync() {
+            print_success("Everything is in sync, nothing to fix");
+            return Ok(());
+        }
+        return fix_project(current_dir, generate_args, &status);
+    }
+    let status = match check_project_status(current_dir, args) {
+        // A source that can't be read is reported against it, so CI can point at it
+        Err(error) if matches!(format, ReportFormat::Github | ReportFormat::Sarif) => {
+            let fallback = Path::new(AI_RULE_SOURCE_DIR);
+            let annotation = Annotation::from_error(current_dir, &error, fallback);
+            print_annotations(format, &[annotation])?;
+            return Err(ExitStatus(1).into());
+        }
+        result => result?,
+    };
+    match format {
+        ReportFormat::Human => print_status_results(&status),
+        ReportFormat::Json => print_status_json(&status)?,
+        ReportFormat::Github | ReportFormat::Sarif => {
+            print_annotations(format, &status_annotations(current_dir, &status))?
+        }
+    }
+
+    match status.exit_code() {
+        0 => Ok(()),
+        code => Err(ExitStatus(code).into()),
+    }
+}
+
+/// Prints `annotations` as GitHub workflow commands or a SARIF log.
+pub fn print_annotations(format: ReportFormat, annotations: &[Annotation]) -> Result<()> {
+    match format {
+        ReportFormat::Sarif => {
+            println!("{}", serde_json::to_string_pretty(&sarif_log(annotations))?)
+        }
+        _ => print!("{}", github_commands(annotations)),
+    }
     Ok(())
 }
 
+/// Points each problem at the file to change: the source of a stale output where
+/// there is one, otherwise the output itself.
+fn status_annotations(current_dir: &Path, status: &ProjectStatus) -> Vec<Annotation> {
+    let source_dir = Path::new(AI_RULE_SOURCE_DIR);
+    let mut annotations = Vec::new();
+    for problem in &status.import_problems {
+        annotations.push(Annotation::new(
+            source_dir.join(LOCK_FILENAME),
+            Level::Error,
+            "import-mismatch",
+            format!("Import {problem}. Run 'ai-rules generate' to fetch it again"),
+        ));
+    }
+    for source in &status.invalid_sources {
+        annotations.push(Annotation::from_message(current_dir, source, source_dir));
+    }
+    // Outputs shared by several agents, like AGENTS.md, get one annotation
+    let mut grouped: BTreeMap<(PathBuf, &'static str, String), Vec<&str>> = BTreeMap::new();
+    for file in &status.files {
+        let key = match file.reason {
+            Reason::LocallyModified => (
+                file.path.clone(),
+                "locally-modified",
+                format!("{} was edited since the last generate", file.path.display()),
+            ),
+            reason => (
+                source_of(current_dir, file),
+                "out-of-sync",
+                format!("{}: {reason} ({})", file.path.display(), file.artifact),
+            ),
+        };
+        let owner = file.agent.as_deref().unwrap_or("all agents");
+        grouped.entry(key).or_default().push(owner);
+    }
+    for ((path, rule, problem), owners) in grouped {
+        let hint = match rule {
+            "locally-modified" => "Run 'ai-rules generate --force' to overwrite it or 'ai-rules generate --adopt' to keep the edits",
+            _ => "Run 'ai-rules generate'",
+        };
+        annotations.push(Annotation::new(
+            path,
+            Level::Error,
+            rule,
+            format!("{problem}, for {}. {hint}", owners.join(", ")),
+        ));
+    }
+    // Agents the per-file report couldn't explain still fail the check
+    for (agent, &in_sync) in &status.agent_statuses {
+        let explained = status
+            .files
+            .iter()
+            .any(|file| file.agent.as_ref().is_none_or(|owner| owner == agent));
+        if (!in_sync || status.body_files_out_of_sync)
+            && !explained
+            && status.import_problems.is_empty()
+        {
+            annotations.push(Annotation::new(
+                source_dir,
+                Level::Error,
+                "out-of-sync",
+                format!("{agent} is out of sync. Run 'ai-rules generate'"),
+            ));
+        }
+    }
+    for agent in &status.stale_agents {
+        annotations.push(Annotation::new(
+            source_dir.join(AI_RULE_CONFIG_FILENAME),
+            Level::Warning,
+            "stale-agent",
+            format!(
+                "Generated files remain for {agent}, which isn't configured. Run 'ai-rules clean'"
+            ),
+        ));
+    }
+    annotations
+}
+
+/// The source file behind an out of sync output, if it can be told from the path.
+fn source_of(current_dir: &Path, file: &FileStatus) -> PathBuf {
+    let source_dir = file.directory.join(AI_RULE_SOURCE_DIR);
+    let candidate = match file.artifact {
+        Artifact::Mcp => Some(source_dir.join(MCP_JSON)),
+        Artifact::Rules => file
+            .path
+            .file_name()
+            .and_then(|name| name.to_str()?.strip_prefix(GENERATED_FILE_PREFIX))
+            .map(|name| source_dir.join(name)),
+        Artifact::Commands | Artifact::Skills => None,
+    };
+    match candidate {
+        Some(source) if current_dir.join(&source).is_file() => {
+            source.strip_prefix(".").unwrap_or(&source).to_path_buf()
+        }
+        _ => file.path.clone(),
+    }
+}
+
 pub fn check_project_status(current_dir: &Path, args: ResolvedStatusArgs) -> Result<ProjectStatus> {
     let registry = AgentToolRegistry::new();
+    // The project is in sync when a generate with the same options would change nothing
+    let mut plan_args = ResolvedGenerateArgs {
+        local_edits: LocalEditPolicy::Force,
+        dry_run: true,
+        ..generate_args(&args)
+    };
+    let fix = args.fix;
     let agents: Vec<String> = args.agents.unwrap_or_else(|| registry.get_all_tool_names());
 
     // Determine command agents - use command_agents if specified, otherwise fall back to agents
@@ -64,179 +297,446 @@ pub fn check_project_status(current_dir: &Path, args: ResolvedStatusArgs) -> Res
         .map(|agent| (agent.clone(), true))
         .collect();
     let mut has_ai_rules = false;
+    let mut import_problems = Vec::new();
+    let mut invalid_sources = Vec::new();
+    let mut out_of_sync = OutOfSync::new();
+    let mut stale_agents: Vec<String> = Vec::new();
+    let mut locally_modified: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
     let filter = file_utils::DirectoryFilter::from_project_root(current_dir);
+    let root_source_options = SourceOptions {
+        rule_order: args.rule_order.clone(),
+        include_tags: args.include_tags.clone(),
+        inherit: args.inherit,
+        local_rules: args.local_rules,
+        ..Default::default()
+    };
+
+    // Directories to plan, with the agents to plan in each: those whose imports can be
+    // read and that changed since the last generate
+    let mut planned_dirs: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
 
-    let traversal_result = file_utils::traverse_project_directories(
+    file_utils::traverse_project_directories(
         current_dir,
         args.nested_depth,
         0,
         &filter,
         &mut |dir| {
-            let is_symlink_mode = detect_symlink_mode(dir);
-            let mut source_files = Vec::new();
-            if is_symlink_mode {
+            let relative_dir = relative_to(current_dir, dir);
+            let problems = check_git_imports(dir)?;
+            if !problems.is_empty() {
+                // The imported files can't be read until the imports are fetched again,
+                // so the directory is left out of the plan and its agents are out of sync
                 has_ai_rules = true;
-            } else {
-                source_files = operations::find_source_files(dir)?;
-                if !source_files.is_empty() {
-                    has_ai_rules = true;
+                for problem in problems {
+                    import_problems.push(match relative_dir == Path::new(".") {
+                        true => problem,
+                        false => format!("{}: {problem}", relative_dir.display()),
+                    });
                 }
-                if !check_body_files(dir, &source_files)? {
-                    return Err(BodyFilesOutOfSync.into());
+                let manifest_agents: Vec<String> = read_manifest(dir)?
+                    .map(|manifest| manifest.agents.into_keys().collect())
+                    .unwrap_or_default();
+                let dir_agents = out_of_sync.entry(relative_dir).or_default();
+                for agent in &all_agents {
+                    if manifest_agents.is_empty() || manifest_agents.contains(agent) {
+                        dir_agents
+                            .entry(agent.clone())
+                            .or_default()
+                            .insert(Artifact::Rules);
+                    }
                 }
+                return Ok(());
             }
-
-            for agent in &agents {
-                if agent_statuses[agent]
-                    && !check_agent_files(dir, agent, &source_files, &registry, is_symlink_mode)?
-                {
-                    agent_statuses.insert(agent.clone(), false);
-                }
+            // Generate skips an invalid mcp.json, so it is checked on its own
+            if let Err(error) = read_mcp_config(dir) {
+                invalid_sources.push(format!("{error:#}"));
             }
 
-            for agent in &agents {
-                if agent_statuses[agent] && !check_mcp_files(dir, agent, &registry)? {
-                    agent_statuses.insert(agent.clone(), false);
+            let manifest = read_manifest(dir)?;
+            if let Some(manifest) = &manifest {
+                for (agent, entries) in &manifest.agents {
+                    if !agent_statuses.contains_key(agent) {
+                        if !stale_agents.contains(agent) {
+                            stale_agents.push(agent.clone());
+                        }
+                        continue;
+                    }
+                    for entry in entries {
+                        if entry.is_locally_modified(dir)? {
+                            locally_modified.entry(agent.clone()).or_default().push(
+                                dir.join(entry.path())
+                                    .strip_prefix(current_dir)?
+                                    .to_path_buf(),
+                            );
+                        }
+                    }
                 }
             }
 
-            for agent in &command_agents {
-                if agent_statuses[agent] && !check_command_files(dir, agent, &registry)? {
-                    agent_statuses.insert(agent.clone(), false);
-                }
+            let variables =
+                TemplateVariables::for_directory(dir, &args.variables, &args.var_overrides)?;
+            let source_options = root_source_options.for_directory(dir, variables)?;
+            if uses_symlink_mode(dir, &source_options)?
+                || !find_source_files_with_options(dir, &source_options)?.is_empty()
+                || (source_options.local_rules == LocalRulesMode::Native
+                    && !find_personal_source_files(dir, &source_options)?.is_empty())
+            {
+                has_ai_rules = true;
             }
 
-            for agent in &agents {
-                if agent_statuses[agent] && !check_skill_files(dir, agent, &registry)? {
-                    agent_statuses.insert(agent.clone(), false);
+            // A directory whose inputs and outputs are as the last generate left them
+            // is in sync without planning it
+            let is_up_to_date = match &manifest {
+                Some(manifest) => {
+                    let inputs = inputs_hash(
+                        dir,
+                        &agents,
+                        &command_agents,
+                        &source_options,
+                        args.output_mode,
+                        args.link_modes,
+                    )?;
+                    manifest.is_up_to_date(dir, &inputs)?
                 }
+                None => false,
+            };
+            if !is_up_to_date {
+                planned_dirs.insert(dir.to_path_buf(), all_agents.clone());
             }
-
             Ok(())
         },
-    );
-
-    match traversal_result {
-        Err(e) if e.is::<BodyFilesOutOfSync>() => {
-            body_files_out_of_sync = true;
-            agent_statuses
-                .iter_mut()
-                .for_each(|(_, status)| *status = false);
+    )?;
+
+    let changes = match planned_dirs.is_empty() {
+        true => Vec::new(),
+        false => {
+            plan_args.targets = Some(planned_dirs);
+            plan_owned_changes(current_dir, plan_args)?
+        }
+    };
+    for owned in &changes {
+        // Status doesn't check the .gitignore section
+        if owned.change.path == current_dir.join(".gitignore") {
+            continue;
+        }
+        // Every agent reads the shared outputs, like the rule bodies
+        let owners: Vec<&String> = match owned.agents.is_empty() {
+            true => {
+                body_files_out_of_sync = true;
+                all_agents.iter().collect()
+            }
+            false => owned.agents.iter().collect(),
+        };
+        for agent in owners
+            .into_iter()
+            .filter(|agent| all_agents.contains(agent))
+        {
+            let artifact = match owned.agents.is_empty() {
+                true => Artifact::Rules,
+                false => artifact_of(&registry, Some(agent), &owned.dir, &owned.change.path),
+            };
+            out_of_sync
+                .entry(relative_to(current_dir, &owned.dir))
+                .or_default()
+                .entry(agent.clone())
+                .or_default()
+                .insert(artifact);
+        }
+    }
+    // --fix regenerates without listing the files first
+    let files = match fix {
+        true => Vec::new(),
+        false => out_of_sync_files(current_dir, &changes, &registry, &locally_modified),
+    };
+    for agents in out_of_sync.values() {
+        for agent in agents.keys() {
+            agent_statuses.insert(agent.clone(), false);
         }
-        Err(e) => return Err(e),
-        Ok(_) => {}
+    }
+    if body_files_out_of_sync {
+        agent_statuses
+            .iter_mut()
+            .for_each(|(_, status)| *status = false);
     }
 
     Ok(ProjectStatus {
         body_files_out_of_sync,
         agent_statuses,
         has_ai_rules,
+        import_problems,
+        stale_agents,
+        locally_modified,
+        files,
+        invalid_sources,
+        out_of_sync,
     })
 }
 
-fn check_body_files(current_dir: &Path, source_files: &[SourceFile]) -> Result<bool> {
-    let generated_dir = generated_body_file_dir(current_dir);
-
-    if source_files.is_empty() {
-        return Ok(!generated_dir.exists());
+/// The generate options matching these status options.
+fn generate_args(args: &ResolvedStatusArgs) -> ResolvedGenerateArgs {
+    ResolvedGenerateArgs {
+        agents: args.agents.clone(),
+        command_agents: args.command_agents.clone(),
+        gitignore: args.gitignore,
+        nested_depth: args.nested_depth,
+        variables: args.variables.clone(),
+        var_overrides: args.var_overrides.clone(),
+        rule_order: args.rule_order.clone(),
+        include_tags: args.include_tags.clone(),
+        inherit: args.inherit,
+        local_rules: args.local_rules,
+        output_mode: args.output_mode,
+        link_modes: args.link_modes,
+        ..Default::default()
     }
-    let expected_body_files = operations::generate_body_contents(source_files, current_dir);
-    file_utils::check_directory_exact_match(&generated_dir, &expected_body_files)
 }
 
-fn check_agent_files(
+/// Regenerates only the directories and agents `status` found out of sync.
+fn fix_project(
     current_dir: &Path,
-    agent_name: &str,
-    source_files: &[SourceFile],
-    registry: &AgentToolRegistry,
-    is_symlink_mode: bool,
-) -> Result<bool> {
-    let Some(tool) = registry.get_tool(agent_name) else {
-        return Ok(true);
-    };
-    if is_symlink_mode {
-        return tool.check_symlink(current_dir);
-    }
-    if tool.uses_inlined_symlink() {
-        // Nested traversal visits directories with no local ai-rules source files.
-        // In those directories, treat inlined-symlink agents like content agents:
-        // the "empty source" state should validate as no generated files expected.
-        if source_files.is_empty() {
-            return tool.check_agent_contents(source_files, current_dir);
+    mut generate_args: ResolvedGenerateArgs,
+    status: &ProjectStatus,
+) -> Result<()> {
+    // Out of sync agents without an out of sync directory need every directory
+    // regenerated. Imports that need fetching are fetched in their own directory
+    let located: BTreeSet<&String> = status
+        .out_of_sync
+        .values()
+        .flat_map(|agents| agents.keys())
+        .collect();
+    let unlocated = status
+        .agent_statuses
+        .iter()
+        .any(|(agent, &in_sync)| !in_sync && !located.contains(agent));
+    if !unlocated {
+        generate_args.targets = Some(
+            status
+                .out_of_sync
+                .iter()
+                .map(|(dir, agents)| (current_dir.join(dir), agents.keys().cloned().collect()))
+                .collect(),
+        );
+    }
+    commands::run_generate(current_dir, generate_args)?;
+
+    println!("\n🔧 Regenerated:");
+    if unlocated {
+        println!("   every directory");
+        return Ok(());
+    }
+    for (dir, agents) in &status.out_of_sync {
+        for (agent, artifacts) in agents {
+            let artifacts: Vec<String> = artifacts.iter().map(Artifact::to_string).collect();
+            println!("   {}: {agent} ({})", dir.display(), artifacts.join(", "));
         }
-        return tool.check_inlined_symlink(current_dir);
     }
-    tool.check_agent_contents(source_files, current_dir)
+    Ok(())
 }
 
-fn check_mcp_files(
+/// Lists each output generate would change, with the agent that owns it and why.
+fn out_of_sync_files(
     current_dir: &Path,
-    agent_name: &str,
+    changes: &[OwnedChange],
     registry: &AgentToolRegistry,
-) -> Result<bool> {
-    let Some(tool) = registry.get_tool(agent_name) else {
-        return Ok(true);
-    };
-    let Some(mcp_gen) = tool.mcp_generator() else {
-        return Ok(true);
-    };
-    mcp_gen.check_mcp(current_dir)
+    locally_modified: &BTreeMap<String, Vec<PathBuf>>,
+) -> Vec<FileStatus> {
+    let relative = |path: &Path| relative_to(current_dir, path);
+    let mut files = Vec::new();
+    for owned in changes {
+        let change = &owned.change;
+        // Status doesn't check the .gitignore section
+        if change.path == current_dir.join(".gitignore") {
+            continue;
+        }
+        let path = relative(&change.path);
+        let reason = match (&change.before, &change.after) {
+            (None, _) => Reason::Missing,
+            (_, None) => Reason::Unexpected,
+            (Some(Snapshot::Symlink(_)), Some(Snapshot::Symlink(_))) => Reason::WrongSymlinkTarget,
+            _ => Reason::StaleContent,
+        };
+        let agents: Vec<Option<&String>> = match owned.agents.is_empty() {
+            true => vec![None],
+            false => owned.agents.iter().map(Some).collect(),
+        };
+        for agent in agents {
+            let edited = agent
+                .and_then(|agent| locally_modified.get(agent))
+                .is_some_and(|paths| paths.contains(&path));
+            files.push(FileStatus {
+                directory: relative(&owned.dir),
+                agent: agent.cloned(),
+                artifact: artifact_of(registry, agent, &owned.dir, &change.path),
+                path: path.clone(),
+                reason: if edited {
+                    Reason::LocallyModified
+                } else {
+                    reason
+                },
+            });
+        }
+    }
+    files.sort_by(|a, b| (&a.directory, &a.agent, &a.path).cmp(&(&b.directory, &b.agent, &b.path)));
+    files
 }
 
-fn check_command_files(
-    current_dir: &Path,
-    agent_name: &str,
+/// Tells MCP, command and skill outputs apart by the agent's gitignore patterns.
+fn artifact_of(
     registry: &AgentToolRegistry,
-) -> Result<bool> {
-    let Some(tool) = registry.get_tool(agent_name) else {
-        return Ok(true);
+    agent: Option<&String>,
+    dir: &Path,
+    path: &Path,
+) -> Artifact {
+    let Some(tool) = agent.and_then(|agent| registry.get_tool(agent)) else {
+        return Artifact::Rules;
     };
-    let Some(cmd_gen) = tool.command_generator() else {
-        return Ok(true);
+    let relative = path.strip_prefix(dir).unwrap_or(path).to_string_lossy();
+    let matches = |patterns: Vec<String>| {
+        patterns.iter().any(|pattern| {
+            let prefix = pattern.split(['*', '?', '[']).next().unwrap_or_default();
+            !prefix.is_empty() && relative.starts_with(prefix)
+        })
     };
-    cmd_gen.check_commands(current_dir)
+    if tool
+        .mcp_generator()
+        .is_some_and(|mcp| matches(mcp.mcp_gitignore_patterns()))
+    {
+        Artifact::Mcp
+    } else if tool
+        .command_generator()
+        .is_some_and(|commands| matches(commands.command_gitignore_patterns()))
+    {
+        Artifact::Commands
+    } else if tool
+        .skills_generator()
+        .is_some_and(|skills| matches(skills.skills_gitignore_patterns()))
+    {
+        Artifact::Skills
+    } else {
+        Artifact::Rules
+    }
 }
 
-fn check_skill_files(
-    current_dir: &Path,
-    agent_name: &str,
-    registry: &AgentToolRegistry,
-) -> Result<bool> {
-    let Some(tool) = registry.get_tool(agent_name) else {
-        return Ok(true);
-    };
-    let Some(skills_gen) = tool.skills_generator() else {
-        return Ok(true);
+/// Sync state of one agent in the JSON report.
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum AgentState {
+    InSync,
+    OutOfSync,
+    LocallyModified,
+}
+
+#[derive(Serialize)]
+struct StatusReport<'a> {
+    in_sync: bool,
+    has_ai_rules: bool,
+    agents: BTreeMap<&'a str, AgentState>,
+    import_problems: &'a [String],
+    invalid_sources: &'a [String],
+    stale_agents: &'a [String],
+    files: &'a [FileStatus],
+}
+
+impl ProjectStatus {
+    fn is_in_sync(&self) -> bool {
+        !self.body_files_out_of_sync
+            && self.agent_statuses.values().all(|&in_sync| in_sync)
+            && self.invalid_sources.is_empty()
+    }
+
+    /// 0 when in sync, 1 when out of sync and 2 when there are no rules.
+    fn exit_code(&self) -> i32 {
+        match (self.has_ai_rules, self.is_in_sync()) {
+            (false, _) => 2,
+            (true, true) => 0,
+            (true, false) => 1,
+        }
+    }
+}
+
+fn print_status_json(status: &ProjectStatus) -> Result<()> {
+    let agents = status
+        .agent_statuses
+        .iter()
+        .map(|(agent, &in_sync)| {
+            let state = if status.locally_modified.contains_key(agent) {
+                AgentState::LocallyModified
+            } else if in_sync && !status.body_files_out_of_sync {
+                AgentState::InSync
+            } else {
+                AgentState::OutOfSync
+            };
+            (agent.as_str(), state)
+        })
+        .collect();
+    let report = StatusReport {
+        in_sync: status.has_ai_rules && status.is_in_sync(),
+        has_ai_rules: status.has_ai_rules,
+        agents,
+        import_problems: &status.import_problems,
+        invalid_sources: &status.invalid_sources,
+        stale_agents: &status.stale_agents,
+        files: &status.files,
     };
-    skills_gen.check_skills(current_dir)
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+fn print_files(status: &ProjectStatus, agent: Option<&String>) {
+    for file in status
+        .files
+        .iter()
+        .filter(|file| file.agent.as_ref() == agent)
+    {
+        println!(
+            "      {}: {} ({})",
+            file.path.display(),
+            file.reason,
+            file.artifact
+        );
+    }
 }
 
 fn print_status_results(status: &ProjectStatus) {
     if !status.has_ai_rules {
         println!("  📝 No AI rules found in this project");
         println!("\n💡 Run 'ai-rules init' to get started");
-        std::process::exit(2);
+        return;
+    }
+
+    for problem in &status.import_problems {
+        println!("  ❌ import {problem}");
+    }
+
+    for source in &status.invalid_sources {
+        println!("  ❌ {source}");
     }
 
-    if status.body_files_out_of_sync {
-        for agent in status.agent_statuses.keys() {
+    if status.files.iter().any(|file| file.agent.is_none()) {
+        println!("  ❌ shared files: out of sync");
+        print_files(status, None);
+    }
+
+    for (agent, in_sync) in &status.agent_statuses {
+        if let Some(paths) = status.locally_modified.get(agent) {
+            let paths: Vec<String> = paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            println!("  ✏️  {agent}: locally modified ({})", paths.join(", "));
+        } else if *in_sync && !status.body_files_out_of_sync {
+            println!("  ✅ {agent}: in sync");
+        } else {
             println!("  ❌ {agent}: out of sync");
         }
-    } else {
-        for (agent, in_sync) in &status.agent_statuses {
-            if *in_sync {
-                println!("  ✅ {agent}: in sync");
-            } else {
-                println!("  ❌ {agent}: out of sync");
-            }
-        }
+        print_files(status, Some(agent));
     }
 
-    print_next_steps(status);
-
-    if status.body_files_out_of_sync || status.agent_statuses.values().any(|&in_sync| !in_sync) {
-        std::process::exit(1);
+    for agent in &status.stale_agents {
+        println!("  ⚠️  {agent}: generated files remain but the agent isn't configured");
     }
+
+    print_next_steps(status);
 }
 
 fn print_next_steps(status: &ProjectStatus) {
@@ -253,12 +753,25 @@ fn print_next_steps(status: &ProjectStatus) {
         println!("\n💡 Next steps:");
         println!("    ai-rules generate --help             # See examples and options to generate sync files");
     }
+    if !status.import_problems.is_empty() {
+        println!("    ai-rules generate                    # Fetch imports at the commits in ai-rules.lock");
+    }
+    if !status.locally_modified.is_empty() {
+        println!("    ai-rules generate --force            # Overwrite the hand edits of generated files");
+        println!("    ai-rules generate --adopt            # Move hand edits of MCP and config files into ai-rules/");
+    }
+    if !status.stale_agents.is_empty() {
+        println!("    ai-rules clean                       # Remove files generated for agents no longer configured");
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::OutputMode;
     use crate::utils::test_utils::helpers::*;
+    use std::collections::BTreeMap;
+    use std::fs;
     use tempfile::TempDir;
 
     const NESTED_DEPTH: usize = 6;
@@ -278,6 +791,7 @@ Test rule content"#;
             agents: None,
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -298,6 +812,7 @@ Test rule content"#;
             agents: None,
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -319,6 +834,7 @@ Test rule content"#;
             agents: None,
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -346,6 +862,7 @@ Test rule content"#;
             agents: None,
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -368,6 +885,7 @@ Test rule content"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: NESTED_DEPTH,
+                ..Default::default()
             },
         )
         .unwrap();
@@ -384,6 +902,7 @@ Test rule content"#;
             agents: None,
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -420,6 +939,7 @@ Test rule content"#;
             agents: None,
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -452,6 +972,7 @@ Test rule content"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth,
+                ..Default::default()
             },
         )
         .unwrap();
@@ -460,6 +981,7 @@ Test rule content"#;
             agents: None,
             command_agents: None,
             nested_depth,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -473,6 +995,7 @@ Test rule content"#;
             agents: None,
             command_agents: None,
             nested_depth: 1,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -497,6 +1020,7 @@ Test rule content"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: 1,
+                ..Default::default()
             },
         )
         .unwrap();
@@ -505,6 +1029,7 @@ Test rule content"#;
             agents: None,
             command_agents: None,
             nested_depth: 1,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -531,6 +1056,7 @@ Test rule content"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: NESTED_DEPTH,
+                ..Default::default()
             },
         )
         .unwrap();
@@ -539,6 +1065,7 @@ Test rule content"#;
             agents: Some(vec!["claude".to_string()]),
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -574,6 +1101,7 @@ Test rule content"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: NESTED_DEPTH,
+                ..Default::default()
             },
         )
         .unwrap();
@@ -595,6 +1123,7 @@ Test rule content"#;
             agents: Some(vec!["claude".to_string()]),
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -622,6 +1151,7 @@ Test rule content"#;
             agents: Some(vec!["claude".to_string()]),
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -647,6 +1177,7 @@ Test rule content"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: NESTED_DEPTH,
+                ..Default::default()
             },
         );
         assert!(generate_result.is_ok());
@@ -655,6 +1186,7 @@ Test rule content"#;
             agents: Some(vec!["claude".to_string()]),
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -688,6 +1220,7 @@ Test command body"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: NESTED_DEPTH,
+                ..Default::default()
             },
         )
         .unwrap();
@@ -715,6 +1248,7 @@ Test command body"#;
             agents: Some(vec!["claude".to_string()]),
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -747,6 +1281,7 @@ Test command body"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: NESTED_DEPTH,
+                ..Default::default()
             },
         )
         .unwrap();
@@ -761,6 +1296,7 @@ Test command body"#;
             agents: Some(vec!["claude".to_string()]),
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -793,6 +1329,7 @@ Test command body"#;
                 command_agents: Some(vec!["claude".to_string(), "amp".to_string()]),
                 gitignore: false,
                 nested_depth: NESTED_DEPTH,
+                ..Default::default()
             },
         );
         assert!(generate_result.is_ok());
@@ -810,6 +1347,7 @@ Test command body"#;
             agents: Some(vec!["amp".to_string()]),
             command_agents: Some(vec!["claude".to_string(), "amp".to_string()]),
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -846,6 +1384,7 @@ Test command body"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: NESTED_DEPTH,
+                ..Default::default()
             },
         );
         assert!(generate_result.is_ok());
@@ -854,6 +1393,7 @@ Test command body"#;
             agents: Some(vec!["claude".to_string()]),
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -886,6 +1426,7 @@ Test command body"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: NESTED_DEPTH,
+                ..Default::default()
             },
         )
         .unwrap();
@@ -904,6 +1445,7 @@ Test command body"#;
             agents: Some(vec!["claude".to_string()]),
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -934,6 +1476,7 @@ Test command body"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: NESTED_DEPTH,
+                ..Default::default()
             },
         );
         assert!(generate_result.is_ok());
@@ -942,6 +1485,7 @@ Test command body"#;
             agents: Some(vec!["claude".to_string()]),
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -968,6 +1512,7 @@ Test command body"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: NESTED_DEPTH,
+                ..Default::default()
             },
         );
         assert!(generate_result.is_ok());
@@ -976,6 +1521,7 @@ Test command body"#;
             agents: Some(vec!["claude".to_string()]),
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -1007,6 +1553,7 @@ Test command body"#;
                 command_agents: None,
                 gitignore: false,
                 nested_depth: NESTED_DEPTH,
+                ..Default::default()
             },
         );
         assert!(generate_result.is_ok());
@@ -1018,6 +1565,7 @@ Test command body"#;
             agents: Some(vec!["claude".to_string()]),
             command_agents: None,
             nested_depth: NESTED_DEPTH,
+            ..Default::default()
         };
         let result = check_project_status(temp_dir.path(), args);
         assert!(result.is_ok());
@@ -1029,4 +1577,529 @@ Test command body"#;
         // Claude should be out of sync because orphaned symlinks exist
         assert!(!status.agent_statuses["claude"]);
     }
+
+    #[test]
+    fn test_status_detects_template_variable_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "ai-rules/test.md", "Run {{test_command}}");
+        create_file(
+            temp_dir.path(),
+            "ai-rules/commands/test.md",
+            "Run {{test_command}}",
+        );
+        let variables = BTreeMap::from([("test_command".to_string(), "cargo test".to_string())]);
+
+        crate::commands::generate::run_generate(
+            temp_dir.path(),
+            crate::cli::ResolvedGenerateArgs {
+                agents: Some(vec!["claude".to_string()]),
+                nested_depth: NESTED_DEPTH,
+                variables: variables.clone(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let in_sync = check_project_status(
+            temp_dir.path(),
+            ResolvedStatusArgs {
+                agents: Some(vec!["claude".to_string()]),
+                nested_depth: NESTED_DEPTH,
+                variables: variables.clone(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(!in_sync.body_files_out_of_sync);
+        assert!(in_sync.agent_statuses["claude"]);
+
+        let overridden = check_project_status(
+            temp_dir.path(),
+            ResolvedStatusArgs {
+                agents: Some(vec!["claude".to_string()]),
+                nested_depth: NESTED_DEPTH,
+                variables,
+                var_overrides: BTreeMap::from([(
+                    "test_command".to_string(),
+                    "make test".to_string(),
+                )]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(overridden.body_files_out_of_sync);
+    }
+
+    #[test]
+    fn test_fix_project_regenerates_only_out_of_sync_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        create_file(project, "ai-rules/root.md", "Root rule");
+        create_file(project, "nested/ai-rules/nested.md", "Nested rule");
+        let args = || ResolvedStatusArgs {
+            agents: Some(vec!["claude".to_string(), "gemini".to_string()]),
+            nested_depth: NESTED_DEPTH,
+            fix: true,
+            ..Default::default()
+        };
+        commands::run_generate(project, generate_args(&args())).unwrap();
+
+        create_file(project, "nested/ai-rules/nested.md", "Changed nested rule");
+
+        let status = check_project_status(project, args()).unwrap();
+        assert!(status.files.is_empty());
+        assert_eq!(
+            status.out_of_sync.keys().collect::<Vec<_>>(),
+            vec![Path::new("nested")]
+        );
+        // Changed after the check, so only regenerated if fix goes beyond what it found
+        create_file(project, "ai-rules/root.md", "Changed root rule");
+        fix_project(project, generate_args(&args()), &status).unwrap();
+
+        assert_file_content(
+            project,
+            "nested/ai-rules/.generated-ai-rules/ai-rules-generated-nested.md",
+            "Changed nested rule\n",
+        );
+        assert_file_content(
+            project,
+            "ai-rules/.generated-ai-rules/ai-rules-generated-root.md",
+            "Root rule\n",
+        );
+    }
+
+    #[test]
+    fn test_status_honours_profile_tags() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "ai-rules/general.md", TEST_RULE_CONTENT);
+        create_file(
+            temp_dir.path(),
+            "ai-rules/ios.md",
+            "---\ndescription: Ios\nalwaysApply: true\ntags: [mobile]\n---\nIos body",
+        );
+        let include_tags = Some(vec!["backend".to_string()]);
+
+        crate::commands::generate::run_generate(
+            temp_dir.path(),
+            crate::cli::ResolvedGenerateArgs {
+                agents: Some(vec!["claude".to_string()]),
+                nested_depth: NESTED_DEPTH,
+                include_tags: include_tags.clone(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let with_profile = check_project_status(
+            temp_dir.path(),
+            ResolvedStatusArgs {
+                agents: Some(vec!["claude".to_string()]),
+                nested_depth: NESTED_DEPTH,
+                include_tags,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(with_profile.agent_statuses["claude"]);
+
+        let without_profile = check_project_status(
+            temp_dir.path(),
+            ResolvedStatusArgs {
+                agents: Some(vec!["claude".to_string()]),
+                nested_depth: NESTED_DEPTH,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(without_profile.body_files_out_of_sync);
+    }
+
+    #[test]
+    fn test_status_accounts_for_inherited_rules() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join(".git")).unwrap();
+        create_file(temp_dir.path(), "ai-rules/org.md", TEST_RULE_CONTENT);
+        create_file(
+            temp_dir.path(),
+            "service/ai-rules/local.md",
+            TEST_RULE_CONTENT,
+        );
+
+        crate::commands::generate::run_generate(
+            temp_dir.path(),
+            crate::cli::ResolvedGenerateArgs {
+                agents: Some(vec!["claude".to_string()]),
+                nested_depth: NESTED_DEPTH,
+                inherit: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let status_args = || ResolvedStatusArgs {
+            agents: Some(vec!["claude".to_string()]),
+            nested_depth: NESTED_DEPTH,
+            inherit: true,
+            ..Default::default()
+        };
+
+        let status = check_project_status(temp_dir.path(), status_args()).unwrap();
+        assert!(!status.body_files_out_of_sync);
+        assert!(status.agent_statuses["claude"]);
+
+        // Only the root rule changes, but the nested composition is now stale
+        create_file(temp_dir.path(), "ai-rules/org.md", "Updated org rule");
+        crate::commands::generate::run_generate(
+            temp_dir.path(),
+            crate::cli::ResolvedGenerateArgs {
+                agents: Some(vec!["claude".to_string()]),
+                nested_depth: 0,
+                inherit: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let status = check_project_status(temp_dir.path(), status_args()).unwrap();
+        assert!(status.body_files_out_of_sync);
+    }
+
+    #[test]
+    fn test_check_project_status_git_import_lock_mismatch() {
+        use crate::operations::git_imports::test_helpers::*;
+
+        let temp_dir = TempDir::new().unwrap();
+        let pack = temp_dir.path().join("pack");
+        let url = create_git_pack(&pack, &[("security.md", TEST_RULE_CONTENT)]);
+        let project = temp_dir.path().join("project");
+        create_file(
+            &project,
+            "ai-rules/ai-rules-config.yaml",
+            &format!("imports:\n  - url: {url}\n    ref: main\n"),
+        );
+        crate::commands::generate::run_generate(
+            &project,
+            crate::cli::ResolvedGenerateArgs {
+                agents: Some(vec!["claude".to_string()]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let status_args = || ResolvedStatusArgs {
+            agents: Some(vec!["claude".to_string()]),
+            ..Default::default()
+        };
+
+        let status = check_project_status(&project, status_args()).unwrap();
+        assert!(status.import_problems.is_empty());
+        assert!(status.agent_statuses["claude"]);
+
+        // Someone else bumped the lock; the local cache still has the old commit
+        let lock_path = project.join("ai-rules/ai-rules.lock");
+        let old_commit = crate::operations::git_imports::read_lock_file(&project)
+            .unwrap()
+            .imports[0]
+            .commit
+            .clone();
+        let new_commit = commit_files(&pack, &[("security.md", "Updated")]);
+        let lock = std::fs::read_to_string(&lock_path).unwrap();
+        std::fs::write(&lock_path, lock.replace(&old_commit, &new_commit)).unwrap();
+
+        let status = check_project_status(&project, status_args()).unwrap();
+        assert_eq!(status.import_problems.len(), 1);
+        assert!(!status.agent_statuses["claude"]);
+    }
+
+    #[test]
+    fn test_status_attributes_import_problems_to_their_directory() {
+        use crate::operations::git_imports::test_helpers::*;
+
+        let temp_dir = TempDir::new().unwrap();
+        let pack = temp_dir.path().join("pack");
+        let url = create_git_pack(&pack, &[("security.md", TEST_RULE_CONTENT)]);
+        let project = temp_dir.path().join("project");
+        create_file(&project, "ai-rules/root.md", "Root rule");
+        create_file(
+            &project,
+            "service/ai-rules/ai-rules-config.yaml",
+            &format!("imports:\n  - url: {url}\n    ref: main\n"),
+        );
+        let status_args = || ResolvedStatusArgs {
+            agents: Some(vec!["claude".to_string()]),
+            nested_depth: NESTED_DEPTH,
+            ..Default::default()
+        };
+        commands::run_generate(&project, generate_args(&status_args())).unwrap();
+        fs::remove_dir_all(project.join("service/ai-rules/.cache")).unwrap();
+
+        let status = check_project_status(&project, status_args()).unwrap();
+        assert_eq!(status.import_problems.len(), 1);
+        assert!(status.import_problems[0].starts_with("service: "));
+        assert!(!status.body_files_out_of_sync);
+        assert_eq!(
+            status.out_of_sync.keys().collect::<Vec<_>>(),
+            vec![Path::new("service")]
+        );
+
+        // The other directories are still checked
+        create_file(&project, "ai-rules/root.md", "Changed root rule");
+        let status = check_project_status(&project, status_args()).unwrap();
+        assert_eq!(
+            status.out_of_sync.keys().collect::<Vec<_>>(),
+            vec![Path::new("."), Path::new("service")]
+        );
+    }
+
+    #[test]
+    fn test_status_checks_native_personal_rules() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "ai-rules/test.md", TEST_RULE_CONTENT);
+        create_file(temp_dir.path(), "ai-rules/notes.local.md", "Use my fork");
+
+        crate::commands::generate::run_generate(
+            temp_dir.path(),
+            crate::cli::ResolvedGenerateArgs {
+                agents: Some(vec!["claude".to_string()]),
+                local_rules: LocalRulesMode::Native,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let status_args = || ResolvedStatusArgs {
+            agents: Some(vec!["claude".to_string()]),
+            local_rules: LocalRulesMode::Native,
+            ..Default::default()
+        };
+        assert!(
+            check_project_status(temp_dir.path(), status_args())
+                .unwrap()
+                .agent_statuses["claude"]
+        );
+
+        create_file(temp_dir.path(), "ai-rules/notes.local.md", "Use upstream");
+        let status = check_project_status(temp_dir.path(), status_args()).unwrap();
+        assert!(!status.body_files_out_of_sync);
+        assert!(!status.agent_statuses["claude"]);
+    }
+
+    #[test]
+    fn test_status_compares_only_managed_block() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "ai-rules/test.md", TEST_RULE_CONTENT);
+        create_file(temp_dir.path(), "CLAUDE.md", "# Team notes\n");
+
+        crate::commands::generate::run_generate(
+            temp_dir.path(),
+            crate::cli::ResolvedGenerateArgs {
+                agents: Some(vec!["claude".to_string()]),
+                output_mode: OutputMode::ManagedBlock,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let status_args = || ResolvedStatusArgs {
+            agents: Some(vec!["claude".to_string()]),
+            output_mode: OutputMode::ManagedBlock,
+            ..Default::default()
+        };
+
+        let claude_path = temp_dir.path().join("CLAUDE.md");
+        let content = std::fs::read_to_string(&claude_path).unwrap();
+        std::fs::write(&claude_path, format!("{content}\nMore notes\n")).unwrap();
+        assert!(
+            check_project_status(temp_dir.path(), status_args())
+                .unwrap()
+                .agent_statuses["claude"]
+        );
+
+        std::fs::write(&claude_path, content.replace("Test rule content", "Edited")).unwrap();
+        assert!(
+            !check_project_status(temp_dir.path(), status_args())
+                .unwrap()
+                .agent_statuses["claude"]
+        );
+    }
+
+    #[test]
+    fn test_status_reports_stale_agents_from_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "ai-rules/test.md", TEST_RULE_CONTENT);
+        create_file(temp_dir.path(), "ai-rules/mcp.json", TEST_MCP_CONFIG);
+
+        crate::commands::generate::run_generate(
+            temp_dir.path(),
+            crate::cli::ResolvedGenerateArgs {
+                agents: Some(vec!["claude".to_string(), "cursor".to_string()]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let status_args = || ResolvedStatusArgs {
+            agents: Some(vec!["claude".to_string()]),
+            ..Default::default()
+        };
+
+        let status = check_project_status(temp_dir.path(), status_args()).unwrap();
+        assert!(status.agent_statuses["claude"]);
+        assert_eq!(status.stale_agents, vec!["cursor".to_string()]);
+
+        create_file(temp_dir.path(), ".mcp.json", "{}");
+        let status = check_project_status(temp_dir.path(), status_args()).unwrap();
+        assert!(!status.agent_statuses["claude"]);
+        assert_eq!(
+            status.locally_modified["claude"],
+            vec![PathBuf::from(".mcp.json")]
+        );
+    }
+
+    #[test]
+    fn test_status_skips_planning_directories_the_manifest_shows_unchanged() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        create_file(project, "ai-rules/test.md", TEST_RULE_CONTENT);
+        create_file(project, "ai-rules/mcp.json", TEST_MCP_CONFIG);
+        let agents = vec!["claude".to_string()];
+        crate::commands::generate::run_generate(
+            project,
+            crate::cli::ResolvedGenerateArgs {
+                agents: Some(agents.clone()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        // The inputs status works out for the root directory
+        let is_up_to_date = || {
+            let variables =
+                TemplateVariables::for_directory(project, &BTreeMap::new(), &BTreeMap::new())
+                    .unwrap();
+            let source_options = SourceOptions::default()
+                .for_directory(project, variables)
+                .unwrap();
+            let inputs = inputs_hash(
+                project,
+                &agents,
+                &agents,
+                &source_options,
+                OutputMode::default(),
+                Default::default(),
+            )
+            .unwrap();
+            read_manifest(project)
+                .unwrap()
+                .unwrap()
+                .is_up_to_date(project, &inputs)
+                .unwrap()
+        };
+        assert!(is_up_to_date());
+
+        // An edited output, a removed rule body and an edited rule each need a plan
+        let mcp = fs::read_to_string(project.join(".mcp.json")).unwrap();
+        create_file(project, ".mcp.json", "{}");
+        assert!(!is_up_to_date());
+        create_file(project, ".mcp.json", &mcp);
+        assert!(is_up_to_date());
+        let bodies = project.join("ai-rules/.generated-ai-rules");
+        let body = fs::read_dir(&bodies)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.extension().is_some_and(|ext| ext == "md"))
+            .unwrap();
+        let body_content = fs::read_to_string(&body).unwrap();
+        fs::remove_file(&body).unwrap();
+        assert!(!is_up_to_date());
+        fs::write(&body, body_content).unwrap();
+        assert!(is_up_to_date());
+        create_file(project, "ai-rules/test.md", "Edited rule");
+        assert!(!is_up_to_date());
+
+        let status = check_project_status(
+            project,
+            ResolvedStatusArgs {
+                agents: Some(agents.clone()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(status.body_files_out_of_sync);
+    }
+
+    #[test]
+    fn test_check_project_status_lists_out_of_sync_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        create_file(project, "ai-rules/test.md", TEST_RULE_CONTENT);
+        create_file(
+            project,
+            "ai-rules/mcp.json",
+            r#"{"mcpServers": {"db": {"command": "db"}}}"#,
+        );
+        let agents = Some(vec!["claude".to_string()]);
+        crate::commands::run_generate(
+            project,
+            crate::cli::ResolvedGenerateArgs {
+                agents: agents.clone(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let status_args = || ResolvedStatusArgs {
+            agents: agents.clone(),
+            ..Default::default()
+        };
+        assert!(check_project_status(project, status_args())
+            .unwrap()
+            .files
+            .is_empty());
+
+        fs::remove_file(project.join("CLAUDE.md")).unwrap();
+        create_file(
+            project,
+            "ai-rules/mcp.json",
+            r#"{"mcpServers": {"db": {"command": "db2"}}}"#,
+        );
+        let status = check_project_status(project, status_args()).unwrap();
+
+        let file = |path: &str, artifact, reason| FileStatus {
+            directory: PathBuf::from("."),
+            agent: Some("claude".to_string()),
+            artifact,
+            path: PathBuf::from(path),
+            reason,
+        };
+        assert_eq!(
+            status.files,
+            vec![
+                file(".mcp.json", Artifact::Mcp, Reason::StaleContent),
+                file("CLAUDE.md", Artifact::Rules, Reason::Missing),
+            ]
+        );
+        assert!(!status.body_files_out_of_sync);
+        assert_file_not_exists(project, "CLAUDE.md");
+    }
+
+    #[test]
+    fn test_status_annotations_point_at_sources() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        create_file(project, "ai-rules/test.md", TEST_RULE_CONTENT);
+        create_file(project, "ai-rules/mcp.json", "{\"mcpServers\": 3}");
+        let args = ResolvedStatusArgs {
+            agents: Some(vec!["claude".to_string(), "cursor".to_string()]),
+            ..Default::default()
+        };
+        let status = check_project_status(project, args).unwrap();
+        assert_eq!(status.exit_code(), 1);
+
+        let annotations = status_annotations(project, &status);
+        let invalid = &annotations[0];
+        assert_eq!(invalid.rule, "invalid-source");
+        assert_eq!(invalid.path, PathBuf::from("ai-rules/mcp.json"));
+        assert_eq!(invalid.line, Some(1));
+        let body = annotations
+            .iter()
+            .find(|annotation| annotation.path == Path::new("ai-rules/test.md"))
+            .unwrap();
+        assert_eq!(
+            body.message,
+            "ai-rules/.generated-ai-rules/ai-rules-generated-test.md: missing (rules), for all agents. Run 'ai-rules generate'"
+        );
+    }
 }
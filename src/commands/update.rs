@@ -0,0 +1,77 @@
+use crate::operations::git_imports::sync_git_imports;
+use crate::utils::file_utils::{traverse_project_directories, DirectoryFilter};
+use crate::utils::plan::Plan;
+use crate::utils::print_utils::print_success;
+use crate::utils::transaction;
+use anyhow::Result;
+use std::path::Path;
+
+pub fn run_update(current_dir: &Path, nested_depth: usize) -> Result<()> {
+    println!("🔄 Updating git imports, nested_depth: {nested_depth}");
+    let filter = DirectoryFilter::from_project_root(current_dir);
+
+    // Every lock file moves together, or none does if a directory fails
+    let mut plan = Plan::default();
+    traverse_project_directories(current_dir, nested_depth, 0, &filter, &mut |dir| {
+        plan.extend(sync_git_imports(dir, true)?)
+    })?;
+    transaction::apply_with_backup(current_dir, &plan, "update")?;
+
+    print_success("Updated ai-rules.lock. Run 'ai-rules generate' to apply the new imports");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::git_imports::test_helpers::*;
+    use crate::operations::git_imports::{check_git_imports, read_lock_file};
+    use crate::utils::test_utils::helpers::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_run_update_moves_nested_locks() {
+        let temp_dir = TempDir::new().unwrap();
+        let pack = temp_dir.path().join("pack");
+        let url = create_git_pack(&pack, &[("style.md", "v1")]);
+        let project = temp_dir.path().join("project");
+        create_file(
+            &project,
+            "service/ai-rules/ai-rules-config.yaml",
+            &format!("imports:\n  - url: {url}\n    ref: main\n"),
+        );
+        sync_and_lock(&project.join("service"), false);
+
+        let new_commit = commit_files(&pack, &[("style.md", "v2")]);
+        run_update(&project, 1).unwrap();
+
+        let lock_file = read_lock_file(&project.join("service")).unwrap();
+        assert_eq!(lock_file.imports[0].commit, new_commit);
+    }
+
+    #[test]
+    fn test_run_update_moves_no_lock_when_a_directory_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let pack = temp_dir.path().join("pack");
+        let url = create_git_pack(&pack, &[("style.md", "v1")]);
+        let project = temp_dir.path().join("project");
+        let import = |git_ref: &str| format!("imports:\n  - url: {url}\n    ref: {git_ref}\n");
+        create_file(&project, "a/ai-rules/ai-rules-config.yaml", &import("main"));
+        sync_and_lock(&project.join("a"), false);
+        let locked = read_lock_file(&project.join("a")).unwrap();
+        create_file(
+            &project,
+            "b/ai-rules/ai-rules-config.yaml",
+            &import("missing"),
+        );
+
+        commit_files(&pack, &[("style.md", "v2")]);
+        assert!(run_update(&project, 1).is_err());
+        assert_eq!(read_lock_file(&project.join("a")).unwrap(), locked);
+
+        // The next sync moves the checkout back to the commit still locked
+        sync_and_lock(&project.join("a"), false);
+        assert!(check_git_imports(&project.join("a")).unwrap().is_empty());
+        assert_eq!(read_lock_file(&project.join("a")).unwrap(), locked);
+    }
+}
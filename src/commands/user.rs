@@ -0,0 +1,206 @@
+use crate::agents::rule_generator::UserScopeTarget;
+use crate::agents::AgentToolRegistry;
+use crate::commands::generate::print_planned_changes;
+use crate::commands::ExitStatus;
+use crate::config::load_directory_config;
+use crate::operations::user_scope::{
+    check_user_files, clean_user_files, generate_user_files, UserScope,
+};
+use crate::operations::GenerationResult;
+use crate::utils::plan::Plan;
+use crate::utils::print_utils::{print_success, print_warning};
+use crate::utils::transaction;
+use anyhow::Result;
+
+/// Agents with user-level files, limited to `agents` (from `--agents`) or the `agents`
+/// of the user's own `ai-rules-config.yaml`.
+fn resolve_user_targets(
+    scope: &UserScope,
+    agents: Option<Vec<String>>,
+    registry: &AgentToolRegistry,
+) -> Result<Vec<(String, UserScopeTarget)>> {
+    let user_config_agents = load_directory_config(&scope.config_dir)?.and_then(|c| c.agents);
+    let requested = agents.or(user_config_agents);
+    let mut names = requested
+        .clone()
+        .unwrap_or_else(|| registry.get_all_tool_names());
+    names.sort();
+
+    let mut targets = Vec::new();
+    for name in names {
+        match registry.get_tool(&name).and_then(|tool| tool.user_scope()) {
+            Some(target) => targets.push((name, target)),
+            None if requested.is_some() => {
+                print_warning(&format!("{name} has no user-level files, skipping"));
+            }
+            None => {}
+        }
+    }
+    Ok(targets)
+}
+
+pub fn run_generate_user(
+    scope: &UserScope,
+    agents: Option<Vec<String>>,
+    dry_run: bool,
+) -> Result<()> {
+    println!(
+        "Generating user-level rules from {}",
+        scope.source_dir().display()
+    );
+    let registry = AgentToolRegistry::new();
+    let mut result = GenerationResult::default();
+
+    let mut plan = Plan::default();
+    for (agent, target) in resolve_user_targets(scope, agents, &registry)? {
+        for path in generate_user_files(scope, &target, &mut plan)? {
+            result.add_file(&agent, path);
+        }
+    }
+    if dry_run {
+        print_planned_changes(&scope.home_dir, plan.changes()?);
+        return Ok(());
+    }
+    // Staged and backed up in the user's ai-rules/ folder, so a failure leaves the
+    // home directory as it was
+    transaction::apply_with_backup_in(
+        &scope.config_dir,
+        &scope.home_dir,
+        &plan,
+        "generate --user",
+    )?;
+
+    result.display(&scope.home_dir);
+    Ok(())
+}
+
+pub fn run_status_user(scope: &UserScope, agents: Option<Vec<String>>) -> Result<()> {
+    println!(
+        "🔍 User-level AI Rules Status for {}",
+        scope.source_dir().display()
+    );
+    let registry = AgentToolRegistry::new();
+
+    let mut any_out_of_sync = false;
+    for (agent, target) in resolve_user_targets(scope, agents, &registry)? {
+        if check_user_files(scope, &target)? {
+            println!("  ✅ {agent}: in sync");
+        } else {
+            println!("  ❌ {agent}: out of sync");
+            any_out_of_sync = true;
+        }
+    }
+
+    if any_out_of_sync {
+        println!("\n💡 Next steps:");
+        println!("    ai-rules generate --user             # Regenerate user-level files");
+        return Err(ExitStatus(1).into());
+    }
+    Ok(())
+}
+
+pub fn run_clean_user(scope: &UserScope) -> Result<()> {
+    println!(
+        "📋 Cleaning user-level files in {}",
+        scope.home_dir.display()
+    );
+    let registry = AgentToolRegistry::new();
+
+    let mut plan = Plan::default();
+    for (_, target) in resolve_user_targets(scope, None, &registry)? {
+        clean_user_files(scope, &target, &mut plan)?;
+    }
+    transaction::apply_with_backup_in(&scope.config_dir, &scope.home_dir, &plan, "clean --user")?;
+
+    print_success("Removed user-level files generated by ai-rules");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::RestoreArgs;
+    use crate::commands::run_restore_user;
+    use crate::utils::backup::list_backups;
+    use crate::utils::test_utils::helpers::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_run_generate_user_writes_agent_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let scope = UserScope {
+            home_dir: temp_dir.path().join("home"),
+            config_dir: temp_dir.path().join("config"),
+        };
+        create_file(
+            &scope.config_dir,
+            "ai-rules/style.md",
+            "Prefer early returns",
+        );
+        create_file(
+            &scope.config_dir,
+            "ai-rules/ai-rules-config.yaml",
+            "agents: [codex, gemini, cursor]\n",
+        );
+
+        run_generate_user(&scope, None, true).unwrap();
+        assert_file_not_exists(&scope.home_dir, ".codex");
+
+        run_generate_user(&scope, None, false).unwrap();
+
+        assert_file_exists(&scope.home_dir, ".codex/AGENTS.md");
+        assert_file_exists(&scope.home_dir, ".gemini/GEMINI.md");
+        assert_file_not_exists(&scope.home_dir, ".claude/CLAUDE.md");
+
+        run_clean_user(&scope).unwrap();
+        assert_file_not_exists(&scope.home_dir, ".codex/AGENTS.md");
+        assert_file_not_exists(&scope.home_dir, ".gemini/GEMINI.md");
+    }
+
+    #[test]
+    fn test_run_generate_user_is_one_transaction_with_a_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let scope = UserScope {
+            home_dir: temp_dir.path().join("home"),
+            config_dir: temp_dir.path().join("config"),
+        };
+        create_file(&scope.config_dir, "ai-rules/style.md", "Old rule");
+        create_file(
+            &scope.config_dir,
+            "ai-rules/commands/standup.md",
+            "Summarize",
+        );
+        let agents = || Some(vec!["claude".to_string(), "gemini".to_string()]);
+        run_generate_user(&scope, agents(), false).unwrap();
+        let old_rules = fs::read_to_string(scope.home_dir.join(".claude/CLAUDE.md")).unwrap();
+
+        // A file where gemini needs a folder fails the run after claude was planned
+        create_file(&scope.config_dir, "ai-rules/style.md", "New rule");
+        fs::remove_dir_all(scope.home_dir.join(".gemini")).unwrap();
+        create_file(&scope.home_dir, ".gemini", "Not a folder");
+        assert!(run_generate_user(&scope, agents(), false).is_err());
+        assert_file_content(&scope.home_dir, ".claude/CLAUDE.md", &old_rules);
+        assert_file_content(
+            &scope.home_dir,
+            ".claude/commands/ai-rules/standup.md",
+            "Summarize",
+        );
+
+        fs::remove_file(scope.home_dir.join(".gemini")).unwrap();
+        run_generate_user(&scope, agents(), false).unwrap();
+        // The failed run keeps a backup too, so the last one is this run's
+        let backup = list_backups(&scope.config_dir).unwrap().pop().unwrap();
+        assert!(backup
+            .paths
+            .contains(&std::path::PathBuf::from(".claude/CLAUDE.md")));
+
+        let args = RestoreArgs {
+            list: false,
+            user: true,
+            id: Some(backup.id),
+        };
+        run_restore_user(&scope, args).unwrap();
+        assert_file_content(&scope.home_dir, ".claude/CLAUDE.md", &old_rules);
+    }
+}
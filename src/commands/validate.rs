@@ -0,0 +1,190 @@
+use crate::agents::AgentToolRegistry;
+use crate::cli::ReportFormat;
+use crate::commands::status::print_annotations;
+use crate::commands::ExitStatus;
+use crate::operations::source_validator::validate_directory;
+use crate::utils::annotations::{Annotation, Level};
+use crate::utils::file_utils::{traverse_project_directories, DirectoryFilter};
+use crate::utils::print_utils::print_success;
+use anyhow::Result;
+use serde_json::json;
+use std::path::Path;
+
+pub fn run_validate(current_dir: &Path, nested_depth: usize, format: ReportFormat) -> Result<()> {
+    if format == ReportFormat::Human {
+        println!("🔍 Validating ai-rules sources, nested_depth: {nested_depth}");
+    }
+    let annotations = validate_project(current_dir, nested_depth)?;
+    print_report(format, &annotations, "All sources are valid")
+}
+
+/// Prints `annotations` in `format`, failing with exit status 1 if any is an error.
+pub fn print_report(format: ReportFormat, annotations: &[Annotation], success: &str) -> Result<()> {
+    let errors = count(annotations, Level::Error);
+    match format {
+        ReportFormat::Human => print_human_report(annotations, success),
+        ReportFormat::Json => {
+            let report = json!({
+                "valid": errors == 0,
+                "errors": errors,
+                "warnings": count(annotations, Level::Warning),
+                "problems": annotations,
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        ReportFormat::Github | ReportFormat::Sarif => print_annotations(format, annotations)?,
+    }
+
+    if errors > 0 {
+        return Err(ExitStatus(1).into());
+    }
+    Ok(())
+}
+
+/// Problems in the sources of every project directory, with paths relative to `current_dir`.
+pub fn validate_project(current_dir: &Path, nested_depth: usize) -> Result<Vec<Annotation>> {
+    let registry = AgentToolRegistry::new();
+    let filter = DirectoryFilter::from_project_root(current_dir);
+    let mut annotations = Vec::new();
+    traverse_project_directories(current_dir, nested_depth, 0, &filter, &mut |dir| {
+        annotations.extend(validate_directory(current_dir, dir, &registry)?);
+        Ok(())
+    })?;
+    Ok(annotations)
+}
+
+fn count(annotations: &[Annotation], level: Level) -> usize {
+    annotations
+        .iter()
+        .filter(|annotation| annotation.level == level)
+        .count()
+}
+
+fn print_human_report(annotations: &[Annotation], success: &str) {
+    for annotation in annotations {
+        let mut location = annotation.path.display().to_string();
+        if let Some(line) = annotation.line {
+            location.push_str(&format!(":{line}"));
+            if let Some(column) = annotation.column {
+                location.push_str(&format!(":{column}"));
+            }
+        }
+        let level = match annotation.level {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        };
+        println!(
+            "{location}: {level}[{}]: {}",
+            annotation.rule, annotation.message
+        );
+    }
+
+    let errors = count(annotations, Level::Error);
+    let warnings = count(annotations, Level::Warning);
+    match (errors, warnings) {
+        (0, 0) => print_success(success),
+        (0, warnings) => println!("\n⚠️  No errors, {warnings} warning(s)"),
+        (errors, warnings) => println!("\n❌ {errors} error(s), {warnings} warning(s)"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::helpers::create_file;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_validate_reports_located_problems_with_codes() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        create_file(
+            project,
+            "ai-rules/ai-rules-config.yaml",
+            "agents: [claude, cursr]\nnestedDepht: 1\n",
+        );
+        create_file(
+            project,
+            "ai-rules/style.md",
+            "---\nalwaysApply: true\nfileMatchng: \"*.rs\"\n---\nUse tabs.",
+        );
+        create_file(project, "ai-rules/empty.md", "");
+        create_file(
+            project,
+            "ai-rules/commands/review.md",
+            "---\ndescription: Review\nallowed-tools: 3\n---\nReview it.",
+        );
+        create_file(
+            project,
+            "ai-rules/skills/deploy/SKILL.md",
+            "---\nname: deploy-app\n---\nDeploy.",
+        );
+        create_file(
+            project,
+            "ai-rules/mcp.json",
+            r#"{
+  "mcpServers": {
+    "db": { "command": "db", "args": "--fast" },
+    "api": {
+      "url": "https://api",
+      "cwd": "/"
+    }
+  }
+}"#,
+        );
+        create_file(project, "ai-rules/firebender-overlay.json", "[1, 2]");
+
+        let problems: Vec<(String, Option<usize>, &str, Level)> = validate_project(project, 0)
+            .unwrap()
+            .into_iter()
+            .map(|problem| {
+                let path = problem.path.display().to_string();
+                (path, problem.line, problem.rule, problem.level)
+            })
+            .collect();
+        let config = "ai-rules/ai-rules-config.yaml".to_string();
+        let skill = "ai-rules/skills/deploy/SKILL.md".to_string();
+        let mcp = "ai-rules/mcp.json".to_string();
+        assert_eq!(
+            problems,
+            vec![
+                (config.clone(), Some(2), "unknown-key", Level::Error),
+                (config, Some(1), "unknown-agent", Level::Error),
+                ("ai-rules/empty.md".into(), None, "empty-rule", Level::Error),
+                (
+                    "ai-rules/style.md".into(),
+                    Some(3),
+                    "unknown-key",
+                    Level::Error
+                ),
+                (
+                    "ai-rules/commands/review.md".into(),
+                    Some(2),
+                    "invalid-frontmatter",
+                    Level::Error
+                ),
+                (skill.clone(), Some(2), "skill-name-mismatch", Level::Error),
+                (skill, Some(2), "missing-description", Level::Error),
+                (mcp.clone(), Some(4), "invalid-mcp", Level::Error),
+                (mcp.clone(), Some(6), "unknown-key", Level::Warning),
+                (mcp, Some(3), "invalid-mcp", Level::Error),
+                (
+                    "ai-rules/firebender-overlay.json".into(),
+                    Some(1),
+                    "invalid-overlay",
+                    Level::Error
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_print_report_fails_with_exit_status_on_errors() {
+        let warning = Annotation::new("ai-rules/a.md", Level::Warning, "long-rule", "Long".into());
+        assert!(print_report(ReportFormat::Github, std::slice::from_ref(&warning), "ok").is_ok());
+
+        let error = Annotation::new("ai-rules/b.md", Level::Error, "empty-rule", "Empty".into());
+        let failure = print_report(ReportFormat::Github, &[warning, error], "ok").unwrap_err();
+        assert!(matches!(failure.downcast_ref(), Some(ExitStatus(1))));
+    }
+}
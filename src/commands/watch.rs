@@ -0,0 +1,291 @@
+use crate::agents::AgentToolRegistry;
+use crate::cli::{ResolvedGenerateArgs, WatchArgs};
+use crate::commands::generate::generate_quietly;
+use crate::config;
+use crate::constants::{AI_RULE_SOURCE_DIR, GENERATED_RULE_BODY_DIR, IMPORT_CACHE_DIR};
+use crate::utils::file_utils::{traverse_project_directories, DirectoryFilter};
+use crate::utils::print_utils::print_info;
+use anyhow::Result;
+use notify::{Config, Event, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::time::{Duration, Instant};
+
+/// How long the sources must stay unchanged before regenerating, so saving several
+/// files at once regenerates once
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// How often the sources are checked when file notifications aren't available
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How many changed files a summary names before counting the rest
+const SUMMARY_SOURCES: usize = 3;
+
+type Events = Sender<notify::Result<Event>>;
+
+pub fn run_watch(current_dir: &Path, args: WatchArgs) -> Result<()> {
+    let (sender, events) = channel();
+    let mut watches = Watches::new(sender, args.poll)?;
+
+    let generate_args = resolve(current_dir, &args)?;
+    let dirs = project_directories(current_dir, generate_args.nested_depth)?;
+    watches.update(&dirs)?;
+    println!(
+        "👀 Watching {} ai-rules folder(s){}, press Ctrl-C to stop",
+        dirs.iter()
+            .filter(|dir| dir.join(AI_RULE_SOURCE_DIR).is_dir())
+            .count(),
+        if watches.polling { " by polling" } else { "" }
+    );
+    regenerate(current_dir, generate_args, None, &dirs);
+
+    while let Ok(event) = events.recv() {
+        let mut changed = BTreeSet::new();
+        collect(event, &mut changed);
+        while let Ok(event) = events.recv_timeout(DEBOUNCE) {
+            collect(event, &mut changed);
+        }
+
+        // The config can change the agents, the depth and the watched folders
+        let generate_args = match resolve(current_dir, &args) {
+            Ok(generate_args) => generate_args,
+            Err(error) => {
+                eprintln!("❌ {error:#}");
+                continue;
+            }
+        };
+        // A folder removed while it is traversed fails until the next change
+        let dirs = match project_directories(current_dir, generate_args.nested_depth)
+            .and_then(|dirs| watches.update(&dirs).map(|()| dirs))
+        {
+            Ok(dirs) => dirs,
+            Err(error) => {
+                eprintln!("❌ {error:#}");
+                continue;
+            }
+        };
+        let sources: BTreeMap<&Path, &Path> = changed
+            .iter()
+            .filter_map(|path| Some((path.as_path(), source_owner(&dirs, path)?)))
+            .collect();
+        if sources.is_empty() {
+            continue;
+        }
+        regenerate(current_dir, generate_args, Some(&sources), &dirs);
+    }
+    Ok(())
+}
+
+fn resolve(current_dir: &Path, args: &WatchArgs) -> Result<ResolvedGenerateArgs> {
+    let config = config::load_config(current_dir)?;
+    args.clone().with_config(config.as_ref())
+}
+
+fn project_directories(current_dir: &Path, nested_depth: usize) -> Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    let filter = DirectoryFilter::from_project_root(current_dir);
+    traverse_project_directories(current_dir, nested_depth, 0, &filter, &mut |dir| {
+        dirs.push(dir.to_path_buf());
+        Ok(())
+    })?;
+    Ok(dirs)
+}
+
+fn collect(event: notify::Result<Event>, changed: &mut BTreeSet<PathBuf>) {
+    match event {
+        // Generate reads the sources, which would otherwise trigger it again
+        Ok(event) if event.kind.is_access() => {}
+        Ok(event) => changed.extend(event.paths),
+        Err(error) => eprintln!("⚠️  {error}"),
+    }
+}
+
+/// The project directory whose `ai-rules/` holds `path`, unless generate wrote it.
+fn source_owner<'a>(dirs: &'a [PathBuf], path: &Path) -> Option<&'a Path> {
+    let owner = dirs
+        .iter()
+        .filter(|dir| path.starts_with(dir.join(AI_RULE_SOURCE_DIR)))
+        .max_by_key(|dir| dir.components().count())?;
+    let relative = path.strip_prefix(owner.join(AI_RULE_SOURCE_DIR)).ok()?;
+    if relative.starts_with(GENERATED_RULE_BODY_DIR) || relative.starts_with(IMPORT_CACHE_DIR) {
+        return None;
+    }
+    Some(owner)
+}
+
+/// `owners` and the directories nested in them, which may inherit their rules.
+fn affected_dirs(dirs: &[PathBuf], owners: &BTreeSet<&Path>) -> BTreeSet<PathBuf> {
+    dirs.iter()
+        .filter(|dir| owners.iter().any(|owner| dir.starts_with(owner)))
+        .cloned()
+        .collect()
+}
+
+/// Regenerates the directories affected by the changed `sources` (keyed to the
+/// directory owning them), or every directory, and prints a one line summary.
+fn regenerate(
+    current_dir: &Path,
+    mut args: ResolvedGenerateArgs,
+    sources: Option<&BTreeMap<&Path, &Path>>,
+    dirs: &[PathBuf],
+) {
+    let label = match sources {
+        Some(sources) => {
+            let mut names: Vec<String> = sources
+                .keys()
+                .take(SUMMARY_SOURCES)
+                .map(|path| {
+                    path.strip_prefix(current_dir)
+                        .unwrap_or(path)
+                        .display()
+                        .to_string()
+                })
+                .collect();
+            if sources.len() > SUMMARY_SOURCES {
+                names.push(format!("{} more", sources.len() - SUMMARY_SOURCES));
+            }
+            names.join(", ")
+        }
+        None => "initial generate".to_string(),
+    };
+    if let Some(sources) = sources {
+        let owners: BTreeSet<&Path> = sources.values().copied().collect();
+        let registry = AgentToolRegistry::new();
+        let mut agents = args
+            .agents
+            .clone()
+            .unwrap_or_else(|| registry.get_all_tool_names());
+        agents.extend(args.command_agents.iter().flatten().cloned());
+        let targets: BTreeMap<PathBuf, Vec<String>> = affected_dirs(dirs, &owners)
+            .into_iter()
+            .map(|dir| (dir, agents.clone()))
+            .collect();
+        args.targets = Some(targets);
+    }
+
+    let started = Instant::now();
+    match generate_quietly(current_dir, args) {
+        Ok(result) => println!(
+            "🔄 {label}: {} updated, {} created, {} removed ({} ms)",
+            result.changes.updated,
+            result.changes.created,
+            result.changes.removed,
+            started.elapsed().as_millis()
+        ),
+        Err(error) => eprintln!("❌ {label}: {error:#}"),
+    }
+}
+
+/// Watches each project directory for a new `ai-rules/` folder, and each `ai-rules/`
+/// folder with everything in it. Falls back to polling when file notifications
+/// aren't available or run out.
+struct Watches {
+    watcher: Box<dyn Watcher>,
+    sender: Events,
+    polling: bool,
+    watched: BTreeSet<(PathBuf, bool)>,
+}
+
+impl Watches {
+    fn new(sender: Events, poll: bool) -> Result<Self> {
+        if !poll {
+            match RecommendedWatcher::new(sender.clone(), Config::default()) {
+                Ok(watcher) => {
+                    return Ok(Self {
+                        watcher: Box::new(watcher),
+                        sender,
+                        polling: false,
+                        watched: BTreeSet::new(),
+                    })
+                }
+                Err(error) => print_info(&format!(
+                    "File notifications aren't available ({error}), polling instead"
+                )),
+            }
+        }
+        Ok(Self {
+            watcher: Self::poll_watcher(sender.clone())?,
+            sender,
+            polling: true,
+            watched: BTreeSet::new(),
+        })
+    }
+
+    fn poll_watcher(sender: Events) -> Result<Box<dyn Watcher>> {
+        let config = Config::default().with_poll_interval(POLL_INTERVAL);
+        Ok(Box::new(PollWatcher::new(sender, config)?))
+    }
+
+    fn update(&mut self, dirs: &[PathBuf]) -> Result<()> {
+        let mut wanted = BTreeSet::new();
+        for dir in dirs {
+            wanted.insert((dir.clone(), false));
+            let source_dir = dir.join(AI_RULE_SOURCE_DIR);
+            if source_dir.is_dir() {
+                wanted.insert((source_dir, true));
+            }
+        }
+        for (path, _) in self.watched.difference(&wanted) {
+            // Fails for folders that were removed, which are no longer watched anyway
+            let _ = self.watcher.unwatch(path);
+        }
+        let added: Vec<_> = wanted.difference(&self.watched).cloned().collect();
+        for (path, recursive) in added {
+            let mode = match recursive {
+                true => RecursiveMode::Recursive,
+                false => RecursiveMode::NonRecursive,
+            };
+            if let Err(error) = self.watcher.watch(&path, mode) {
+                if self.polling {
+                    return Err(error.into());
+                }
+                print_info(&format!(
+                    "Can't watch {} ({error}), polling instead",
+                    path.display()
+                ));
+                self.watcher = Self::poll_watcher(self.sender.clone())?;
+                self.polling = true;
+                self.watched.clear();
+                return self.update(dirs);
+            }
+        }
+        self.watched = wanted;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_regenerate_their_directory_and_nested_ones() {
+        let root = Path::new("/project");
+        let dirs = vec![
+            root.to_path_buf(),
+            root.join("app"),
+            root.join("app/web"),
+            root.join("lib"),
+        ];
+
+        let owner = |path: &str| source_owner(&dirs, &root.join(path));
+        assert_eq!(
+            owner("app/ai-rules/style.md"),
+            Some(root.join("app").as_path())
+        );
+        assert_eq!(owner("ai-rules/commands/review.md"), Some(root));
+        assert_eq!(owner("app/ai-rules"), Some(root.join("app").as_path()));
+        assert_eq!(
+            owner("app/ai-rules/.generated-ai-rules/manifest.json"),
+            None
+        );
+        assert_eq!(owner("ai-rules/.cache/imports/shared/rule.md"), None);
+        assert_eq!(owner("app/CLAUDE.md"), None);
+
+        let app = root.join("app");
+        let owners = BTreeSet::from([app.as_path()]);
+        assert_eq!(
+            affected_dirs(&dirs, &owners),
+            BTreeSet::from([root.join("app"), root.join("app/web")])
+        );
+    }
+}
@@ -2,6 +2,7 @@ use crate::constants::{AI_RULE_CONFIG_FILENAME, AI_RULE_SOURCE_DIR};
 use crate::utils::git_utils::find_git_root;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -11,6 +12,178 @@ pub struct Config {
     pub gitignore: Option<bool>,
     pub no_gitignore: Option<bool>,
     pub nested_depth: Option<usize>,
+    pub variables: Option<BTreeMap<String, String>>,
+    pub rule_order: Option<Vec<String>>,
+    pub profiles: Option<BTreeMap<String, Profile>>,
+    /// Compose rules from ancestor ai-rules/ directories into nested projects
+    pub inherit: Option<bool>,
+    /// Inherited rule names to leave out of this directory
+    pub disable_inherited: Option<Vec<String>>,
+    /// Rule packs whose rules, commands, skills and MCP servers are merged into this directory
+    pub imports: Option<Vec<ImportEntry>>,
+    /// Where personal `*.local.md` rules are written
+    pub local_rules: Option<LocalRulesMode>,
+    /// Whether ai-rules owns agent files like CLAUDE.md or only a block inside them
+    pub output_mode: Option<OutputMode>,
+    /// How generated rules, commands and skills point at their sources
+    pub link_mode: Option<LinkModeConfig>,
+    /// Which `ai-rules lint` checks run and how severe their findings are
+    pub lint: Option<LintConfig>,
+}
+
+/// The `lint:` section of the config file.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct LintConfig {
+    /// Severity of each check by its code, such as `oversize-rule: error`
+    pub checks: Option<BTreeMap<String, LintSeverity>>,
+    /// Rules longer than this many lines are reported as oversize
+    pub max_rule_lines: Option<usize>,
+    /// Descriptions of optional rules with fewer words than this are reported as vague
+    pub min_description_words: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Off,
+    Warning,
+    Error,
+}
+
+/// How a generated output points at the file or folder it was generated from.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkMode {
+    #[default]
+    Symlink,
+    /// A real copy, for tools that don't follow symlinks
+    Copy,
+    /// Hard links to the source files (same filesystem only)
+    Hardlink,
+}
+
+/// `link_mode` as written in the config file: one mode for everything, or a default
+/// with per-artifact overrides.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum LinkModeConfig {
+    All(LinkMode),
+    PerArtifact(LinkModeOverrides),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LinkModeOverrides {
+    pub default: Option<LinkMode>,
+    pub rules: Option<LinkMode>,
+    pub commands: Option<LinkMode>,
+    pub skills: Option<LinkMode>,
+}
+
+/// The link mode of each generated artifact type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LinkModes {
+    /// Agent instruction files such as `CLAUDE.md`
+    pub rules: LinkMode,
+    pub commands: LinkMode,
+    pub skills: LinkMode,
+}
+
+impl LinkModeConfig {
+    pub fn resolve(&self) -> LinkModes {
+        match self {
+            LinkModeConfig::All(mode) => LinkModes {
+                rules: *mode,
+                commands: *mode,
+                skills: *mode,
+            },
+            LinkModeConfig::PerArtifact(overrides) => {
+                let default = overrides.default.unwrap_or_default();
+                LinkModes {
+                    rules: overrides.rules.unwrap_or(default),
+                    commands: overrides.commands.unwrap_or(default),
+                    skills: overrides.skills.unwrap_or(default),
+                }
+            }
+        }
+    }
+}
+
+/// How ai-rules writes the instruction file each agent reads, such as `CLAUDE.md`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputMode {
+    /// The file is replaced with a symlink to the generated rules
+    #[default]
+    File,
+    /// The generated rules go between `<!-- ai-rules:begin -->` and `<!-- ai-rules:end -->`
+    /// markers, and the rest of the file is left alone
+    ManagedBlock,
+}
+
+/// How personal rules from `ai-rules/*.local.md` and `ai-rules/local/` are emitted.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LocalRulesMode {
+    /// Merged into the regular generated files
+    #[default]
+    Merged,
+    /// Written to agents' native personal files, such as `CLAUDE.local.md`
+    Native,
+}
+
+/// What to do when an imported rule, command, skill or MCP server has the same name
+/// as an existing one.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    #[default]
+    Error,
+    LocalWins,
+    ImportWins,
+}
+
+/// An `imports:` entry: either a plain path or a map with options.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ImportEntry {
+    Path(String),
+    Detailed(ImportSpec),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ImportSpec {
+    /// Directory laid out like `ai-rules/`, relative to the project directory.
+    /// For git imports, the directory inside the repository (defaults to its root).
+    pub path: Option<String>,
+    /// Git repository to fetch the pack from
+    pub url: Option<String>,
+    /// Branch, tag or commit of `url` (defaults to `HEAD`)
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+    #[serde(default)]
+    pub on_conflict: ConflictPolicy,
+}
+
+impl ImportEntry {
+    pub fn spec(&self) -> ImportSpec {
+        match self {
+            Self::Path(path) => ImportSpec {
+                path: Some(path.clone()),
+                ..Default::default()
+            },
+            Self::Detailed(spec) => spec.clone(),
+        }
+    }
+}
+
+/// A named selection of rules and agents, activated with `--profile <name>`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Profile {
+    /// Only rules tagged with at least one of these tags (plus untagged rules) are used
+    pub include_tags: Option<Vec<String>>,
+    /// Agents to generate for, unless `--agents` is given
+    pub agents: Option<Vec<String>>,
 }
 
 pub fn load_config(current_dir: &Path) -> Result<Option<Config>> {
@@ -23,15 +196,7 @@ pub fn load_config(current_dir: &Path) -> Result<Option<Config>> {
         let config_path = dir.join(AI_RULE_SOURCE_DIR).join(AI_RULE_CONFIG_FILENAME);
 
         if config_path.exists() {
-            let config_content = std::fs::read_to_string(&config_path).with_context(|| {
-                format!("Failed to read config file: {}", config_path.display())
-            })?;
-
-            let config: Config = serde_yaml::from_str(&config_content).with_context(|| {
-                format!("Failed to parse config file: {}", config_path.display())
-            })?;
-
-            return Ok(Some(config));
+            return read_config_file(&config_path).map(Some);
         }
 
         // Stop if we've reached git root (after checking it)
@@ -56,6 +221,25 @@ pub fn load_config(current_dir: &Path) -> Result<Option<Config>> {
     Ok(None)
 }
 
+/// Loads the config file that lives directly in `dir/ai-rules/`, without walking up.
+///
+/// Used for nested projects, whose config can override some of the root settings.
+pub fn load_directory_config(dir: &Path) -> Result<Option<Config>> {
+    let config_path = dir.join(AI_RULE_SOURCE_DIR).join(AI_RULE_CONFIG_FILENAME);
+    if !config_path.exists() {
+        return Ok(None);
+    }
+    read_config_file(&config_path).map(Some)
+}
+
+fn read_config_file(config_path: &Path) -> Result<Config> {
+    let config_content = std::fs::read_to_string(config_path)
+        .with_context(|| format!("Failed to read config file: {}", config_path.display()))?;
+
+    serde_yaml::from_str(&config_content)
+        .with_context(|| format!("Failed to parse config file: {}", config_path.display()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,6 +349,143 @@ command_agents: ["claude", "amp"]
         );
     }
 
+    #[test]
+    fn test_load_config_with_variables() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_content = r#"
+variables:
+  test_command: cargo test
+  team: platform
+"#;
+        create_config_file(temp_dir.path(), config_content);
+
+        let config = load_config(temp_dir.path()).unwrap().unwrap();
+        let variables = config.variables.unwrap();
+
+        assert_eq!(variables["test_command"], "cargo test");
+        assert_eq!(variables["team"], "platform");
+    }
+
+    #[test]
+    fn test_load_config_with_rule_order() {
+        let temp_dir = TempDir::new().unwrap();
+        create_config_file(temp_dir.path(), "rule_order: [safety, testing]\n");
+
+        let config = load_config(temp_dir.path()).unwrap().unwrap();
+
+        assert_eq!(
+            config.rule_order,
+            Some(vec!["safety".to_string(), "testing".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_profiles() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_content = r#"
+profiles:
+  backend:
+    include_tags: [rust, db]
+    agents: [claude, codex]
+"#;
+        create_config_file(temp_dir.path(), config_content);
+
+        let config = load_config(temp_dir.path()).unwrap().unwrap();
+        let backend = &config.profiles.unwrap()["backend"];
+
+        assert_eq!(
+            backend.include_tags,
+            Some(vec!["rust".to_string(), "db".to_string()])
+        );
+        assert_eq!(
+            backend.agents,
+            Some(vec!["claude".to_string(), "codex".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_link_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        create_config_file(temp_dir.path(), "link_mode: copy\n");
+        let config = load_config(temp_dir.path()).unwrap().unwrap();
+        assert_eq!(
+            config.link_mode.unwrap().resolve(),
+            LinkModes {
+                rules: LinkMode::Copy,
+                commands: LinkMode::Copy,
+                skills: LinkMode::Copy,
+            }
+        );
+
+        create_config_file(
+            temp_dir.path(),
+            "link_mode:\n  default: hardlink\n  skills: copy\n",
+        );
+        let config = load_config(temp_dir.path()).unwrap().unwrap();
+        assert_eq!(
+            config.link_mode.unwrap().resolve(),
+            LinkModes {
+                rules: LinkMode::Hardlink,
+                commands: LinkMode::Hardlink,
+                skills: LinkMode::Copy,
+            }
+        );
+    }
+
+    #[test]
+    fn test_load_config_with_imports() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_content = r#"
+imports:
+  - ../platform-rules/ai-rules
+  - path: third_party/ai-rules-pack
+    on_conflict: import-wins
+  - url: https://github.com/example/rules.git
+    ref: v1.2.0
+"#;
+        create_config_file(temp_dir.path(), config_content);
+
+        let config = load_config(temp_dir.path()).unwrap().unwrap();
+        let imports: Vec<ImportSpec> = config
+            .imports
+            .unwrap()
+            .iter()
+            .map(ImportEntry::spec)
+            .collect();
+
+        assert_eq!(
+            imports[0].path.as_deref(),
+            Some("../platform-rules/ai-rules")
+        );
+        assert_eq!(imports[0].on_conflict, ConflictPolicy::Error);
+        assert_eq!(
+            imports[1].path.as_deref(),
+            Some("third_party/ai-rules-pack")
+        );
+        assert_eq!(imports[1].on_conflict, ConflictPolicy::ImportWins);
+        assert_eq!(
+            imports[2].url.as_deref(),
+            Some("https://github.com/example/rules.git")
+        );
+        assert_eq!(imports[2].git_ref.as_deref(), Some("v1.2.0"));
+    }
+
+    #[test]
+    fn test_load_directory_config_does_not_traverse() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        create_config_file(root, "agents: [\"claude\"]\n");
+
+        let nested = root.join("subproject");
+        fs::create_dir_all(&nested).unwrap();
+        assert!(load_directory_config(&nested).unwrap().is_none());
+
+        create_config_file(&nested, "nested_depth: 1\n");
+        let config = load_directory_config(&nested).unwrap().unwrap();
+        assert_eq!(config.nested_depth, Some(1));
+    }
+
     #[test]
     fn test_load_config_from_subdirectory() {
         let temp_dir = TempDir::new().unwrap();
@@ -5,8 +5,14 @@ pub const OPTIONAL_RULES_FILENAME: &str = "ai-rules-generated-optional.md";
 pub const INLINED_AGENTS_FILENAME: &str = "ai-rules-generated-AGENTS.md";
 pub const AGENTS_MD_FILENAME: &str = "AGENTS.md";
 pub const AI_RULE_CONFIG_FILENAME: &str = "ai-rules-config.yaml";
+pub const LOCK_FILENAME: &str = "ai-rules.lock";
+pub const IMPORT_CACHE_DIR: &str = ".cache/imports";
 pub const GENERATED_FILE_PREFIX: &str = "ai-rules-generated-";
 pub const GENERATED_COMMAND_SUFFIX: &str = "ai-rules";
+/// Written into folders that `link_mode: copy` or `hardlink` created
+pub const GENERATED_COPY_MARKER: &str = ".ai-rules-generated";
+/// Lists what `generate` wrote in a directory; lives in the generated body directory
+pub const MANIFEST_FILENAME: &str = "manifest.json";
 
 pub const CLAUDE_SKILLS_DIR: &str = ".claude/skills";
 pub const CODEX_SKILLS_DIR: &str = ".codex/skills";
@@ -14,6 +20,9 @@ pub const AMP_SKILLS_DIR: &str = ".agents/skills";
 pub const CURSOR_SKILLS_DIR: &str = ".cursor/skills";
 pub const SKILL_FILENAME: &str = "SKILL.md";
 pub const SKILLS_DIR: &str = "skills";
+pub const PARTIALS_DIR: &str = "partials";
+pub const LOCAL_RULES_DIR: &str = "local";
+pub const LOCAL_RULE_SUFFIX: &str = ".local";
 
 pub const FIREBENDER_JSON: &str = "firebender.json";
 pub const FIREBENDER_OVERLAY_JSON: &str = "firebender-overlay.json";
@@ -30,5 +39,12 @@ pub const CURSOR_COMMANDS_SUBDIR: &str = "ai-rules";
 pub const AMP_COMMANDS_DIR: &str = ".agents/commands";
 pub const FIREBENDER_COMMANDS_DIR: &str = ".firebender/commands";
 
+pub const CLAUDE_USER_RULES_FILE: &str = ".claude/CLAUDE.md";
+pub const CLAUDE_USER_COMMANDS_DIR: &str = ".claude/commands/ai-rules";
+pub const CODEX_USER_RULES_FILE: &str = ".codex/AGENTS.md";
+
+pub const CLAUDE_LOCAL_MD: &str = "CLAUDE.local.md";
+pub const CODEX_OVERRIDE_MD: &str = "AGENTS.override.md";
+
 // Embedded template content (compile-time inclusion)
 pub const OPTIONAL_RULES_TEMPLATE: &str = include_str!("templates/optional_rules.md");
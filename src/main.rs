@@ -8,9 +8,13 @@ mod operations;
 mod utils;
 
 use cli::run_cli;
+use commands::ExitStatus;
 
 fn main() {
     if let Err(e) = run_cli() {
+        if let Some(ExitStatus(code)) = e.downcast_ref() {
+            std::process::exit(*code);
+        }
         eprintln!("❌ Error: {e:?}");
         std::process::exit(1);
     }
@@ -1,11 +1,9 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 use crate::constants::GENERATED_FILE_PREFIX;
-
-/// YAML frontmatter delimiter
-const FRONTMATTER_DELIMITER: &str = "---";
+use crate::utils::frontmatter::split_frontmatter;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FrontMatter {
@@ -19,21 +17,47 @@ pub struct FrontMatter {
         default
     )]
     pub file_matching_patterns: Option<Vec<String>>,
+    /// Position of the rule in generated output; lower values come first.
+    /// `priority` is accepted as an alias.
+    #[serde(alias = "priority", default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<i64>,
+    /// Tags used by config profiles to select subsets of rules
+    #[serde(
+        deserialize_with = "deserialize_comma_separated_optional",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tags: Option<Vec<String>>,
+    /// Whether nested projects inherit this rule, overriding the directory's `inherit` setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inherit: Option<bool>,
 }
 
+/// Accepts a comma-separated string or a list (used by `fileMatching` and `tags`).
 fn deserialize_comma_separated_optional<'de, D>(
     deserializer: D,
 ) -> Result<Option<Vec<String>>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    let opt = Option::<String>::deserialize(deserializer)?;
-    Ok(opt.map(|s| {
-        s.split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect()
-    }))
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Patterns {
+        CommaSeparated(String),
+        List(Vec<String>),
+    }
+
+    let patterns = match Option::<Patterns>::deserialize(deserializer)? {
+        None => return Ok(None),
+        Some(Patterns::CommaSeparated(s)) => s.split(',').map(str::to_string).collect(),
+        Some(Patterns::List(list)) => list,
+    };
+    let patterns: Vec<String> = patterns
+        .iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    Ok((!patterns.is_empty()).then_some(patterns))
 }
 
 impl FrontMatter {
@@ -49,6 +73,9 @@ impl FrontMatter {
             description,
             always_apply: true,
             file_matching_patterns: None,
+            order: None,
+            tags: None,
+            inherit: None,
         }
     }
 }
@@ -61,10 +88,8 @@ pub struct SourceFile {
 }
 
 impl SourceFile {
-    pub fn from_file<P: AsRef<Path>>(file_path: P) -> Result<Self> {
-        let path = file_path.as_ref();
-        let content = std::fs::read_to_string(path)
-            .with_context(|| format!("Failed to read file '{}'", path.display()))?;
+    /// Parses already-loaded file content, using `path` for naming and error messages.
+    pub fn from_content(content: &str, path: &Path) -> Result<Self> {
         let base_file_name = path
             .file_stem()
             .and_then(|name| name.to_str())
@@ -72,7 +97,7 @@ impl SourceFile {
             .to_string();
 
         let file_path_str = path.display().to_string();
-        let mut source_file = Self::parse(&content, &file_path_str)?;
+        let mut source_file = Self::parse(content, &file_path_str)?;
         source_file.base_file_name = base_file_name;
         Ok(source_file)
     }
@@ -92,54 +117,21 @@ impl SourceFile {
     }
 
     fn parse(content: &str, file_path: &str) -> Result<Self> {
-        let content = content.trim_start();
-
-        if content.is_empty() {
-            return Err(anyhow!("File '{}' is empty", file_path));
-        }
-
-        let has_frontmatter = content.starts_with(FRONTMATTER_DELIMITER);
+        let split = split_frontmatter(content, Path::new(file_path))?;
+        let body = split.body.trim_start().to_string();
 
-        if !has_frontmatter {
+        let Some(frontmatter) = split.frontmatter else {
+            if body.is_empty() {
+                return Err(anyhow!("File '{}' is empty", file_path));
+            }
             return Ok(SourceFile {
                 front_matter: FrontMatter::with_defaults_from_path(file_path),
-                body: content.to_string(),
+                body,
                 base_file_name: String::new(),
             });
-        }
-
-        if content.len() < FRONTMATTER_DELIMITER.len() {
-            return Err(anyhow!(
-                "File '{}' is too short to contain YAML frontmatter",
-                file_path
-            ));
-        }
-
-        let mut frontmatter_sections = content.splitn(3, FRONTMATTER_DELIMITER);
-
-        frontmatter_sections.next();
-
-        let frontmatter_str = frontmatter_sections.next().ok_or_else(|| {
-            anyhow!(
-                "Missing closing frontmatter delimiter '{}' in file '{}'",
-                FRONTMATTER_DELIMITER,
-                file_path
-            )
-        })?;
-
-        let body = frontmatter_sections
-            .next()
-            .ok_or_else(|| {
-                anyhow!(
-                    "Missing body content after frontmatter in file '{}'",
-                    file_path
-                )
-            })?
-            .trim_start()
-            .to_string();
+        };
 
-        let front_matter: FrontMatter = serde_yaml::from_str(frontmatter_str)
-            .with_context(|| format!("Failed to parse YAML frontmatter in file '{file_path}'. Ensure the YAML is valid and properly formatted"))?;
+        let front_matter: FrontMatter = frontmatter.deserialize(Path::new(file_path))?;
 
         Ok(SourceFile {
             front_matter,
@@ -175,6 +167,23 @@ This is a test body"#;
         assert_eq!(result.body, "# Test Rules\nThis is a test body");
     }
 
+    #[test]
+    fn test_parse_order_and_priority_alias() {
+        let ordered = SourceFile::parse(
+            "---\ndescription: Safety\nalwaysApply: true\norder: -5\n---\nBody",
+            "safety.md",
+        )
+        .unwrap();
+        assert_eq!(ordered.front_matter.order, Some(-5));
+
+        let prioritized = SourceFile::parse(
+            "---\ndescription: Style\nalwaysApply: true\npriority: 3\n---\nBody",
+            "style.md",
+        )
+        .unwrap();
+        assert_eq!(prioritized.front_matter.order, Some(3));
+    }
+
     #[test]
     fn test_empty_file_matching() {
         let content = r#"---
@@ -268,4 +277,71 @@ This is a test body"#;
         assert_eq!(result.front_matter.file_matching_patterns, None);
         assert_eq!(result.body, "# Just markdown");
     }
+
+    #[test]
+    fn test_parse_tags() {
+        let listed = SourceFile::parse(
+            "---\ndescription: Db\nalwaysApply: true\ntags: [rust, db]\n---\nBody",
+            "db.md",
+        )
+        .unwrap();
+        assert_eq!(
+            listed.front_matter.tags,
+            Some(vec!["rust".to_string(), "db".to_string()])
+        );
+
+        let comma_separated = SourceFile::parse(
+            "---\ndescription: Db\nalwaysApply: true\ntags: \"rust, db\"\n---\nBody",
+            "db.md",
+        )
+        .unwrap();
+        assert_eq!(comma_separated.front_matter.tags, listed.front_matter.tags);
+    }
+
+    #[test]
+    fn test_parse_file_matching_list() {
+        let content = "---\ndescription: List\nalwaysApply: false\nfileMatching:\n  - \"**/*.ts\"\n  - src/**/*.rs\n---\nBody";
+
+        let result = SourceFile::parse(content, "test.md").unwrap();
+
+        assert_eq!(
+            result.front_matter.file_matching_patterns,
+            Some(vec!["**/*.ts".to_string(), "src/**/*.rs".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_dashes_in_values_crlf_and_bom() {
+        let content = "\u{feff}---\r\ndescription: Before --- after\r\nalwaysApply: true\r\n---\r\nIntro\r\n\r\n---\r\n\r\nMore\r\n";
+
+        let result = SourceFile::parse(content, "test.md").unwrap();
+
+        assert_eq!(result.front_matter.description, "Before --- after");
+        assert_eq!(result.body, "Intro\n\n---\n\nMore\n");
+    }
+
+    #[test]
+    fn test_parse_toml_frontmatter() {
+        let content = "+++\ndescription = \"Toml\"\nalwaysApply = false\nfileMatching = [\"*.py\"]\n+++\nBody";
+
+        let result = SourceFile::parse(content, "test.md").unwrap();
+
+        assert_eq!(result.front_matter.description, "Toml");
+        assert!(!result.front_matter.always_apply);
+        assert_eq!(
+            result.front_matter.file_matching_patterns,
+            Some(vec!["*.py".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_frontmatter_reports_line() {
+        let content = "---\ndescription: Test\nalwaysApply: maybe\n---\nBody";
+
+        let error = SourceFile::parse(content, "test.md")
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.starts_with("test.md:3:"), "{error}");
+    }
 }
@@ -113,6 +113,23 @@ pub fn generate_inlined_required_content(source_files: &[SourceFile]) -> String
     parts.join("\n")
 }
 
+/// Inlines every rule, whether or not it is `alwaysApply`. Used for files that can't
+/// reference the generated body files.
+pub fn generate_fully_inlined_content(source_files: &[SourceFile]) -> String {
+    let parts: Vec<String> = source_files
+        .iter()
+        .map(|source_file| {
+            let mut part = String::new();
+            if !source_file.front_matter.description.is_empty() {
+                part.push_str(&format!("# {}\n\n", source_file.front_matter.description));
+            }
+            part.push_str(&ensure_trailing_newline(source_file.body.clone()));
+            part
+        })
+        .collect();
+    parts.join("\n")
+}
+
 pub fn inlined_agents_relative_path() -> PathBuf {
     Path::new(AI_RULE_SOURCE_DIR)
         .join(GENERATED_RULE_BODY_DIR)
@@ -135,6 +152,9 @@ mod tests {
                 description: description.to_string(),
                 always_apply,
                 file_matching_patterns: None,
+                order: None,
+                tags: None,
+                inherit: None,
             },
             body: body.to_string(),
             base_file_name: base_file_name.to_string(),
@@ -2,50 +2,83 @@ use crate::agents::AgentToolRegistry;
 use crate::constants::GENERATED_RULE_BODY_DIR;
 use crate::operations::body_generator::generated_body_file_dir;
 use crate::operations::legacy_cleaner::clean_legacy_agent_directories;
+use crate::operations::local_rules::clean_local_rules_file;
+use crate::operations::manifest::{read_manifest, remove_manifest_outputs};
+use crate::utils::backup;
+use crate::utils::plan::{Operation, Plan};
 use anyhow::Result;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 const LEGACY_FILE_NAMES: &[&str] = &[".goosehints"]; // These are the old rule file names for ai coding agents
 const LEGACY_DIRECTORIES: &[&str] = &[GENERATED_RULE_BODY_DIR]; // These are the old directory names for ai coding agents
 
+/// Plans removing everything generate creates for `agents`. Generate plans this
+/// first and overrides it with the outputs it still produces.
 pub fn clean_generated_files(
     current_dir: &Path,
     agents: &[String],
     registry: &AgentToolRegistry,
+    plan: &mut Plan,
 ) -> Result<()> {
+    let manifest = read_manifest(current_dir)?;
+    if let Some(manifest) = &manifest {
+        for entry in manifest.agents.values().flatten() {
+            if entry.is_intact(current_dir)? {
+                plan.mark_regenerable(current_dir.join(entry.path()));
+            }
+        }
+    }
+
+    // Everything in the generated directory is recreated except backups
     let generated_dir = generated_body_file_dir(current_dir);
     if generated_dir.exists() {
-        fs::remove_dir_all(&generated_dir)?;
+        let backups = generated_dir.join(backup::BACKUPS_DIR);
+        if backups.exists() {
+            let mut entries: Vec<PathBuf> = fs::read_dir(&generated_dir)?
+                .map(|entry| Ok(entry?.path()))
+                .collect::<Result<_>>()?;
+            entries.sort();
+            for path in entries {
+                if path != backups {
+                    plan.push(Operation::delete(path))?;
+                }
+            }
+        } else {
+            plan.push(Operation::delete(generated_dir))?;
+        }
     }
 
     for directory in LEGACY_DIRECTORIES {
         let directory_path = current_dir.join(directory);
         if directory_path.exists() {
-            fs::remove_dir_all(&directory_path)?;
+            plan.push(Operation::delete(directory_path))?;
         }
     }
 
     for file_name in LEGACY_FILE_NAMES {
         let file_path = current_dir.join(file_name);
         if file_path.exists() {
-            fs::remove_file(&file_path)?;
+            plan.push(Operation::delete(file_path))?;
         }
     }
 
     // Clean legacy agent directories (roo, cline, kilocode migrated to AGENTS.md)
-    clean_legacy_agent_directories(current_dir)?;
+    plan.extend(clean_legacy_agent_directories(current_dir)?)?;
 
     for agent in agents {
         if let Some(tool) = registry.get_tool(agent) {
-            tool.clean(current_dir)?;
+            plan.extend(tool.clean(current_dir)?)?;
+            if let Some(output) = tool.local_rules_output() {
+                plan.extend(clean_local_rules_file(current_dir, &output))?;
+            }
         }
     }
 
     for agent in agents {
         if let Some(tool) = registry.get_tool(agent) {
             if let Some(mcp_gen) = tool.mcp_generator() {
-                mcp_gen.clean_mcp(current_dir)?;
+                plan.extend(mcp_gen.clean_mcp(current_dir)?)?;
             }
         }
     }
@@ -54,7 +87,7 @@ pub fn clean_generated_files(
     for agent in agents {
         if let Some(tool) = registry.get_tool(agent) {
             if let Some(cmd_gen) = tool.command_generator() {
-                cmd_gen.clean_commands(current_dir)?;
+                plan.extend(cmd_gen.clean_commands(current_dir)?)?;
             }
         }
     }
@@ -63,10 +96,16 @@ pub fn clean_generated_files(
     for agent in agents {
         if let Some(tool) = registry.get_tool(agent) {
             if let Some(skills_gen) = tool.skills_generator() {
-                skills_gen.clean_skills(current_dir)?;
+                plan.extend(skills_gen.clean_skills(current_dir)?)?;
             }
         }
     }
 
+    // The name-based cleanup above covers projects generated before manifests existed;
+    // the manifest also catches outputs whose names the agents no longer produce.
+    if let Some(manifest) = manifest {
+        plan.extend(remove_manifest_outputs(current_dir, &manifest, agents)?)?;
+    }
+
     Ok(())
 }
@@ -1,21 +1,45 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::constants::{AI_RULE_SOURCE_DIR, COMMANDS_DIR, GENERATED_COMMAND_SUFFIX, MD_EXTENSION};
-use crate::utils::file_utils::{
-    calculate_relative_path, create_relative_symlink, find_files_by_extension,
-};
+use crate::operations::body_generator::generated_body_file_reference_path;
+use crate::operations::imports::{find_import_roots, merge_imported};
+use crate::operations::template_variables::{is_templated_file, TemplateVariables};
+use crate::utils::file_utils::{calculate_relative_path, find_files_by_extension};
+use crate::utils::frontmatter::check_frontmatter_syntax;
+use crate::utils::plan::Operation;
+use crate::utils::print_utils::print_warning;
 
 #[derive(Debug, Clone)]
 pub struct CommandFile {
     pub name: String,
+    /// Symlink target relative to the project directory
     pub relative_path: PathBuf,
     pub full_path: PathBuf,
+    /// The file in ai-rules/commands/ or an import. Differs from `full_path` when the command
+    /// uses template variables and is rendered into the generated directory.
+    pub source_path: PathBuf,
 }
 
-/// Finds all command markdown files in ai-rules/commands/ directory
+/// Finds all command markdown files in ai-rules/commands/ and in the `commands/`
+/// directory of each import
 pub fn find_command_files(current_dir: &Path) -> Result<Vec<CommandFile>> {
-    let commands_dir = current_dir.join(AI_RULE_SOURCE_DIR).join(COMMANDS_DIR);
+    let mut command_files = find_command_files_in(current_dir, Path::new(AI_RULE_SOURCE_DIR))?;
+    for root in find_import_roots(current_dir)? {
+        let imported = find_command_files_in(current_dir, &root.relative_dir)?;
+        merge_imported(&mut command_files, imported, &root, "command", |command| {
+            command.name.clone()
+        })?;
+    }
+    Ok(command_files)
+}
+
+/// Finds the commands in `<source_dir>/commands/`, where `source_dir` is relative to
+/// the project directory
+fn find_command_files_in(current_dir: &Path, source_dir: &Path) -> Result<Vec<CommandFile>> {
+    let commands_dir = current_dir.join(source_dir).join(COMMANDS_DIR);
 
     if !commands_dir.exists() || !commands_dir.is_dir() {
         return Ok(Vec::new());
@@ -27,14 +51,18 @@ pub fn find_command_files(current_dir: &Path) -> Result<Vec<CommandFile>> {
     for path in command_paths {
         if let Some(file_stem) = path.file_stem() {
             if let Some(name) = file_stem.to_str() {
-                let relative_path = PathBuf::from(AI_RULE_SOURCE_DIR)
-                    .join(COMMANDS_DIR)
-                    .join(path.file_name().unwrap());
+                let file_name = path.file_name().unwrap();
+                let relative_path = if is_templated_file(&path) {
+                    generated_body_file_reference_path(COMMANDS_DIR).join(file_name)
+                } else {
+                    source_dir.join(COMMANDS_DIR).join(file_name)
+                };
 
                 command_files.push(CommandFile {
                     name: name.to_string(),
+                    full_path: current_dir.join(&relative_path),
                     relative_path,
-                    full_path: path,
+                    source_path: path,
                 });
             }
         }
@@ -43,128 +71,82 @@ pub fn find_command_files(current_dir: &Path) -> Result<Vec<CommandFile>> {
     Ok(command_files)
 }
 
-/// Creates individual symlinks for each command file in the target directory
+/// Renders commands that use template variables into the generated directory.
+/// Returns the rendered file contents keyed by output path.
+///
+/// Commands with malformed frontmatter are reported as warnings, since agents may
+/// silently ignore them.
+pub fn generate_rendered_command_contents(
+    current_dir: &Path,
+    variables: &TemplateVariables,
+) -> Result<HashMap<PathBuf, String>> {
+    let mut rendered = HashMap::new();
+    for command_file in find_command_files(current_dir)? {
+        let content = fs::read_to_string(&command_file.source_path)
+            .with_context(|| format!("Failed to read {}", command_file.source_path.display()))?;
+        if let Err(error) = check_frontmatter_syntax(&content, &command_file.source_path) {
+            print_warning(&format!("{error:#}"));
+        }
+        if command_file.full_path == command_file.source_path {
+            continue;
+        }
+        rendered.insert(
+            command_file.full_path,
+            variables.render(&content, &command_file.source_path)?,
+        );
+    }
+    Ok(rendered)
+}
+
+/// Symlinks for each command file in the target directory
 pub fn create_command_symlinks_with_extension(
     current_dir: &Path,
     target_dir: &str,
     extension: &str,
-) -> Result<Vec<PathBuf>> {
-    let command_files = find_command_files(current_dir)?;
-    if command_files.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    let mut created_symlinks = Vec::new();
-
-    for command_file in command_files {
+) -> Result<Vec<Operation>> {
+    let mut symlinks = Vec::new();
+    for command_file in find_command_files(current_dir)? {
         let symlink_name = format!(
             "{}-{}.{}",
             command_file.name, GENERATED_COMMAND_SUFFIX, extension
         );
         let from_path = PathBuf::from(target_dir).join(&symlink_name);
         let relative_source = calculate_relative_path(&from_path, &command_file.relative_path);
-        let symlink_path = current_dir.join(&from_path);
-
-        create_relative_symlink(&symlink_path, &relative_source)?;
-        created_symlinks.push(symlink_path);
+        symlinks.push(Operation::symlink(
+            current_dir.join(&from_path),
+            relative_source,
+        ));
     }
 
-    Ok(created_symlinks)
+    Ok(symlinks)
 }
 
-/// Removes generated command symlinks from target directory
+/// Removes generated command symlinks, and copies made by `link_mode`, from target directory
 pub fn remove_generated_command_symlinks_with_extension(
     current_dir: &Path,
     target_dir: &str,
     extension: &str,
-) -> Result<()> {
-    use std::fs;
-
+) -> Result<Vec<Operation>> {
     let target_path = current_dir.join(target_dir);
-    if !target_path.exists() {
-        return Ok(());
+    if !target_path.is_dir() {
+        return Ok(Vec::new());
     }
 
+    let suffix_pattern = format!("-{}.{}", GENERATED_COMMAND_SUFFIX, extension);
+    let mut removals = Vec::new();
     for entry in fs::read_dir(&target_path)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        if let Some(file_name) = path.file_name() {
-            if let Some(name_str) = file_name.to_str() {
-                let suffix_pattern = format!("-{}.{}", GENERATED_COMMAND_SUFFIX, extension);
-                if name_str.ends_with(&suffix_pattern) && path.is_symlink() {
-                    fs::remove_file(&path)?;
-                }
-            }
+        let path = entry?.path();
+        let is_generated = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(&suffix_pattern));
+        if is_generated && (path.is_symlink() || path.is_file()) {
+            removals.push(Operation::delete(path));
         }
     }
+    removals.sort_by(|a, b| a.path().cmp(b.path()));
 
-    Ok(())
-}
-
-/// Checks if generated command symlinks are in sync
-pub fn check_command_symlinks_in_sync_with_extension(
-    current_dir: &Path,
-    target_dir: &str,
-    extension: &str,
-) -> Result<bool> {
-    use std::fs;
-
-    let command_files = find_command_files(current_dir)?;
-    let target_path = current_dir.join(target_dir);
-
-    if command_files.is_empty() {
-        if !target_path.exists() {
-            return Ok(true);
-        }
-
-        for entry in fs::read_dir(&target_path)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if let Some(file_name) = path.file_name() {
-                if let Some(name_str) = file_name.to_str() {
-                    let suffix_pattern = format!("-{}.{}", GENERATED_COMMAND_SUFFIX, extension);
-                    if name_str.ends_with(&suffix_pattern) && path.is_symlink() {
-                        return Ok(false);
-                    }
-                }
-            }
-        }
-        return Ok(true);
-    }
-
-    for command_file in command_files {
-        let symlink_name = format!(
-            "{}-{}.{}",
-            command_file.name, GENERATED_COMMAND_SUFFIX, extension
-        );
-        let symlink_path = target_path.join(&symlink_name);
-
-        if !symlink_path.is_symlink() {
-            return Ok(false);
-        }
-
-        let actual_target = fs::read_link(&symlink_path)?;
-        let resolved_target = if actual_target.is_absolute() {
-            actual_target
-        } else {
-            let symlink_parent = symlink_path.parent().unwrap_or(current_dir);
-            symlink_parent.join(&actual_target)
-        };
-
-        let resolved_canonical = resolved_target.canonicalize().unwrap_or(resolved_target);
-        let expected_canonical = command_file
-            .full_path
-            .canonicalize()
-            .unwrap_or(command_file.full_path.clone());
-
-        if resolved_canonical != expected_canonical {
-            return Ok(false);
-        }
-    }
-
-    Ok(true)
+    Ok(removals)
 }
 
 /// Returns gitignore patterns for generated command symlinks
@@ -180,107 +162,38 @@ pub fn get_command_gitignore_patterns_with_extension(
 
 // === Subfolder-based command symlinks (for Claude) ===
 
-/// Creates symlinks for commands in a subdirectory (e.g., .claude/commands/ai-rules/)
+/// Symlinks for commands in a subdirectory (e.g., .claude/commands/ai-rules/)
 pub fn create_command_symlinks_in_subdir(
     current_dir: &Path,
     target_dir: &str,
     subdir: &str,
-) -> Result<Vec<PathBuf>> {
-    let command_files = find_command_files(current_dir)?;
-    if command_files.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    let mut created_symlinks = Vec::new();
-
-    for command_file in command_files {
+) -> Result<Vec<Operation>> {
+    let mut symlinks = Vec::new();
+    for command_file in find_command_files(current_dir)? {
         // Use original name in subfolder (e.g., ai-rules/commit.md)
         let symlink_name = format!("{}.md", command_file.name);
         let from_path = PathBuf::from(target_dir).join(subdir).join(&symlink_name);
         let relative_source = calculate_relative_path(&from_path, &command_file.relative_path);
-        let symlink_path = current_dir.join(&from_path);
-
-        create_relative_symlink(&symlink_path, &relative_source)?;
-        created_symlinks.push(symlink_path);
+        symlinks.push(Operation::symlink(
+            current_dir.join(&from_path),
+            relative_source,
+        ));
     }
 
-    Ok(created_symlinks)
+    Ok(symlinks)
 }
 
-/// Removes command symlinks from a subdirectory
+/// Removes the subdirectory holding the command symlinks
 pub fn remove_command_symlinks_in_subdir(
     current_dir: &Path,
     target_dir: &str,
     subdir: &str,
-) -> Result<()> {
-    use std::fs;
-
+) -> Result<Vec<Operation>> {
     let subdir_path = current_dir.join(target_dir).join(subdir);
-    if subdir_path.exists() {
-        fs::remove_dir_all(&subdir_path)?;
-    }
-    Ok(())
-}
-
-/// Checks if command symlinks in subdirectory are in sync
-pub fn check_command_symlinks_in_subdir_in_sync(
-    current_dir: &Path,
-    target_dir: &str,
-    subdir: &str,
-) -> Result<bool> {
-    use std::fs;
-
-    let command_files = find_command_files(current_dir)?;
-    let subdir_path = current_dir.join(target_dir).join(subdir);
-
-    if command_files.is_empty() {
-        // No commands - subfolder should not exist
-        return Ok(!subdir_path.exists());
-    }
-
     if !subdir_path.exists() {
-        return Ok(false);
-    }
-
-    // Check all expected symlinks exist and point to correct targets
-    for command_file in &command_files {
-        let symlink_name = format!("{}.md", command_file.name);
-        let symlink_path = subdir_path.join(&symlink_name);
-
-        if !symlink_path.is_symlink() {
-            return Ok(false);
-        }
-
-        let actual_target = fs::read_link(&symlink_path)?;
-        let resolved_target = if actual_target.is_absolute() {
-            actual_target
-        } else {
-            let symlink_parent = symlink_path.parent().unwrap_or(current_dir);
-            symlink_parent.join(&actual_target)
-        };
-
-        let resolved_canonical = resolved_target.canonicalize().unwrap_or(resolved_target);
-        let expected_canonical = command_file
-            .full_path
-            .canonicalize()
-            .unwrap_or(command_file.full_path.clone());
-
-        if resolved_canonical != expected_canonical {
-            return Ok(false);
-        }
-    }
-
-    // Check no extra files exist in subfolder
-    let mut expected_count = 0;
-    for entry in fs::read_dir(&subdir_path)? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_symlink() || path.is_file() {
-            expected_count += 1;
-        }
+        return Ok(Vec::new());
     }
-
-    Ok(expected_count == command_files.len())
+    Ok(vec![Operation::delete(subdir_path)])
 }
 
 /// Returns gitignore patterns for subfolder-based command symlinks
@@ -291,6 +204,7 @@ pub fn get_command_gitignore_patterns_subdir(target_dir: &str, subdir: &str) ->
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::test_utils::helpers::apply_operations;
     use std::fs;
     use tempfile::TempDir;
 
@@ -331,6 +245,7 @@ mod tests {
             create_command_symlinks_with_extension(temp_dir.path(), ".claude/commands", "md")
                 .unwrap();
         assert_eq!(symlinks.len(), 2);
+        apply_operations(temp_dir.path(), symlinks);
 
         let commit_symlink = temp_dir
             .path()
@@ -349,13 +264,24 @@ mod tests {
         fs::create_dir_all(&commands_dir).unwrap();
         fs::write(commands_dir.join("test.md"), "Test").unwrap();
 
-        create_command_symlinks_with_extension(temp_dir.path(), ".claude/commands", "md").unwrap();
+        apply_operations(
+            temp_dir.path(),
+            create_command_symlinks_with_extension(temp_dir.path(), ".claude/commands", "md")
+                .unwrap(),
+        );
 
         let commands_path = temp_dir.path().join(".claude/commands");
         fs::write(commands_path.join("custom.md"), "User's custom command").unwrap();
 
-        remove_generated_command_symlinks_with_extension(temp_dir.path(), ".claude/commands", "md")
-            .unwrap();
+        apply_operations(
+            temp_dir.path(),
+            remove_generated_command_symlinks_with_extension(
+                temp_dir.path(),
+                ".claude/commands",
+                "md",
+            )
+            .unwrap(),
+        );
 
         let generated = commands_path.join(format!("test-{}.md", GENERATED_COMMAND_SUFFIX));
         assert!(!generated.exists());
@@ -5,6 +5,16 @@ use std::path::{Path, PathBuf};
 #[derive(Debug, Default)]
 pub struct GenerationResult {
     pub files_by_agent: BTreeMap<String, Vec<PathBuf>>,
+    pub changes: ChangeCounts,
+}
+
+/// How the generated outputs changed since the previous generate.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ChangeCounts {
+    pub unchanged: usize,
+    pub updated: usize,
+    pub created: usize,
+    pub removed: usize,
 }
 
 impl GenerationResult {
@@ -15,7 +25,26 @@ impl GenerationResult {
             .push(file_path);
     }
 
+    pub fn merge(&mut self, other: GenerationResult) {
+        for (agent, files) in other.files_by_agent {
+            self.files_by_agent.entry(agent).or_default().extend(files);
+        }
+        self.changes.unchanged += other.changes.unchanged;
+        self.changes.updated += other.changes.updated;
+        self.changes.created += other.changes.created;
+        self.changes.removed += other.changes.removed;
+    }
+
     pub fn display(&self, current_dir: &Path) {
+        if self.changes != ChangeCounts::default() {
+            println!(
+                "{} unchanged, {} updated, {} created, {} removed",
+                self.changes.unchanged,
+                self.changes.updated,
+                self.changes.created,
+                self.changes.removed
+            );
+        }
         if self.files_by_agent.is_empty() {
             return;
         }
@@ -0,0 +1,576 @@
+use crate::config::{load_directory_config, ImportSpec};
+use crate::constants::{AI_RULE_SOURCE_DIR, IMPORT_CACHE_DIR, LOCK_FILENAME};
+use crate::utils::plan::{Operation, Plan};
+use crate::utils::transaction;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const DEFAULT_GIT_REF: &str = "HEAD";
+const LOCK_FILE_HEADER: &str =
+    "# Generated by ai-rules. Run 'ai-rules update' to move imports to their latest commit.\n";
+
+/// `ai-rules/ai-rules.lock`: the commit each git import is pinned to.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LockFile {
+    #[serde(default)]
+    pub imports: Vec<LockedImport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LockedImport {
+    pub url: String,
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    pub commit: String,
+}
+
+impl LockFile {
+    fn find(&self, url: &str, git_ref: &str) -> Option<&LockedImport> {
+        self.imports
+            .iter()
+            .find(|locked| locked.url == url && locked.git_ref == git_ref)
+    }
+}
+
+/// A git import from the config, with its ref defaulted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitImport {
+    pub url: String,
+    pub git_ref: String,
+}
+
+impl GitImport {
+    pub fn from_spec(spec: &ImportSpec) -> Option<Self> {
+        spec.url.as_ref().map(|url| Self {
+            url: url.clone(),
+            git_ref: spec
+                .git_ref
+                .clone()
+                .unwrap_or_else(|| DEFAULT_GIT_REF.to_string()),
+        })
+    }
+
+    /// Rejects a url or ref that git would parse as an option.
+    fn validate(&self) -> Result<()> {
+        if self.url.starts_with('-') {
+            return Err(anyhow!("Invalid import url '{}'", self.url));
+        }
+        if self.git_ref.starts_with('-') {
+            return Err(anyhow!(
+                "Invalid ref '{}' for import {}: refs can't start with '-'",
+                self.git_ref,
+                self.url
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn label(&self) -> String {
+        format!("{}@{}", self.url, self.git_ref)
+    }
+
+    /// Checkout directory of this import, relative to the project directory
+    pub fn cache_relative_dir(&self) -> PathBuf {
+        let key: String = self
+            .label()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        PathBuf::from(AI_RULE_SOURCE_DIR)
+            .join(IMPORT_CACHE_DIR)
+            .join(key)
+    }
+}
+
+pub fn lock_file_path(current_dir: &Path) -> PathBuf {
+    current_dir.join(AI_RULE_SOURCE_DIR).join(LOCK_FILENAME)
+}
+
+pub fn read_lock_file(current_dir: &Path) -> Result<LockFile> {
+    let path = lock_file_path(current_dir);
+    if !path.exists() {
+        return Ok(LockFile::default());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let lock_file: LockFile = serde_yaml::from_str(&content)
+        .with_context(|| format!("Invalid lock file {}", path.display()))?;
+    if let Some(locked) = lock_file
+        .imports
+        .iter()
+        .find(|locked| !is_commit_id(&locked.commit))
+    {
+        return Err(anyhow!(
+            "Invalid commit '{}' for {} in {}",
+            locked.commit,
+            locked.url,
+            path.display()
+        ));
+    }
+    Ok(lock_file)
+}
+
+/// A full SHA-1 or SHA-256 commit id.
+fn is_commit_id(commit: &str) -> bool {
+    matches!(commit.len(), 40 | 64) && commit.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Writes the lock file, or removes it once there are no git imports.
+fn lock_file_operation(current_dir: &Path, lock_file: &LockFile) -> Result<Operation> {
+    let path = lock_file_path(current_dir);
+    if lock_file.imports.is_empty() {
+        return Ok(Operation::delete(path));
+    }
+    let content = format!("{LOCK_FILE_HEADER}{}", serde_yaml::to_string(lock_file)?);
+    Ok(Operation::write(path, content))
+}
+
+/// Returns the git imports declared by the directory's own config.
+pub fn find_git_imports(current_dir: &Path) -> Result<Vec<GitImport>> {
+    let imports = load_directory_config(current_dir)?
+        .and_then(|config| config.imports)
+        .unwrap_or_default();
+    let git_imports: Vec<GitImport> = imports
+        .iter()
+        .filter_map(|entry| GitImport::from_spec(&entry.spec()))
+        .collect();
+    for git_import in &git_imports {
+        git_import.validate()?;
+    }
+    Ok(git_imports)
+}
+
+/// Makes sure every git import is checked out, and returns the lock file write that
+/// records newly added imports, if the lock changes.
+///
+/// Nothing is fetched when the cache already matches the lock. With `update`, every
+/// ref is fetched again and the lock moves to its current commit.
+///
+/// Checkouts only live in the gitignored cache, so they are moved right away; the
+/// lock file is left to the caller's plan and only changes if the whole run does. A
+/// checkout left ahead of its lock by a failed run is moved back to the locked commit
+/// by the next sync.
+pub fn sync_git_imports(current_dir: &Path, update: bool) -> Result<Option<Operation>> {
+    let git_imports = find_git_imports(current_dir)?;
+    let lock_file = read_lock_file(current_dir)?;
+    if git_imports.is_empty() && lock_file.imports.is_empty() {
+        return Ok(None);
+    }
+
+    let mut new_lock_file = LockFile::default();
+    for git_import in &git_imports {
+        let checkout_dir = current_dir.join(git_import.cache_relative_dir());
+        let locked_commit = lock_file
+            .find(&git_import.url, &git_import.git_ref)
+            .map(|locked| locked.commit.clone());
+
+        let commit = match locked_commit {
+            Some(commit) if !update => {
+                checkout_locked_commit(current_dir, &checkout_dir, git_import, &commit)?;
+                commit
+            }
+            previous => {
+                let commit = fetch_ref(current_dir, &checkout_dir, git_import)?;
+                checkout(&checkout_dir, &commit)?;
+                if update && previous.as_deref() != Some(commit.as_str()) {
+                    println!("  ⬆️  {} -> {}", git_import.label(), short_commit(&commit));
+                }
+                commit
+            }
+        };
+
+        new_lock_file.imports.push(LockedImport {
+            url: git_import.url.clone(),
+            git_ref: git_import.git_ref.clone(),
+            commit,
+        });
+    }
+
+    if new_lock_file == lock_file {
+        return Ok(None);
+    }
+    lock_file_operation(current_dir, &new_lock_file).map(Some)
+}
+
+/// Describes every git import whose lock entry and cached checkout disagree. Never fetches.
+pub fn check_git_imports(current_dir: &Path) -> Result<Vec<String>> {
+    let git_imports = find_git_imports(current_dir)?;
+    if git_imports.is_empty() {
+        return Ok(Vec::new());
+    }
+    let lock_file = read_lock_file(current_dir)?;
+
+    let mut problems = Vec::new();
+    for git_import in &git_imports {
+        let label = git_import.label();
+        let Some(locked) = lock_file.find(&git_import.url, &git_import.git_ref) else {
+            problems.push(format!("{label} is not in {LOCK_FILENAME}"));
+            continue;
+        };
+        let checkout_dir = current_dir.join(git_import.cache_relative_dir());
+        match checked_out_commit(&checkout_dir) {
+            None => problems.push(format!("{label} has not been fetched")),
+            Some(commit) if commit != locked.commit => problems.push(format!(
+                "{label} is checked out at {} but {LOCK_FILENAME} pins {}",
+                short_commit(&commit),
+                short_commit(&locked.commit)
+            )),
+            Some(_) => {}
+        }
+    }
+    Ok(problems)
+}
+
+fn checkout_locked_commit(
+    current_dir: &Path,
+    checkout_dir: &Path,
+    git_import: &GitImport,
+    commit: &str,
+) -> Result<()> {
+    if checked_out_commit(checkout_dir).as_deref() == Some(commit) {
+        return Ok(());
+    }
+    if !has_commit(checkout_dir, commit) {
+        fetch_ref(current_dir, checkout_dir, git_import)?;
+    }
+    if !has_commit(checkout_dir, commit) {
+        in_checkout_dir(current_dir, checkout_dir, || {
+            run_git(
+                checkout_dir,
+                &["fetch", "--quiet", "--", &git_import.url, commit],
+            )
+            .with_context(|| {
+                format!(
+                    "Locked commit {commit} of {} is unavailable",
+                    git_import.label()
+                )
+            })
+        })?;
+    }
+    checkout(checkout_dir, commit)
+}
+
+/// Fetches the import's ref into its checkout directory and returns the commit it points to.
+fn fetch_ref(current_dir: &Path, checkout_dir: &Path, git_import: &GitImport) -> Result<String> {
+    in_checkout_dir(current_dir, checkout_dir, || {
+        run_git(
+            checkout_dir,
+            &[
+                "fetch",
+                "--quiet",
+                "--",
+                &git_import.url,
+                &git_import.git_ref,
+            ],
+        )
+        .with_context(|| format!("Failed to fetch {}", git_import.label()))?;
+        run_git(checkout_dir, &["rev-parse", "FETCH_HEAD^{commit}"])
+    })
+}
+
+/// Runs `f` in the checkout directory, creating it first. If `f` fails, whatever was
+/// created for a new checkout is removed, so a failed fetch leaves nothing behind.
+fn in_checkout_dir<T>(
+    current_dir: &Path,
+    checkout_dir: &Path,
+    f: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    let created = init_checkout_dir(current_dir, checkout_dir)?;
+    let result = f();
+    if result.is_err() {
+        remove_created(&created)?;
+    }
+    result
+}
+
+/// Creates and initializes the checkout directory if needed, returning the entries
+/// it created: the outermost new folder and the cache's .gitignore.
+fn init_checkout_dir(current_dir: &Path, checkout_dir: &Path) -> Result<Vec<PathBuf>> {
+    if checkout_dir.join(".git").exists() {
+        return Ok(Vec::new());
+    }
+    let mut created: Vec<PathBuf> = checkout_dir
+        .ancestors()
+        .take_while(|ancestor| !ancestor.exists())
+        .last()
+        .map(Path::to_path_buf)
+        .into_iter()
+        .collect();
+    fs::create_dir_all(checkout_dir)?;
+    // Keep the cache out of the enclosing repository
+    if let Some(cache_root) = checkout_dir.parent() {
+        let gitignore = cache_root.join(".gitignore");
+        if !gitignore.exists() {
+            let mut plan = Plan::default();
+            plan.push(Operation::write(&gitignore, "*\n"))?;
+            if let Err(error) = transaction::apply(current_dir, &plan, None) {
+                remove_created(&created)?;
+                return Err(error);
+            }
+            if !created.iter().any(|dir| gitignore.starts_with(dir)) {
+                created.push(gitignore);
+            }
+        }
+    }
+    if let Err(error) = run_git(checkout_dir, &["init", "--quiet"]) {
+        remove_created(&created)?;
+        return Err(error);
+    }
+    Ok(created)
+}
+
+fn remove_created(created: &[PathBuf]) -> Result<()> {
+    for path in created.iter().rev() {
+        if path.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+fn checkout(checkout_dir: &Path, commit: &str) -> Result<()> {
+    run_git(
+        checkout_dir,
+        &["checkout", "--quiet", "--force", "--detach", commit, "--"],
+    )?;
+    Ok(())
+}
+
+fn has_commit(checkout_dir: &Path, commit: &str) -> bool {
+    checkout_dir.join(".git").exists()
+        && run_git(
+            checkout_dir,
+            &["cat-file", "-e", &format!("{commit}^{{commit}}")],
+        )
+        .is_ok()
+}
+
+fn checked_out_commit(checkout_dir: &Path) -> Option<String> {
+    if !checkout_dir.join(".git").exists() {
+        return None;
+    }
+    run_git(checkout_dir, &["rev-parse", "--verify", "--quiet", "HEAD"]).ok()
+}
+
+fn short_commit(commit: &str) -> &str {
+    &commit[..commit.len().min(12)]
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git. Git imports require the git CLI")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    use std::path::Path;
+    use std::process::Command;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed");
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// Syncs the imports like `generate` does and writes the lock file it returns.
+    pub fn sync_and_lock(dir: &Path, update: bool) {
+        let lock_file = super::sync_git_imports(dir, update).unwrap();
+        crate::utils::test_utils::helpers::apply_operations(dir, lock_file);
+    }
+
+    /// Creates a git repository with `files` committed on branch `main`
+    /// and returns its `file://` URL.
+    pub fn create_git_pack(dir: &Path, files: &[(&str, &str)]) -> String {
+        std::fs::create_dir_all(dir).unwrap();
+        git(dir, &["init", "--quiet"]);
+        git(dir, &["checkout", "--quiet", "-b", "main"]);
+        commit_files(dir, files);
+        format!("file://{}", dir.display())
+    }
+
+    /// Commits `files` to the repository and returns the new commit.
+    pub fn commit_files(dir: &Path, files: &[(&str, &str)]) -> String {
+        for (path, content) in files {
+            let file_path = dir.join(path);
+            std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+            std::fs::write(file_path, content).unwrap();
+        }
+        git(dir, &["add", "-A"]);
+        git(dir, &["commit", "--quiet", "-m", "Update pack"]);
+        git(dir, &["rev-parse", "HEAD"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::*;
+    use super::*;
+    use crate::utils::test_utils::helpers::*;
+    use tempfile::TempDir;
+
+    fn setup(temp_dir: &TempDir) -> (PathBuf, String) {
+        let url = create_git_pack(&temp_dir.path().join("pack"), &[("style.md", "v1")]);
+        let project = temp_dir.path().join("project");
+        create_file(
+            &project,
+            "ai-rules/ai-rules-config.yaml",
+            &format!("imports:\n  - url: {url}\n    ref: main\n"),
+        );
+        (project, url)
+    }
+
+    #[test]
+    fn test_sync_git_imports_writes_lock_and_checkout() {
+        let temp_dir = TempDir::new().unwrap();
+        let (project, url) = setup(&temp_dir);
+
+        sync_and_lock(&project, false);
+
+        let lock_file = read_lock_file(&project).unwrap();
+        assert_eq!(lock_file.imports.len(), 1);
+        assert_eq!(lock_file.imports[0].url, url);
+        assert_eq!(lock_file.imports[0].git_ref, "main");
+        let git_import = &find_git_imports(&project).unwrap()[0];
+        assert_file_content(
+            &project,
+            &git_import
+                .cache_relative_dir()
+                .join("style.md")
+                .display()
+                .to_string(),
+            "v1",
+        );
+        assert!(check_git_imports(&project).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sync_git_imports_respects_lock_until_update() {
+        let temp_dir = TempDir::new().unwrap();
+        let (project, _) = setup(&temp_dir);
+        sync_and_lock(&project, false);
+        let locked = read_lock_file(&project).unwrap();
+
+        let new_commit = commit_files(&temp_dir.path().join("pack"), &[("style.md", "v2")]);
+        sync_and_lock(&project, false);
+        assert_eq!(read_lock_file(&project).unwrap(), locked);
+
+        sync_and_lock(&project, true);
+        assert_eq!(
+            read_lock_file(&project).unwrap().imports[0].commit,
+            new_commit
+        );
+    }
+
+    #[test]
+    fn test_check_git_imports_reports_mismatch() {
+        let temp_dir = TempDir::new().unwrap();
+        let (project, _) = setup(&temp_dir);
+        assert_eq!(
+            check_git_imports(&project).unwrap(),
+            vec![format!(
+                "{} is not in ai-rules.lock",
+                find_git_imports(&project).unwrap()[0].label()
+            )]
+        );
+
+        sync_and_lock(&project, false);
+        let mut lock_file = read_lock_file(&project).unwrap();
+        lock_file.imports[0].commit = "0000000000000000000000000000000000000000".to_string();
+        apply_operations(
+            &project,
+            [lock_file_operation(&project, &lock_file).unwrap()],
+        );
+
+        let problems = check_git_imports(&project).unwrap();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("pins 000000000000"), "{}", problems[0]);
+    }
+
+    #[test]
+    fn test_find_git_imports_rejects_option_like_ref() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(
+            temp_dir.path(),
+            "ai-rules/ai-rules-config.yaml",
+            "imports:\n  - url: https://example.com/pack.git\n    ref: --upload-pack=touch\n",
+        );
+
+        let error = sync_git_imports(temp_dir.path(), false).unwrap_err();
+        assert!(
+            error.to_string().contains("can't start with '-'"),
+            "{error}"
+        );
+        assert_file_not_exists(temp_dir.path(), "ai-rules/.cache");
+    }
+
+    #[test]
+    fn test_sync_git_imports_leaves_nothing_behind_when_the_fetch_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let url = create_git_pack(&temp_dir.path().join("pack"), &[("style.md", "v1")]);
+        let project = temp_dir.path().join("project");
+        create_file(
+            &project,
+            "ai-rules/ai-rules-config.yaml",
+            &format!("imports:\n  - url: {url}\n    ref: missing\n"),
+        );
+
+        assert!(sync_git_imports(&project, false).is_err());
+        assert_file_not_exists(&project, "ai-rules/.cache");
+        assert!(read_lock_file(&project).unwrap().imports.is_empty());
+    }
+
+    #[test]
+    fn test_read_lock_file_rejects_partial_commits() {
+        let temp_dir = TempDir::new().unwrap();
+        for commit in ["", "abc123", &"g".repeat(40)] {
+            create_file(
+                temp_dir.path(),
+                "ai-rules/ai-rules.lock",
+                &format!(
+                    "imports:\n  - url: file:///pack\n    ref: main\n    commit: '{commit}'\n"
+                ),
+            );
+            let error = read_lock_file(temp_dir.path()).unwrap_err();
+            assert!(error.to_string().contains("Invalid commit"), "{error}");
+        }
+
+        create_file(
+            temp_dir.path(),
+            "ai-rules/ai-rules.lock",
+            &format!(
+                "imports:\n  - url: file:///pack\n    ref: main\n    commit: {}\n",
+                "a".repeat(40)
+            ),
+        );
+        assert_eq!(read_lock_file(temp_dir.path()).unwrap().imports.len(), 1);
+    }
+}
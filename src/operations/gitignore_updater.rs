@@ -1,6 +1,11 @@
 use crate::agents::AgentToolRegistry;
-use crate::constants::{AGENTS_MD_FILENAME, AI_RULE_SOURCE_DIR, GENERATED_RULE_BODY_DIR};
+use crate::config::OutputMode;
+use crate::constants::{
+    AGENTS_MD_FILENAME, AI_RULE_SOURCE_DIR, GENERATED_RULE_BODY_DIR, LOCAL_RULES_DIR,
+    LOCAL_RULE_SUFFIX, MD_EXTENSION,
+};
 use crate::utils::git_utils::check_gitignore_patterns_to_root;
+use crate::utils::plan::Operation;
 use crate::utils::print_utils::print_info;
 use anyhow::Result;
 use std::collections::HashSet;
@@ -45,6 +50,21 @@ fn collect_all_gitignore_patterns(
         .collect();
     base_patterns.extend(skill_patterns);
 
+    let local_output_patterns: Vec<String> = registry
+        .get_all_tool_names()
+        .iter()
+        .filter_map(|name| registry.get_tool(name.as_str()))
+        .filter_map(|tool| tool.local_rules_output())
+        .map(|output| output.file_name.to_string())
+        .collect();
+    base_patterns.extend(local_output_patterns);
+
+    // Personal rules stay out of version control
+    base_patterns.push(format!(
+        "{AI_RULE_SOURCE_DIR}/*{LOCAL_RULE_SUFFIX}.{MD_EXTENSION}"
+    ));
+    base_patterns.push(format!("{AI_RULE_SOURCE_DIR}/{LOCAL_RULES_DIR}"));
+
     let base_pattern = Path::new(AI_RULE_SOURCE_DIR)
         .join(GENERATED_RULE_BODY_DIR)
         .display()
@@ -78,7 +98,7 @@ fn remove_ai_rules_section(content: String) -> String {
     }
 }
 
-fn update_gitignore(current_dir: &Path, patterns: Vec<String>) -> Result<()> {
+fn update_gitignore(current_dir: &Path, patterns: Vec<String>) -> Operation {
     let gitignore_path = current_dir.join(".gitignore");
 
     let patterns: HashSet<String> = patterns.into_iter().collect();
@@ -101,21 +121,23 @@ fn update_gitignore(current_dir: &Path, patterns: Vec<String>) -> Result<()> {
         content.push_str("# End AI Rules\n");
     }
 
-    fs::write(&gitignore_path, content)?;
-    Ok(())
+    Operation::write(gitignore_path, content)
 }
 
-pub fn remove_gitignore_section(current_dir: &Path, registry: &AgentToolRegistry) -> Result<()> {
+pub fn remove_gitignore_section(current_dir: &Path) -> Result<Option<Operation>> {
     let gitignore_path = current_dir.join(".gitignore");
 
     if !gitignore_path.exists() {
-        return Ok(());
+        return Ok(None);
     }
 
     let content = fs::read_to_string(&gitignore_path)?;
     let content = remove_ai_rules_section(content);
-    fs::write(&gitignore_path, content)?;
+    Ok(Some(Operation::write(gitignore_path, content)))
+}
 
+/// Lists the parent directories whose .gitignore already ignores generated rules.
+pub fn report_parent_gitignores(current_dir: &Path, registry: &AgentToolRegistry) -> Result<()> {
     let patterns = collect_all_gitignore_patterns(registry, 2);
     let parent_dirs_with_gitignore = check_gitignore_patterns_to_root(current_dir, &patterns)?;
 
@@ -133,14 +155,29 @@ pub fn update_project_gitignore(
     current_dir: &Path,
     registry: &AgentToolRegistry,
     nested_depth: usize,
-) -> Result<()> {
-    let patterns = collect_all_gitignore_patterns(registry, nested_depth);
+    output_mode: OutputMode,
+) -> Operation {
+    let mut patterns = collect_all_gitignore_patterns(registry, nested_depth);
+    if output_mode == OutputMode::ManagedBlock {
+        // Files holding a managed block are shared with hand-written text and stay committed
+        let managed_files: HashSet<&str> = registry
+            .get_all_tool_names()
+            .iter()
+            .filter_map(|name| registry.get_tool(name.as_str()))
+            .filter_map(|tool| tool.managed_block_file())
+            .collect();
+        patterns.retain(|pattern| {
+            let file_name = pattern.trim_start_matches("**/").trim_start_matches('/');
+            !managed_files.contains(file_name)
+        });
+    }
     update_gitignore(current_dir, patterns)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::test_utils::helpers::apply_operations;
     use std::fs;
     use tempfile::TempDir;
 
@@ -150,7 +187,7 @@ mod tests {
         let temp_path = temp_dir.path();
 
         let patterns = vec!["*.tmp".to_string(), "build/".to_string()];
-        update_gitignore(temp_path, patterns).unwrap();
+        apply_operations(temp_path, [update_gitignore(temp_path, patterns)]);
 
         let gitignore_path = temp_path.join(".gitignore");
         assert!(gitignore_path.exists());
@@ -176,7 +213,7 @@ build/
         fs::write(&gitignore_path, existing_content).unwrap();
 
         let patterns = vec!["*.new".to_string()];
-        update_gitignore(temp_path, patterns).unwrap();
+        apply_operations(temp_path, [update_gitignore(temp_path, patterns)]);
 
         let content = fs::read_to_string(&gitignore_path).unwrap();
         let expected = r#"# Existing content
@@ -210,7 +247,7 @@ old_build/
         fs::write(&gitignore_path, existing_content).unwrap();
 
         let patterns = vec!["*.new".to_string(), "new_build/".to_string()];
-        update_gitignore(temp_path, patterns).unwrap();
+        apply_operations(temp_path, [update_gitignore(temp_path, patterns)]);
 
         let content = fs::read_to_string(&gitignore_path).unwrap();
         let expected = r#"# Existing content
@@ -240,7 +277,7 @@ new_build/
         fs::write(&gitignore_path, existing_content).unwrap();
 
         let patterns = vec![];
-        update_gitignore(temp_path, patterns).unwrap();
+        apply_operations(temp_path, [update_gitignore(temp_path, patterns)]);
 
         let content = fs::read_to_string(&gitignore_path).unwrap();
         assert_eq!(content, "# Existing content\n*.old\n");
@@ -264,8 +301,7 @@ build/
 *.other"#;
         fs::write(&gitignore_path, existing_content).unwrap();
 
-        let registry = AgentToolRegistry::new();
-        remove_gitignore_section(temp_path, &registry).unwrap();
+        apply_operations(temp_path, remove_gitignore_section(temp_path).unwrap());
 
         let content = fs::read_to_string(&gitignore_path).unwrap();
         let expected = r#"# Existing content
@@ -287,8 +323,7 @@ build/
         let existing_content = "# Existing content\n*.old\n";
         fs::write(&gitignore_path, existing_content).unwrap();
 
-        let registry = AgentToolRegistry::new();
-        remove_gitignore_section(temp_path, &registry).unwrap();
+        apply_operations(temp_path, remove_gitignore_section(temp_path).unwrap());
 
         let content = fs::read_to_string(&gitignore_path).unwrap();
         assert_eq!(content, "# Existing content\n*.old\n");
@@ -319,4 +354,22 @@ build/
             "Should include AMP skill pattern"
         );
     }
+
+    #[test]
+    fn test_gitignore_includes_personal_rule_patterns() {
+        let registry = AgentToolRegistry::new();
+        let patterns = collect_all_gitignore_patterns(&registry, 0);
+
+        for expected in [
+            "/ai-rules/*.local.md",
+            "/ai-rules/local",
+            "/CLAUDE.local.md",
+            "/AGENTS.override.md",
+        ] {
+            assert!(
+                patterns.iter().any(|p| p == expected),
+                "Should include {expected}"
+            );
+        }
+    }
 }
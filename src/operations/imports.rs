@@ -0,0 +1,192 @@
+use crate::config::{load_directory_config, ConflictPolicy};
+use crate::operations::git_imports::GitImport;
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+/// A rule pack listed under `imports:`, laid out like an `ai-rules/` directory
+/// (rule `*.md` files, `commands/`, `skills/` and `mcp.json`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportRoot {
+    /// How the import is named in messages: its path, or `url@ref` for git imports
+    pub label: String,
+    /// The pack directory relative to the project directory. For git imports, this is
+    /// inside the cached checkout.
+    pub relative_dir: PathBuf,
+    /// `relative_dir` joined onto the project directory
+    pub dir: PathBuf,
+    pub on_conflict: ConflictPolicy,
+}
+
+/// Returns the imports declared by the directory's own `ai-rules/ai-rules-config.yaml`.
+///
+/// Imports are not transitive: a pack's own config is never read. Git imports must
+/// already be fetched (see `sync_git_imports`).
+pub fn find_import_roots(current_dir: &Path) -> Result<Vec<ImportRoot>> {
+    let Some(imports) = load_directory_config(current_dir)?.and_then(|config| config.imports)
+    else {
+        return Ok(Vec::new());
+    };
+
+    let mut roots = Vec::new();
+    for entry in imports {
+        let spec = entry.spec();
+        let subdir = spec.path.clone().unwrap_or_default();
+        let (label, relative_dir) = match GitImport::from_spec(&spec) {
+            Some(git_import) => {
+                let checkout_dir = git_import.cache_relative_dir();
+                if !current_dir.join(&checkout_dir).is_dir() {
+                    return Err(anyhow!(
+                        "Import '{}' has not been fetched. Run 'ai-rules generate' or 'ai-rules update'",
+                        git_import.label()
+                    ));
+                }
+                (git_import.label(), checkout_dir.join(&subdir))
+            }
+            None if subdir.is_empty() => {
+                return Err(anyhow!(
+                    "Imports in {} need a 'path' or a 'url'",
+                    current_dir.display()
+                ));
+            }
+            None => (subdir.clone(), PathBuf::from(&subdir)),
+        };
+        let dir = current_dir.join(&relative_dir);
+        if !dir.is_dir() {
+            return Err(anyhow!(
+                "Import '{}' in {} is not a directory",
+                label,
+                current_dir.display()
+            ));
+        }
+        roots.push(ImportRoot {
+            label,
+            relative_dir,
+            dir,
+            on_conflict: spec.on_conflict,
+        });
+    }
+    Ok(roots)
+}
+
+/// Merges items found in an import into `items`, matching by name.
+///
+/// On a collision, the import's `on_conflict` policy decides which item is kept.
+/// Collisions between two imports are resolved by the later import's policy.
+pub fn merge_imported<T>(
+    items: &mut Vec<T>,
+    imported: Vec<T>,
+    root: &ImportRoot,
+    kind: &str,
+    name_of: impl Fn(&T) -> String,
+) -> Result<()> {
+    for item in imported {
+        let name = name_of(&item);
+        match items.iter().position(|existing| name_of(existing) == name) {
+            None => items.push(item),
+            Some(index) => match root.on_conflict {
+                ConflictPolicy::Error => {
+                    return Err(conflict_error(kind, &name, root));
+                }
+                ConflictPolicy::LocalWins => {}
+                ConflictPolicy::ImportWins => items[index] = item,
+            },
+        }
+    }
+    Ok(())
+}
+
+pub fn conflict_error(kind: &str, name: &str, root: &ImportRoot) -> anyhow::Error {
+    anyhow!(
+        "{} '{}' from import '{}' conflicts with an existing {}. Set 'on_conflict: local-wins' or 'on_conflict: import-wins' on the import to choose one",
+        kind,
+        name,
+        root.label,
+        kind
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::helpers::*;
+    use tempfile::TempDir;
+
+    fn root(on_conflict: ConflictPolicy) -> ImportRoot {
+        ImportRoot {
+            label: "pack".to_string(),
+            relative_dir: PathBuf::from("pack"),
+            dir: PathBuf::from("/project/pack"),
+            on_conflict,
+        }
+    }
+
+    #[test]
+    fn test_find_import_roots() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("project");
+        create_file(temp_dir.path(), "shared/ai-rules/style.md", "Style");
+        create_file(
+            &project,
+            "ai-rules/ai-rules-config.yaml",
+            "imports:\n  - ../shared/ai-rules\n  - path: ../shared/ai-rules\n    on_conflict: local-wins\n",
+        );
+
+        let roots = find_import_roots(&project).unwrap();
+
+        assert_eq!(roots.len(), 2);
+        assert_eq!(roots[0].relative_dir, PathBuf::from("../shared/ai-rules"));
+        assert_eq!(roots[0].on_conflict, ConflictPolicy::Error);
+        assert_eq!(roots[1].on_conflict, ConflictPolicy::LocalWins);
+        assert!(roots[0].dir.join("style.md").exists());
+    }
+
+    #[test]
+    fn test_find_import_roots_missing_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(
+            temp_dir.path(),
+            "ai-rules/ai-rules-config.yaml",
+            "imports:\n  - missing\n",
+        );
+
+        let error = find_import_roots(temp_dir.path()).unwrap_err().to_string();
+        assert!(error.contains("'missing'"), "{error}");
+    }
+
+    #[test]
+    fn test_merge_imported_policies() {
+        let name_of = |item: &(&str, &str)| item.0.to_string();
+
+        let mut items = vec![("style", "local")];
+        let error = merge_imported(
+            &mut items,
+            vec![("style", "pack")],
+            &root(ConflictPolicy::Error),
+            "rule",
+            name_of,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("rule 'style' from import 'pack'"), "{error}");
+
+        merge_imported(
+            &mut items,
+            vec![("style", "pack"), ("testing", "pack")],
+            &root(ConflictPolicy::LocalWins),
+            "rule",
+            name_of,
+        )
+        .unwrap();
+        assert_eq!(items, vec![("style", "local"), ("testing", "pack")]);
+
+        merge_imported(
+            &mut items,
+            vec![("style", "other")],
+            &root(ConflictPolicy::ImportWins),
+            "rule",
+            name_of,
+        )
+        .unwrap();
+        assert_eq!(items, vec![("style", "other"), ("testing", "pack")]);
+    }
+}
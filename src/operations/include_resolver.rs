@@ -0,0 +1,297 @@
+use crate::constants::{MD_EXTENSION, PARTIALS_DIR};
+use crate::utils::frontmatter::frontmatter_line_count;
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const INCLUDE_COMMENT_PREFIX: &str = "<!-- ai-rules:include";
+const INCLUDE_COMMENT_SUFFIX: &str = "-->";
+const PARTIAL_PREFIX: &str = "{{>";
+const PARTIAL_SUFFIX: &str = "}}";
+
+/// Expands include directives in a rule file.
+///
+/// Two forms are supported:
+/// * `<!-- ai-rules:include ../shared/testing.md -->` on its own line, resolved
+///   relative to the `ai-rules/` directory
+/// * `{{> testing}}`, resolved to `ai-rules/partials/testing.md`
+///
+/// Included files may include other files. Directives inside the leading
+/// frontmatter block and inside fenced code blocks are left untouched.
+pub fn expand_includes(content: &str, source_path: &Path, ai_rules_dir: &Path) -> Result<String> {
+    let mut stack = vec![normalize(source_path)];
+    expand_recursive(content, source_path, ai_rules_dir, &mut stack)
+}
+
+fn expand_recursive(
+    content: &str,
+    current_file: &Path,
+    ai_rules_dir: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> Result<String> {
+    let frontmatter_end = frontmatter_line_count(content);
+    let mut in_code_fence = false;
+    let mut output = String::with_capacity(content.len());
+
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim();
+        if index < frontmatter_end {
+            output.push_str(line);
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_fence = !in_code_fence;
+        }
+        if in_code_fence {
+            output.push_str(line);
+            continue;
+        }
+
+        let line_number = index + 1;
+        if let Some(target) = parse_include_comment(trimmed) {
+            let included = include_file(
+                &ai_rules_dir.join(target),
+                current_file,
+                line_number,
+                ai_rules_dir,
+                stack,
+            )?;
+            output.push_str(&included);
+            if line.ends_with('\n') && !included.ends_with('\n') {
+                output.push('\n');
+            }
+            continue;
+        }
+
+        output.push_str(&expand_partials_in_line(
+            line,
+            current_file,
+            line_number,
+            ai_rules_dir,
+            stack,
+        )?);
+    }
+
+    Ok(output)
+}
+
+fn expand_partials_in_line(
+    line: &str,
+    current_file: &Path,
+    line_number: usize,
+    ai_rules_dir: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> Result<String> {
+    let mut output = String::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find(PARTIAL_PREFIX) {
+        let after_prefix = &rest[start + PARTIAL_PREFIX.len()..];
+        let Some(end) = after_prefix.find(PARTIAL_SUFFIX) else {
+            break;
+        };
+        let name = after_prefix[..end].trim();
+        if name.is_empty() {
+            return Err(anyhow!(
+                "{}:{}: empty partial name in '{{{{> }}}}' directive",
+                current_file.display(),
+                line_number
+            ));
+        }
+
+        output.push_str(&rest[..start]);
+        let included = include_file(
+            &partial_path(ai_rules_dir, name),
+            current_file,
+            line_number,
+            ai_rules_dir,
+            stack,
+        )?;
+        output.push_str(included.strip_suffix('\n').unwrap_or(&included));
+        rest = &after_prefix[end + PARTIAL_SUFFIX.len()..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn include_file(
+    target: &Path,
+    current_file: &Path,
+    line_number: usize,
+    ai_rules_dir: &Path,
+    stack: &mut Vec<PathBuf>,
+) -> Result<String> {
+    if !target.is_file() {
+        return Err(anyhow!(
+            "{}:{}: included file '{}' does not exist",
+            current_file.display(),
+            line_number,
+            target.display()
+        ));
+    }
+
+    let normalized = normalize(target);
+    if stack.contains(&normalized) {
+        let chain: Vec<String> = stack
+            .iter()
+            .chain(std::iter::once(&normalized))
+            .map(|path| path.display().to_string())
+            .collect();
+        return Err(anyhow!(
+            "{}:{}: include cycle detected: {}",
+            current_file.display(),
+            line_number,
+            chain.join(" -> ")
+        ));
+    }
+
+    let content = fs::read_to_string(target).with_context(|| {
+        format!(
+            "{}:{}: failed to read included file '{}'",
+            current_file.display(),
+            line_number,
+            target.display()
+        )
+    })?;
+
+    stack.push(normalized);
+    let expanded = expand_recursive(&content, target, ai_rules_dir, stack)?;
+    stack.pop();
+
+    Ok(strip_frontmatter(&expanded).trim_start().to_string())
+}
+
+fn parse_include_comment(trimmed_line: &str) -> Option<&str> {
+    let inner = trimmed_line
+        .strip_prefix(INCLUDE_COMMENT_PREFIX)?
+        .strip_suffix(INCLUDE_COMMENT_SUFFIX)?
+        .trim();
+    if inner.is_empty() {
+        None
+    } else {
+        Some(inner)
+    }
+}
+
+fn partial_path(ai_rules_dir: &Path, name: &str) -> PathBuf {
+    let mut path = ai_rules_dir.join(PARTIALS_DIR).join(name);
+    if path.extension().is_none() {
+        path.set_extension(MD_EXTENSION);
+    }
+    path
+}
+
+fn strip_frontmatter(content: &str) -> &str {
+    let end_line = frontmatter_line_count(content);
+    if end_line == 0 {
+        return content;
+    }
+    let offset: usize = content
+        .split_inclusive('\n')
+        .take(end_line)
+        .map(str::len)
+        .sum();
+    &content[offset..]
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::helpers::*;
+    use tempfile::TempDir;
+
+    fn expand(temp_dir: &TempDir, file: &str) -> Result<String> {
+        let ai_rules_dir = temp_dir.path().join("ai-rules");
+        let path = ai_rules_dir.join(file);
+        let content = fs::read_to_string(&path).unwrap();
+        expand_includes(&content, &path, &ai_rules_dir)
+    }
+
+    #[test]
+    fn test_expand_includes_comment_directive() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "shared/testing.md", "Run the tests.\n");
+        create_file(
+            temp_dir.path(),
+            "ai-rules/rule.md",
+            "# Rule\n<!-- ai-rules:include ../shared/testing.md -->\nDone\n",
+        );
+
+        let result = expand(&temp_dir, "rule.md").unwrap();
+        assert_eq!(result, "# Rule\nRun the tests.\nDone\n");
+    }
+
+    #[test]
+    fn test_expand_includes_partial_directive() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(
+            temp_dir.path(),
+            "ai-rules/partials/testing.md",
+            "cargo test\n",
+        );
+        create_file(
+            temp_dir.path(),
+            "ai-rules/rule.md",
+            "Always run `{{> testing}}` before committing.\n",
+        );
+
+        let result = expand(&temp_dir, "rule.md").unwrap();
+        assert_eq!(result, "Always run `cargo test` before committing.\n");
+    }
+
+    #[test]
+    fn test_expand_includes_nested_and_strips_partial_frontmatter() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(
+            temp_dir.path(),
+            "ai-rules/partials/outer.md",
+            "---\ndescription: partial\n---\nOuter\n{{> inner}}\n",
+        );
+        create_file(temp_dir.path(), "ai-rules/partials/inner.md", "Inner\n");
+        create_file(temp_dir.path(), "ai-rules/rule.md", "{{> outer}}\n");
+
+        let result = expand(&temp_dir, "rule.md").unwrap();
+        assert_eq!(result, "Outer\nInner\n");
+    }
+
+    #[test]
+    fn test_expand_includes_skips_frontmatter_and_code_fences() {
+        let temp_dir = TempDir::new().unwrap();
+        let content = "---\ndescription: \"{{> nope}}\"\n---\n```\n{{> nope}}\n```\n";
+        create_file(temp_dir.path(), "ai-rules/rule.md", content);
+
+        let result = expand(&temp_dir, "rule.md").unwrap();
+        assert_eq!(result, content);
+    }
+
+    #[test]
+    fn test_expand_includes_missing_file_reports_line() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(
+            temp_dir.path(),
+            "ai-rules/rule.md",
+            "line one\n<!-- ai-rules:include missing.md -->\n",
+        );
+
+        let error = expand(&temp_dir, "rule.md").unwrap_err().to_string();
+        assert!(error.contains("rule.md:2"), "unexpected error: {error}");
+        assert!(error.contains("missing.md"), "unexpected error: {error}");
+    }
+
+    #[test]
+    fn test_expand_includes_detects_cycles() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "ai-rules/partials/a.md", "{{> b}}\n");
+        create_file(temp_dir.path(), "ai-rules/partials/b.md", "{{> a}}\n");
+        create_file(temp_dir.path(), "ai-rules/rule.md", "{{> a}}\n");
+
+        let error = expand(&temp_dir, "rule.md").unwrap_err().to_string();
+        assert!(error.contains("include cycle detected"), "{error}");
+        assert!(error.contains("b.md:1"), "{error}");
+    }
+}
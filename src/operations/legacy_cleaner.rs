@@ -1,7 +1,8 @@
 use crate::constants::GENERATED_FILE_PREFIX;
+use crate::utils::plan::Operation;
 use anyhow::Result;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Legacy directory configurations for agents that migrated to AGENTS.md
 /// Each entry is (agent_dir, optional_rules_subdir)
@@ -13,65 +14,57 @@ const LEGACY_AGENT_DIRS: &[(&str, Option<&str>)] = &[
 
 /// Cleans up legacy generated files from agents that have migrated to AGENTS.md.
 /// Only removes files with the ai-rules-generated- prefix, then removes empty directories.
-pub fn clean_legacy_agent_directories(current_dir: &Path) -> Result<()> {
+pub fn clean_legacy_agent_directories(current_dir: &Path) -> Result<Vec<Operation>> {
+    let mut removals = Vec::new();
     for (agent_dir, rules_subdir) in LEGACY_AGENT_DIRS {
-        let rules_path = if let Some(subdir) = rules_subdir {
-            current_dir.join(agent_dir).join(subdir)
-        } else {
-            current_dir.join(agent_dir)
+        let agent_path = current_dir.join(agent_dir);
+        let rules_path = match rules_subdir {
+            Some(subdir) => agent_path.join(subdir),
+            None => agent_path.clone(),
         };
-
-        if rules_path.exists() && rules_path.is_dir() {
-            remove_generated_files_from_directory(&rules_path)?;
-            remove_directory_if_empty(&rules_path)?;
+        if !rules_path.is_dir() {
+            continue;
         }
 
-        // Try to remove the parent agent directory if it's now empty
-        let agent_path = current_dir.join(agent_dir);
-        if agent_path.exists() && agent_path.is_dir() {
-            remove_directory_if_empty(&agent_path)?;
+        let (generated, others) = partition_generated_files(&rules_path)?;
+        if !others.is_empty() {
+            removals.extend(generated.into_iter().map(Operation::delete));
+            continue;
+        }
+        // The rules directory is empty once cleaned, and so is the agent directory
+        // if nothing else is in it
+        let (_, agent_others) = partition_generated_files(&agent_path)?;
+        if agent_path != rules_path && agent_others.iter().any(|path| *path != rules_path) {
+            removals.push(Operation::delete(rules_path));
+        } else {
+            removals.push(Operation::delete(agent_path));
         }
     }
 
-    Ok(())
+    Ok(removals)
 }
 
-/// Removes files with the ai-rules-generated- prefix from a directory
-fn remove_generated_files_from_directory(dir: &Path) -> Result<()> {
-    if !dir.exists() {
-        return Ok(());
-    }
-
+/// Splits the entries of a directory into files with the ai-rules-generated- prefix
+/// and everything else, each sorted.
+fn partition_generated_files(dir: &Path) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut generated = Vec::new();
+    let mut others = Vec::new();
     for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.is_file() {
-            if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                if filename.starts_with(GENERATED_FILE_PREFIX) {
-                    fs::remove_file(&path)?;
-                }
-            }
+        let path = entry?.path();
+        let is_generated = path.is_file()
+            && path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|filename| filename.starts_with(GENERATED_FILE_PREFIX));
+        if is_generated {
+            generated.push(path);
+        } else {
+            others.push(path);
         }
     }
-
-    Ok(())
-}
-
-/// Removes a directory only if it's empty
-fn remove_directory_if_empty(dir: &Path) -> Result<()> {
-    if !dir.exists() {
-        return Ok(());
-    }
-
-    // Check if directory is empty
-    let is_empty = fs::read_dir(dir)?.next().is_none();
-
-    if is_empty {
-        fs::remove_dir(dir)?;
-    }
-
-    Ok(())
+    generated.sort();
+    others.sort();
+    Ok((generated, others))
 }
 
 #[cfg(test)]
@@ -92,8 +85,8 @@ mod tests {
         );
         create_file(temp_dir.path(), ".roo/rules/custom-rule.md", "user file");
 
-        let result = clean_legacy_agent_directories(temp_dir.path());
-        assert!(result.is_ok());
+        let removals = clean_legacy_agent_directories(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), removals);
 
         // Generated file should be removed
         assert_file_not_exists(temp_dir.path(), ".roo/rules/ai-rules-generated-test.md");
@@ -114,8 +107,8 @@ mod tests {
             "generated",
         );
 
-        let result = clean_legacy_agent_directories(temp_dir.path());
-        assert!(result.is_ok());
+        let removals = clean_legacy_agent_directories(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), removals);
 
         // Both rules dir and .roo dir should be removed (both empty)
         assert_file_not_exists(temp_dir.path(), ".roo/rules");
@@ -134,8 +127,8 @@ mod tests {
         );
         create_file(temp_dir.path(), ".roo/mcp.json", "mcp config");
 
-        let result = clean_legacy_agent_directories(temp_dir.path());
-        assert!(result.is_ok());
+        let removals = clean_legacy_agent_directories(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), removals);
 
         // Rules dir should be removed (empty after cleanup)
         assert_file_not_exists(temp_dir.path(), ".roo/rules");
@@ -155,8 +148,8 @@ mod tests {
             "generated",
         );
 
-        let result = clean_legacy_agent_directories(temp_dir.path());
-        assert!(result.is_ok());
+        let removals = clean_legacy_agent_directories(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), removals);
 
         // Directory should be removed
         assert_file_not_exists(temp_dir.path(), ".clinerules");
@@ -173,8 +166,8 @@ mod tests {
             "generated",
         );
 
-        let result = clean_legacy_agent_directories(temp_dir.path());
-        assert!(result.is_ok());
+        let removals = clean_legacy_agent_directories(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), removals);
 
         // Both should be removed
         assert_file_not_exists(temp_dir.path(), ".kilocode/rules");
@@ -186,8 +179,8 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
 
         // No legacy directories exist
-        let result = clean_legacy_agent_directories(temp_dir.path());
-        assert!(result.is_ok());
+        let removals = clean_legacy_agent_directories(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), removals);
     }
 
     #[test]
@@ -210,8 +203,8 @@ mod tests {
             "optional",
         );
 
-        let result = clean_legacy_agent_directories(temp_dir.path());
-        assert!(result.is_ok());
+        let removals = clean_legacy_agent_directories(temp_dir.path()).unwrap();
+        apply_operations(temp_dir.path(), removals);
 
         assert_file_not_exists(temp_dir.path(), ".roo");
     }
@@ -0,0 +1,187 @@
+use crate::agents::AgentToolRegistry;
+use crate::models::SourceFile;
+use crate::operations::manifest::Manifest;
+use crate::utils::diff_utils::unified_diff;
+use crate::utils::plan::{Operation, Plan};
+use crate::utils::transaction;
+use anyhow::{bail, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What `generate` does with generated files that were edited by hand.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LocalEditPolicy {
+    /// Stop and show the edits
+    #[default]
+    Refuse,
+    /// Overwrite the edits
+    Force,
+    /// Move the edits into the source files, then generate
+    Adopt,
+}
+
+/// A generated output that changed since the manifest recorded it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalEdit {
+    pub agent: String,
+    pub path: PathBuf,
+}
+
+/// Returns the outputs of `agents` that were edited after the last generate.
+pub fn find_local_edits(
+    current_dir: &Path,
+    manifest: &Manifest,
+    agents: &[String],
+) -> Result<Vec<LocalEdit>> {
+    let mut edits = Vec::new();
+    for (agent, entries) in &manifest.agents {
+        if !agents.contains(agent) {
+            continue;
+        }
+        for entry in entries {
+            let path = current_dir.join(entry.path());
+            // Agents that share an output record it once each
+            if edits.iter().any(|edit: &LocalEdit| edit.path == path) {
+                continue;
+            }
+            if entry.is_locally_modified(current_dir)? {
+                edits.push(LocalEdit {
+                    agent: agent.clone(),
+                    path,
+                });
+            }
+        }
+    }
+    Ok(edits)
+}
+
+/// The content `generate` would write to the edited file, if it is a single generated file.
+fn expected_content(
+    current_dir: &Path,
+    edit: &LocalEdit,
+    source_files: &[SourceFile],
+    registry: &AgentToolRegistry,
+) -> Option<String> {
+    let tool = registry.get_tool(&edit.agent)?;
+    if let Some(content) = tool
+        .mcp_generator()
+        .and_then(|mcp_gen| written_content(mcp_gen.generate_mcp(current_dir), &edit.path))
+    {
+        return Some(content);
+    }
+    if source_files.is_empty() || tool.uses_inlined_symlink() {
+        return None;
+    }
+    tool.generate_agent_contents(source_files, current_dir)
+        .remove(&edit.path)
+}
+
+/// The contents an operation among `operations` writes to `path`.
+fn written_content(operations: Vec<Operation>, path: &Path) -> Option<String> {
+    operations
+        .into_iter()
+        .find_map(|operation| match operation {
+            Operation::Write {
+                path: written,
+                contents,
+            } if written == path => String::from_utf8(contents).ok(),
+            _ => None,
+        })
+}
+
+/// Describes the edit as a diff from the content `generate` would write.
+pub fn describe_local_edit(
+    current_dir: &Path,
+    edit: &LocalEdit,
+    source_files: &[SourceFile],
+    registry: &AgentToolRegistry,
+) -> String {
+    let relative = edit.path.strip_prefix(current_dir).unwrap_or(&edit.path);
+    let edited = match fs::read_to_string(&edit.path) {
+        Ok(edited) if !edit.path.is_symlink() => edited,
+        _ => return format!("{} ({}): edited\n", relative.display(), edit.agent),
+    };
+    match expected_content(current_dir, edit, source_files, registry) {
+        Some(expected) => unified_diff(
+            &expected,
+            &edited,
+            &format!("{} (generated)", relative.display()),
+            &format!("{} (edited)", relative.display()),
+        ),
+        None => format!("{} ({}): edited\n", relative.display(), edit.agent),
+    }
+}
+
+/// Moves the edit into the source files through a transaction and returns the source
+/// file written. Only
+/// MCP and supplemental config files can be adopted; rule files are edited in ai-rules/.
+pub fn adopt_local_edit(
+    current_dir: &Path,
+    edit: &LocalEdit,
+    registry: &AgentToolRegistry,
+) -> Result<PathBuf> {
+    let relative = edit.path.strip_prefix(current_dir).unwrap_or(&edit.path);
+    let mcp_gen = registry
+        .get_tool(&edit.agent)
+        .and_then(|tool| tool.mcp_generator())
+        .filter(|mcp_gen| {
+            mcp_gen
+                .generate_mcp(current_dir)
+                .iter()
+                .any(|operation| operation.path() == edit.path)
+        });
+    if let (Some(mcp_gen), false) = (mcp_gen, edit.path.is_symlink()) {
+        let edited = fs::read_to_string(&edit.path)?;
+        if let Some(adoption) = mcp_gen.adopt_mcp(current_dir, &edited)? {
+            let source = adoption.path().to_path_buf();
+            let mut plan = Plan::default();
+            plan.push(adoption)?;
+            transaction::apply(current_dir, &plan, None)?;
+            return Ok(source);
+        }
+    }
+    bail!(
+        "Can't adopt the edits to {}: move them into the rules in ai-rules/, or rerun with --force to overwrite them",
+        relative.display()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::manifest::build_manifest;
+    use crate::operations::GenerationResult;
+    use crate::utils::plan::Plan;
+    use crate::utils::test_utils::helpers::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_local_edits_and_adopt_mcp_edit() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = AgentToolRegistry::new();
+        let mcp = "{\n  \"mcpServers\": {}\n}\n";
+        create_file(temp_dir.path(), "ai-rules/mcp.json", mcp);
+        create_file(temp_dir.path(), ".mcp.json", mcp);
+        let mut result = GenerationResult::default();
+        result.add_file("claude", temp_dir.path().join(".mcp.json"));
+        let manifest =
+            build_manifest(temp_dir.path(), &result, None, &registry, &Plan::default()).unwrap();
+        let agents = vec!["claude".to_string()];
+        assert!(find_local_edits(temp_dir.path(), &manifest, &agents)
+            .unwrap()
+            .is_empty());
+
+        let edited = r#"{"mcpServers": {"db": {"command": "db-mcp"}}}"#;
+        create_file(temp_dir.path(), ".mcp.json", edited);
+        let edits = find_local_edits(temp_dir.path(), &manifest, &agents).unwrap();
+        assert_eq!(edits.len(), 1);
+        assert!(
+            describe_local_edit(temp_dir.path(), &edits[0], &[], &registry)
+                .contains(&format!("+{edited}"))
+        );
+
+        let source = adopt_local_edit(temp_dir.path(), &edits[0], &registry).unwrap();
+        assert_eq!(source, temp_dir.path().join("ai-rules/mcp.json"));
+        assert!(fs::read_to_string(source).unwrap().contains("db-mcp"));
+    }
+}
@@ -0,0 +1,100 @@
+use crate::agents::rule_generator::LocalRulesOutput;
+use crate::models::SourceFile;
+use crate::operations::body_generator::{
+    generate_fully_inlined_content, generate_inlined_agents_content,
+};
+use crate::utils::plan::Operation;
+use crate::utils::print_utils::print_warning;
+use std::fs;
+use std::path::Path;
+
+/// First line of every native personal rules file ai-rules writes. Files without it
+/// are never overwritten or removed.
+const LOCAL_FILE_MARKER: &str = "<!-- Generated by ai-rules from personal rules";
+
+/// Returns the content of the agent's native personal rules file, or `None` when there
+/// are no personal rules.
+///
+/// Personal rules are inlined in full since they have no generated body files to
+/// reference.
+pub fn generate_local_rules_content(
+    output: &LocalRulesOutput,
+    shared_files: &[SourceFile],
+    personal_files: &[SourceFile],
+) -> Option<String> {
+    if personal_files.is_empty() {
+        return None;
+    }
+    let mut content = format!(
+        "{LOCAL_FILE_MARKER} (ai-rules/*.local.md, ai-rules/local/). Do not commit this file. -->\n\n"
+    );
+    if output.includes_shared_rules && !shared_files.is_empty() {
+        content.push_str(&generate_inlined_agents_content(shared_files));
+        content.push('\n');
+    }
+    content.push_str(&generate_fully_inlined_content(personal_files));
+    Some(content)
+}
+
+fn is_generated_local_file(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|content| content.starts_with(LOCAL_FILE_MARKER))
+}
+
+/// Returns the write of the agent's native personal rules file, if it is generated.
+///
+/// An existing file that ai-rules didn't write is left untouched.
+pub fn generate_local_rules_file(
+    current_dir: &Path,
+    output: &LocalRulesOutput,
+    shared_files: &[SourceFile],
+    personal_files: &[SourceFile],
+) -> Option<Operation> {
+    let file_path = current_dir.join(output.file_name);
+    let content = generate_local_rules_content(output, shared_files, personal_files)?;
+    if file_path.exists() && !is_generated_local_file(&file_path) {
+        print_warning(&format!(
+            "Skipping {}: it wasn't generated by ai-rules. Move its content into ai-rules/local/ to manage it with ai-rules",
+            file_path.display()
+        ));
+        return None;
+    }
+    Some(Operation::write(file_path, content))
+}
+
+/// Removes the agent's native personal rules file if ai-rules wrote it.
+pub fn clean_local_rules_file(current_dir: &Path, output: &LocalRulesOutput) -> Option<Operation> {
+    let file_path = current_dir.join(output.file_name);
+    is_generated_local_file(&file_path).then(|| Operation::delete(file_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::helpers::*;
+    use tempfile::TempDir;
+
+    const OVERRIDE: LocalRulesOutput = LocalRulesOutput {
+        file_name: "AGENTS.override.md",
+        includes_shared_rules: true,
+    };
+
+    #[test]
+    fn test_generate_local_rules_file_keeps_hand_written_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let personal = vec![create_test_source_file(
+            "notes",
+            "Mine",
+            false,
+            vec![],
+            "Use my fork",
+        )];
+        create_file(temp_dir.path(), "AGENTS.override.md", "Hand written");
+
+        let written = generate_local_rules_file(temp_dir.path(), &OVERRIDE, &[], &personal);
+        let removed = clean_local_rules_file(temp_dir.path(), &OVERRIDE);
+
+        assert_eq!(written, None);
+        assert_eq!(removed, None);
+        assert_file_content(temp_dir.path(), "AGENTS.override.md", "Hand written");
+    }
+}
@@ -0,0 +1,178 @@
+use crate::agents::AgentToolRegistry;
+use crate::constants::AGENTS_MD_FILENAME;
+use crate::models::SourceFile;
+use crate::operations::body_generator::generate_inlined_agents_content;
+use crate::operations::source_reader::get_ai_rules_dir;
+use crate::utils::file_utils::ensure_trailing_newline;
+use crate::utils::plan::Operation;
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MANAGED_BLOCK_BEGIN: &str = "<!-- ai-rules:begin -->";
+pub const MANAGED_BLOCK_END: &str = "<!-- ai-rules:end -->";
+
+/// Byte range of the block, from the start of the begin marker to the end of the end marker.
+fn find_block(content: &str) -> Option<(usize, usize)> {
+    let start = content.find(MANAGED_BLOCK_BEGIN)?;
+    let end = start + content[start..].find(MANAGED_BLOCK_END)? + MANAGED_BLOCK_END.len();
+    Some((start, end))
+}
+
+pub fn has_managed_block(content: &str) -> bool {
+    find_block(content).is_some()
+}
+
+/// Returns the text between the markers.
+pub fn extract_managed_block(content: &str) -> Option<&str> {
+    let (start, end) = find_block(content)?;
+    let inner = &content[start + MANAGED_BLOCK_BEGIN.len()..end - MANAGED_BLOCK_END.len()];
+    Some(inner.strip_prefix('\n').unwrap_or(inner))
+}
+
+/// Replaces the block in `content` with `body`, or appends a new block at the end.
+pub fn splice_managed_block(content: &str, body: &str) -> String {
+    let block = format!(
+        "{MANAGED_BLOCK_BEGIN}\n{}{MANAGED_BLOCK_END}",
+        ensure_trailing_newline(body.to_string())
+    );
+    match find_block(content) {
+        Some((start, end)) => format!("{}{block}{}", &content[..start], &content[end..]),
+        None if content.trim().is_empty() => format!("{block}\n"),
+        None => format!("{}\n\n{block}\n", content.trim_end()),
+    }
+}
+
+/// Removes the block and the blank lines around it, keeping the rest of `content`.
+pub fn remove_managed_block(content: &str) -> String {
+    let Some((start, end)) = find_block(content) else {
+        return content.to_string();
+    };
+    let before = content[..start].trim_end();
+    let after = content[end..].trim_start();
+    match (before.is_empty(), after.is_empty()) {
+        (true, _) => after.to_string(),
+        (false, true) => format!("{before}\n"),
+        (false, false) => format!("{before}\n\n{after}"),
+    }
+}
+
+/// Reads a regular file, treating a missing file or a symlink written in `file`
+/// output mode as empty.
+fn read_user_content(path: &Path) -> Result<String> {
+    if path.is_symlink() || !path.is_file() {
+        return Ok(String::new());
+    }
+    Ok(fs::read_to_string(path)?)
+}
+
+/// The rules that go in the block: the pure `ai-rules/AGENTS.md` in symlink mode,
+/// otherwise the inlined content the `file` mode symlinks point at.
+pub fn managed_block_body(
+    current_dir: &Path,
+    source_files: &[SourceFile],
+    is_symlink_mode: bool,
+) -> Result<Option<String>> {
+    if is_symlink_mode {
+        let agents_md = get_ai_rules_dir(current_dir).join(AGENTS_MD_FILENAME);
+        return Ok(Some(fs::read_to_string(agents_md)?));
+    }
+    let content = generate_inlined_agents_content(source_files);
+    Ok((!content.is_empty()).then_some(content))
+}
+
+/// An agent instruction file that holds a managed block.
+#[derive(Debug)]
+pub struct ManagedFile {
+    pub path: PathBuf,
+    /// Agents that read the file
+    pub agents: Vec<String>,
+    /// New content, or `None` if the file should be removed
+    pub content: Option<String>,
+}
+
+/// Computes the new content of every agent file holding a managed block.
+///
+/// This must run before the generated files are cleaned: the current content of each
+/// file decides where the block goes.
+pub fn plan_managed_files(
+    current_dir: &Path,
+    agents: &[String],
+    registry: &AgentToolRegistry,
+    body: Option<&str>,
+) -> Result<Vec<ManagedFile>> {
+    let mut planned: Vec<ManagedFile> = Vec::new();
+    for agent in agents {
+        let Some(file_name) = registry
+            .get_tool(agent)
+            .and_then(|tool| tool.managed_block_file().map(str::to_string))
+        else {
+            continue;
+        };
+        let path = current_dir.join(file_name);
+        if let Some(file) = planned.iter_mut().find(|file| file.path == path) {
+            file.agents.push(agent.clone());
+            continue;
+        }
+
+        let existing = read_user_content(&path)?;
+        let content = match body {
+            Some(body) => Some(splice_managed_block(&existing, body)),
+            None => Some(remove_managed_block(&existing)).filter(|rest| !rest.trim().is_empty()),
+        };
+        planned.push(ManagedFile {
+            path,
+            agents: vec![agent.clone()],
+            content,
+        });
+    }
+    Ok(planned)
+}
+
+/// Writes for the planned files, replacing symlinks left by `file` output mode.
+pub fn managed_file_operations(planned: &[ManagedFile]) -> Vec<Operation> {
+    let mut operations = Vec::new();
+    for file in planned {
+        if file.path.is_symlink() {
+            operations.push(Operation::delete(&file.path));
+        }
+        match &file.content {
+            Some(content) => operations.push(Operation::write(&file.path, content.as_str())),
+            None if file.path.is_file() => operations.push(Operation::delete(&file.path)),
+            None => {}
+        }
+    }
+    operations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HAND_WRITTEN: &str = "# Team notes\n\nAsk before deploying.\n";
+
+    #[test]
+    fn test_splice_managed_block_appends_and_replaces_in_place() {
+        let appended = splice_managed_block(HAND_WRITTEN, "Rule one");
+        assert_eq!(
+            appended,
+            "# Team notes\n\nAsk before deploying.\n\n<!-- ai-rules:begin -->\nRule one\n<!-- ai-rules:end -->\n"
+        );
+
+        let moved = format!("# Top\n\n{}\n\nFooter\n", appended.trim_end());
+        let replaced = splice_managed_block(&moved, "Rule two");
+        assert!(replaced.starts_with("# Top\n\n# Team notes"));
+        assert!(replaced
+            .contains("<!-- ai-rules:begin -->\nRule two\n<!-- ai-rules:end -->\n\nFooter\n"));
+        assert_eq!(extract_managed_block(&replaced), Some("Rule two\n"));
+    }
+
+    #[test]
+    fn test_remove_managed_block_keeps_user_text() {
+        let content = splice_managed_block(HAND_WRITTEN, "Rule one");
+
+        assert_eq!(remove_managed_block(&content), HAND_WRITTEN);
+        assert_eq!(remove_managed_block(&splice_managed_block("", "Rule")), "");
+        assert_eq!(remove_managed_block(HAND_WRITTEN), HAND_WRITTEN);
+    }
+}
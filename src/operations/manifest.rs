@@ -0,0 +1,648 @@
+use crate::agents::AgentToolRegistry;
+use crate::config::{LinkModes, OutputMode};
+use crate::constants::{
+    AI_RULE_SOURCE_DIR, GENERATED_COPY_MARKER, LOCK_FILENAME, MANIFEST_FILENAME,
+};
+use crate::operations::body_generator::generated_body_file_dir;
+use crate::operations::generation_result::ChangeCounts;
+use crate::operations::imports::find_import_roots;
+use crate::operations::managed_block::{
+    extract_managed_block, has_managed_block, remove_managed_block,
+};
+use crate::operations::source_reader::{find_inherited_source_files, SourceOptions};
+use crate::operations::GenerationResult;
+use crate::utils::backup::BACKUPS_DIR;
+use crate::utils::plan::{Operation, Plan, Snapshot};
+use crate::utils::transaction::STAGING_DIR;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Everything `generate` wrote for one directory, per agent.
+///
+/// Stored as `ai-rules/.generated-ai-rules/manifest.json`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Version of ai-rules that wrote the manifest
+    pub version: String,
+    /// What generate read, from [`inputs_hash`]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub inputs: String,
+    /// The outputs in the generated folder every agent shares, like the rule bodies
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub shared_outputs: String,
+    pub agents: BTreeMap<String, Vec<ManifestEntry>>,
+}
+
+impl Manifest {
+    /// Returns true if generating `current_dir` again would change nothing: this
+    /// version of ai-rules wrote the manifest from the same `inputs`, and every output
+    /// it lists is still as generated.
+    pub fn is_up_to_date(&self, current_dir: &Path, inputs: &str) -> Result<bool> {
+        if self.version != env!("CARGO_PKG_VERSION") || self.inputs != inputs {
+            return Ok(false);
+        }
+        let generated_dir = generated_body_file_dir(current_dir);
+        let names = match generated_dir.is_dir() {
+            true => sorted_names(&generated_dir)?,
+            false => Vec::new(),
+        };
+        if shared_outputs_hash(&generated_dir, names, Snapshot::read)? != self.shared_outputs {
+            return Ok(false);
+        }
+        for entry in self.agents.values().flatten() {
+            if !entry.is_intact(current_dir)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// One generated output. Paths are relative to the project directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ManifestEntry {
+    File {
+        path: String,
+        hash: String,
+    },
+    Symlink {
+        path: String,
+        target: String,
+    },
+    /// A folder copied by `link_mode: copy` or `hardlink`
+    Directory {
+        path: String,
+        hash: String,
+    },
+    /// The ai-rules block inside a hand-written agent file
+    ManagedBlock {
+        path: String,
+        hash: String,
+    },
+    /// Keys ai-rules owns inside a JSON file the user also edits
+    JsonKeys {
+        path: String,
+        keys: Vec<String>,
+        hash: String,
+    },
+}
+
+impl ManifestEntry {
+    pub fn path(&self) -> &str {
+        match self {
+            ManifestEntry::File { path, .. }
+            | ManifestEntry::Symlink { path, .. }
+            | ManifestEntry::Directory { path, .. }
+            | ManifestEntry::ManagedBlock { path, .. }
+            | ManifestEntry::JsonKeys { path, .. } => path,
+        }
+    }
+
+    /// Describes the output as it is on disk now, or `None` if it is gone.
+    fn read(
+        current_dir: &Path,
+        path: &Path,
+        json_keys: Option<Vec<String>>,
+    ) -> Result<Option<Self>> {
+        Ok(Snapshot::read(path)?
+            .map(|snapshot| Self::from_snapshot(current_dir, path, &snapshot, json_keys)))
+    }
+
+    /// Describes the output at `path` given what is there.
+    fn from_snapshot(
+        current_dir: &Path,
+        path: &Path,
+        snapshot: &Snapshot,
+        json_keys: Option<Vec<String>>,
+    ) -> Self {
+        let relative = path
+            .strip_prefix(current_dir)
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        match snapshot {
+            Snapshot::Symlink(target) => ManifestEntry::Symlink {
+                path: relative,
+                target: target.display().to_string(),
+            },
+            Snapshot::Directory(_) => ManifestEntry::Directory {
+                path: relative,
+                hash: directory_hash(snapshot),
+            },
+            Snapshot::File(bytes) => {
+                let text = std::str::from_utf8(bytes).ok();
+                if let Some(keys) = json_keys {
+                    let hash = json_keys_hash(text.unwrap_or_default(), &keys);
+                    return ManifestEntry::JsonKeys {
+                        path: relative,
+                        keys,
+                        hash,
+                    };
+                }
+                match text.and_then(extract_managed_block) {
+                    Some(block) => ManifestEntry::ManagedBlock {
+                        path: relative,
+                        hash: content_hash(block.as_bytes()),
+                    },
+                    None => ManifestEntry::File {
+                        path: relative,
+                        hash: content_hash(bytes),
+                    },
+                }
+            }
+        }
+    }
+
+    /// Returns true if the output on disk still matches the entry.
+    pub fn is_intact(&self, current_dir: &Path) -> Result<bool> {
+        let path = current_dir.join(self.path());
+        let json_keys = match self {
+            ManifestEntry::JsonKeys { keys, .. } => Some(keys.clone()),
+            _ => None,
+        };
+        Ok(Self::read(current_dir, &path, json_keys)?.as_ref() == Some(self))
+    }
+
+    /// Returns true if the output still exists but was changed after it was generated.
+    /// Hard links are skipped: they change whenever their source is edited.
+    pub fn is_locally_modified(&self, current_dir: &Path) -> Result<bool> {
+        let path = current_dir.join(self.path());
+        let Ok(metadata) = path.symlink_metadata() else {
+            return Ok(false);
+        };
+        if metadata.is_file() && metadata.nlink() > 1 {
+            return Ok(false);
+        }
+        Ok(!self.is_intact(current_dir)?)
+    }
+
+    /// Removes the output, or only its keys for `JsonKeys`.
+    fn removal(&self, current_dir: &Path) -> Result<Option<Operation>> {
+        let path = current_dir.join(self.path());
+        let operation = match self {
+            ManifestEntry::Symlink { .. } if path.is_symlink() => Operation::delete(path),
+            ManifestEntry::File { .. } if path.is_file() && !path.is_symlink() => {
+                Operation::delete(path)
+            }
+            ManifestEntry::Directory { .. } if path.is_dir() && !path.is_symlink() => {
+                Operation::delete(path)
+            }
+            ManifestEntry::ManagedBlock { .. } if path.is_file() && !path.is_symlink() => {
+                let content = fs::read_to_string(&path)?;
+                if !has_managed_block(&content) {
+                    return Ok(None);
+                }
+                let rest = remove_managed_block(&content);
+                if rest.trim().is_empty() {
+                    Operation::delete(path)
+                } else {
+                    Operation::write(path, rest)
+                }
+            }
+            ManifestEntry::JsonKeys { keys, .. } if path.is_file() => Operation::MergeJson {
+                path,
+                remove: keys.clone(),
+                set: Vec::new(),
+            },
+            _ => return Ok(None),
+        };
+        Ok(Some(operation))
+    }
+}
+
+fn manifest_path(current_dir: &Path) -> PathBuf {
+    generated_body_file_dir(current_dir).join(MANIFEST_FILENAME)
+}
+
+pub fn read_manifest(current_dir: &Path) -> Result<Option<Manifest>> {
+    let path = manifest_path(current_dir);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path)?;
+    let manifest = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(Some(manifest))
+}
+
+/// Builds the manifest for the outputs in `result` as `plan` leaves them, keeping the
+/// entries of agents that weren't generated this time (e.g. `generate --agents claude`)
+/// while their outputs exist.
+pub fn build_manifest(
+    current_dir: &Path,
+    result: &GenerationResult,
+    previous: Option<Manifest>,
+    registry: &AgentToolRegistry,
+    plan: &Plan,
+) -> Result<Manifest> {
+    let mut agents = BTreeMap::new();
+    for (agent, entries) in previous.map(|manifest| manifest.agents).unwrap_or_default() {
+        if result.files_by_agent.contains_key(&agent) {
+            continue;
+        }
+        let mut kept = Vec::new();
+        for entry in entries {
+            if plan.exists(&current_dir.join(entry.path()))? {
+                kept.push(entry);
+            }
+        }
+        if !kept.is_empty() {
+            agents.insert(agent, kept);
+        }
+    }
+
+    for (agent, paths) in &result.files_by_agent {
+        let mcp_generator = registry
+            .get_tool(agent)
+            .and_then(|tool| tool.mcp_generator());
+        let mut entries = Vec::new();
+        for path in paths {
+            let Some(snapshot) = plan.snapshot(path)? else {
+                continue;
+            };
+            let json_keys = match (&mcp_generator, &snapshot) {
+                (Some(mcp_gen), Snapshot::File(bytes)) => std::str::from_utf8(bytes)
+                    .ok()
+                    .and_then(|content| mcp_gen.generated_json_keys(content)),
+                _ => None,
+            };
+            let entry = ManifestEntry::from_snapshot(current_dir, path, &snapshot, json_keys);
+            if !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+        agents.insert(agent.clone(), entries);
+    }
+
+    let generated_dir = generated_body_file_dir(current_dir);
+    let names = plan.children(&generated_dir)?.unwrap_or_default();
+    Ok(Manifest {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        shared_outputs: shared_outputs_hash(&generated_dir, names, |path| plan.snapshot(path))?,
+        agents,
+        ..Default::default()
+    })
+}
+
+/// Writes the manifest, or removes it when nothing was generated.
+pub fn manifest_operation(current_dir: &Path, manifest: &Manifest) -> Result<Operation> {
+    let path = manifest_path(current_dir);
+    if manifest.agents.is_empty() {
+        return Ok(Operation::delete(path));
+    }
+    Ok(Operation::write(
+        path,
+        serde_json::to_string_pretty(manifest)? + "\n",
+    ))
+}
+
+/// Removes the outputs the manifest lists for `agents`.
+pub fn remove_manifest_outputs(
+    current_dir: &Path,
+    manifest: &Manifest,
+    agents: &[String],
+) -> Result<Vec<Operation>> {
+    let mut removals = Vec::new();
+    for (agent, entries) in &manifest.agents {
+        if agents.contains(agent) {
+            for entry in entries {
+                removals.extend(entry.removal(current_dir)?);
+            }
+        }
+    }
+    Ok(removals)
+}
+
+/// The outputs the manifest lists for `agents`, as they are on disk now, keyed by path.
+pub fn read_outputs(
+    current_dir: &Path,
+    manifest: &Manifest,
+    agents: &[String],
+) -> Result<BTreeMap<String, ManifestEntry>> {
+    let mut outputs = BTreeMap::new();
+    for (agent, entries) in &manifest.agents {
+        if !agents.contains(agent) {
+            continue;
+        }
+        for entry in entries {
+            let json_keys = match entry {
+                ManifestEntry::JsonKeys { keys, .. } => Some(keys.clone()),
+                _ => None,
+            };
+            let path = current_dir.join(entry.path());
+            if let Some(output) = ManifestEntry::read(current_dir, &path, json_keys)? {
+                outputs.insert(entry.path().to_string(), output);
+            }
+        }
+    }
+    Ok(outputs)
+}
+
+/// Compares the outputs of `agents` before generate, from [`read_outputs`], with the
+/// manifest generate wrote.
+pub fn count_changes(
+    before: &BTreeMap<String, ManifestEntry>,
+    manifest: &Manifest,
+    agents: &[String],
+) -> ChangeCounts {
+    let mut after = BTreeMap::new();
+    for (agent, entries) in &manifest.agents {
+        if agents.contains(agent) {
+            after.extend(entries.iter().map(|entry| (entry.path(), entry)));
+        }
+    }
+    let mut counts = ChangeCounts::default();
+    for (path, entry) in &after {
+        match before.get(*path) {
+            Some(previous) if previous == *entry => counts.unchanged += 1,
+            Some(_) => counts.updated += 1,
+            None => counts.created += 1,
+        }
+    }
+    counts.removed = before
+        .keys()
+        .filter(|path| !after.contains_key(path.as_str()))
+        .count();
+    counts
+}
+
+/// FNV-1a, which is enough to notice changed content without another dependency.
+pub fn content_hash(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("fnv1a64:{hash:016x}")
+}
+
+/// Hashes everything generating `current_dir` reads besides ai-rules itself: the
+/// options, the rules it inherits, the files in `ai-rules/` and the imports. The lock
+/// file is left out, as generate writes it after planning; the checkouts it pins are
+/// hashed with the other imports.
+pub fn inputs_hash(
+    current_dir: &Path,
+    agents: &[String],
+    command_agents: &[String],
+    source_options: &SourceOptions,
+    output_mode: OutputMode,
+    link_modes: LinkModes,
+) -> Result<String> {
+    let mut bytes = format!(
+        "{agents:?}\0{command_agents:?}\0{output_mode:?}\0{link_modes:?}\0{source_options:?}\0"
+    )
+    .into_bytes();
+    let inherited = find_inherited_source_files(current_dir, source_options)?;
+    bytes.extend(format!("{inherited:?}\0").as_bytes());
+
+    // The generated folder and the import cache start with a dot
+    let source_dir = current_dir.join(AI_RULE_SOURCE_DIR);
+    if source_dir.is_dir() {
+        for name in sorted_names(&source_dir)? {
+            if !name.to_string_lossy().starts_with('.') && name != LOCK_FILENAME {
+                collect_tree(&source_dir, Path::new(&name), &mut Vec::new(), &mut bytes)?;
+            }
+        }
+    }
+    for root in find_import_roots(current_dir)? {
+        collect_tree(current_dir, &root.relative_dir, &mut Vec::new(), &mut bytes)?;
+    }
+    Ok(content_hash(&bytes))
+}
+
+fn sorted_names(dir: &Path) -> Result<Vec<OsString>> {
+    let mut names = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<Result<Vec<_>, _>>()?;
+    names.sort();
+    Ok(names)
+}
+
+/// Adds the path and content of everything at `base/relative` to `bytes`. Symlinks
+/// are followed, so a rule linked in from elsewhere counts by its content; `visited`
+/// holds the folders being read, so a link to one of them isn't followed again.
+fn collect_tree(
+    base: &Path,
+    relative: &Path,
+    visited: &mut Vec<PathBuf>,
+    bytes: &mut Vec<u8>,
+) -> Result<()> {
+    let path = base.join(relative);
+    bytes.extend(relative.display().to_string().as_bytes());
+    bytes.push(0);
+    let Ok(metadata) = fs::metadata(&path) else {
+        // A broken symlink
+        return Ok(());
+    };
+    if !metadata.is_dir() {
+        bytes.extend(fs::read(&path)?);
+        bytes.push(0);
+        return Ok(());
+    }
+    let canonical = path.canonicalize()?;
+    if visited.contains(&canonical) {
+        return Ok(());
+    }
+    visited.push(canonical);
+    for name in sorted_names(&path)? {
+        if name != ".git" {
+            collect_tree(base, &relative.join(name), visited, bytes)?;
+        }
+    }
+    visited.pop();
+    Ok(())
+}
+
+/// Hashes the generated folder at `generated_dir` as `snapshot` reads its entries
+/// `names`, leaving out the manifest and the folders backups and transactions use.
+fn shared_outputs_hash(
+    generated_dir: &Path,
+    names: impl IntoIterator<Item = OsString>,
+    snapshot: impl Fn(&Path) -> Result<Option<Snapshot>>,
+) -> Result<String> {
+    let mut entries = BTreeMap::new();
+    for name in names {
+        if [MANIFEST_FILENAME, BACKUPS_DIR, STAGING_DIR]
+            .iter()
+            .any(|skipped| name == *skipped)
+        {
+            continue;
+        }
+        if let Some(entry) = snapshot(&generated_dir.join(&name))? {
+            entries.insert(PathBuf::from(name), entry);
+        }
+    }
+    Ok(directory_hash(&Snapshot::Directory(entries)))
+}
+
+fn directory_hash(dir: &Snapshot) -> String {
+    fn collect(entries: &BTreeMap<PathBuf, Snapshot>, prefix: &Path, bytes: &mut Vec<u8>) {
+        for (name, entry) in entries {
+            if name == Path::new(GENERATED_COPY_MARKER) {
+                continue;
+            }
+            let relative = prefix.join(name);
+            bytes.extend(relative.display().to_string().as_bytes());
+            bytes.push(0);
+            match entry {
+                Snapshot::Directory(entries) => collect(entries, &relative, bytes),
+                Snapshot::File(contents) => {
+                    bytes.extend(contents);
+                    bytes.push(0);
+                }
+                Snapshot::Symlink(target) => {
+                    bytes.extend(target.display().to_string().as_bytes());
+                    bytes.push(0);
+                }
+            }
+        }
+    }
+    let mut bytes = Vec::new();
+    if let Snapshot::Directory(entries) = dir {
+        collect(entries, Path::new(""), &mut bytes);
+    }
+    content_hash(&bytes)
+}
+
+fn json_keys_hash(content: &str, keys: &[String]) -> String {
+    let json: Value = serde_json::from_str(content).unwrap_or(Value::Null);
+    let values: Vec<String> = keys
+        .iter()
+        .map(|key| json.pointer(key).map(Value::to_string).unwrap_or_default())
+        .collect();
+    content_hash(values.join("\n").as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::helpers::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_build_manifest_keeps_agents_not_generated() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = AgentToolRegistry::new();
+        create_file(temp_dir.path(), "CLAUDE.md", "Rules");
+        create_file(temp_dir.path(), ".cursor/mcp.json", "{}");
+        let previous = Manifest {
+            version: "1.0.0".to_string(),
+            agents: BTreeMap::from([
+                (
+                    "cursor".to_string(),
+                    vec![ManifestEntry::File {
+                        path: ".cursor/mcp.json".to_string(),
+                        hash: content_hash(b"{}"),
+                    }],
+                ),
+                (
+                    "goose".to_string(),
+                    vec![ManifestEntry::File {
+                        path: "AGENTS.md".to_string(),
+                        hash: content_hash(b"Gone"),
+                    }],
+                ),
+            ]),
+            ..Default::default()
+        };
+        let mut result = GenerationResult::default();
+        result.add_file("claude", temp_dir.path().join("CLAUDE.md"));
+
+        let manifest = build_manifest(
+            temp_dir.path(),
+            &result,
+            Some(previous),
+            &registry,
+            &Plan::default(),
+        )
+        .unwrap();
+
+        assert_eq!(manifest.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            manifest.agents.keys().collect::<Vec<_>>(),
+            vec!["claude", "cursor"]
+        );
+        assert!(manifest.agents["claude"][0]
+            .is_intact(temp_dir.path())
+            .unwrap());
+        create_file(temp_dir.path(), "CLAUDE.md", "Edited");
+        assert!(!manifest.agents["claude"][0]
+            .is_intact(temp_dir.path())
+            .unwrap());
+    }
+
+    #[test]
+    fn test_remove_manifest_outputs_only_removes_owned_json_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(
+            temp_dir.path(),
+            ".gemini/settings.json",
+            r#"{"theme": "dark", "mcpServers": {"mine": {}, "ai-rules-generated-db": {}}}"#,
+        );
+        let manifest = Manifest {
+            version: "1.0.0".to_string(),
+            agents: BTreeMap::from([(
+                "gemini".to_string(),
+                vec![ManifestEntry::JsonKeys {
+                    path: ".gemini/settings.json".to_string(),
+                    keys: vec!["/mcpServers/ai-rules-generated-db".to_string()],
+                    hash: String::new(),
+                }],
+            )]),
+            ..Default::default()
+        };
+
+        let removals =
+            remove_manifest_outputs(temp_dir.path(), &manifest, &["gemini".to_string()]).unwrap();
+        apply_operations(temp_dir.path(), removals);
+
+        let content = fs::read_to_string(temp_dir.path().join(".gemini/settings.json")).unwrap();
+        let json: Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(json["theme"], "dark");
+        assert!(json["mcpServers"].get("mine").is_some());
+        assert!(json["mcpServers"].get("ai-rules-generated-db").is_none());
+    }
+
+    #[test]
+    fn test_count_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = AgentToolRegistry::new();
+        for name in ["CLAUDE.md", "AGENTS.md", "GEMINI.md"] {
+            create_file(temp_dir.path(), name, "Rules");
+        }
+        let mut result = GenerationResult::default();
+        for name in ["CLAUDE.md", "AGENTS.md", "GEMINI.md"] {
+            result.add_file("claude", temp_dir.path().join(name));
+        }
+        let previous =
+            build_manifest(temp_dir.path(), &result, None, &registry, &Plan::default()).unwrap();
+        let agents = vec!["claude".to_string()];
+        let before = read_outputs(temp_dir.path(), &previous, &agents).unwrap();
+
+        create_file(temp_dir.path(), "AGENTS.md", "New rules");
+        create_file(temp_dir.path(), ".mcp.json", "{}");
+        let mut result = GenerationResult::default();
+        for name in ["CLAUDE.md", "AGENTS.md", ".mcp.json"] {
+            result.add_file("claude", temp_dir.path().join(name));
+        }
+        let manifest =
+            build_manifest(temp_dir.path(), &result, None, &registry, &Plan::default()).unwrap();
+
+        assert_eq!(
+            count_changes(&before, &manifest, &agents),
+            ChangeCounts {
+                unchanged: 1,
+                updated: 1,
+                created: 1,
+                removed: 1,
+            }
+        );
+    }
+}
@@ -1,8 +1,11 @@
+use crate::config::ConflictPolicy;
 use crate::constants::{AI_RULE_SOURCE_DIR, MCP_JSON, MCP_SERVERS_FIELD};
+use crate::operations::imports::{conflict_error, find_import_roots, ImportRoot};
 use crate::utils::file_utils::ensure_trailing_newline;
-use anyhow::{Context as _, Result};
+use crate::utils::plan::Operation;
+use anyhow::{anyhow, Context as _, Result};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
@@ -38,21 +41,80 @@ pub enum McpServerConfig {
     },
 }
 
-fn read_mcp_source_file_content(current_dir: &Path) -> Result<Option<String>> {
-    let mcp_source_path = current_dir.join(AI_RULE_SOURCE_DIR).join(MCP_JSON);
-
+fn read_mcp_file(mcp_source_path: &Path) -> Result<Option<String>> {
     if !mcp_source_path.exists() {
         return Ok(None);
     }
-    let content = fs::read_to_string(&mcp_source_path)
+    let content = fs::read_to_string(mcp_source_path)
         .with_context(|| format!("Failed to read {}", mcp_source_path.display()))?;
 
-    let _config: McpConfig = serde_json::from_str(&content)
-        .with_context(|| format!("Invalid MCP configuration in {}", mcp_source_path.display()))?;
+    // Located as `file:line:column: message`, like frontmatter errors
+    let _config: McpConfig = serde_json::from_str(&content).map_err(|error| {
+        let message = error.to_string();
+        let message = message
+            .rsplit_once(" at line ")
+            .map_or(message.as_str(), |(message, _)| message);
+        anyhow!(
+            "{}:{}:{}: invalid MCP configuration: {message}",
+            mcp_source_path.display(),
+            error.line(),
+            // serde_json reports column 0 at the end of a line
+            error.column().max(1)
+        )
+    })?;
 
     Ok(Some(content))
 }
 
+/// Reads ai-rules/mcp.json with the servers of each import's `mcp.json` merged in.
+/// Without imported servers the local file is returned unchanged.
+fn read_mcp_source_file_content(current_dir: &Path) -> Result<Option<String>> {
+    let local_content = read_mcp_file(&current_dir.join(AI_RULE_SOURCE_DIR).join(MCP_JSON))?;
+
+    let mut merged: Option<Value> = None;
+    for root in find_import_roots(current_dir)? {
+        let Some(imported_content) = read_mcp_file(&root.dir.join(MCP_JSON))? else {
+            continue;
+        };
+        let json = match merged.take() {
+            Some(json) => json,
+            None => match &local_content {
+                Some(content) => serde_json::from_str(content)?,
+                None => json!({ MCP_SERVERS_FIELD: {} }),
+            },
+        };
+        let imported: Value = serde_json::from_str(&imported_content)?;
+        merged = Some(merge_mcp_servers(json, imported, &root)?);
+    }
+
+    match merged {
+        Some(json) => Ok(Some(serde_json::to_string_pretty(&json)?)),
+        None => Ok(local_content),
+    }
+}
+
+fn merge_mcp_servers(mut json: Value, imported: Value, root: &ImportRoot) -> Result<Value> {
+    let Some(Value::Object(imported_servers)) = imported.get(MCP_SERVERS_FIELD).cloned() else {
+        return Ok(json);
+    };
+    let Some(Value::Object(servers)) = json.get_mut(MCP_SERVERS_FIELD) else {
+        return Ok(json);
+    };
+    for (name, server) in imported_servers {
+        if servers.contains_key(&name) {
+            match root.on_conflict {
+                ConflictPolicy::Error => {
+                    return Err(conflict_error("MCP server", &name, root));
+                }
+                ConflictPolicy::LocalWins => continue,
+                ConflictPolicy::ImportWins => {}
+            }
+        }
+        servers.insert(name, server);
+    }
+    Ok(json)
+}
+
 pub fn read_mcp_config(current_dir: &Path) -> Result<Option<String>> {
     match read_mcp_source_file_content(current_dir)? {
         Some(content) => Ok(Some(ensure_trailing_newline(content))),
@@ -70,6 +132,43 @@ pub fn extract_mcp_servers_for_firebender(current_dir: &Path) -> Result<Option<V
     }
 }
 
+/// Returns the write that moves edits of a generated MCP file back into
+/// ai-rules/mcp.json: servers that were changed or added are stored there and removed
+/// ones are dropped. Servers that only come
+/// from imports can't be removed this way.
+pub fn adopt_mcp_servers(current_dir: &Path, edited_servers: &Value) -> Result<Operation> {
+    let generated = extract_mcp_servers_for_firebender(current_dir)?.unwrap_or(json!({}));
+    let source_path = current_dir.join(AI_RULE_SOURCE_DIR).join(MCP_JSON);
+    let mut source: Value = match read_mcp_file(&source_path)? {
+        Some(content) => serde_json::from_str(&content)?,
+        None => json!({ MCP_SERVERS_FIELD: {} }),
+    };
+    let (Some(edited), Some(generated), Some(servers)) = (
+        edited_servers.as_object(),
+        generated.as_object(),
+        source
+            .get_mut(MCP_SERVERS_FIELD)
+            .and_then(Value::as_object_mut),
+    ) else {
+        anyhow::bail!("Expected \"{MCP_SERVERS_FIELD}\" to be an object");
+    };
+    for (name, server) in edited {
+        if generated.get(name) != Some(server) {
+            servers.insert(name.clone(), server.clone());
+        }
+    }
+    for name in generated.keys() {
+        if !edited.contains_key(name) {
+            servers.remove(name);
+        }
+    }
+
+    Ok(Operation::write(
+        source_path,
+        ensure_trailing_newline(serde_json::to_string_pretty(&source)?),
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -351,4 +450,69 @@ mod tests {
         let result = read_mcp_config(temp_dir.path());
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_read_mcp_config_merges_imported_servers() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("project");
+        create_file(&project, "ai-rules/mcp.json", TEST_MCP_CONFIG);
+        create_file(
+            temp_dir.path(),
+            "pack/mcp.json",
+            r#"{"mcpServers": {"docs": {"type": "http", "url": "https://docs.example.com"}}}"#,
+        );
+        create_file(
+            &project,
+            "ai-rules/ai-rules-config.yaml",
+            "imports:\n  - ../pack\n",
+        );
+
+        let content = read_mcp_config(&project).unwrap().unwrap();
+        let json: Value = serde_json::from_str(&content).unwrap();
+        let servers = json[MCP_SERVERS_FIELD].as_object().unwrap();
+
+        assert!(servers.contains_key("docs"));
+        assert!(servers.len() > 1);
+        assert!(content.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_read_mcp_config_import_conflict() {
+        let temp_dir = TempDir::new().unwrap();
+        let server =
+            |command: &str| format!(r#"{{"mcpServers": {{"db": {{"command": "{command}"}}}}}}"#);
+        create_file(temp_dir.path(), "ai-rules/mcp.json", &server("local"));
+        create_file(temp_dir.path(), "pack/mcp.json", &server("imported"));
+        let config_path = "ai-rules/ai-rules-config.yaml";
+
+        create_file(temp_dir.path(), config_path, "imports:\n  - pack\n");
+        let error = read_mcp_config(temp_dir.path()).unwrap_err().to_string();
+        assert!(error.contains("MCP server 'db'"), "{error}");
+
+        create_file(
+            temp_dir.path(),
+            config_path,
+            "imports:\n  - path: pack\n    on_conflict: import-wins\n",
+        );
+        let servers = extract_mcp_servers_for_firebender(temp_dir.path())
+            .unwrap()
+            .unwrap();
+        assert_eq!(servers["db"]["command"], "imported");
+    }
+
+    #[test]
+    fn test_adopt_mcp_servers() {
+        let temp_dir = TempDir::new().unwrap();
+        create_file(temp_dir.path(), "ai-rules/mcp.json", TEST_MCP_CONFIG);
+        let edited = json!({
+            "figma": { "type": "http", "url": "https://mcp.figma.com/mcp" }
+        });
+
+        let adoption = adopt_mcp_servers(temp_dir.path(), &edited).unwrap();
+        let path = adoption.path().to_path_buf();
+        apply_operations(temp_dir.path(), [adoption]);
+
+        let adopted: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(adopted[MCP_SERVERS_FIELD], edited);
+    }
 }
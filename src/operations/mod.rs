@@ -2,24 +2,37 @@ pub mod body_generator;
 pub mod cleaner;
 pub mod command_reader;
 pub mod generation_result;
+pub mod git_imports;
 pub mod gitignore_updater;
+pub mod imports;
+pub mod include_resolver;
 pub mod legacy_cleaner;
+pub mod local_edits;
+pub mod local_rules;
+pub mod managed_block;
+pub mod manifest;
 pub mod mcp_reader;
 pub mod optional_rules;
+pub mod rule_linter;
 pub mod skills_reader;
 pub mod source_reader;
+pub mod source_validator;
+pub mod template_variables;
+pub mod user_scope;
 
 pub use body_generator::{generate_all_rule_references, generate_body_contents};
 pub use cleaner::clean_generated_files;
 #[allow(unused_imports)]
 pub use command_reader::{find_command_files, CommandFile};
 pub use generation_result::GenerationResult;
-pub use gitignore_updater::{remove_gitignore_section, update_project_gitignore};
+pub use gitignore_updater::{
+    remove_gitignore_section, report_parent_gitignores, update_project_gitignore,
+};
 #[allow(unused_imports)]
 pub use legacy_cleaner::clean_legacy_agent_directories;
 #[allow(unused_imports)]
 pub use skills_reader::{
-    check_skill_symlinks_in_sync, create_skill_symlinks, find_skill_folders,
-    get_skill_gitignore_patterns, remove_generated_skills, SkillFolder,
+    create_skill_symlinks, find_skill_folders, get_skill_gitignore_patterns,
+    remove_generated_skills, SkillFolder,
 };
 pub use source_reader::find_source_files;
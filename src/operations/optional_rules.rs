@@ -64,6 +64,9 @@ mod tests {
                 description: description.to_string(),
                 always_apply,
                 file_matching_patterns: Some(file_patterns),
+                order: None,
+                tags: None,
+                inherit: None,
             },
             body: body.to_string(),
             base_file_name: base_name.to_string(),
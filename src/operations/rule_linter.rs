@@ -0,0 +1,544 @@
+use crate::config::{LintConfig, LintSeverity};
+use crate::models::SourceFile;
+use crate::operations::source_reader::get_md_files_in_ai_rules_dir;
+use crate::utils::annotations::{Annotation, Level};
+use crate::utils::frontmatter::{split_frontmatter, Frontmatter};
+use crate::utils::schema::{did_you_mean, locate_key, locate_word};
+use anyhow::{bail, Context, Result};
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Each check's code and its severity unless the config changes it.
+pub const LINT_CHECKS: &[(&str, LintSeverity)] = &[
+    ("broken-link", LintSeverity::Error),
+    ("duplicate-heading", LintSeverity::Warning),
+    ("duplicate-paragraph", LintSeverity::Warning),
+    ("unmatched-glob", LintSeverity::Warning),
+    ("vague-description", LintSeverity::Warning),
+    ("oversize-rule", LintSeverity::Warning),
+];
+
+const DEFAULT_MAX_RULE_LINES: usize = 300;
+const DEFAULT_MIN_DESCRIPTION_WORDS: usize = 4;
+/// Shorter paragraphs are too common to be worth comparing
+const MIN_PARAGRAPH_WORDS: usize = 8;
+/// Share of their distinct words two paragraphs must have in common to be near-duplicates
+const PARAGRAPH_SIMILARITY: f64 = 0.8;
+
+static MARKDOWN_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\[[^\]]*\]\(\s*<?([^)\s>]+)>?(?:\s+[^)]*)?\)").expect("valid regex")
+});
+/// `@path/to/file` references, which Claude and others read as file imports. The last
+/// character can't be a dot so a reference ending a sentence doesn't take the period.
+static FILE_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|\s)@([\w./-]*[\w/-])").expect("valid regex"));
+static HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^#{1,6}\s+(.+?)[\s#]*$").expect("valid regex"));
+static CODE_SPAN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"`[^`]*`").expect("valid regex"));
+
+/// The checks to run, with the level of their findings, and their limits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintSettings {
+    levels: BTreeMap<&'static str, Level>,
+    max_rule_lines: usize,
+    min_description_words: usize,
+}
+
+impl LintSettings {
+    pub fn from_config(config: Option<&LintConfig>) -> Result<Self> {
+        let config = config.cloned().unwrap_or_default();
+        let mut severities: BTreeMap<&'static str, LintSeverity> =
+            LINT_CHECKS.iter().copied().collect();
+        for (check, severity) in config.checks.unwrap_or_default() {
+            let Some(&(code, _)) = LINT_CHECKS.iter().find(|(code, _)| *code == check) else {
+                let mut message = format!("Unknown lint check '{check}' in the config file");
+                if let Some(suggestion) = did_you_mean(&check, check_codes()) {
+                    message.push_str(&format!(", did you mean '{suggestion}'?"));
+                }
+                bail!(message);
+            };
+            severities.insert(code, severity);
+        }
+        let levels = severities
+            .into_iter()
+            .filter_map(|(code, severity)| match severity {
+                LintSeverity::Off => None,
+                LintSeverity::Warning => Some((code, Level::Warning)),
+                LintSeverity::Error => Some((code, Level::Error)),
+            })
+            .collect();
+        Ok(Self {
+            levels,
+            max_rule_lines: config.max_rule_lines.unwrap_or(DEFAULT_MAX_RULE_LINES),
+            min_description_words: config
+                .min_description_words
+                .unwrap_or(DEFAULT_MIN_DESCRIPTION_WORDS),
+        })
+    }
+
+    fn level(&self, check: &str) -> Option<Level> {
+        self.levels.get(check).copied()
+    }
+}
+
+pub fn check_codes() -> impl Iterator<Item = &'static str> {
+    LINT_CHECKS.iter().map(|(code, _)| *code)
+}
+
+/// A rule file of the directory being linted.
+struct Rule {
+    /// Relative to the project root
+    path: PathBuf,
+    file: PathBuf,
+    source: SourceFile,
+    frontmatter: Option<Frontmatter>,
+    body: String,
+    /// 1-based line number in the file of the first line of `body`
+    body_line: usize,
+}
+
+impl Rule {
+    fn read(root: &Path, file: &Path) -> Result<Self> {
+        let content = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let source = SourceFile::from_content(&content, file)?;
+        let split = split_frontmatter(&content, file)?;
+        let body_line =
+            content.split_inclusive('\n').count() - split.body.split_inclusive('\n').count() + 1;
+        Ok(Self {
+            path: file.strip_prefix(root).unwrap_or(file).to_path_buf(),
+            file: file.to_path_buf(),
+            source,
+            frontmatter: split.frontmatter,
+            body: split.body,
+            body_line,
+        })
+    }
+
+    /// The body's lines outside fenced code blocks, with their line numbers in the file.
+    fn prose_lines(&self) -> Vec<(usize, &str)> {
+        let mut fence: Option<&str> = None;
+        let mut lines = Vec::new();
+        for (index, line) in self.body.lines().enumerate() {
+            let trimmed = line.trim_start();
+            let marker = ["```", "~~~"]
+                .into_iter()
+                .find(|marker| trimmed.starts_with(marker));
+            match (fence, marker) {
+                (None, Some(marker)) => fence = Some(marker),
+                (Some(open), Some(marker)) if open == marker => fence = None,
+                (None, None) => lines.push((self.body_line + index, line)),
+                _ => {}
+            }
+        }
+        lines
+    }
+
+    /// Where `key` is in the frontmatter, or the frontmatter's first line.
+    fn locate_frontmatter_key(&self, key: &str) -> Option<(usize, usize)> {
+        let frontmatter = self.frontmatter.as_ref()?;
+        let location = locate_key(&frontmatter.raw, &[key.to_string()]).unwrap_or((1, 1));
+        Some((location.0 + frontmatter.first_line - 1, location.1))
+    }
+}
+
+/// Runs the enabled checks over the rules in `dir/ai-rules/`. Paths in the returned
+/// annotations are relative to `root`.
+pub fn lint_directory(root: &Path, dir: &Path, settings: &LintSettings) -> Result<Vec<Annotation>> {
+    let mut annotations = Vec::new();
+    let mut rules = Vec::new();
+    let mut files = get_md_files_in_ai_rules_dir(dir)?;
+    files.sort();
+    for file in files {
+        match Rule::read(root, &file) {
+            Ok(rule) => rules.push(rule),
+            Err(error) => {
+                let relative = file.strip_prefix(root).unwrap_or(&file);
+                let annotation = Annotation::from_error(root, &error, relative);
+                annotations.push(annotation.with_rule("invalid-source"));
+            }
+        }
+    }
+
+    if let Some(level) = settings.level("broken-link") {
+        for rule in &rules {
+            check_links(dir, rule, level, &mut annotations);
+        }
+    }
+    if let Some(level) = settings.level("duplicate-heading") {
+        check_headings(&rules, level, &mut annotations);
+    }
+    if let Some(level) = settings.level("duplicate-paragraph") {
+        check_paragraphs(&rules, level, &mut annotations);
+    }
+    if let Some(level) = settings.level("unmatched-glob") {
+        check_globs(dir, &rules, level, &mut annotations);
+    }
+    if let Some(level) = settings.level("vague-description") {
+        for rule in &rules {
+            check_description(
+                rule,
+                settings.min_description_words,
+                level,
+                &mut annotations,
+            );
+        }
+    }
+    if let Some(level) = settings.level("oversize-rule") {
+        for rule in &rules {
+            check_size(rule, settings.max_rule_lines, level, &mut annotations);
+        }
+    }
+    Ok(annotations)
+}
+
+/// The file a link points at, or `None` for URLs, anchors and templated links.
+fn link_path(target: &str) -> Option<&str> {
+    if target.contains("://")
+        || target.starts_with("mailto:")
+        || target.starts_with('#')
+        || target.contains("{{")
+    {
+        return None;
+    }
+    target
+        .split(['#', '?'])
+        .next()
+        .filter(|path| !path.is_empty())
+}
+
+/// Links resolve from the rule file, or from the project directory the agent reads
+/// the generated rules in.
+fn check_links(dir: &Path, rule: &Rule, level: Level, annotations: &mut Vec<Annotation>) {
+    let rule_dir = rule.file.parent().unwrap_or(dir);
+    for (line, text) in rule.prose_lines() {
+        // Code spans keep their width so columns stay right
+        let text = CODE_SPAN.replace_all(text, |span: &regex::Captures| " ".repeat(span[0].len()));
+        let links = MARKDOWN_LINK
+            .captures_iter(&text)
+            .filter_map(|link| link.get(1));
+        let references = FILE_REFERENCE
+            .captures_iter(&text)
+            .filter_map(|reference| reference.get(1))
+            .filter(|reference| reference.as_str().contains(['/', '.']));
+        for target in links.chain(references) {
+            let Some(path) = link_path(target.as_str()) else {
+                continue;
+            };
+            if rule_dir.join(path).exists() || dir.join(path).exists() {
+                continue;
+            }
+            annotations.push(
+                Annotation::new(
+                    rule.path.clone(),
+                    level,
+                    "broken-link",
+                    format!("`{path}` doesn't exist"),
+                )
+                .at(Some((line, target.start() + 1))),
+            );
+        }
+    }
+}
+
+fn check_headings(rules: &[Rule], level: Level, annotations: &mut Vec<Annotation>) {
+    let mut first: BTreeMap<String, (&Path, usize)> = BTreeMap::new();
+    for rule in rules {
+        let mut in_rule = BTreeSet::new();
+        for (line, text) in rule.prose_lines() {
+            let Some(heading) = HEADING.captures(text) else {
+                continue;
+            };
+            let key = heading[1].to_lowercase();
+            if !in_rule.insert(key.clone()) {
+                continue;
+            }
+            match first.get(&key) {
+                Some((path, first_line)) => annotations.push(
+                    Annotation::new(
+                        rule.path.clone(),
+                        level,
+                        "duplicate-heading",
+                        format!(
+                            "heading `{}` is also in {}:{first_line}",
+                            &heading[1],
+                            path.display()
+                        ),
+                    )
+                    .at(Some((line, 1))),
+                ),
+                None => {
+                    first.insert(key, (&rule.path, line));
+                }
+            }
+        }
+    }
+}
+
+/// The 1-based first line and distinct lowercase words of each paragraph worth comparing.
+fn paragraphs(rule: &Rule) -> Vec<(usize, BTreeSet<String>)> {
+    let mut paragraphs = Vec::new();
+    let mut current: Option<(usize, Vec<&str>)> = None;
+    let mut previous_line = 0;
+    for (line, text) in rule.prose_lines() {
+        let breaks = text.trim().is_empty() || HEADING.is_match(text) || line != previous_line + 1;
+        if breaks {
+            paragraphs.extend(current.take());
+        }
+        if !text.trim().is_empty() && !HEADING.is_match(text) {
+            current.get_or_insert((line, Vec::new())).1.push(text);
+        }
+        previous_line = line;
+    }
+    paragraphs.extend(current);
+    paragraphs
+        .into_iter()
+        .filter_map(|(line, texts)| {
+            let words: Vec<String> = texts
+                .iter()
+                .flat_map(|text| text.split(|c: char| !c.is_alphanumeric()))
+                .filter(|word| !word.is_empty())
+                .map(str::to_lowercase)
+                .collect();
+            (words.len() >= MIN_PARAGRAPH_WORDS).then(|| (line, words.into_iter().collect()))
+        })
+        .collect()
+}
+
+fn check_paragraphs(rules: &[Rule], level: Level, annotations: &mut Vec<Annotation>) {
+    let paragraphs: Vec<_> = rules.iter().map(paragraphs).collect();
+    for (index, rule) in rules.iter().enumerate() {
+        for (line, words) in &paragraphs[index] {
+            let earlier = (0..index).find_map(|other| {
+                paragraphs[other]
+                    .iter()
+                    .find(|(_, other_words)| {
+                        let shared = words.intersection(other_words).count();
+                        let all = words.union(other_words).count();
+                        shared as f64 / all as f64 >= PARAGRAPH_SIMILARITY
+                    })
+                    .map(|(other_line, _)| (&rules[other].path, other_line))
+            });
+            if let Some((path, other_line)) = earlier {
+                annotations.push(
+                    Annotation::new(
+                        rule.path.clone(),
+                        level,
+                        "duplicate-paragraph",
+                        format!(
+                            "this paragraph is nearly the same as the one at {}:{other_line}",
+                            path.display()
+                        ),
+                    )
+                    .at(Some((*line, 1))),
+                );
+            }
+        }
+    }
+}
+
+/// Files of the project directory, relative to it, leaving out what git ignores.
+fn project_files(dir: &Path) -> Vec<PathBuf> {
+    WalkBuilder::new(dir)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+        .filter_map(|entry| Some(entry.path().strip_prefix(dir).ok()?.to_path_buf()))
+        .collect()
+}
+
+fn check_globs(dir: &Path, rules: &[Rule], level: Level, annotations: &mut Vec<Annotation>) {
+    let patterns: Vec<(&Rule, &String)> = rules
+        .iter()
+        .flat_map(|rule| {
+            let patterns = rule.source.front_matter.file_matching_patterns.iter();
+            patterns.flatten().map(move |pattern| (rule, pattern))
+        })
+        // Rendered with template variables, so only known after generate
+        .filter(|(_, pattern)| !pattern.contains("{{"))
+        .collect();
+    if patterns.is_empty() {
+        return;
+    }
+
+    let files = project_files(dir);
+    for (rule, pattern) in patterns {
+        let matcher = OverrideBuilder::new(dir)
+            .add(pattern)
+            .and_then(|builder| builder.build());
+        let message = match matcher {
+            Ok(matcher)
+                if files
+                    .iter()
+                    .any(|file| matcher.matched(file, false).is_whitelist()) =>
+            {
+                continue
+            }
+            Ok(_) => format!("`{pattern}` in fileMatching matches no files"),
+            Err(error) => format!("`{pattern}` in fileMatching isn't a valid glob: {error}"),
+        };
+        let location = rule.frontmatter.as_ref().and_then(|frontmatter| {
+            let (key_line, _) = locate_key(&frontmatter.raw, &["fileMatching".to_string()])?;
+            let (line, column) = locate_word(&frontmatter.raw, pattern, key_line)?;
+            Some((line + frontmatter.first_line - 1, column))
+        });
+        annotations.push(
+            Annotation::new(rule.path.clone(), level, "unmatched-glob", message)
+                .at(location.or_else(|| rule.locate_frontmatter_key("fileMatching"))),
+        );
+    }
+}
+
+const LOAD_HINT: &str = "agents read it to decide whether to load the rule";
+
+/// Agents decide whether to load an optional rule from its description alone.
+fn check_description(
+    rule: &Rule,
+    min_words: usize,
+    level: Level,
+    annotations: &mut Vec<Annotation>,
+) {
+    let front_matter = &rule.source.front_matter;
+    if front_matter.always_apply {
+        return;
+    }
+    let description = front_matter.description.trim();
+    let message = match description.split_whitespace().count() {
+        0 => format!("the optional rule has no description; {LOAD_HINT}"),
+        words if words < min_words => {
+            format!("description `{description}` has fewer than {min_words} words; {LOAD_HINT}")
+        }
+        _ => return,
+    };
+    annotations.push(
+        Annotation::new(rule.path.clone(), level, "vague-description", message)
+            .at(rule.locate_frontmatter_key("description")),
+    );
+}
+
+fn check_size(rule: &Rule, max_lines: usize, level: Level, annotations: &mut Vec<Annotation>) {
+    let lines = rule.body.trim_end().lines().count();
+    if lines > max_lines {
+        annotations.push(
+            Annotation::new(
+                rule.path.clone(),
+                level,
+                "oversize-rule",
+                format!("the rule is {lines} lines, over the limit of {max_lines}; split it up"),
+            )
+            .at(Some((rule.body_line, 1))),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::helpers::create_file;
+    use tempfile::TempDir;
+
+    fn lint(project: &Path, config: &str) -> Vec<(String, Option<usize>, &'static str, Level)> {
+        let config: LintConfig = serde_yaml::from_str(config).unwrap();
+        let settings = LintSettings::from_config(Some(&config)).unwrap();
+        lint_directory(project, project, &settings)
+            .unwrap()
+            .into_iter()
+            .map(|problem| {
+                let path = problem.path.display().to_string();
+                (path, problem.line, problem.rule, problem.level)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_checks_rule_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path();
+        let shared = "Always run the full test suite before pushing a branch to the remote.";
+        create_file(project, "src/main.rs", "fn main() {}");
+        create_file(project, "docs/setup.md", "# Setup");
+        create_file(
+            project,
+            "ai-rules/api.md",
+            &[
+                "---",
+                "description: API",
+                "alwaysApply: false",
+                "fileMatching: \"**/*.rs, **/*.go\"",
+                "---",
+                "# Testing",
+                "",
+                shared,
+                "",
+                "See [setup](docs/setup.md), [guide](docs/guide.md#intro) and @docs/missing.md.",
+                "",
+                "```",
+                "[not](a-link.md)",
+                "```",
+            ]
+            .join("\n"),
+        );
+        create_file(
+            project,
+            "ai-rules/style.md",
+            &format!(
+                "# testing\n\n{shared}\n\nMail `@team/x.md` or read [site](https://example.com).\n"
+            ),
+        );
+
+        let api = "ai-rules/api.md".to_string();
+        assert_eq!(
+            lint(project, "max_rule_lines: 5"),
+            vec![
+                (api.clone(), Some(10), "broken-link", Level::Error),
+                (api.clone(), Some(10), "broken-link", Level::Error),
+                (
+                    "ai-rules/style.md".into(),
+                    Some(1),
+                    "duplicate-heading",
+                    Level::Warning
+                ),
+                (
+                    "ai-rules/style.md".into(),
+                    Some(3),
+                    "duplicate-paragraph",
+                    Level::Warning
+                ),
+                (api.clone(), Some(4), "unmatched-glob", Level::Warning),
+                (api.clone(), Some(2), "vague-description", Level::Warning),
+                (api, Some(6), "oversize-rule", Level::Warning),
+            ]
+        );
+
+        let config = "checks:
+  broken-link: off
+  duplicate-heading: error
+  duplicate-paragraph: off
+  unmatched-glob: off
+  vague-description: off";
+        assert_eq!(
+            lint(project, config),
+            vec![(
+                "ai-rules/style.md".into(),
+                Some(1),
+                "duplicate-heading",
+                Level::Error
+            )]
+        );
+    }
+
+    #[test]
+    fn test_unknown_check_is_rejected_with_a_suggestion() {
+        let config: LintConfig = serde_yaml::from_str("checks:\n  broken-links: off\n").unwrap();
+        let error = LintSettings::from_config(Some(&config)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown lint check 'broken-links' in the config file, did you mean 'broken-link'?"
+        );
+    }
+}
@@ -1,20 +1,51 @@
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::constants::{AI_RULE_SOURCE_DIR, GENERATED_FILE_PREFIX, SKILLS_DIR, SKILL_FILENAME};
-use crate::utils::file_utils::{calculate_relative_path, create_relative_symlink};
+use crate::operations::body_generator::generated_body_file_reference_path;
+use crate::operations::imports::{find_import_roots, merge_imported};
+use crate::operations::template_variables::{is_templated_file, TemplateVariables};
+use crate::utils::file_utils::calculate_relative_path;
+use crate::utils::frontmatter::check_frontmatter_syntax;
+use crate::utils::plan::Operation;
+use crate::utils::print_utils::print_warning;
 
 #[derive(Debug, Clone)]
 pub struct SkillFolder {
     pub name: String,
+    /// Symlink target relative to the project directory
     pub relative_path: PathBuf,
     pub full_path: PathBuf,
+    /// The folder in ai-rules/skills/ or an import. Differs from `full_path` when SKILL.md
+    /// uses template variables and the skill is rendered into the generated directory.
+    pub source_path: PathBuf,
+    /// `source_path` relative to the project directory
+    pub source_relative_path: PathBuf,
 }
 
-/// Finds all valid skill folders in ai-rules/skills/ directory
+/// Finds all valid skill folders in ai-rules/skills/ and in the `skills/` directory
+/// of each import
 pub fn find_skill_folders(current_dir: &Path) -> Result<Vec<SkillFolder>> {
-    let skills_dir = current_dir.join(AI_RULE_SOURCE_DIR).join(SKILLS_DIR);
+    let mut skill_folders = find_skill_folders_in(current_dir, Path::new(AI_RULE_SOURCE_DIR))?;
+    for root in find_import_roots(current_dir)? {
+        let imported = find_skill_folders_in(current_dir, &root.relative_dir)?;
+        merge_imported(&mut skill_folders, imported, &root, "skill", |skill| {
+            skill.name.clone()
+        })?;
+    }
+
+    // Sort for deterministic output across filesystems
+    skill_folders.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(skill_folders)
+}
+
+/// Finds the skills in `<source_dir>/skills/`, where `source_dir` is relative to the
+/// project directory
+fn find_skill_folders_in(current_dir: &Path, source_dir: &Path) -> Result<Vec<SkillFolder>> {
+    let skills_dir = current_dir.join(source_dir).join(SKILLS_DIR);
 
     // If the skills directory doesn't exist, return empty list
     if !skills_dir.exists() || !skills_dir.is_dir() {
@@ -32,10 +63,9 @@ pub fn find_skill_folders(current_dir: &Path) -> Result<Vec<SkillFolder>> {
         // Skip non-directories with a warning
         if !path.is_dir() {
             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                eprintln!(
-                    "Warning: Skipping '{}' in skills directory - not a directory",
-                    name
-                );
+                print_warning(&format!(
+                    "Skipping '{name}' in skills directory - not a directory"
+                ));
             }
             continue;
         }
@@ -44,24 +74,28 @@ pub fn find_skill_folders(current_dir: &Path) -> Result<Vec<SkillFolder>> {
         let skill_file = path.join(SKILL_FILENAME);
         if !skill_file.exists() {
             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                eprintln!(
-                    "Warning: Skipping '{}' - missing {} file",
-                    name, SKILL_FILENAME
-                );
+                print_warning(&format!(
+                    "Skipping '{name}' - missing {SKILL_FILENAME} file"
+                ));
             }
             continue;
         }
 
         // Get the folder name
         if let Some(folder_name) = path.file_name().and_then(|n| n.to_str()) {
-            let relative_path = PathBuf::from(AI_RULE_SOURCE_DIR)
-                .join(SKILLS_DIR)
-                .join(folder_name);
+            let source_relative_path = source_dir.join(SKILLS_DIR).join(folder_name);
+            let relative_path = if is_templated_file(&skill_file) {
+                generated_body_file_reference_path(SKILLS_DIR).join(folder_name)
+            } else {
+                source_relative_path.clone()
+            };
 
             skill_folders.push(SkillFolder {
                 name: folder_name.to_string(),
+                full_path: current_dir.join(&relative_path),
                 relative_path,
-                full_path: path,
+                source_path: path,
+                source_relative_path,
             });
         }
     }
@@ -69,143 +103,100 @@ pub fn find_skill_folders(current_dir: &Path) -> Result<Vec<SkillFolder>> {
     Ok(skill_folders)
 }
 
-/// Creates symlinks for each skill folder in the target directory
-pub fn create_skill_symlinks(current_dir: &Path, target_dir: &str) -> Result<Vec<PathBuf>> {
-    let skill_folders = find_skill_folders(current_dir)?;
-
-    if skill_folders.is_empty() {
-        return Ok(Vec::new());
+/// Renders the SKILL.md of skills that use template variables into the generated directory.
+/// Returns the rendered file contents keyed by output path.
+///
+/// Skills with malformed SKILL.md frontmatter are reported as warnings.
+pub fn generate_rendered_skill_contents(
+    current_dir: &Path,
+    variables: &TemplateVariables,
+) -> Result<HashMap<PathBuf, String>> {
+    let mut rendered = HashMap::new();
+    for skill in find_skill_folders(current_dir)? {
+        let source_file = skill.source_path.join(SKILL_FILENAME);
+        let content = fs::read_to_string(&source_file)
+            .with_context(|| format!("Failed to read {}", source_file.display()))?;
+        if let Err(error) = check_frontmatter_syntax(&content, &source_file) {
+            print_warning(&format!("{error:#}"));
+        }
+        if skill.full_path == skill.source_path {
+            continue;
+        }
+        rendered.insert(
+            skill.full_path.join(SKILL_FILENAME),
+            variables.render(&content, &source_file)?,
+        );
     }
+    Ok(rendered)
+}
 
-    let mut created_symlinks = Vec::new();
+/// Links every non-SKILL.md entry of a rendered skill back to its source folder,
+/// so scripts and references next to SKILL.md keep working.
+pub fn link_rendered_skill_resources(current_dir: &Path) -> Result<Vec<Operation>> {
+    let mut links = Vec::new();
+    for skill in find_skill_folders(current_dir)? {
+        if skill.full_path == skill.source_path {
+            continue;
+        }
+        let mut entry_names = fs::read_dir(&skill.source_path)?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entry_names.sort();
+        for entry_name in entry_names {
+            if entry_name == SKILL_FILENAME {
+                continue;
+            }
+            let link_relative = skill.relative_path.join(&entry_name);
+            let source_relative = skill.source_relative_path.join(&entry_name);
+            links.push(Operation::symlink(
+                current_dir.join(&link_relative),
+                calculate_relative_path(&link_relative, &source_relative),
+            ));
+        }
+    }
+    Ok(links)
+}
 
-    for skill in skill_folders {
+/// Symlinks for each skill folder in the target directory
+pub fn create_skill_symlinks(current_dir: &Path, target_dir: &str) -> Result<Vec<Operation>> {
+    let mut symlinks = Vec::new();
+    for skill in find_skill_folders(current_dir)? {
         // Create symlink name with prefix: ai-rules-generated-<name>
         let symlink_name = format!("{}{}", GENERATED_FILE_PREFIX, skill.name);
         let from_path = PathBuf::from(target_dir).join(&symlink_name);
 
         // Calculate the relative path from symlink location to source
         let relative_source = calculate_relative_path(&from_path, &skill.relative_path);
-
-        // Create the actual symlink
-        let symlink_path = current_dir.join(&from_path);
-        create_relative_symlink(&symlink_path, &relative_source)?;
-
-        created_symlinks.push(symlink_path);
+        symlinks.push(Operation::symlink(
+            current_dir.join(&from_path),
+            relative_source,
+        ));
     }
 
-    Ok(created_symlinks)
+    Ok(symlinks)
 }
 
-pub fn remove_generated_skills(current_dir: &Path, target_dir: &str) -> Result<()> {
+pub fn remove_generated_skills(current_dir: &Path, target_dir: &str) -> Result<Vec<Operation>> {
     let target_path = current_dir.join(target_dir);
 
-    if !target_path.exists() {
-        return Ok(());
+    if !target_path.is_dir() {
+        return Ok(Vec::new());
     }
 
+    let mut removals = Vec::new();
     for entry in fs::read_dir(&target_path)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-            if file_name.starts_with(GENERATED_FILE_PREFIX) {
-                if path.is_dir() && !path.is_symlink() {
-                    fs::remove_dir_all(&path)
-                        .with_context(|| format!("Failed to remove: {}", path.display()))?;
-                } else {
-                    fs::remove_file(&path)
-                        .with_context(|| format!("Failed to remove: {}", path.display()))?;
-                }
-            }
+        let path = entry?.path();
+        let is_generated = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(GENERATED_FILE_PREFIX));
+        if is_generated {
+            removals.push(Operation::delete(path));
         }
     }
+    removals.sort_by(|a, b| a.path().cmp(b.path()));
 
-    Ok(())
-}
-
-/// Checks if generated skill symlinks are in sync
-pub fn check_skill_symlinks_in_sync(current_dir: &Path, target_dir: &str) -> Result<bool> {
-    let skill_folders = find_skill_folders(current_dir)?;
-    let target_path = current_dir.join(target_dir);
-
-    // If no source skills exist, check that no generated symlinks exist
-    if skill_folders.is_empty() {
-        if !target_path.exists() {
-            return Ok(true);
-        }
-
-        // Check for any orphaned generated symlinks
-        for entry in fs::read_dir(&target_path)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                if file_name.starts_with(GENERATED_FILE_PREFIX) && path.is_symlink() {
-                    // Found an orphaned generated symlink
-                    return Ok(false);
-                }
-            }
-        }
-        return Ok(true);
-    }
-
-    // Check each source skill has a corresponding symlink
-    for skill in &skill_folders {
-        let symlink_name = format!("{}{}", GENERATED_FILE_PREFIX, skill.name);
-        let symlink_path = target_path.join(&symlink_name);
-
-        // Check symlink exists
-        if !symlink_path.is_symlink() {
-            return Ok(false);
-        }
-
-        // Check symlink points to correct target
-        let actual_target = fs::read_link(&symlink_path)?;
-        let resolved_target = if actual_target.is_absolute() {
-            actual_target
-        } else {
-            let symlink_parent = symlink_path.parent().unwrap_or(current_dir);
-            symlink_parent.join(&actual_target)
-        };
-
-        let resolved_canonical = resolved_target
-            .canonicalize()
-            .unwrap_or(resolved_target.clone());
-        let expected_canonical = skill
-            .full_path
-            .canonicalize()
-            .unwrap_or(skill.full_path.clone());
-
-        if resolved_canonical != expected_canonical {
-            return Ok(false);
-        }
-    }
-
-    // Check for orphaned generated symlinks
-    if target_path.exists() {
-        for entry in fs::read_dir(&target_path)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                if file_name.starts_with(GENERATED_FILE_PREFIX) && path.is_symlink() {
-                    // Extract the skill name from the symlink name
-                    let skill_name = file_name.strip_prefix(GENERATED_FILE_PREFIX).unwrap_or("");
-
-                    // Check if this skill exists in our source skills
-                    let skill_exists = skill_folders.iter().any(|s| s.name == skill_name);
-
-                    if !skill_exists {
-                        // Orphaned symlink found
-                        return Ok(false);
-                    }
-                }
-            }
-        }
-    }
-
-    Ok(true)
+    Ok(removals)
 }
 
 /// Returns gitignore patterns for generated skill symlinks
@@ -216,8 +207,30 @@ pub fn get_skill_gitignore_patterns(target_dir: &str) -> Vec<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::test_utils::helpers::{apply_operations, planned_changes};
     use tempfile::TempDir;
 
+    fn link_skills(root: &Path, target_dir: &str) -> Vec<PathBuf> {
+        let operations = create_skill_symlinks(root, target_dir).unwrap();
+        let paths = operations
+            .iter()
+            .map(|op| op.path().to_path_buf())
+            .collect();
+        apply_operations(root, operations);
+        paths
+    }
+
+    fn remove_skills(root: &Path, target_dir: &str) {
+        apply_operations(root, remove_generated_skills(root, target_dir).unwrap());
+    }
+
+    /// Whether relinking the skills would leave `target_dir` as it is.
+    fn skills_in_sync(root: &Path, target_dir: &str) -> bool {
+        let mut operations = remove_generated_skills(root, target_dir).unwrap();
+        operations.extend(create_skill_symlinks(root, target_dir).unwrap());
+        planned_changes(operations).is_empty()
+    }
+
     fn create_skill_folder(temp_dir: &Path, skill_name: &str, content: &str) -> PathBuf {
         let skill_dir = temp_dir
             .join(AI_RULE_SOURCE_DIR)
@@ -305,7 +318,7 @@ mod tests {
         create_skill_folder(temp_dir.path(), "my-skill", "skill content");
         create_skill_folder(temp_dir.path(), "another-skill", "more content");
 
-        let symlinks = create_skill_symlinks(temp_dir.path(), ".claude/skills").unwrap();
+        let symlinks = link_skills(temp_dir.path(), ".claude/skills");
         assert_eq!(symlinks.len(), 2);
 
         // Check symlinks exist
@@ -330,7 +343,7 @@ mod tests {
     fn test_create_skill_symlinks_no_skills() {
         let temp_dir = TempDir::new().unwrap();
 
-        let symlinks = create_skill_symlinks(temp_dir.path(), ".claude/skills").unwrap();
+        let symlinks = link_skills(temp_dir.path(), ".claude/skills");
         assert_eq!(symlinks.len(), 0);
     }
 
@@ -340,7 +353,7 @@ mod tests {
 
         // Create source skills and generate symlinks
         create_skill_folder(temp_dir.path(), "my-skill", "skill content");
-        create_skill_symlinks(temp_dir.path(), ".claude/skills").unwrap();
+        link_skills(temp_dir.path(), ".claude/skills");
 
         // Create a user's custom skill (not a symlink, a real folder)
         let user_skill = temp_dir
@@ -351,7 +364,7 @@ mod tests {
         fs::write(user_skill.join(SKILL_FILENAME), "user content").unwrap();
 
         // Remove generated symlinks
-        remove_generated_skills(temp_dir.path(), ".claude/skills").unwrap();
+        remove_skills(temp_dir.path(), ".claude/skills");
 
         // Check generated symlink is gone
         let generated = temp_dir
@@ -382,7 +395,7 @@ mod tests {
         fs::create_dir_all(&user_skill).unwrap();
         fs::write(user_skill.join(SKILL_FILENAME), "user content").unwrap();
 
-        remove_generated_skills(temp_dir.path(), ".claude/skills").unwrap();
+        remove_skills(temp_dir.path(), ".claude/skills");
 
         // Generated directory should be removed
         assert!(!generated_dir.exists());
@@ -399,15 +412,13 @@ mod tests {
         create_skill_folder(temp_dir.path(), "my-skill", "skill content");
 
         // Before generating, should be out of sync
-        let result = check_skill_symlinks_in_sync(temp_dir.path(), ".claude/skills").unwrap();
-        assert!(!result);
+        assert!(!skills_in_sync(temp_dir.path(), ".claude/skills"));
 
         // Generate symlinks
-        create_skill_symlinks(temp_dir.path(), ".claude/skills").unwrap();
+        link_skills(temp_dir.path(), ".claude/skills");
 
         // Now should be in sync
-        let result = check_skill_symlinks_in_sync(temp_dir.path(), ".claude/skills").unwrap();
-        assert!(result);
+        assert!(skills_in_sync(temp_dir.path(), ".claude/skills"));
     }
 
     #[test]
@@ -416,7 +427,7 @@ mod tests {
 
         // Create and generate skills
         create_skill_folder(temp_dir.path(), "my-skill", "skill content");
-        create_skill_symlinks(temp_dir.path(), ".claude/skills").unwrap();
+        link_skills(temp_dir.path(), ".claude/skills");
 
         // Create an orphaned symlink manually
         let orphaned_path = temp_dir
@@ -430,8 +441,7 @@ mod tests {
         std::os::unix::fs::symlink(&fake_target, &orphaned_path).unwrap();
 
         // Should be out of sync due to orphaned symlink
-        let result = check_skill_symlinks_in_sync(temp_dir.path(), ".claude/skills").unwrap();
-        assert!(!result);
+        assert!(!skills_in_sync(temp_dir.path(), ".claude/skills"));
     }
 
     #[test]
@@ -439,8 +449,7 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
 
         // No source skills and no target directory - should be in sync
-        let result = check_skill_symlinks_in_sync(temp_dir.path(), ".claude/skills").unwrap();
-        assert!(result);
+        assert!(skills_in_sync(temp_dir.path(), ".claude/skills"));
     }
 
     #[test]
@@ -463,7 +472,7 @@ mod tests {
         assert_eq!(result.len(), 3);
 
         // Create symlinks and verify they work
-        let symlinks = create_skill_symlinks(temp_dir.path(), ".claude/skills").unwrap();
+        let symlinks = link_skills(temp_dir.path(), ".claude/skills");
         assert_eq!(symlinks.len(), 3);
 
         // Verify we can read through all symlinks
@@ -484,7 +493,7 @@ mod tests {
         fs::write(skill_dir.join("examples/example1.md"), "example content").unwrap();
 
         // Create symlink
-        let symlinks = create_skill_symlinks(temp_dir.path(), ".claude/skills").unwrap();
+        let symlinks = link_skills(temp_dir.path(), ".claude/skills");
         assert_eq!(symlinks.len(), 1);
 
         let symlink = &symlinks[0];
@@ -528,7 +537,7 @@ mod tests {
         assert_eq!(result[0].name, "shared-skill");
 
         // Generate symlinks
-        let symlinks = create_skill_symlinks(temp_dir.path(), ".claude/skills").unwrap();
+        let symlinks = link_skills(temp_dir.path(), ".claude/skills");
         assert_eq!(symlinks.len(), 1);
 
         // Content should be accessible
@@ -553,7 +562,7 @@ mod tests {
         assert!(!broken_symlink.exists()); // exists() returns false for broken symlinks
 
         // Remove should clean up broken symlinks
-        remove_generated_skills(temp_dir.path(), ".claude/skills").unwrap();
+        remove_skills(temp_dir.path(), ".claude/skills");
 
         // Broken symlink should be removed
         assert!(!broken_symlink.is_symlink());
@@ -565,10 +574,10 @@ mod tests {
 
         // Create a skill, generate symlinks, then delete the source
         create_skill_folder(temp_dir.path(), "my-skill", "content");
-        create_skill_symlinks(temp_dir.path(), ".claude/skills").unwrap();
+        link_skills(temp_dir.path(), ".claude/skills");
 
         // Verify in sync
-        assert!(check_skill_symlinks_in_sync(temp_dir.path(), ".claude/skills").unwrap());
+        assert!(skills_in_sync(temp_dir.path(), ".claude/skills"));
 
         // Delete the source skill (but leave the symlink)
         fs::remove_dir_all(
@@ -581,7 +590,7 @@ mod tests {
         .unwrap();
 
         // Should now be out of sync (orphaned symlink pointing to deleted source)
-        assert!(!check_skill_symlinks_in_sync(temp_dir.path(), ".claude/skills").unwrap());
+        assert!(!skills_in_sync(temp_dir.path(), ".claude/skills"));
     }
 
     #[test]
@@ -590,7 +599,7 @@ mod tests {
 
         // Create initial skill
         create_skill_folder(temp_dir.path(), "my-skill", "original content");
-        create_skill_symlinks(temp_dir.path(), ".claude/skills").unwrap();
+        link_skills(temp_dir.path(), ".claude/skills");
 
         // Verify initial content
         let symlink_path = temp_dir
@@ -612,7 +621,7 @@ mod tests {
         fs::write(&skill_path, "updated content").unwrap();
 
         // Regenerate should work without error (symlink already exists)
-        create_skill_symlinks(temp_dir.path(), ".claude/skills").unwrap();
+        link_skills(temp_dir.path(), ".claude/skills");
 
         // Content should be updated (same symlink, but source changed)
         assert_eq!(
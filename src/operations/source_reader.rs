@@ -1,7 +1,15 @@
-use crate::constants::{AGENTS_MD_FILENAME, AI_RULE_SOURCE_DIR, MD_EXTENSION};
+use crate::config::{load_directory_config, LocalRulesMode};
+use crate::constants::{
+    AGENTS_MD_FILENAME, AI_RULE_SOURCE_DIR, LOCAL_RULES_DIR, LOCAL_RULE_SUFFIX, MD_EXTENSION,
+};
 use crate::models::SourceFile;
+use crate::operations::imports::{find_import_roots, merge_imported};
+use crate::operations::include_resolver::expand_includes;
+use crate::operations::template_variables::TemplateVariables;
 use crate::utils::file_utils::find_files_by_extension;
-use anyhow::Result;
+use crate::utils::frontmatter::has_frontmatter;
+use crate::utils::git_utils::find_git_root;
+use anyhow::{Context, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -9,36 +17,270 @@ pub fn get_ai_rules_dir(current_dir: &Path) -> PathBuf {
     current_dir.join(AI_RULE_SOURCE_DIR)
 }
 
-fn get_md_files_in_ai_rules_dir(current_dir: &Path) -> Result<Vec<PathBuf>> {
+pub fn get_md_files_in_ai_rules_dir(current_dir: &Path) -> Result<Vec<PathBuf>> {
     let ai_rules_dir = get_ai_rules_dir(current_dir);
 
     if !ai_rules_dir.exists() || !ai_rules_dir.is_dir() {
         return Ok(Vec::new());
     }
 
-    find_files_by_extension(&ai_rules_dir, MD_EXTENSION)
+    let mut md_files = find_files_by_extension(&ai_rules_dir, MD_EXTENSION)?;
+    let personal_dir = ai_rules_dir.join(LOCAL_RULES_DIR);
+    if personal_dir.is_dir() {
+        md_files.extend(find_files_by_extension(&personal_dir, MD_EXTENSION)?);
+    }
+    Ok(md_files)
+}
+
+/// Returns true for personal rules: `ai-rules/*.local.md` and files in `ai-rules/local/`.
+pub fn is_personal_rule(path: &Path) -> bool {
+    let in_personal_dir = path.parent().is_some_and(|parent| {
+        parent.ends_with(Path::new(AI_RULE_SOURCE_DIR).join(LOCAL_RULES_DIR))
+    });
+    let has_local_suffix = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| stem.ends_with(LOCAL_RULE_SUFFIX));
+    in_personal_dir || has_local_suffix
+}
+
+/// The name a rule file is known by: its file name without `.md` and, for
+/// personal rules, without `.local`.
+fn rule_name(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match stem.strip_suffix(LOCAL_RULE_SUFFIX) {
+        Some(name) if is_personal_rule(path) => name.to_string(),
+        _ => stem,
+    }
+}
+
+/// Returns the rule files of `current_dir`: its own `ai-rules/*.md` merged with the
+/// rules of its imports, matched by name. With `include_personal`, a personal rule
+/// replaces the shared rule with the same name; otherwise personal rules are left out.
+fn find_rule_paths(current_dir: &Path, include_personal: bool) -> Result<Vec<PathBuf>> {
+    let mut rule_paths = get_md_files_in_ai_rules_dir(current_dir)?;
+    if !include_personal {
+        rule_paths.retain(|path| !is_personal_rule(path));
+    }
+    let personal_names: Vec<String> = rule_paths
+        .iter()
+        .filter(|path| is_personal_rule(path))
+        .map(|path| rule_name(path))
+        .collect();
+    rule_paths.retain(|path| is_personal_rule(path) || !personal_names.contains(&rule_name(path)));
+
+    for root in find_import_roots(current_dir)? {
+        let imported = find_files_by_extension(&root.dir, MD_EXTENSION)?;
+        merge_imported(&mut rule_paths, imported, &root, "rule", |path| {
+            rule_name(path)
+        })?;
+    }
+    Ok(rule_paths)
 }
 
+/// Settings that shape how the rule sources of a single directory are loaded.
+#[derive(Debug, Clone, Default)]
+pub struct SourceOptions {
+    /// Variables substituted into `{{name}}` placeholders. When `None`, placeholders are left as-is.
+    pub variables: Option<TemplateVariables>,
+    /// Rule names (file names without `.md`) that come first, in this order.
+    pub rule_order: Vec<String>,
+    /// When set, only untagged rules and rules with one of these tags are used.
+    pub include_tags: Option<Vec<String>>,
+    /// Compose rules from ancestor `ai-rules/` directories up to the git root.
+    pub inherit: bool,
+    /// Inherited rule names to leave out.
+    pub disable_inherited: Vec<String>,
+    /// With `Native`, personal rules are left out and loaded with `find_personal_source_files`.
+    pub local_rules: LocalRulesMode,
+}
+
+impl SourceOptions {
+    /// Builds the options for one project directory, starting from the root settings
+    /// in `self`. The directory's own config file can replace `rule_order` and `inherit`.
+    pub fn for_directory(&self, current_dir: &Path, variables: TemplateVariables) -> Result<Self> {
+        let directory_config = load_directory_config(current_dir)?.unwrap_or_default();
+        Ok(Self {
+            variables: Some(variables),
+            rule_order: directory_config
+                .rule_order
+                .unwrap_or_else(|| self.rule_order.clone()),
+            include_tags: self.include_tags.clone(),
+            inherit: directory_config.inherit.unwrap_or(self.inherit),
+            disable_inherited: directory_config.disable_inherited.unwrap_or_default(),
+            local_rules: self.local_rules,
+        })
+    }
+}
+
+/// Finds and parses the rule files directly inside `ai-rules/`.
+///
+/// Subdirectories such as `partials/`, `commands/` and `skills/` are never treated as rules.
 pub fn find_source_files(current_dir: &Path) -> Result<Vec<SourceFile>> {
-    let source_files = get_md_files_in_ai_rules_dir(current_dir)?;
-    if source_files.is_empty() {
+    find_source_files_with_options(current_dir, &SourceOptions::default())
+}
+
+/// Finds the rules of `current_dir` composed with the rules it inherits, filtered and ordered.
+pub fn find_source_files_with_options(
+    current_dir: &Path,
+    options: &SourceOptions,
+) -> Result<Vec<SourceFile>> {
+    let include_personal = options.local_rules == LocalRulesMode::Merged;
+    let rule_paths = find_rule_paths(current_dir, include_personal)?;
+
+    let mut source_files = parse_source_files(rule_paths, options)?;
+    for inherited_file in find_inherited_source_files(current_dir, options)? {
+        // Local rules override inherited rules with the same name
+        if !contains_rule(&source_files, &inherited_file.base_file_name) {
+            source_files.push(inherited_file);
+        }
+    }
+
+    if let Some(include_tags) = &options.include_tags {
+        source_files.retain(|source_file| has_included_tag(source_file, include_tags));
+    }
+    sort_source_files(&mut source_files, &options.rule_order);
+    Ok(source_files)
+}
+
+/// Finds the personal rules of `current_dir`, filtered and ordered like the shared ones.
+pub fn find_personal_source_files(
+    current_dir: &Path,
+    options: &SourceOptions,
+) -> Result<Vec<SourceFile>> {
+    let mut rule_paths = get_md_files_in_ai_rules_dir(current_dir)?;
+    rule_paths.retain(|path| is_personal_rule(path));
+
+    let mut source_files = parse_source_files(rule_paths, options)?;
+    if let Some(include_tags) = &options.include_tags {
+        source_files.retain(|source_file| has_included_tag(source_file, include_tags));
+    }
+    sort_source_files(&mut source_files, &options.rule_order);
+    Ok(source_files)
+}
+
+/// Collects the rules a nested project inherits from ancestor `ai-rules/` directories,
+/// nearest ancestor first. Only directories with their own `ai-rules/` inherit.
+///
+/// A rule is inherited when its `inherit` frontmatter is `true`, or when it is unset and
+/// inheritance is enabled for the directory.
+pub fn find_inherited_source_files(
+    current_dir: &Path,
+    options: &SourceOptions,
+) -> Result<Vec<SourceFile>> {
+    if !get_ai_rules_dir(current_dir).is_dir() {
         return Ok(Vec::new());
     }
+    let Some(git_root) = find_git_root(current_dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut inherited: Vec<SourceFile> = Vec::new();
+    let mut dir = current_dir;
+    while dir != git_root {
+        let Some(parent) = dir.parent() else {
+            break;
+        };
+        dir = parent;
+
+        // Personal rules stay in their own directory
+        let rule_paths = find_rule_paths(dir, false)?;
+        for source_file in parse_source_files(rule_paths, options)? {
+            let name = &source_file.base_file_name;
+            let is_inheritable = source_file.front_matter.inherit.unwrap_or(options.inherit);
+            if is_inheritable
+                && !contains_rule(&inherited, name)
+                && !options
+                    .disable_inherited
+                    .iter()
+                    .any(|disabled| rule_name_matches(disabled, name))
+            {
+                inherited.push(source_file);
+            }
+        }
+    }
+
+    Ok(inherited)
+}
+
+fn contains_rule(source_files: &[SourceFile], name: &str) -> bool {
+    source_files
+        .iter()
+        .any(|source_file| source_file.base_file_name == name)
+}
+
+/// Matches a configured rule name, with or without the `.md` extension.
+fn rule_name_matches(configured: &str, base_file_name: &str) -> bool {
+    configured.strip_suffix(".md").unwrap_or(configured) == base_file_name
+}
+
+fn has_included_tag(source_file: &SourceFile, include_tags: &[String]) -> bool {
+    match &source_file.front_matter.tags {
+        None => true,
+        Some(tags) => tags.iter().any(|tag| include_tags.contains(tag)),
+    }
+}
 
-    parse_source_files(source_files)
+/// Orders rules for generated output: rules listed in `rule_order` first, then rules
+/// with an `order` frontmatter value (ascending), then the rest. Ties sort by file name.
+fn sort_source_files(source_files: &mut [SourceFile], rule_order: &[String]) {
+    let sort_key = |source_file: &SourceFile| {
+        let listed_position = rule_order
+            .iter()
+            .position(|name| rule_name_matches(name, &source_file.base_file_name));
+        match (listed_position, source_file.front_matter.order) {
+            (Some(position), _) => (0, position as i64),
+            (None, Some(order)) => (1, order),
+            (None, None) => (2, 0),
+        }
+    };
+    source_files.sort_by(|a, b| {
+        sort_key(a)
+            .cmp(&sort_key(b))
+            .then_with(|| a.base_file_name.cmp(&b.base_file_name))
+    });
 }
 
-fn parse_source_files(original_source_files: Vec<PathBuf>) -> Result<Vec<SourceFile>> {
+fn parse_source_files(
+    original_source_files: Vec<PathBuf>,
+    options: &SourceOptions,
+) -> Result<Vec<SourceFile>> {
     let mut source_files = Vec::new();
     for original_source_file in original_source_files {
-        let source_file = SourceFile::from_file(&original_source_file)?;
+        let content = fs::read_to_string(&original_source_file)
+            .with_context(|| format!("Failed to read file '{}'", original_source_file.display()))?;
+        // Rule files live directly in ai-rules/ (or ai-rules/local/), so includes resolve from there
+        let mut ai_rules_dir = original_source_file.parent().unwrap_or(Path::new("."));
+        if ai_rules_dir
+            .file_name()
+            .is_some_and(|name| name == LOCAL_RULES_DIR)
+        {
+            ai_rules_dir = ai_rules_dir.parent().unwrap_or(ai_rules_dir);
+        }
+        let mut expanded = expand_includes(&content, &original_source_file, ai_rules_dir)?;
+        if let Some(variables) = &options.variables {
+            expanded = variables.render(&expanded, &original_source_file)?;
+        }
+        let mut source_file = SourceFile::from_content(&expanded, &original_source_file)?;
+        source_file.base_file_name = rule_name(&original_source_file);
         source_files.push(source_file);
     }
     Ok(source_files)
 }
 
+/// Symlink mode is only used when the directory doesn't inherit any rules.
+pub fn uses_symlink_mode(current_dir: &Path, options: &SourceOptions) -> Result<bool> {
+    Ok(detect_symlink_mode(current_dir)
+        && find_inherited_source_files(current_dir, options)?.is_empty())
+}
+
+/// Personal rules always count here, so their presence switches both local rules modes
+/// to standard generation.
 pub fn detect_symlink_mode(current_dir: &Path) -> bool {
-    let md_files = match get_md_files_in_ai_rules_dir(current_dir) {
+    let md_files = match find_rule_paths(current_dir, true) {
         Ok(files) => files,
         Err(_) => return false,
     };
@@ -47,12 +289,9 @@ pub fn detect_symlink_mode(current_dir: &Path) -> bool {
         return false;
     }
 
+    // Imported rules can't be symlinked, so the only rule must be the local AGENTS.md
     let agents_file = &md_files[0];
-    let filename = agents_file
-        .file_name()
-        .and_then(|name| name.to_str())
-        .unwrap_or("");
-    if filename != AGENTS_MD_FILENAME {
+    if *agents_file != get_ai_rules_dir(current_dir).join(AGENTS_MD_FILENAME) {
         return false;
     }
     is_pure_markdown(agents_file)
@@ -60,9 +299,8 @@ pub fn detect_symlink_mode(current_dir: &Path) -> bool {
 
 fn is_pure_markdown(file_path: &Path) -> bool {
     if let Ok(content) = fs::read_to_string(file_path) {
-        let trimmed = content.trim_start();
-        // Pure markdown doesn't start with YAML frontmatter
-        !trimmed.starts_with("---")
+        // Pure markdown doesn't start with a frontmatter block
+        !has_frontmatter(&content)
     } else {
         false
     }
@@ -137,6 +375,129 @@ This is a test rule."#;
         assert_eq!(sorted_result[0].body, "# Test Rule\nThis is a test rule.");
     }
 
+    #[test]
+    fn test_find_source_files_expands_includes_and_ignores_partials() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        let ai_rules_dir = temp_path.join("ai-rules");
+
+        fs::create_dir_all(ai_rules_dir.join("partials")).unwrap();
+        fs::write(
+            ai_rules_dir.join("partials").join("testing.md"),
+            "Run `cargo test`.\n",
+        )
+        .unwrap();
+        fs::write(
+            ai_rules_dir.join("rust.md"),
+            "---\ndescription: Rust\nalwaysApply: true\n---\n# Rust\n{{> testing}}\n",
+        )
+        .unwrap();
+
+        let result = find_source_files(temp_path).unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].base_file_name, "rust");
+        assert_eq!(result[0].body, "# Rust\nRun `cargo test`.\n");
+    }
+
+    #[test]
+    fn test_find_source_files_with_options_renders_variables() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        let ai_rules_dir = temp_path.join("ai-rules");
+
+        fs::create_dir_all(&ai_rules_dir).unwrap();
+        fs::write(
+            ai_rules_dir.join("testing.md"),
+            "Run `{{test_command}}` in {{ci}}.\n",
+        )
+        .unwrap();
+
+        let options = SourceOptions {
+            variables: Some(TemplateVariables::from_pairs(&[
+                ("test_command", "pnpm test"),
+                ("ci", "CI"),
+            ])),
+            ..Default::default()
+        };
+        let result = find_source_files_with_options(temp_path, &options).unwrap();
+        assert_eq!(result[0].body, "Run `pnpm test` in CI.\n");
+
+        let undefined = SourceOptions {
+            variables: Some(TemplateVariables::default()),
+            ..Default::default()
+        };
+        assert!(find_source_files_with_options(temp_path, &undefined).is_err());
+
+        let unrendered = find_source_files(temp_path).unwrap();
+        assert_eq!(unrendered[0].body, "Run `{{test_command}}` in {{ci}}.\n");
+    }
+
+    #[test]
+    fn test_find_source_files_filters_by_include_tags() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        let ai_rules_dir = temp_path.join("ai-rules");
+        fs::create_dir_all(&ai_rules_dir).unwrap();
+
+        let rule =
+            |tags: &str| format!("---\ndescription: d\nalwaysApply: true\n{tags}---\nBody\n");
+        fs::write(ai_rules_dir.join("general.md"), rule("")).unwrap();
+        fs::write(ai_rules_dir.join("db.md"), rule("tags: [rust, db]\n")).unwrap();
+        fs::write(ai_rules_dir.join("ios.md"), rule("tags: [mobile]\n")).unwrap();
+
+        let options = SourceOptions {
+            include_tags: Some(vec!["db".to_string()]),
+            ..Default::default()
+        };
+        let names: Vec<String> = find_source_files_with_options(temp_path, &options)
+            .unwrap()
+            .into_iter()
+            .map(|file| file.base_file_name)
+            .collect();
+
+        assert_eq!(names, vec!["db", "general"]);
+        assert_eq!(find_source_files(temp_path).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_find_source_files_applies_rule_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        let ai_rules_dir = temp_path.join("ai-rules");
+        fs::create_dir_all(&ai_rules_dir).unwrap();
+
+        let rule =
+            |order: &str| format!("---\ndescription: d\nalwaysApply: true\n{order}---\nBody\n");
+        fs::write(ai_rules_dir.join("a-style.md"), rule("")).unwrap();
+        fs::write(ai_rules_dir.join("b-testing.md"), rule("order: 2\n")).unwrap();
+        fs::write(ai_rules_dir.join("c-safety.md"), rule("priority: 1\n")).unwrap();
+        fs::write(ai_rules_dir.join("d-tie.md"), rule("order: 2\n")).unwrap();
+        fs::write(ai_rules_dir.join("e-first.md"), rule("order: 5\n")).unwrap();
+
+        let names = |options: &SourceOptions| -> Vec<String> {
+            find_source_files_with_options(temp_path, options)
+                .unwrap()
+                .into_iter()
+                .map(|file| file.base_file_name)
+                .collect()
+        };
+
+        assert_eq!(
+            names(&SourceOptions::default()),
+            vec!["c-safety", "b-testing", "d-tie", "e-first", "a-style"]
+        );
+
+        let options = SourceOptions {
+            rule_order: vec!["e-first.md".to_string(), "a-style".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            names(&options),
+            vec!["e-first", "a-style", "c-safety", "b-testing", "d-tie"]
+        );
+    }
+
     #[test]
     fn test_parse_source_files() {
         let temp_dir = TempDir::new().unwrap();
@@ -167,7 +528,7 @@ Content for second rule."#;
         fs::write(&file2, source_file_content2).unwrap();
 
         let source_files = vec![file1, file2];
-        let result = parse_source_files(source_files).unwrap();
+        let result = parse_source_files(source_files, &SourceOptions::default()).unwrap();
 
         assert_eq!(result.len(), 2);
 
@@ -205,7 +566,7 @@ Content for second rule."#;
         fs::write(&file1, invalid_content).unwrap();
 
         let source_files = vec![file1];
-        let result = parse_source_files(source_files);
+        let result = parse_source_files(source_files, &SourceOptions::default());
 
         assert!(result.is_err());
     }
@@ -305,4 +666,47 @@ alwaysApply: true
         let result = detect_symlink_mode(temp_path);
         assert!(result);
     }
+
+    #[test]
+    fn test_detect_symlink_mode_with_personal_rule() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+
+        fs::create_dir_all(temp_path.join("ai-rules/local")).unwrap();
+        fs::write(
+            temp_path.join(AI_RULE_SOURCE_DIR).join(AGENTS_MD_FILENAME),
+            "# Pure markdown",
+        )
+        .unwrap();
+        fs::write(temp_path.join("ai-rules/local/notes.md"), "# Mine").unwrap();
+
+        assert!(!detect_symlink_mode(temp_path));
+    }
+
+    #[test]
+    fn test_find_source_files_with_personal_rules() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        let rule = |body: &str| format!("---\ndescription: Rule\nalwaysApply: true\n---\n{body}");
+
+        fs::create_dir_all(temp_path.join("ai-rules/local")).unwrap();
+        fs::write(temp_path.join("ai-rules/style.md"), rule("Shared style")).unwrap();
+        fs::write(temp_path.join("ai-rules/style.local.md"), rule("My style")).unwrap();
+        fs::write(temp_path.join("ai-rules/local/notes.md"), rule("My notes")).unwrap();
+
+        let merged = find_source_files(temp_path).unwrap();
+        let bodies: Vec<&str> = merged.iter().map(|file| file.body.as_str()).collect();
+        assert_eq!(bodies, vec!["My notes", "My style"]);
+        assert_eq!(merged[1].base_file_name, "style");
+
+        let options = SourceOptions {
+            local_rules: LocalRulesMode::Native,
+            ..Default::default()
+        };
+        let shared = find_source_files_with_options(temp_path, &options).unwrap();
+        let personal = find_personal_source_files(temp_path, &options).unwrap();
+        assert_eq!(shared.len(), 1);
+        assert_eq!(shared[0].body, "Shared style");
+        assert_eq!(personal.len(), 2);
+    }
 }
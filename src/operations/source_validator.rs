@@ -0,0 +1,606 @@
+use crate::agents::AgentToolRegistry;
+use crate::config::{Config, ImportSpec, LintConfig, Profile};
+use crate::constants::{
+    AI_RULE_CONFIG_FILENAME, AI_RULE_SOURCE_DIR, COMMANDS_DIR, FIREBENDER_OVERLAY_JSON, MCP_JSON,
+    MCP_SERVERS_FIELD, MD_EXTENSION, SKILLS_DIR, SKILL_FILENAME,
+};
+use crate::models::source_file::FrontMatter;
+use crate::operations::rule_linter::check_codes;
+use crate::operations::source_reader::get_md_files_in_ai_rules_dir;
+use crate::utils::annotations::{Annotation, Level};
+use crate::utils::file_utils::find_files_by_extension;
+use crate::utils::frontmatter::{split_frontmatter, Frontmatter, SplitContent};
+use crate::utils::schema::{
+    deserialize_reporting_unknown, did_you_mean, locate_key, locate_word, struct_fields, KeyPath,
+};
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Keys a server in `mcp.json` can have; agents may read others, so those only warn.
+const MCP_SERVER_KEYS: &[&str] = &["command", "args", "env", "type", "url", "headers"];
+
+/// The command frontmatter agents read. Only deserialized, to check the types.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[allow(dead_code)]
+struct CommandFrontmatter {
+    description: Option<String>,
+    allowed_tools: Option<StringOrList>,
+    argument_hint: Option<String>,
+    model: Option<String>,
+    disable_model_invocation: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[allow(dead_code)]
+struct SkillFrontmatter {
+    name: Option<String>,
+    description: Option<String>,
+    license: Option<String>,
+    allowed_tools: Option<StringOrList>,
+    metadata: Option<BTreeMap<String, Value>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+#[allow(dead_code)]
+enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+/// Checks every source in `dir/ai-rules/` without writing anything. Paths in the
+/// returned annotations are relative to `root`.
+pub fn validate_directory(
+    root: &Path,
+    dir: &Path,
+    registry: &AgentToolRegistry,
+) -> Result<Vec<Annotation>> {
+    let mut annotations = Vec::new();
+    validate_config(root, dir, registry, &mut annotations)?;
+    validate_rules(root, dir, &mut annotations)?;
+    validate_commands(root, dir, &mut annotations)?;
+    validate_skills(root, dir, &mut annotations)?;
+    validate_mcp(root, dir, &mut annotations)?;
+    validate_overlay(root, dir, &mut annotations)?;
+    Ok(annotations)
+}
+
+fn relative(root: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(root).unwrap_or(path).to_path_buf()
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+fn unknown_key(
+    path: PathBuf,
+    level: Level,
+    text: &str,
+    first_line: usize,
+    key: &KeyPath,
+    candidates: &[&str],
+) -> Annotation {
+    let mut message = format!("unknown key `{}`", key.join("."));
+    let suggestion = key
+        .last()
+        .and_then(|last| did_you_mean(last, candidates.iter().copied()));
+    if let Some(suggestion) = suggestion {
+        message.push_str(&format!(", did you mean `{suggestion}`?"));
+    }
+    let location = locate_key(text, key).map(|(line, column)| (line + first_line - 1, column));
+    Annotation::new(path, level, "unknown-key", message).at(location)
+}
+
+/// Splits `content`, reporting frontmatter that is never closed.
+fn split(
+    root: &Path,
+    path: &Path,
+    content: &str,
+    annotations: &mut Vec<Annotation>,
+) -> Option<SplitContent> {
+    match split_frontmatter(content, path) {
+        Ok(split) => Some(split),
+        Err(error) => {
+            let annotation = Annotation::from_error(root, &error, &relative(root, path));
+            annotations.push(annotation.with_rule("invalid-frontmatter"));
+            None
+        }
+    }
+}
+
+/// Deserializes `frontmatter` as `T`, reporting wrong types and unknown keys.
+fn check_frontmatter<T: DeserializeOwned>(
+    root: &Path,
+    path: &Path,
+    frontmatter: &Frontmatter,
+    unknown_level: Level,
+    annotations: &mut Vec<Annotation>,
+) -> Option<T> {
+    match frontmatter.deserialize_reporting_unknown::<T>(path) {
+        Ok((value, unknown)) => {
+            for key in &unknown {
+                annotations.push(unknown_key(
+                    relative(root, path),
+                    unknown_level,
+                    &frontmatter.raw,
+                    frontmatter.first_line,
+                    key,
+                    struct_fields::<T>(),
+                ));
+            }
+            Some(value)
+        }
+        Err(error) => {
+            let annotation = Annotation::from_error(root, &error, &relative(root, path));
+            annotations.push(annotation.with_rule("invalid-frontmatter"));
+            None
+        }
+    }
+}
+
+fn validate_rules(root: &Path, dir: &Path, annotations: &mut Vec<Annotation>) -> Result<()> {
+    for path in get_md_files_in_ai_rules_dir(dir)? {
+        let content = read(&path)?;
+        let Some(split) = split(root, &path, &content, annotations) else {
+            continue;
+        };
+        match &split.frontmatter {
+            // ai-rules is the only reader of rule frontmatter, so unknown keys are typos
+            Some(frontmatter) => {
+                check_frontmatter::<FrontMatter>(
+                    root,
+                    &path,
+                    frontmatter,
+                    Level::Error,
+                    annotations,
+                );
+            }
+            None if split.body.trim().is_empty() => annotations.push(Annotation::new(
+                relative(root, &path),
+                Level::Error,
+                "empty-rule",
+                "the rule file is empty".to_string(),
+            )),
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+fn validate_commands(root: &Path, dir: &Path, annotations: &mut Vec<Annotation>) -> Result<()> {
+    let commands_dir = dir.join(AI_RULE_SOURCE_DIR).join(COMMANDS_DIR);
+    if !commands_dir.is_dir() {
+        return Ok(());
+    }
+    for path in find_files_by_extension(&commands_dir, MD_EXTENSION)? {
+        let content = read(&path)?;
+        let Some(split) = split(root, &path, &content, annotations) else {
+            continue;
+        };
+        // Agents keep adding command keys, so ones we don't know only warn
+        if let Some(frontmatter) = &split.frontmatter {
+            check_frontmatter::<CommandFrontmatter>(
+                root,
+                &path,
+                frontmatter,
+                Level::Warning,
+                annotations,
+            );
+        }
+    }
+    Ok(())
+}
+
+fn validate_skills(root: &Path, dir: &Path, annotations: &mut Vec<Annotation>) -> Result<()> {
+    let skills_dir = dir.join(AI_RULE_SOURCE_DIR).join(SKILLS_DIR);
+    if !skills_dir.is_dir() {
+        return Ok(());
+    }
+    let mut folders: Vec<PathBuf> = fs::read_dir(&skills_dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    folders.sort();
+    for folder in folders.into_iter().filter(|folder| folder.is_dir()) {
+        let folder_name = folder
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let path = folder.join(SKILL_FILENAME);
+        if !path.exists() {
+            annotations.push(Annotation::new(
+                relative(root, &folder),
+                Level::Warning,
+                "missing-skill-file",
+                format!("the skill has no {SKILL_FILENAME}, so it is skipped"),
+            ));
+            continue;
+        }
+
+        let content = read(&path)?;
+        let Some(split) = split(root, &path, &content, annotations) else {
+            continue;
+        };
+        let Some(frontmatter) = &split.frontmatter else {
+            annotations.push(Annotation::new(
+                relative(root, &path),
+                Level::Error,
+                "missing-description",
+                format!("{SKILL_FILENAME} needs `name: {folder_name}` and a `description`"),
+            ));
+            continue;
+        };
+        let Some(skill) = check_frontmatter::<SkillFrontmatter>(
+            root,
+            &path,
+            frontmatter,
+            Level::Warning,
+            annotations,
+        ) else {
+            continue;
+        };
+
+        let locate = |key: &str| {
+            locate_key(&frontmatter.raw, &[key.to_string()])
+                .map(|(line, column)| (line + frontmatter.first_line - 1, column))
+        };
+        let first_line = Some((frontmatter.first_line, 1));
+        match skill.name {
+            // Rendered with template variables, so only known after generate
+            Some(name) if name.contains("{{") => {}
+            Some(name) if name != folder_name => annotations.push(
+                Annotation::new(
+                    relative(root, &path),
+                    Level::Error,
+                    "skill-name-mismatch",
+                    format!("`name` is `{name}`, but the skill folder is `{folder_name}`"),
+                )
+                .at(locate("name")),
+            ),
+            Some(_) => {}
+            None => annotations.push(
+                Annotation::new(
+                    relative(root, &path),
+                    Level::Error,
+                    "skill-name-mismatch",
+                    format!("`name` is missing; set it to `{folder_name}`, the folder name"),
+                )
+                .at(first_line),
+            ),
+        }
+        if skill
+            .description
+            .is_none_or(|description| description.trim().is_empty())
+        {
+            annotations.push(
+                Annotation::new(
+                    relative(root, &path),
+                    Level::Error,
+                    "missing-description",
+                    "`description` is missing; agents use it to decide when to load the skill"
+                        .to_string(),
+                )
+                .at(locate("description").or(first_line)),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// A JSON syntax error, located like serde_json reports it.
+fn json_error(path: PathBuf, rule: &'static str, error: &serde_json::Error) -> Annotation {
+    let message = error.to_string();
+    let message = message
+        .rsplit_once(" at line ")
+        .map_or(message.as_str(), |(message, _)| message);
+    Annotation::new(path, Level::Error, rule, message.to_string())
+        // serde_json reports column 0 at the end of a line
+        .at(Some((error.line(), error.column().max(1))))
+}
+
+fn is_string_list(value: &Value) -> bool {
+    value
+        .as_array()
+        .is_some_and(|items| items.iter().all(Value::is_string))
+}
+
+fn is_string_map(value: &Value) -> bool {
+    value
+        .as_object()
+        .is_some_and(|entries| entries.values().all(Value::is_string))
+}
+
+/// The key path of `key` in the server `name`, or of the server itself for "".
+fn server_key<'a>(name: &'a str, key: &'a str) -> Vec<&'a str> {
+    match key {
+        "" => vec![MCP_SERVERS_FIELD, name],
+        key => vec![MCP_SERVERS_FIELD, name, key],
+    }
+}
+
+fn validate_mcp(root: &Path, dir: &Path, annotations: &mut Vec<Annotation>) -> Result<()> {
+    let path = dir.join(AI_RULE_SOURCE_DIR).join(MCP_JSON);
+    if !path.exists() {
+        return Ok(());
+    }
+    let content = read(&path)?;
+    let relative = relative(root, &path);
+    let json: Value = match serde_json::from_str(&content) {
+        Ok(json) => json,
+        Err(error) => {
+            annotations.push(json_error(relative, "invalid-mcp", &error));
+            return Ok(());
+        }
+    };
+    let problem = |level: Level, key: &[&str], message: String| {
+        let key: KeyPath = key.iter().map(|key| key.to_string()).collect();
+        // Keys of a server written on one line aren't found, so point at the server
+        let location = (1..=key.len())
+            .rev()
+            .find_map(|len| locate_key(&content, &key[..len]));
+        Annotation::new(relative.clone(), level, "invalid-mcp", message)
+            .at(location.or(Some((1, 1))))
+    };
+
+    let Some(object) = json.as_object() else {
+        annotations.push(problem(
+            Level::Error,
+            &[],
+            "mcp.json must be a JSON object".to_string(),
+        ));
+        return Ok(());
+    };
+    for key in object.keys().filter(|key| *key != MCP_SERVERS_FIELD) {
+        annotations.push(unknown_key(
+            relative.clone(),
+            Level::Warning,
+            &content,
+            1,
+            &vec![key.clone()],
+            &[MCP_SERVERS_FIELD],
+        ));
+    }
+    let Some(servers) = object.get(MCP_SERVERS_FIELD) else {
+        annotations.push(problem(
+            Level::Error,
+            &[],
+            format!("`{MCP_SERVERS_FIELD}` is missing"),
+        ));
+        return Ok(());
+    };
+    let Some(servers) = servers.as_object() else {
+        annotations.push(problem(
+            Level::Error,
+            &[MCP_SERVERS_FIELD],
+            format!("`{MCP_SERVERS_FIELD}` must be an object of servers"),
+        ));
+        return Ok(());
+    };
+
+    for (name, server) in servers {
+        let Some(server) = server.as_object() else {
+            annotations.push(problem(
+                Level::Error,
+                &server_key(name, ""),
+                format!("server `{name}` must be an object"),
+            ));
+            continue;
+        };
+        let mut errors = Vec::new();
+        let server_type = server.get("type").and_then(Value::as_str);
+        match (server.get("command"), server.get("url")) {
+            (Some(command), _) => {
+                if command.as_str().is_none_or(|command| command.is_empty()) {
+                    errors.push((
+                        "command",
+                        "`command` must be a non-empty string".to_string(),
+                    ));
+                }
+                if server.get("args").is_some_and(|args| !is_string_list(args)) {
+                    errors.push(("args", "`args` must be a list of strings".to_string()));
+                }
+                if server.get("env").is_some_and(|env| !is_string_map(env)) {
+                    errors.push(("env", "`env` must map names to strings".to_string()));
+                }
+                if server.contains_key("type") && server_type != Some("stdio") {
+                    errors.push((
+                        "type",
+                        "`type` must be \"stdio\" for a `command` server".to_string(),
+                    ));
+                }
+                for key in ["url", "headers"] {
+                    if server.contains_key(key) {
+                        errors.push((
+                            key,
+                            format!(
+                                "`{key}` is for remote servers and can't be used with `command`"
+                            ),
+                        ));
+                    }
+                }
+            }
+            (None, Some(url)) => {
+                if !url.is_string() {
+                    errors.push(("url", "`url` must be a string".to_string()));
+                }
+                if server_type != Some("http") {
+                    errors.push((
+                        "type",
+                        "a `url` server needs `\"type\": \"http\"`".to_string(),
+                    ));
+                }
+                if server
+                    .get("headers")
+                    .is_some_and(|headers| !is_string_map(headers))
+                {
+                    errors.push(("headers", "`headers` must map names to strings".to_string()));
+                }
+            }
+            (None, None) => errors.push((
+                "",
+                "needs a `command` to run or the `url` of a remote server".to_string(),
+            )),
+        }
+        for (key, message) in errors {
+            let key = if key.is_empty() || server.contains_key(key) {
+                key
+            } else {
+                ""
+            };
+            annotations.push(problem(
+                Level::Error,
+                &server_key(name, key),
+                format!("server `{name}`: {message}"),
+            ));
+        }
+        for key in server
+            .keys()
+            .filter(|key| !MCP_SERVER_KEYS.contains(&key.as_str()))
+        {
+            let path = server_key(name, key)
+                .iter()
+                .map(|key| key.to_string())
+                .collect();
+            annotations.push(unknown_key(
+                relative.clone(),
+                Level::Warning,
+                &content,
+                1,
+                &path,
+                MCP_SERVER_KEYS,
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_overlay(root: &Path, dir: &Path, annotations: &mut Vec<Annotation>) -> Result<()> {
+    let path = dir.join(AI_RULE_SOURCE_DIR).join(FIREBENDER_OVERLAY_JSON);
+    if !path.exists() {
+        return Ok(());
+    }
+    match serde_json::from_str::<Value>(&read(&path)?) {
+        Ok(Value::Object(_)) => {}
+        Ok(_) => annotations.push(
+            Annotation::new(
+                relative(root, &path),
+                Level::Error,
+                "invalid-overlay",
+                "the overlay must be a JSON object".to_string(),
+            )
+            .at(Some((1, 1))),
+        ),
+        Err(error) => {
+            annotations.push(json_error(relative(root, &path), "invalid-overlay", &error))
+        }
+    }
+    Ok(())
+}
+
+fn validate_config(
+    root: &Path,
+    dir: &Path,
+    registry: &AgentToolRegistry,
+    annotations: &mut Vec<Annotation>,
+) -> Result<()> {
+    let path = dir.join(AI_RULE_SOURCE_DIR).join(AI_RULE_CONFIG_FILENAME);
+    if !path.exists() {
+        return Ok(());
+    }
+    let content = read(&path)?;
+    let relative = relative(root, &path);
+    let parsed =
+        deserialize_reporting_unknown::<_, Config>(serde_yaml::Deserializer::from_str(&content));
+    let (config, unknown) = match parsed {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            let message = error.to_string();
+            let message = message
+                .rsplit_once(" at line ")
+                .map_or(message.as_str(), |(message, _)| message);
+            let location = error
+                .location()
+                .map(|location| (location.line(), location.column()));
+            annotations.push(
+                Annotation::new(
+                    relative,
+                    Level::Error,
+                    "invalid-config",
+                    message.to_string(),
+                )
+                .at(location.or(Some((1, 1)))),
+            );
+            return Ok(());
+        }
+    };
+
+    let candidates = [
+        struct_fields::<Config>(),
+        struct_fields::<Profile>(),
+        struct_fields::<ImportSpec>(),
+        struct_fields::<LintConfig>(),
+    ]
+    .concat();
+    for key in &unknown {
+        annotations.push(unknown_key(
+            relative.clone(),
+            Level::Error,
+            &content,
+            1,
+            key,
+            &candidates,
+        ));
+    }
+
+    let lint_checks = config.lint.and_then(|lint| lint.checks).unwrap_or_default();
+    for check in lint_checks.keys() {
+        if !check_codes().any(|code| code == check) {
+            let key = vec!["lint".to_string(), "checks".to_string(), check.clone()];
+            let codes: Vec<&str> = check_codes().collect();
+            let annotation = unknown_key(relative.clone(), Level::Error, &content, 1, &key, &codes);
+            annotations.push(annotation);
+        }
+    }
+
+    // Generate skips agent names it doesn't know without a word
+    let mut agent_lists = vec![
+        (vec!["agents".to_string()], config.agents),
+        (vec!["command_agents".to_string()], config.command_agents),
+    ];
+    for (name, profile) in config.profiles.unwrap_or_default() {
+        let key = vec!["profiles".to_string(), name, "agents".to_string()];
+        agent_lists.push((key, profile.agents));
+    }
+    let mut known = registry.get_all_tool_names();
+    known.sort();
+    for (key, agents) in agent_lists {
+        for agent in agents.into_iter().flatten() {
+            if registry.get_tool(&agent).is_some() {
+                continue;
+            }
+            let message = match did_you_mean(&agent, known.iter().map(String::as_str)) {
+                Some(suggestion) => {
+                    format!("unknown agent `{agent}`, did you mean `{suggestion}`?")
+                }
+                None => format!(
+                    "unknown agent `{agent}`; supported agents are {}",
+                    known.join(", ")
+                ),
+            };
+            let location = locate_key(&content, &key)
+                .and_then(|(line, _)| locate_word(&content, &agent, line));
+            annotations.push(
+                Annotation::new(relative.clone(), Level::Error, "unknown-agent", message)
+                    .at(location),
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,366 @@
+use crate::config::load_directory_config;
+use crate::utils::git_utils::find_git_root;
+use anyhow::{anyhow, Result};
+use regex::{Captures, Regex};
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::LazyLock;
+
+pub const PROJECT_NAME_VARIABLE: &str = "project_name";
+pub const RELATIVE_DIR_VARIABLE: &str = "relative_dir";
+pub const PACKAGE_MANAGER_VARIABLE: &str = "package_manager";
+
+/// Marker files used to detect the package manager, checked in order in each directory.
+const PACKAGE_MANAGER_MARKERS: &[(&str, &str)] = &[
+    ("pnpm-lock.yaml", "pnpm"),
+    ("yarn.lock", "yarn"),
+    ("bun.lockb", "bun"),
+    ("bun.lock", "bun"),
+    ("package-lock.json", "npm"),
+    ("Cargo.toml", "cargo"),
+    ("go.mod", "go"),
+    ("uv.lock", "uv"),
+    ("poetry.lock", "poetry"),
+    ("Pipfile", "pipenv"),
+    ("requirements.txt", "pip"),
+    ("Gemfile", "bundler"),
+    ("build.gradle.kts", "gradle"),
+    ("build.gradle", "gradle"),
+    ("settings.gradle.kts", "gradle"),
+    ("settings.gradle", "gradle"),
+    ("pom.xml", "maven"),
+    ("Package.swift", "swiftpm"),
+    ("package.json", "npm"),
+];
+
+/// Matches `{{ name }}` placeholders, plus an optional leading `\` (escape) or `$`
+/// (e.g. GitHub Actions `${{ ... }}` expressions, which are left untouched).
+static PLACEHOLDER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(\\|\$)?\{\{\s*([A-Za-z_][A-Za-z0-9_-]*)\s*\}\}").expect("valid regex")
+});
+
+/// Variables available to `{{name}}` placeholders in rule, command and skill files.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemplateVariables {
+    values: BTreeMap<String, String>,
+}
+
+impl TemplateVariables {
+    /// Resolves the variables for a project directory.
+    ///
+    /// Precedence (highest to lowest): CLI `--var` overrides, the directory's own
+    /// `ai-rules/ai-rules-config.yaml`, the root config, built-in variables.
+    pub fn for_directory(
+        current_dir: &Path,
+        config_variables: &BTreeMap<String, String>,
+        overrides: &BTreeMap<String, String>,
+    ) -> Result<Self> {
+        let mut values = builtin_variables(current_dir);
+        values.extend(config_variables.clone());
+        if let Some(directory_variables) =
+            load_directory_config(current_dir)?.and_then(|config| config.variables)
+        {
+            values.extend(directory_variables);
+        }
+        values.extend(overrides.clone());
+        Ok(Self { values })
+    }
+
+    #[cfg(test)]
+    pub fn from_pairs(pairs: &[(&str, &str)]) -> Self {
+        Self {
+            values: pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Substitutes every placeholder in `content`, failing on the first undefined variable.
+    ///
+    /// `\{{name}}` renders as a literal `{{name}}`. Fenced code blocks are left untouched,
+    /// and undefined placeholders in inline code are kept as written, so snippets such as
+    /// Vue or Handlebars templates don't need escaping.
+    pub fn render(&self, content: &str, file_path: &Path) -> Result<String> {
+        let mut rendered = String::with_capacity(content.len());
+
+        for (index, line, in_code_fence) in prose_lines(content) {
+            if in_code_fence {
+                rendered.push_str(line);
+                continue;
+            }
+            let code_spans = inline_code_spans(line);
+            let mut undefined: Option<String> = None;
+            let replaced = PLACEHOLDER_REGEX.replace_all(line, |caps: &Captures| {
+                let whole = &caps[0];
+                match caps.get(1).map(|m| m.as_str()) {
+                    Some("\\") => whole[1..].to_string(),
+                    Some(_) => whole.to_string(),
+                    None => match self.get(&caps[2]) {
+                        Some(value) => value.to_string(),
+                        None => {
+                            let start = caps.get(0).map_or(0, |m| m.start());
+                            if !code_spans.iter().any(|span| span.contains(&start)) {
+                                undefined.get_or_insert_with(|| caps[2].to_string());
+                            }
+                            whole.to_string()
+                        }
+                    },
+                }
+            });
+
+            if let Some(name) = undefined {
+                return Err(anyhow!(
+                    "{}:{}: undefined template variable '{}'. Define it under 'variables:' in ai-rules-config.yaml or pass --var {}=<value>",
+                    file_path.display(),
+                    index + 1,
+                    name,
+                    name
+                ));
+            }
+            rendered.push_str(&replaced);
+        }
+
+        Ok(rendered)
+    }
+}
+
+/// Yields each line with its index and whether it is part of a fenced code block,
+/// fence lines included.
+fn prose_lines(content: &str) -> impl Iterator<Item = (usize, &str, bool)> {
+    let mut in_code_fence = false;
+    content
+        .split_inclusive('\n')
+        .enumerate()
+        .map(move |(index, line)| {
+            let trimmed = line.trim();
+            let is_fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+            if is_fence {
+                in_code_fence = !in_code_fence;
+            }
+            (index, line, in_code_fence || is_fence)
+        })
+}
+
+/// Byte ranges of the inline code spans in `line`, backticks included.
+fn inline_code_spans(line: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut search_from = 0;
+    while let Some(offset) = line[search_from..].find('`') {
+        let open = search_from + offset;
+        let ticks = line[open..].len() - line[open..].trim_start_matches('`').len();
+        let delimiter = &line[open..open + ticks];
+        let body_start = open + ticks;
+        let Some(close) = line[body_start..].find(delimiter) else {
+            break;
+        };
+        let end = body_start + close + ticks;
+        spans.push(open..end);
+        search_from = end;
+    }
+    spans
+}
+
+/// Returns true if `content` has at least one placeholder that rendering would replace.
+pub fn contains_placeholders(content: &str) -> bool {
+    prose_lines(content)
+        .filter(|(_, _, in_code_fence)| !in_code_fence)
+        .any(|(_, line, _)| {
+            PLACEHOLDER_REGEX
+                .captures_iter(line)
+                .any(|caps| caps.get(1).is_none())
+        })
+}
+
+/// Returns true if the file at `path` is readable text containing placeholders.
+pub fn is_templated_file(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|content| contains_placeholders(&content))
+}
+
+/// Parses `key=value` pairs passed via `--var`.
+pub fn parse_variable_overrides(raw_values: &[String]) -> Result<BTreeMap<String, String>> {
+    let mut overrides = BTreeMap::new();
+    for raw in raw_values {
+        let (key, value) = raw
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid --var '{raw}' (expected key=value)"))?;
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(anyhow!("Invalid --var '{raw}': key cannot be empty"));
+        }
+        overrides.insert(key.to_string(), value.to_string());
+    }
+    Ok(overrides)
+}
+
+fn builtin_variables(current_dir: &Path) -> BTreeMap<String, String> {
+    let mut values = BTreeMap::new();
+    let git_root = find_git_root(current_dir);
+    let project_root = git_root.as_deref().unwrap_or(current_dir);
+
+    if let Some(name) = project_root.file_name().and_then(|name| name.to_str()) {
+        values.insert(PROJECT_NAME_VARIABLE.to_string(), name.to_string());
+    }
+
+    let relative_dir = current_dir
+        .strip_prefix(project_root)
+        .ok()
+        .filter(|path| !path.as_os_str().is_empty())
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| ".".to_string());
+    values.insert(RELATIVE_DIR_VARIABLE.to_string(), relative_dir);
+
+    if let Some(package_manager) = detect_package_manager(current_dir, git_root.as_deref()) {
+        values.insert(
+            PACKAGE_MANAGER_VARIABLE.to_string(),
+            package_manager.to_string(),
+        );
+    }
+
+    values
+}
+
+/// Looks for package manager marker files from `current_dir` up to the git root.
+fn detect_package_manager(current_dir: &Path, git_root: Option<&Path>) -> Option<&'static str> {
+    let mut dir = current_dir;
+    loop {
+        for (marker, package_manager) in PACKAGE_MANAGER_MARKERS {
+            if dir.join(marker).is_file() {
+                return Some(package_manager);
+            }
+        }
+
+        if git_root.is_none_or(|root| dir == root) {
+            return None;
+        }
+        dir = dir.parent()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::helpers::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_render_substitutes_variables() {
+        let variables = TemplateVariables::from_pairs(&[("test_command", "cargo test")]);
+
+        let result = variables
+            .render(
+                "Run `{{test_command}}` or `{{ test_command }}`\n",
+                Path::new("rule.md"),
+            )
+            .unwrap();
+
+        assert_eq!(result, "Run `cargo test` or `cargo test`\n");
+    }
+
+    #[test]
+    fn test_render_undefined_variable_reports_line() {
+        let variables = TemplateVariables::default();
+
+        let error = variables
+            .render("first\nRun {{missing}}\n", Path::new("rule.md"))
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("rule.md:2"), "{error}");
+        assert!(error.contains("'missing'"), "{error}");
+    }
+
+    #[test]
+    fn test_render_leaves_escaped_and_expression_placeholders() {
+        let variables = TemplateVariables::default();
+        let content = "\\{{literal}} ${{ github_sha }} {{> partial}} {{ a.b }}\n";
+
+        let result = variables.render(content, Path::new("rule.md")).unwrap();
+
+        assert_eq!(
+            result,
+            "{{literal}} ${{ github_sha }} {{> partial}} {{ a.b }}\n"
+        );
+        assert!(!contains_placeholders(content));
+        assert!(contains_placeholders("{{name}}"));
+    }
+
+    #[test]
+    fn test_render_leaves_code_snippets_alone() {
+        let variables = TemplateVariables::from_pairs(&[("message", "Hello")]);
+        let content = "Use `{{ count }}` in templates:\n\n```vue\n<p>{{ message }}</p>\n<p>{{ count }}</p>\n```\n\nSay {{message}}.\n";
+
+        let result = variables.render(content, Path::new("vue.md")).unwrap();
+
+        assert_eq!(
+            result,
+            "Use `{{ count }}` in templates:\n\n```vue\n<p>{{ message }}</p>\n<p>{{ count }}</p>\n```\n\nSay Hello.\n"
+        );
+        assert!(!contains_placeholders("```\n{{ message }}\n```\n"));
+    }
+
+    #[test]
+    fn test_parse_variable_overrides() {
+        let overrides =
+            parse_variable_overrides(&["a=1".to_string(), "b=x=y".to_string()]).unwrap();
+        assert_eq!(overrides["a"], "1");
+        assert_eq!(overrides["b"], "x=y");
+
+        assert!(parse_variable_overrides(&["novalue".to_string()]).is_err());
+        assert!(parse_variable_overrides(&["=value".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_builtin_variables() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("my-project");
+        fs::create_dir_all(root.join(".git")).unwrap();
+        create_file(&root, "packages/web/pnpm-lock.yaml", "");
+        create_file(&root, "Cargo.toml", "");
+
+        let web = TemplateVariables::for_directory(
+            &root.join("packages/web"),
+            &BTreeMap::new(),
+            &BTreeMap::new(),
+        )
+        .unwrap();
+        assert_eq!(web.get(PROJECT_NAME_VARIABLE), Some("my-project"));
+        assert_eq!(web.get(RELATIVE_DIR_VARIABLE), Some("packages/web"));
+        assert_eq!(web.get(PACKAGE_MANAGER_VARIABLE), Some("pnpm"));
+
+        let root_variables =
+            TemplateVariables::for_directory(&root, &BTreeMap::new(), &BTreeMap::new()).unwrap();
+        assert_eq!(root_variables.get(RELATIVE_DIR_VARIABLE), Some("."));
+        assert_eq!(root_variables.get(PACKAGE_MANAGER_VARIABLE), Some("cargo"));
+    }
+
+    #[test]
+    fn test_for_directory_precedence() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        create_file(
+            root,
+            "ai-rules/ai-rules-config.yaml",
+            "variables:\n  test_command: npm test\n  lint: eslint\n",
+        );
+        let config_variables = BTreeMap::from([
+            ("test_command".to_string(), "cargo test".to_string()),
+            ("team".to_string(), "platform".to_string()),
+            ("lint".to_string(), "clippy".to_string()),
+        ]);
+        let overrides = BTreeMap::from([("lint".to_string(), "none".to_string())]);
+
+        let variables =
+            TemplateVariables::for_directory(root, &config_variables, &overrides).unwrap();
+
+        assert_eq!(variables.get("team"), Some("platform"));
+        assert_eq!(variables.get("test_command"), Some("npm test"));
+        assert_eq!(variables.get("lint"), Some("none"));
+    }
+}
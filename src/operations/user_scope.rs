@@ -0,0 +1,341 @@
+use crate::agents::rule_generator::UserScopeTarget;
+use crate::constants::{AI_RULE_SOURCE_DIR, GENERATED_FILE_PREFIX, MD_EXTENSION};
+use crate::models::SourceFile;
+use crate::operations::body_generator::generate_fully_inlined_content;
+use crate::operations::command_reader::find_command_files;
+use crate::operations::skills_reader::find_skill_folders;
+use crate::operations::source_reader::find_source_files;
+use crate::utils::plan::{Operation, Plan};
+use crate::utils::print_utils::print_warning;
+use anyhow::{anyhow, Result};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// First line of every user-level rules file ai-rules writes. Files without it are
+/// never overwritten or removed.
+const USER_FILE_MARKER: &str = "<!-- Generated by 'ai-rules generate --user'";
+
+/// The user-level source and output locations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserScope {
+    /// Where agent files are written (`$HOME`)
+    pub home_dir: PathBuf,
+    /// Directory containing the user's `ai-rules/` folder (`$XDG_CONFIG_HOME`,
+    /// falling back to `~/.config`)
+    pub config_dir: PathBuf,
+}
+
+impl UserScope {
+    pub fn from_env() -> Result<Self> {
+        let home_dir = env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .ok_or_else(|| anyhow!("Cannot find the home directory. Set HOME to use --user"))?;
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home_dir.join(".config"));
+        Ok(Self {
+            home_dir,
+            config_dir,
+        })
+    }
+
+    pub fn source_dir(&self) -> PathBuf {
+        self.config_dir.join(AI_RULE_SOURCE_DIR)
+    }
+}
+
+/// What ai-rules owns in the home directory for one agent.
+struct ExpectedUserFiles {
+    rules_file: PathBuf,
+    rules_content: Option<String>,
+    /// Symlink paths and the absolute paths they point to
+    links: Vec<(PathBuf, PathBuf)>,
+}
+
+fn expected_user_files(scope: &UserScope, target: &UserScopeTarget) -> Result<ExpectedUserFiles> {
+    let source_files = find_source_files(&scope.config_dir)?;
+    let rules_content =
+        (!source_files.is_empty()).then(|| user_rules_content(&scope.source_dir(), &source_files));
+
+    let mut links = Vec::new();
+    if let Some(commands_dir) = target.commands_dir {
+        for command in find_command_files(&scope.config_dir)? {
+            let link = scope
+                .home_dir
+                .join(commands_dir)
+                .join(format!("{}.{MD_EXTENSION}", command.name));
+            links.push((link, command.source_path));
+        }
+    }
+    if let Some(skills_dir) = target.skills_dir {
+        for skill in find_skill_folders(&scope.config_dir)? {
+            let link = scope
+                .home_dir
+                .join(skills_dir)
+                .join(format!("{GENERATED_FILE_PREFIX}{}", skill.name));
+            links.push((link, skill.source_path));
+        }
+    }
+
+    Ok(ExpectedUserFiles {
+        rules_file: scope.home_dir.join(target.rules_file),
+        rules_content,
+        links,
+    })
+}
+
+/// User-level files can't reference rule bodies by path, so every rule is inlined and
+/// `alwaysApply` and `fileMatching` don't apply here.
+fn user_rules_content(source_dir: &Path, source_files: &[SourceFile]) -> String {
+    format!(
+        "{USER_FILE_MARKER} from {}. Edit the rules there; changes to this file are overwritten. -->\n\n{}",
+        source_dir.display(),
+        generate_fully_inlined_content(source_files)
+    )
+}
+
+fn is_generated_user_file(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|content| content.starts_with(USER_FILE_MARKER))
+}
+
+/// Plans the agent's user-level files and returns their paths.
+///
+/// An existing rules file that ai-rules didn't write is left untouched.
+pub fn generate_user_files(
+    scope: &UserScope,
+    target: &UserScopeTarget,
+    plan: &mut Plan,
+) -> Result<Vec<PathBuf>> {
+    let expected = expected_user_files(scope, target)?;
+    clean_user_links(scope, target, plan)?;
+
+    let mut written = Vec::new();
+    match &expected.rules_content {
+        Some(_)
+            if expected.rules_file.exists() && !is_generated_user_file(&expected.rules_file) =>
+        {
+            print_warning(&format!(
+                "Skipping {}: it wasn't generated by ai-rules. Move its content into {} to manage it with ai-rules",
+                expected.rules_file.display(),
+                scope.source_dir().display()
+            ));
+        }
+        Some(content) => {
+            plan.push(Operation::write(&expected.rules_file, content.as_str()))?;
+            written.push(expected.rules_file.clone());
+        }
+        None => remove_generated_rules_file(&expected.rules_file, plan)?,
+    }
+
+    for (link, link_target) in expected.links {
+        plan.push(Operation::symlink(&link, link_target))?;
+        // A link to the source folder is recreated as it was
+        plan.mark_regenerable(link.clone());
+        written.push(link);
+    }
+    remove_empty_commands_dir(scope, target, plan)?;
+    Ok(written)
+}
+
+/// Returns true if the agent's user-level files match the user's `ai-rules/` folder.
+pub fn check_user_files(scope: &UserScope, target: &UserScopeTarget) -> Result<bool> {
+    let expected = expected_user_files(scope, target)?;
+
+    let rules_in_sync = match &expected.rules_content {
+        Some(content) => fs::read_to_string(&expected.rules_file).ok().as_ref() == Some(content),
+        None => !is_generated_user_file(&expected.rules_file),
+    };
+    if !rules_in_sync {
+        return Ok(false);
+    }
+
+    let mut expected_links: Vec<&PathBuf> = expected.links.iter().map(|(link, _)| link).collect();
+    let mut actual_links = find_user_links(scope, target)?;
+    expected_links.sort();
+    actual_links.sort();
+    if expected_links != actual_links.iter().collect::<Vec<_>>() {
+        return Ok(false);
+    }
+    Ok(expected
+        .links
+        .iter()
+        .all(|(link, link_target)| fs::read_link(link).is_ok_and(|actual| actual == *link_target)))
+}
+
+/// Plans removing the agent's user-level files written by ai-rules.
+pub fn clean_user_files(
+    scope: &UserScope,
+    target: &UserScopeTarget,
+    plan: &mut Plan,
+) -> Result<()> {
+    remove_generated_rules_file(&scope.home_dir.join(target.rules_file), plan)?;
+    clean_user_links(scope, target, plan)?;
+    remove_empty_commands_dir(scope, target, plan)
+}
+
+fn remove_generated_rules_file(path: &Path, plan: &mut Plan) -> Result<()> {
+    if is_generated_user_file(path) {
+        plan.push(Operation::delete(path))?;
+    }
+    Ok(())
+}
+
+fn clean_user_links(scope: &UserScope, target: &UserScopeTarget, plan: &mut Plan) -> Result<()> {
+    for link in find_user_links(scope, target)? {
+        plan.mark_regenerable(link.clone());
+        plan.push(Operation::delete(link))?;
+    }
+    Ok(())
+}
+
+/// The commands folder belongs to ai-rules, so it goes once nothing is left in it.
+fn remove_empty_commands_dir(
+    scope: &UserScope,
+    target: &UserScopeTarget,
+    plan: &mut Plan,
+) -> Result<()> {
+    if let Some(commands_dir) = target.commands_dir {
+        let commands_dir = scope.home_dir.join(commands_dir);
+        if plan
+            .children(&commands_dir)?
+            .is_some_and(|children| children.is_empty())
+        {
+            plan.push(Operation::delete(commands_dir))?;
+        }
+    }
+    Ok(())
+}
+
+/// Symlinks ai-rules owns: everything in the commands directory and prefixed skills.
+fn find_user_links(scope: &UserScope, target: &UserScopeTarget) -> Result<Vec<PathBuf>> {
+    let mut links = Vec::new();
+    let mut collect = |dir: PathBuf, prefix: &str| -> Result<()> {
+        if !dir.is_dir() {
+            return Ok(());
+        }
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let is_owned = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(prefix));
+            if is_owned && path.is_symlink() {
+                links.push(path);
+            }
+        }
+        Ok(())
+    };
+    if let Some(commands_dir) = target.commands_dir {
+        collect(scope.home_dir.join(commands_dir), "")?;
+    }
+    if let Some(skills_dir) = target.skills_dir {
+        collect(scope.home_dir.join(skills_dir), GENERATED_FILE_PREFIX)?;
+    }
+    Ok(links)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::helpers::*;
+    use crate::utils::transaction;
+    use tempfile::TempDir;
+
+    const TARGET: UserScopeTarget = UserScopeTarget {
+        rules_file: ".claude/CLAUDE.md",
+        commands_dir: Some(".claude/commands/ai-rules"),
+        skills_dir: Some(".claude/skills"),
+    };
+
+    fn generate(scope: &UserScope) -> Vec<PathBuf> {
+        let mut plan = Plan::default();
+        let written = generate_user_files(scope, &TARGET, &mut plan).unwrap();
+        transaction::apply(&scope.config_dir, &plan, None).unwrap();
+        written
+    }
+
+    fn clean(scope: &UserScope) {
+        let mut plan = Plan::default();
+        clean_user_files(scope, &TARGET, &mut plan).unwrap();
+        transaction::apply(&scope.config_dir, &plan, None).unwrap();
+    }
+
+    fn scope(temp_dir: &TempDir) -> UserScope {
+        UserScope {
+            home_dir: temp_dir.path().join("home"),
+            config_dir: temp_dir.path().join("config"),
+        }
+    }
+
+    #[test]
+    fn test_generate_check_and_clean_user_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let scope = scope(&temp_dir);
+        create_file(
+            &scope.config_dir,
+            "ai-rules/style.md",
+            "---\ndescription: Style\nalwaysApply: true\n---\nPrefer early returns",
+        );
+        create_file(
+            &scope.config_dir,
+            "ai-rules/commands/standup.md",
+            "Summarize",
+        );
+        create_file(
+            &scope.config_dir,
+            "ai-rules/skills/notes/SKILL.md",
+            "---\nname: notes\ndescription: Notes\n---\nTake notes",
+        );
+        create_file(&scope.home_dir, ".claude/skills/mine/SKILL.md", "Mine");
+
+        let written = generate(&scope);
+
+        assert_eq!(written.len(), 3);
+        let rules = fs::read_to_string(scope.home_dir.join(".claude/CLAUDE.md")).unwrap();
+        assert!(rules.starts_with(USER_FILE_MARKER));
+        assert!(rules.ends_with("# Style\n\nPrefer early returns\n"));
+        assert_file_content(
+            &scope.home_dir,
+            ".claude/commands/ai-rules/standup.md",
+            "Summarize",
+        );
+        assert_file_content(
+            &scope.home_dir,
+            ".claude/skills/ai-rules-generated-notes/SKILL.md",
+            "---\nname: notes\ndescription: Notes\n---\nTake notes",
+        );
+        assert!(check_user_files(&scope, &TARGET).unwrap());
+
+        create_file(&scope.config_dir, "ai-rules/commands/review.md", "Review");
+        assert!(!check_user_files(&scope, &TARGET).unwrap());
+
+        clean(&scope);
+        assert_file_not_exists(&scope.home_dir, ".claude/CLAUDE.md");
+        assert_file_not_exists(&scope.home_dir, ".claude/commands/ai-rules");
+        assert_file_not_exists(&scope.home_dir, ".claude/skills/ai-rules-generated-notes");
+        assert_file_exists(&scope.home_dir, ".claude/skills/mine/SKILL.md");
+    }
+
+    #[test]
+    fn test_generate_user_files_keeps_hand_written_rules_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let scope = scope(&temp_dir);
+        create_file(
+            &scope.config_dir,
+            "ai-rules/style.md",
+            "Prefer early returns",
+        );
+        create_file(&scope.home_dir, ".claude/CLAUDE.md", "My own notes");
+
+        let written = generate(&scope);
+        clean(&scope);
+
+        assert!(written.is_empty());
+        assert_file_content(&scope.home_dir, ".claude/CLAUDE.md", "My own notes");
+        assert!(!check_user_files(&scope, &TARGET).unwrap());
+    }
+}
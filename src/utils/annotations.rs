@@ -0,0 +1,293 @@
+//! Problems reported against files, printed as GitHub workflow commands or as SARIF for
+//! code scanning.
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Errors located as `file:line:column: message`, like frontmatter and MCP errors.
+static LOCATED_MESSAGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)^(.+?):(\d+):(\d+): (.+)$").expect("valid regex"));
+
+/// Rule ids and what they mean, for the SARIF rule list.
+const RULES: &[(&str, &str)] = &[
+    (
+        "out-of-sync",
+        "A generated file doesn't match what generate would write",
+    ),
+    (
+        "locally-modified",
+        "A generated file was edited since the last generate",
+    ),
+    ("invalid-source", "A source file in ai-rules/ can't be used"),
+    (
+        "import-mismatch",
+        "A git import doesn't match ai-rules.lock",
+    ),
+    (
+        "stale-agent",
+        "Generated files remain for an agent that isn't configured",
+    ),
+    (
+        "invalid-frontmatter",
+        "Frontmatter that can't be parsed or has a value of the wrong type",
+    ),
+    ("unknown-key", "A key that nothing reads, usually a typo"),
+    ("empty-rule", "A rule file without any content"),
+    (
+        "skill-name-mismatch",
+        "A SKILL.md name that is missing or differs from its folder",
+    ),
+    ("missing-description", "A SKILL.md without a description"),
+    ("missing-skill-file", "A skill folder without SKILL.md"),
+    (
+        "invalid-mcp",
+        "An mcp.json that isn't a valid MCP configuration",
+    ),
+    (
+        "invalid-overlay",
+        "An overlay file that isn't a JSON object",
+    ),
+    (
+        "invalid-config",
+        "A config file that can't be parsed or has a value of the wrong type",
+    ),
+    ("unknown-agent", "An agent name that isn't supported"),
+    (
+        "broken-link",
+        "A relative link or @ reference to a file that doesn't exist",
+    ),
+    (
+        "duplicate-heading",
+        "A heading that another rule of the same directory also has",
+    ),
+    (
+        "duplicate-paragraph",
+        "A paragraph nearly identical to one in another rule",
+    ),
+    (
+        "unmatched-glob",
+        "A fileMatching glob that matches no files",
+    ),
+    (
+        "vague-description",
+        "An optional rule whose description is too short for agents to decide on it",
+    ),
+    ("oversize-rule", "A rule longer than the configured limit"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+/// One problem. `path` is relative to the project root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Annotation {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub level: Level,
+    /// A stable id for the kind of problem
+    #[serde(rename = "code")]
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl Annotation {
+    pub fn new(
+        path: impl Into<PathBuf>,
+        level: Level,
+        rule: &'static str,
+        message: String,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            line: None,
+            column: None,
+            level,
+            rule,
+            message,
+        }
+    }
+
+    /// Places the annotation at `location`, a 1-based line and column.
+    pub fn at(mut self, location: Option<(usize, usize)>) -> Self {
+        if let Some((line, column)) = location {
+            self.line = Some(line);
+            self.column = Some(column);
+        }
+        self
+    }
+
+    pub fn with_rule(mut self, rule: &'static str) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Locates `error` at the first cause with a `file:line:column` prefix, or at
+    /// `fallback` if none has one.
+    pub fn from_error(root: &Path, error: &anyhow::Error, fallback: &Path) -> Self {
+        error
+            .chain()
+            .find_map(|cause| Self::located(root, &cause.to_string()))
+            .unwrap_or_else(|| {
+                Self::new(
+                    fallback,
+                    Level::Error,
+                    "invalid-source",
+                    format!("{error:#}"),
+                )
+            })
+    }
+
+    /// Like [`from_error`](Self::from_error), for an error already formatted as text.
+    pub fn from_message(root: &Path, message: &str, fallback: &Path) -> Self {
+        Self::located(root, message).unwrap_or_else(|| {
+            Self::new(
+                fallback,
+                Level::Error,
+                "invalid-source",
+                message.to_string(),
+            )
+        })
+    }
+
+    fn located(root: &Path, message: &str) -> Option<Self> {
+        let captures = LOCATED_MESSAGE.captures(message)?;
+        let path = Path::new(captures.get(1)?.as_str());
+        Some(Self {
+            path: path.strip_prefix(root).unwrap_or(path).to_path_buf(),
+            line: captures[2].parse().ok(),
+            column: captures[3].parse().ok(),
+            level: Level::Error,
+            rule: "invalid-source",
+            message: captures[4].to_string(),
+        })
+    }
+}
+
+/// Escapes data for a workflow command; properties also escape `:` and `,`.
+fn escape(text: &str, property: bool) -> String {
+    let text = text
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A");
+    match property {
+        true => text.replace(':', "%3A").replace(',', "%2C"),
+        false => text,
+    }
+}
+
+/// `::error file=...,line=...::message` lines that GitHub Actions shows inline.
+pub fn github_commands(annotations: &[Annotation]) -> String {
+    let mut output = String::new();
+    for annotation in annotations {
+        let command = match annotation.level {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        };
+        let mut properties = vec![format!(
+            "file={}",
+            escape(&annotation.path.display().to_string(), true)
+        )];
+        if let Some(line) = annotation.line {
+            properties.push(format!("line={line}"));
+        }
+        if let Some(column) = annotation.column {
+            properties.push(format!("col={column}"));
+        }
+        properties.push(format!(
+            "title={}",
+            escape(&format!("ai-rules {}", annotation.rule), true)
+        ));
+        output.push_str(&format!(
+            "::{command} {}::{}\n",
+            properties.join(","),
+            escape(&annotation.message, false)
+        ));
+    }
+    output
+}
+
+/// A SARIF 2.1.0 log with one run.
+pub fn sarif_log(annotations: &[Annotation]) -> Value {
+    let rules: Vec<Value> = RULES
+        .iter()
+        .map(|(id, description)| json!({"id": id, "shortDescription": {"text": description}}))
+        .collect();
+    let results: Vec<Value> = annotations
+        .iter()
+        .map(|annotation| {
+            let mut location = json!({
+                "artifactLocation": {"uri": annotation.path.display().to_string()}
+            });
+            if let Some(line) = annotation.line {
+                location["region"] = json!({
+                    "startLine": line,
+                    "startColumn": annotation.column.unwrap_or(1),
+                });
+            }
+            json!({
+                "ruleId": annotation.rule,
+                "level": match annotation.level {
+                    Level::Error => "error",
+                    Level::Warning => "warning",
+                },
+                "message": {"text": annotation.message},
+                "locations": [{"physicalLocation": location}],
+            })
+        })
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {"driver": {
+                "name": "ai-rules",
+                "version": env!("CARGO_PKG_VERSION"),
+                "rules": rules,
+            }},
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_annotations_from_located_errors() {
+        let root = Path::new("/project");
+        let error = anyhow!("/project/ai-rules/a.md:3:5: invalid YAML frontmatter: bad, key")
+            .context("Failed to read rules");
+        let annotation = Annotation::from_error(root, &error, Path::new("ai-rules"));
+        assert_eq!(annotation.path, PathBuf::from("ai-rules/a.md"));
+        assert_eq!((annotation.line, annotation.column), (Some(3), Some(5)));
+
+        assert_eq!(
+            github_commands(std::slice::from_ref(&annotation)),
+            "::error file=ai-rules/a.md,line=3,col=5,title=ai-rules invalid-source::invalid YAML frontmatter: bad, key\n"
+        );
+        let sarif = sarif_log(&[annotation]);
+        let result = &sarif["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], "invalid-source");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["region"]["startLine"],
+            3
+        );
+
+        let unlocated = Annotation::from_error(root, &anyhow!("No\nrules"), Path::new("ai-rules"));
+        assert_eq!(unlocated.path, PathBuf::from("ai-rules"));
+        assert_eq!(
+            github_commands(&[unlocated]),
+            "::error file=ai-rules,title=ai-rules invalid-source::No%0Arules\n"
+        );
+    }
+}
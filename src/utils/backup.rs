@@ -0,0 +1,387 @@
+//! Snapshots of files that `generate` and `clean` remove or overwrite, restored with
+//! `ai-rules restore`.
+//!
+//! A [`Backup`] is only taken in projects with an `ai-rules/` folder to keep it in.
+//! [`transaction::apply`](crate::utils::transaction::apply) saves each entry into it
+//! just before replacing it, so every agent's cleanup is covered.
+//!
+//! `backup.json` is rewritten after every file is copied, so a backup cut short by a
+//! failing command still lists what it holds.
+
+use crate::constants::{AI_RULE_SOURCE_DIR, GENERATED_RULE_BODY_DIR};
+use crate::utils::plan::{Operation, Plan};
+use crate::utils::transaction;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::os::unix::fs as unix_fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const BACKUPS_DIR: &str = "backups";
+const BACKUP_INDEX: &str = "backup.json";
+const FILES_DIR: &str = "files";
+
+/// Metadata stored next to the backed up files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub id: String,
+    /// The command that removed or overwrote the files
+    pub command: String,
+    /// Paths relative to the project root, or the home directory for `--user`
+    pub paths: Vec<PathBuf>,
+}
+
+/// The backup one command is writing.
+pub struct Backup {
+    /// Holds the `ai-rules/` folder the backup is kept in
+    store: PathBuf,
+    /// The files backed up are under it
+    root: PathBuf,
+    info: BackupInfo,
+}
+
+/// `ai-rules/.generated-ai-rules/backups` under the project root.
+pub fn backups_dir(root: &Path) -> PathBuf {
+    root.join(AI_RULE_SOURCE_DIR)
+        .join(GENERATED_RULE_BODY_DIR)
+        .join(BACKUPS_DIR)
+}
+
+impl Backup {
+    /// Starts a backup for `command` of files under `root`, kept in the `ai-rules/`
+    /// folder of `store`: the project itself, or the user's folder for `--user`.
+    /// `None` without that folder, as there is nowhere to keep it that clean wouldn't
+    /// create.
+    pub fn start(store: &Path, root: &Path, command: &str) -> Option<Self> {
+        if !store.join(AI_RULE_SOURCE_DIR).is_dir() {
+            return None;
+        }
+        Some(Backup {
+            store: store.to_path_buf(),
+            root: root.to_path_buf(),
+            info: BackupInfo {
+                id: new_backup_id(store),
+                command: command.to_string(),
+                paths: Vec::new(),
+            },
+        })
+    }
+
+    /// Copies `path` into the backup, unless it is generated, outside the project
+    /// or already saved.
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return Ok(());
+        };
+        let is_generated_dir = relative
+            .components()
+            .any(|component| component == Component::Normal(GENERATED_RULE_BODY_DIR.as_ref()));
+        if is_generated_dir
+            || self
+                .info
+                .paths
+                .iter()
+                .any(|saved| relative.starts_with(saved))
+            || path.symlink_metadata().is_err()
+        {
+            return Ok(());
+        }
+
+        let backup_dir = backups_dir(&self.store).join(&self.info.id);
+        copy_preserving_links(path, &backup_dir.join(FILES_DIR).join(relative))
+            .with_context(|| format!("Failed to back up {}", path.display()))?;
+        self.info.paths.push(relative.to_path_buf());
+        fs::write(
+            backup_dir.join(BACKUP_INDEX),
+            serde_json::to_string_pretty(&self.info)? + "\n",
+        )?;
+        Ok(())
+    }
+
+    /// Prints the backup id if anything was backed up.
+    pub fn finish(self) {
+        if !self.info.paths.is_empty() {
+            println!(
+                "💾 Backed up {} replaced file(s). Run 'ai-rules restore {}' to put them back",
+                self.info.paths.len(),
+                self.info.id
+            );
+        }
+    }
+}
+
+/// Copies a file, symlink or folder, keeping symlinks as symlinks.
+pub fn copy_preserving_links(source: &Path, destination: &Path) -> Result<()> {
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let metadata = source.symlink_metadata()?;
+    if metadata.is_symlink() {
+        unix_fs::symlink(fs::read_link(source)?, destination)?;
+    } else if metadata.is_dir() {
+        fs::create_dir_all(destination)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_preserving_links(&entry.path(), &destination.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(source, destination)?;
+    }
+    Ok(())
+}
+
+/// UTC timestamp such as `20261018T093000Z`, with a suffix if that id is taken.
+fn new_backup_id(root: &Path) -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let (days, time) = (seconds / 86_400, seconds % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    let id = format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    );
+    let backups = backups_dir(root);
+    if !backups.join(&id).exists() {
+        return id;
+    }
+    (2..)
+        .map(|n| format!("{id}-{n}"))
+        .find(|candidate| !backups.join(candidate).exists())
+        .unwrap_or(id)
+}
+
+/// Returns the backups under `root`, oldest first.
+pub fn list_backups(root: &Path) -> Result<Vec<BackupInfo>> {
+    Ok(read_backups(root)?
+        .into_iter()
+        .map(|(_, info)| info)
+        .collect())
+}
+
+/// The backups under `root` with the folder each one is kept in, oldest first.
+fn read_backups(root: &Path) -> Result<Vec<(PathBuf, BackupInfo)>> {
+    let backups = backups_dir(root);
+    if !backups.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut infos = Vec::new();
+    for entry in fs::read_dir(&backups)? {
+        let backup_dir = entry?.path();
+        let index = backup_dir.join(BACKUP_INDEX);
+        if index.is_file() {
+            let info: BackupInfo = serde_json::from_str(&fs::read_to_string(&index)?)
+                .with_context(|| format!("Failed to parse {}", index.display()))?;
+            infos.push((backup_dir, info));
+        }
+    }
+    infos.sort_by(|(_, a), (_, b)| a.id.cmp(&b.id));
+    Ok(infos)
+}
+
+/// Puts the files of backup `id`, kept in `store`, back in place under `root`,
+/// replacing what is there now. The files are restored in one transaction, and what
+/// they replace is backed up first.
+pub fn restore_backup(store: &Path, root: &Path, id: &str) -> Result<BackupInfo> {
+    let Some((backup_dir, info)) = read_backups(store)?
+        .into_iter()
+        .find(|(_, info)| info.id == id)
+    else {
+        bail!("No backup named '{id}'. Run 'ai-rules restore --list' to see the backups");
+    };
+    let files = backup_dir.join(FILES_DIR);
+    let mut plan = Plan::default();
+    for path in &info.paths {
+        // backup.json can be edited, so a path could point anywhere
+        if !is_inside_root(path) {
+            bail!(
+                "Backup {id} lists {}, which is not a relative path inside {}",
+                path.display(),
+                root.display()
+            );
+        }
+        let target = root.join(path);
+        plan.push(Operation::delete(&target))?;
+        plan_copy(&mut plan, &files.join(path), &target)
+            .with_context(|| format!("Failed to restore {}", path.display()))?;
+    }
+    transaction::apply_with_backup_in(store, root, &plan, "restore")?;
+    Ok(info)
+}
+
+/// True for a relative path that only names entries below the root, without `..`.
+fn is_inside_root(path: &Path) -> bool {
+    path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Plans recreating the file, symlink or folder at `source` at `destination`.
+fn plan_copy(plan: &mut Plan, source: &Path, destination: &Path) -> Result<()> {
+    let metadata = source.symlink_metadata()?;
+    if metadata.is_symlink() {
+        plan.push(Operation::symlink(destination, fs::read_link(source)?))
+    } else if metadata.is_dir() {
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            plan_copy(plan, &entry.path(), &destination.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        plan.push(Operation::write(destination, fs::read(source)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::helpers::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_backup_and_restore() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        create_file(root, ".cursor/rules/team.mdc", "Hand written");
+        create_file(root, "CLAUDE.md", "Notes");
+        create_file(root, "ai-rules/.generated-ai-rules/body.md", "Generated");
+
+        let mut backup = Backup::start(root, root, "clean").unwrap();
+        backup.save(&root.join(".cursor/rules")).unwrap();
+        backup.save(&root.join(".cursor/rules/team.mdc")).unwrap();
+        backup
+            .save(&root.join("ai-rules/.generated-ai-rules/body.md"))
+            .unwrap();
+        backup.finish();
+        fs::remove_dir_all(root.join(".cursor")).unwrap();
+
+        let backups = list_backups(root).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].command, "clean");
+        assert_eq!(backups[0].paths, vec![PathBuf::from(".cursor/rules")]);
+
+        restore_backup(root, root, &backups[0].id).unwrap();
+        assert_file_content(root, ".cursor/rules/team.mdc", "Hand written");
+        assert!(restore_backup(root, root, "missing").is_err());
+        assert!(Backup::start(&root.join(".cursor"), &root.join(".cursor"), "clean").is_none());
+    }
+
+    #[test]
+    fn test_with_backup_and_restore() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        create_file(root, ".cursor/rules/team.mdc", "Hand written");
+        create_file(root, "CLAUDE.md", "Notes");
+        create_file(root, "ai-rules/.generated-ai-rules/body.md", "Generated");
+        // A file where the plan needs a folder, so the first apply fails
+        create_file(root, "docs", "Not a folder");
+
+        let mut failing = Plan::default();
+        failing
+            .extend([
+                Operation::write(root.join("CLAUDE.md"), "Replaced"),
+                Operation::write(root.join("docs/AGENTS.md"), "Rules"),
+            ])
+            .unwrap();
+        assert!(transaction::apply_with_backup(root, &failing, "generate").is_err());
+        let partial = list_backups(root).unwrap();
+        assert_eq!(partial[0].paths, vec![PathBuf::from("CLAUDE.md")]);
+        restore_backup(root, root, &partial[0].id).unwrap();
+        assert_file_content(root, "CLAUDE.md", "Notes");
+        fs::remove_dir_all(backups_dir(root)).unwrap();
+
+        let mut clean = Plan::default();
+        clean
+            .extend([
+                Operation::delete(root.join(".cursor/rules")),
+                Operation::write(root.join("CLAUDE.md"), "Notes"),
+                Operation::delete(root.join("ai-rules/.generated-ai-rules/body.md")),
+            ])
+            .unwrap();
+        transaction::apply_with_backup(root, &clean, "clean").unwrap();
+        let backups = list_backups(root).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].command, "clean");
+        assert_eq!(backups[0].paths, vec![PathBuf::from(".cursor/rules")]);
+
+        restore_backup(root, root, &backups[0].id).unwrap();
+        assert_file_content(root, ".cursor/rules/team.mdc", "Hand written");
+        assert!(restore_backup(root, root, "missing").is_err());
+    }
+
+    #[test]
+    fn test_restore_rejects_paths_outside_the_project() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("project");
+        create_file(&root, "ai-rules/test.md", "Rule");
+        create_file(&root, "CLAUDE.md", "Current");
+        create_file(temp_dir.path(), "outside.md", "Outside");
+
+        for path in [
+            "../outside.md",
+            temp_dir.path().join("outside.md").to_str().unwrap(),
+        ] {
+            let backup_dir = backups_dir(&root).join("edited");
+            create_file(&backup_dir, "files/CLAUDE.md", "Backed up");
+            let info = BackupInfo {
+                id: "edited".to_string(),
+                command: "generate".to_string(),
+                paths: vec![PathBuf::from("CLAUDE.md"), PathBuf::from(path)],
+            };
+            create_file(
+                &backup_dir,
+                BACKUP_INDEX,
+                &serde_json::to_string(&info).unwrap(),
+            );
+
+            let error = restore_backup(&root, &root, "edited").unwrap_err();
+            assert!(
+                error.to_string().contains("not a relative path inside"),
+                "{error}"
+            );
+            assert_file_content(&root, "CLAUDE.md", "Current");
+            assert_file_content(temp_dir.path(), "outside.md", "Outside");
+        }
+    }
+
+    #[test]
+    fn test_restore_changes_nothing_when_a_file_is_missing() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        create_file(root, "ai-rules/test.md", "Rule");
+        create_file(root, "CLAUDE.md", "Current");
+        create_file(root, "AGENTS.md", "Current");
+        let backup_dir = backups_dir(root).join("partial");
+        create_file(&backup_dir, "files/CLAUDE.md", "Backed up");
+        let info = BackupInfo {
+            id: "partial".to_string(),
+            command: "clean".to_string(),
+            paths: vec![PathBuf::from("CLAUDE.md"), PathBuf::from("AGENTS.md")],
+        };
+        create_file(
+            &backup_dir,
+            BACKUP_INDEX,
+            &serde_json::to_string(&info).unwrap(),
+        );
+
+        assert!(restore_backup(root, root, "partial").is_err());
+        assert_file_content(root, "CLAUDE.md", "Current");
+        assert_file_content(root, "AGENTS.md", "Current");
+    }
+}
@@ -0,0 +1,187 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Lines of context shown around each change.
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Line diff from the longest common subsequence. Files here are small, so the quadratic
+/// table is fine.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(Line::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(Line::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(new[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// Returns a unified diff from `old` to `new`, or an empty string if they're equal.
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let lines = diff_lines(&old_lines, &new_lines);
+    if lines.iter().all(|line| matches!(line, Line::Same(_))) {
+        return String::new();
+    }
+
+    let mut output = format!("--- {old_label}\n+++ {new_label}\n");
+    let changes: Vec<usize> = (0..lines.len())
+        .filter(|&index| !matches!(lines[index], Line::Same(_)))
+        .collect();
+
+    let mut hunk_start = 0;
+    while hunk_start < changes.len() {
+        // Group changes whose context overlaps into one hunk
+        let mut hunk_end = hunk_start;
+        while hunk_end + 1 < changes.len()
+            && changes[hunk_end + 1] - changes[hunk_end] <= 2 * CONTEXT_LINES
+        {
+            hunk_end += 1;
+        }
+        let first = changes[hunk_start].saturating_sub(CONTEXT_LINES);
+        let last = (changes[hunk_end] + CONTEXT_LINES).min(lines.len() - 1);
+
+        let count =
+            |keep: fn(&Line) -> bool, range: &[Line]| range.iter().filter(|l| keep(l)).count();
+        let old_start = count(|l| !matches!(l, Line::Added(_)), &lines[..first]) + 1;
+        let new_start = count(|l| !matches!(l, Line::Removed(_)), &lines[..first]) + 1;
+        let old_len = count(|l| !matches!(l, Line::Added(_)), &lines[first..=last]);
+        let new_len = count(|l| !matches!(l, Line::Removed(_)), &lines[first..=last]);
+        // An empty range starts at the line before it, as in `diff -u`
+        let old_start = if old_len == 0 {
+            old_start - 1
+        } else {
+            old_start
+        };
+        let new_start = if new_len == 0 {
+            new_start - 1
+        } else {
+            new_start
+        };
+        output.push_str(&format!(
+            "@@ -{old_start},{old_len} +{new_start},{new_len} @@\n"
+        ));
+        for line in &lines[first..=last] {
+            let (prefix, text) = match line {
+                Line::Same(text) => (' ', text),
+                Line::Removed(text) => ('-', text),
+                Line::Added(text) => ('+', text),
+            };
+            output.push(prefix);
+            output.push_str(text);
+            output.push('\n');
+        }
+        hunk_start = hunk_end + 1;
+    }
+    output
+}
+
+/// Leaf values of `value` keyed by JSON pointer. Arrays and empty objects are leaves.
+fn json_leaves(value: &Value, pointer: String, leaves: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let key = key.replace('~', "~0").replace('/', "~1");
+                json_leaves(child, format!("{pointer}/{key}"), leaves);
+            }
+        }
+        _ => {
+            leaves.insert(pointer, value.clone());
+        }
+    }
+}
+
+/// Lists the keys added (`+`), removed (`-`) and changed (`~`) from `old` to `new`, one
+/// line per JSON pointer.
+pub fn json_key_diff(old: &Value, new: &Value) -> Vec<String> {
+    let (mut old_leaves, mut new_leaves) = (BTreeMap::new(), BTreeMap::new());
+    json_leaves(old, String::new(), &mut old_leaves);
+    json_leaves(new, String::new(), &mut new_leaves);
+
+    let mut pointers: Vec<&String> = old_leaves.keys().chain(new_leaves.keys()).collect();
+    pointers.sort();
+    pointers.dedup();
+    pointers
+        .into_iter()
+        .filter_map(
+            |pointer| match (old_leaves.get(pointer), new_leaves.get(pointer)) {
+                (Some(old), Some(new)) if old == new => None,
+                (Some(old), Some(new)) => Some(format!("~ {pointer}: {old} -> {new}")),
+                (Some(old), None) => Some(format!("- {pointer}: {old}")),
+                (None, Some(new)) => Some(format!("+ {pointer}: {new}")),
+                (None, None) => None,
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\n";
+        let new = "one\ntwo\nthree\nfour\n5\nsix\nseven\neight\nnine\nten\n";
+
+        assert_eq!(unified_diff(old, old, "a", "b"), "");
+        assert_eq!(
+            unified_diff("", "new\n", "a", "b"),
+            "--- a\n+++ b\n@@ -0,0 +1,1 @@\n+new\n"
+        );
+        assert_eq!(
+            unified_diff(old, new, "a", "b"),
+            "--- a\n+++ b\n@@ -2,8 +2,9 @@\n two\n three\n four\n-five\n+5\n six\n seven\n eight\n nine\n+ten\n"
+        );
+    }
+
+    #[test]
+    fn test_json_key_diff() {
+        let old = serde_json::json!({
+            "theme": "dark",
+            "mcpServers": {"db": {"command": "db"}, "old": {"command": "old"}}
+        });
+        let new = serde_json::json!({
+            "theme": "dark",
+            "mcpServers": {"db": {"command": "db2"}, "a/b": {"args": ["x"]}}
+        });
+
+        assert_eq!(json_key_diff(&old, &old), Vec::<String>::new());
+        assert_eq!(
+            json_key_diff(&old, &new),
+            vec![
+                "+ /mcpServers/a~1b/args: [\"x\"]",
+                "~ /mcpServers/db/command: \"db\" -> \"db2\"",
+                "- /mcpServers/old/command: \"old\"",
+            ]
+        );
+    }
+}
@@ -1,10 +1,11 @@
-use crate::constants::{AGENTS_MD_FILENAME, AI_RULE_SOURCE_DIR};
+use crate::config::LinkMode;
+use crate::constants::{AGENTS_MD_FILENAME, AI_RULE_SOURCE_DIR, GENERATED_COPY_MARKER};
 use crate::operations::body_generator::inlined_agents_relative_path;
-use anyhow::Result;
+use crate::utils::plan::{Operation, Plan};
+use anyhow::{bail, Context, Result};
 
 use std::collections::HashMap;
 use std::fs;
-use std::os::unix::fs as unix_fs;
 use std::path::{Path, PathBuf};
 
 // Re-export DirectoryFilter so existing callers don't need to change their imports
@@ -42,121 +43,105 @@ pub fn find_files_by_extension(dir: &Path, extension: &str) -> Result<Vec<PathBu
     Ok(files)
 }
 
-pub fn create_relative_symlink(symlink_path: &Path, relative_target: &Path) -> Result<()> {
-    if let Some(parent) = symlink_path.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent)?;
-        }
+/// Replaces the symlink planned at `link` with a copy or hard links of what it
+/// points to. Folders are copied recursively and marked with `GENERATED_COPY_MARKER`.
+pub fn materialize_link(plan: &mut Plan, link: &Path, mode: LinkMode) -> Result<()> {
+    if mode == LinkMode::Symlink {
+        return Ok(());
     }
+    let target = plan.resolve(link)?;
+    if !plan.exists(&target)? {
+        bail!("Failed to resolve {}", link.display());
+    }
+    let mut operations = vec![Operation::delete(link)];
+    if plan.children(&target)?.is_some() {
+        copy_directory(plan, &target, link, mode, &mut operations)?;
+        operations.push(Operation::write(link.join(GENERATED_COPY_MARKER), ""));
+    } else {
+        copy_file(plan, &target, link, mode, &mut operations)?;
+    }
+    plan.extend(operations)
+}
 
-    if symlink_path.exists() || symlink_path.is_symlink() {
-        fs::remove_file(symlink_path)?;
+fn copy_directory(
+    plan: &Plan,
+    source: &Path,
+    destination: &Path,
+    mode: LinkMode,
+    operations: &mut Vec<Operation>,
+) -> Result<()> {
+    for name in plan.children(source)?.unwrap_or_default() {
+        // Follow symlinks inside the folder, such as rendered skill resources
+        let entry_source = plan.resolve(&source.join(&name))?;
+        let entry_destination = destination.join(&name);
+        if plan.children(&entry_source)?.is_some() {
+            copy_directory(plan, &entry_source, &entry_destination, mode, operations)?;
+        } else {
+            copy_file(plan, &entry_source, &entry_destination, mode, operations)?;
+        }
     }
+    Ok(())
+}
 
-    unix_fs::symlink(relative_target, symlink_path)?;
+fn copy_file(
+    plan: &Plan,
+    source: &Path,
+    destination: &Path,
+    mode: LinkMode,
+    operations: &mut Vec<Operation>,
+) -> Result<()> {
+    if mode == LinkMode::Hardlink {
+        operations.push(Operation::HardLink {
+            path: destination.to_path_buf(),
+            source: source.to_path_buf(),
+        });
+        return Ok(());
+    }
+    let contents = plan
+        .read(source)?
+        .with_context(|| format!("Failed to copy {}", source.display()))?;
+    operations.push(Operation::write(destination, contents));
     Ok(())
 }
 
 pub fn calculate_relative_path(from_path: &Path, target_relative_to_root: &Path) -> PathBuf {
+    if target_relative_to_root.is_absolute() {
+        return target_relative_to_root.to_path_buf();
+    }
     let slash_count = from_path.to_str().unwrap_or("").matches('/').count();
     let up_dirs = "../".repeat(slash_count);
     PathBuf::from(up_dirs + &target_relative_to_root.display().to_string())
 }
 
-pub fn create_symlink_to_agents_md(current_dir: &Path, output_path: &Path) -> Result<bool> {
-    let source_full_path = current_dir
-        .join(AI_RULE_SOURCE_DIR)
-        .join(AGENTS_MD_FILENAME);
-
-    if !source_full_path.exists() {
-        return Ok(false);
-    }
-
-    let link = current_dir.join(output_path);
+/// A symlink at `output_path` to `ai-rules/AGENTS.md`, or `None` if there is no
+/// such file.
+pub fn symlink_to_agents_md(current_dir: &Path, output_path: &Path) -> Option<Operation> {
     let source_relative = PathBuf::from(AI_RULE_SOURCE_DIR).join(AGENTS_MD_FILENAME);
-    let relative_source = calculate_relative_path(output_path, &source_relative);
-
-    create_relative_symlink(&link, &relative_source)?;
-
-    Ok(true)
-}
-
-pub fn check_agents_md_symlink(current_dir: &Path, symlink_path: &Path) -> Result<bool> {
-    if !symlink_path.is_symlink() {
-        return Ok(false);
-    }
-
-    let expected_target = current_dir
-        .join(AI_RULE_SOURCE_DIR)
-        .join(AGENTS_MD_FILENAME);
-    let actual_target = fs::read_link(symlink_path)?;
-
-    let resolved_target = if actual_target.is_absolute() {
-        actual_target
-    } else {
-        // For relative paths, resolve from the symlink's parent directory
-        let symlink_parent = symlink_path.parent().unwrap_or(current_dir);
-        symlink_parent.join(&actual_target)
-    };
-
-    // Canonicalize both paths to handle ".." components properly
-    let resolved_canonical = resolved_target.canonicalize().unwrap_or(resolved_target);
-    let expected_canonical = expected_target
-        .canonicalize()
-        .unwrap_or_else(|_| expected_target.clone());
-
-    Ok(resolved_canonical == expected_canonical && expected_target.exists())
-}
-
-pub fn create_symlink_to_inlined_file(current_dir: &Path, output_path: &Path) -> Result<bool> {
-    let inlined_relative = inlined_agents_relative_path();
-    let source_full_path = current_dir.join(&inlined_relative);
-
-    if !source_full_path.exists() {
-        return Ok(false);
+    if !current_dir.join(&source_relative).exists() {
+        return None;
     }
-
-    let link = current_dir.join(output_path);
-    let relative_source = calculate_relative_path(output_path, &inlined_relative);
-
-    create_relative_symlink(&link, &relative_source)?;
-
-    Ok(true)
+    let relative_source = calculate_relative_path(output_path, &source_relative);
+    Some(Operation::symlink(
+        current_dir.join(output_path),
+        relative_source,
+    ))
 }
 
-pub fn check_inlined_file_symlink(current_dir: &Path, symlink_path: &Path) -> Result<bool> {
-    if !symlink_path.is_symlink() {
-        return Ok(false);
-    }
-
-    let inlined_relative = inlined_agents_relative_path();
-    let expected_target = current_dir.join(&inlined_relative);
-    let actual_target = fs::read_link(symlink_path)?;
-
-    let resolved_target = if actual_target.is_absolute() {
-        actual_target
-    } else {
-        let symlink_parent = symlink_path.parent().unwrap_or(current_dir);
-        symlink_parent.join(&actual_target)
-    };
-
-    let resolved_canonical = resolved_target.canonicalize().unwrap_or(resolved_target);
-    let expected_canonical = expected_target
-        .canonicalize()
-        .unwrap_or_else(|_| expected_target.clone());
-
-    Ok(resolved_canonical == expected_canonical && expected_target.exists())
+/// A symlink at `output_path` to the generated file with every rule inlined.
+pub fn symlink_to_inlined_file(current_dir: &Path, output_path: &Path) -> Operation {
+    let relative_source = calculate_relative_path(output_path, &inlined_agents_relative_path());
+    Operation::symlink(current_dir.join(output_path), relative_source)
 }
 
-pub fn write_directory_files(files_to_write: &HashMap<PathBuf, String>) -> Result<()> {
-    for (file_path, content) in files_to_write {
-        if let Some(parent) = file_path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        fs::write(file_path, content)?;
-    }
-
-    Ok(())
+/// Writes for each file in `files`.
+pub fn write_operations(files: HashMap<PathBuf, String>) -> Vec<Operation> {
+    let mut operations: Vec<Operation> = files
+        .into_iter()
+        .map(|(path, content)| Operation::write(path, content))
+        .collect();
+    // Sorted so the plan reads the same on every run
+    operations.sort_by(|a, b| a.path().cmp(b.path()));
+    operations
 }
 
 pub fn traverse_project_directories<F>(
@@ -263,39 +248,11 @@ where
     Ok(())
 }
 
-pub fn check_directory_exact_match(
-    dir: &Path,
-    expected_files: &HashMap<PathBuf, String>,
-) -> Result<bool> {
-    if !dir.exists() {
-        return Ok(false);
-    }
-
-    let actual_files: Vec<_> = fs::read_dir(dir)?
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.path().is_file())
-        .collect();
-
-    if actual_files.len() != expected_files.len() {
-        return Ok(false);
-    }
-
-    for (file_path, expected_content) in expected_files {
-        if !file_path.exists() {
-            return Ok(false);
-        }
-        let actual_content = fs::read_to_string(file_path)?;
-        if actual_content != *expected_content {
-            return Ok(false);
-        }
-    }
-
-    Ok(true)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::plan::PlannedChange;
+    use crate::utils::test_utils::helpers::*;
     use std::fs;
     use std::os::unix::fs::symlink;
     use tempfile::TempDir;
@@ -375,6 +332,22 @@ mod tests {
         assert_eq!(filenames[8], "zebra.md");
     }
 
+    #[test]
+    fn test_write_operations_are_sorted() {
+        let files = HashMap::from([
+            (PathBuf::from("/p/subdir/file2.txt"), "content2".to_string()),
+            (PathBuf::from("/p/file1.txt"), "content1".to_string()),
+        ]);
+
+        assert_eq!(
+            write_operations(files),
+            vec![
+                Operation::write("/p/file1.txt", "content1"),
+                Operation::write("/p/subdir/file2.txt", "content2"),
+            ]
+        );
+    }
+
     #[test]
     fn test_write_directory_files() {
         let temp_dir = TempDir::new().unwrap();
@@ -384,37 +357,38 @@ mod tests {
         files_to_write.insert(temp_path.join("file1.txt"), "content1".to_string());
         files_to_write.insert(temp_path.join("subdir/file2.txt"), "content2".to_string());
 
-        write_directory_files(&files_to_write).unwrap();
-
-        assert!(temp_path.join("file1.txt").exists());
-        assert!(temp_path.join("subdir/file2.txt").exists());
-
-        let content1 = fs::read_to_string(temp_path.join("file1.txt")).unwrap();
-        assert_eq!(content1, "content1");
+        apply_operations(temp_path, write_operations(files_to_write));
 
-        let content2 = fs::read_to_string(temp_path.join("subdir/file2.txt")).unwrap();
-        assert_eq!(content2, "content2");
+        assert_file_content(temp_path, "file1.txt", "content1");
+        assert_file_content(temp_path, "subdir/file2.txt", "content2");
     }
 
     #[test]
     fn test_check_directory_exact_match() {
         let temp_dir = TempDir::new().unwrap();
         let temp_path = temp_dir.path();
+        let output_dir = temp_path.join("output");
 
         let mut expected_files = HashMap::new();
-        expected_files.insert(temp_path.join("file1.txt"), "content1".to_string());
-        expected_files.insert(temp_path.join("file2.txt"), "content2".to_string());
-
-        fs::write(temp_path.join("file1.txt"), "content1").unwrap();
-        fs::write(temp_path.join("file2.txt"), "content2").unwrap();
+        expected_files.insert(output_dir.join("file1.txt"), "content1".to_string());
+        expected_files.insert(output_dir.join("file2.txt"), "content2".to_string());
+        // The directory is replaced by exactly the expected files
+        let replace_directory = || {
+            let mut operations = vec![Operation::delete(&output_dir)];
+            operations.extend(write_operations(expected_files.clone()));
+            planned_changes(operations)
+        };
 
-        assert!(check_directory_exact_match(temp_path, &expected_files).unwrap());
+        create_file(temp_path, "output/file1.txt", "content1");
+        create_file(temp_path, "output/file2.txt", "content2");
+        assert!(replace_directory().is_empty());
 
-        fs::write(temp_path.join("file2.txt"), "different_content").unwrap();
-        assert!(!check_directory_exact_match(temp_path, &expected_files).unwrap());
+        create_file(temp_path, "output/file2.txt", "different_content");
+        assert!(!replace_directory().is_empty());
 
-        fs::write(temp_path.join("extra_file.txt"), "extra").unwrap();
-        assert!(!check_directory_exact_match(temp_path, &expected_files).unwrap());
+        create_file(temp_path, "output/file2.txt", "content2");
+        create_file(temp_path, "output/extra_file.txt", "extra");
+        assert!(!replace_directory().is_empty());
     }
 
     #[test]
@@ -511,49 +485,49 @@ mod tests {
     }
 
     #[test]
-    fn test_create_symlink_to_agents_md_success() {
+    fn test_symlink_to_agents_md_success() {
         let temp_dir = TempDir::new().unwrap();
         let temp_path = temp_dir.path();
+        create_file(temp_path, "ai-rules/AGENTS.md", "# Test content");
 
-        fs::create_dir_all(temp_path.join(AI_RULE_SOURCE_DIR)).unwrap();
-        fs::write(
-            temp_path.join(AI_RULE_SOURCE_DIR).join(AGENTS_MD_FILENAME),
-            "# Test content",
-        )
-        .unwrap();
-
-        let result = create_symlink_to_agents_md(temp_path, Path::new(AGENTS_MD_FILENAME)).unwrap();
-
-        assert!(result);
-        let symlink_path = temp_path.join(AGENTS_MD_FILENAME);
-        assert!(symlink_path.is_symlink());
-
-        let content = fs::read_to_string(&symlink_path).unwrap();
-        assert_eq!(content, "# Test content");
+        let operation = symlink_to_agents_md(temp_path, Path::new(AGENTS_MD_FILENAME)).unwrap();
+        assert_eq!(
+            operation,
+            Operation::symlink(temp_path.join("AGENTS.md"), "ai-rules/AGENTS.md")
+        );
+        apply_operations(temp_path, [operation]);
+        assert_file_content(temp_path, "AGENTS.md", "# Test content");
     }
 
     #[test]
-    fn test_create_symlink_to_agents_md_nested_path() {
+    fn test_symlink_to_agents_md_nested_path() {
         let temp_dir = TempDir::new().unwrap();
         let temp_path = temp_dir.path();
-
-        fs::create_dir_all(temp_path.join(AI_RULE_SOURCE_DIR)).unwrap();
-        fs::write(
-            temp_path.join(AI_RULE_SOURCE_DIR).join(AGENTS_MD_FILENAME),
-            "# Nested test",
-        )
-        .unwrap();
+        create_file(temp_path, "ai-rules/AGENTS.md", "# Nested test");
 
         let nested_output = Path::new("nested/dir").join(AGENTS_MD_FILENAME);
-        let result = create_symlink_to_agents_md(temp_path, &nested_output).unwrap();
+        let operation = symlink_to_agents_md(temp_path, &nested_output).unwrap();
+        apply_operations(temp_path, [operation]);
 
-        assert!(result);
-        let symlink_path = temp_path.join(&nested_output);
-        assert!(symlink_path.exists());
-        assert!(symlink_path.is_symlink());
+        assert!(temp_path.join(&nested_output).is_symlink());
+        assert_file_content(temp_path, "nested/dir/AGENTS.md", "# Nested test");
+    }
 
-        let content = fs::read_to_string(&symlink_path).unwrap();
-        assert_eq!(content, "# Nested test");
+    #[test]
+    fn test_symlink_to_agents_md_missing_source() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(symlink_to_agents_md(temp_dir.path(), Path::new("CLAUDE.md")).is_none());
+    }
+
+    /// What relinking `output_path` to `ai-rules/AGENTS.md` would change.
+    fn agents_md_link_changes(current_dir: &Path, output_path: &Path) -> Vec<PlannedChange> {
+        let link = current_dir.join(output_path);
+        let mut operations = Vec::new();
+        if link.exists() || link.is_symlink() {
+            operations.push(Operation::delete(link));
+        }
+        operations.extend(symlink_to_agents_md(current_dir, output_path));
+        planned_changes(operations)
     }
 
     #[test]
@@ -561,10 +535,10 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let temp_path = temp_dir.path();
 
-        fs::write(temp_path.join("CLAUDE.md"), "regular file content").unwrap();
+        create_file(temp_path, "ai-rules/AGENTS.md", "# Source content");
+        create_file(temp_path, "CLAUDE.md", "regular file content");
 
-        let result = check_agents_md_symlink(temp_path, &temp_path.join("CLAUDE.md")).unwrap();
-        assert!(!result);
+        assert!(!agents_md_link_changes(temp_path, Path::new("CLAUDE.md")).is_empty());
     }
 
     #[test]
@@ -572,8 +546,9 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let temp_path = temp_dir.path();
 
-        let result = check_agents_md_symlink(temp_path, &temp_path.join("CLAUDE.md")).unwrap();
-        assert!(!result);
+        create_file(temp_path, "ai-rules/AGENTS.md", "# Source content");
+
+        assert!(!agents_md_link_changes(temp_path, Path::new("CLAUDE.md")).is_empty());
     }
 
     #[test]
@@ -581,47 +556,61 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let temp_path = temp_dir.path();
 
-        fs::create_dir_all(temp_path.join("ai-rules")).unwrap();
-        fs::write(temp_path.join("ai-rules/AGENTS.md"), "# Source content").unwrap();
-
-        let result = create_symlink_to_agents_md(temp_path, Path::new("CLAUDE.md"));
-        assert!(result.is_ok());
-
-        let symlink_path = temp_path.join("CLAUDE.md");
+        create_file(temp_path, "ai-rules/AGENTS.md", "# Source content");
+        apply_operations(
+            temp_path,
+            symlink_to_agents_md(temp_path, Path::new("CLAUDE.md")),
+        );
 
-        let result = check_agents_md_symlink(temp_path, &symlink_path).unwrap();
-        assert!(result);
+        assert!(agents_md_link_changes(temp_path, Path::new("CLAUDE.md")).is_empty());
     }
 
     #[test]
-    #[cfg(unix)]
     fn test_check_agents_md_symlink_wrong_target() {
         let temp_dir = TempDir::new().unwrap();
         let temp_path = temp_dir.path();
 
-        fs::create_dir_all(temp_path.join("ai-rules")).unwrap();
-        fs::write(temp_path.join("ai-rules/AGENTS.md"), "# Source content").unwrap();
-
-        fs::write(temp_path.join("wrong-target.md"), "# Wrong content").unwrap();
+        create_file(temp_path, "ai-rules/AGENTS.md", "# Source content");
+        create_file(temp_path, "wrong-target.md", "# Wrong content");
+        symlink("wrong-target.md", temp_path.join("CLAUDE.md")).unwrap();
 
-        let symlink_path = temp_path.join("CLAUDE.md");
-        symlink("wrong-target.md", &symlink_path).unwrap();
-
-        let result = check_agents_md_symlink(temp_path, &symlink_path).unwrap();
-        assert!(!result);
+        assert!(!agents_md_link_changes(temp_path, Path::new("CLAUDE.md")).is_empty());
     }
 
     #[test]
-    #[cfg(unix)]
     fn test_check_agents_md_symlink_missing_source() {
         let temp_dir = TempDir::new().unwrap();
         let temp_path = temp_dir.path();
 
-        let symlink_path = temp_path.join("CLAUDE.md");
-        symlink("ai-rules/AGENTS.md", &symlink_path).unwrap();
+        symlink("ai-rules/AGENTS.md", temp_path.join("CLAUDE.md")).unwrap();
+
+        assert!(!agents_md_link_changes(temp_path, Path::new("CLAUDE.md")).is_empty());
+    }
+
+    #[test]
+    fn test_materialize_link_copies_planned_folders() {
+        let temp_dir = TempDir::new().unwrap();
+        let temp_path = temp_dir.path();
+        create_file(temp_path, "ai-rules/skills/deploy/run.sh", "deploy");
+        let link = temp_path.join(".claude/skills/deploy");
+
+        let mut plan = Plan::default();
+        plan.extend([
+            Operation::write(temp_path.join("rendered/deploy/SKILL.md"), "Deploy"),
+            Operation::symlink(
+                temp_path.join("rendered/deploy/run.sh"),
+                "../../ai-rules/skills/deploy/run.sh",
+            ),
+            Operation::symlink(&link, "../../rendered/deploy"),
+        ])
+        .unwrap();
+        materialize_link(&mut plan, &link, LinkMode::Copy).unwrap();
+        crate::utils::transaction::apply(temp_path, &plan, None).unwrap();
 
-        let result = check_agents_md_symlink(temp_path, &symlink_path).unwrap();
-        assert!(!result);
+        assert!(!link.is_symlink());
+        assert_file_content(temp_path, ".claude/skills/deploy/SKILL.md", "Deploy");
+        assert_file_content(temp_path, ".claude/skills/deploy/run.sh", "deploy");
+        assert!(link.join(GENERATED_COPY_MARKER).is_file());
     }
 
     #[test]
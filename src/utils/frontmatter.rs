@@ -0,0 +1,285 @@
+use crate::utils::schema::{deserialize_reporting_unknown, KeyPath};
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::de::{DeserializeOwned, IgnoredAny};
+use std::path::Path;
+use std::sync::LazyLock;
+
+const UTF8_BOM: char = '\u{feff}';
+pub const YAML_DELIMITER: &str = "---";
+pub const TOML_DELIMITER: &str = "+++";
+
+/// serde_yaml appends the location to its messages; we report our own, file-relative one.
+static YAML_LOCATION_SUFFIX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r" at line \d+ column \d+$").expect("valid regex"));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontmatterFormat {
+    Yaml,
+    Toml,
+}
+
+impl FrontmatterFormat {
+    fn from_delimiter_line(line: &str) -> Option<Self> {
+        match line.trim_end() {
+            YAML_DELIMITER => Some(Self::Yaml),
+            TOML_DELIMITER => Some(Self::Toml),
+            _ => None,
+        }
+    }
+
+    fn delimiter(self) -> &'static str {
+        match self {
+            Self::Yaml => YAML_DELIMITER,
+            Self::Toml => TOML_DELIMITER,
+        }
+    }
+}
+
+/// The raw text between the frontmatter delimiters of a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frontmatter {
+    pub format: FrontmatterFormat,
+    pub raw: String,
+    /// 1-based line number in the file of the first line of `raw`
+    pub first_line: usize,
+}
+
+/// A file split into its optional frontmatter and the remaining body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitContent {
+    pub frontmatter: Option<Frontmatter>,
+    pub body: String,
+}
+
+/// Splits `content` into frontmatter and body.
+///
+/// The frontmatter must start on the first non-blank line with a line that is exactly
+/// `---` (YAML) or `+++` (TOML), and ends at the next line that is exactly the same
+/// delimiter. A UTF-8 BOM is removed and CRLF line endings are normalized to LF.
+pub fn split_frontmatter(content: &str, path: &Path) -> Result<SplitContent> {
+    let content = normalize_content(content);
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+
+    let Some(open_index) = lines.iter().position(|line| !line.trim().is_empty()) else {
+        return Ok(SplitContent {
+            frontmatter: None,
+            body: String::new(),
+        });
+    };
+
+    let Some(format) = FrontmatterFormat::from_delimiter_line(lines[open_index]) else {
+        return Ok(SplitContent {
+            frontmatter: None,
+            body: lines[open_index..].concat(),
+        });
+    };
+
+    let close_index = lines[open_index + 1..]
+        .iter()
+        .position(|line| line.trim_end() == format.delimiter())
+        .map(|offset| open_index + 1 + offset)
+        .ok_or_else(|| {
+            anyhow!(
+                "{}:{}:1: frontmatter opened with '{}' is never closed",
+                path.display(),
+                open_index + 1,
+                format.delimiter()
+            )
+        })?;
+
+    Ok(SplitContent {
+        frontmatter: Some(Frontmatter {
+            format,
+            raw: lines[open_index + 1..close_index].concat(),
+            first_line: open_index + 2,
+        }),
+        body: lines[close_index + 1..].concat(),
+    })
+}
+
+/// Returns true if `content` starts with a `---` or `+++` delimiter line.
+pub fn has_frontmatter(content: &str) -> bool {
+    normalize_content(content)
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .and_then(FrontmatterFormat::from_delimiter_line)
+        .is_some()
+}
+
+/// Returns how many lines of `content` (including leading blank lines and both
+/// delimiters) belong to the frontmatter block, or 0 if there is no closed block.
+pub fn frontmatter_line_count(content: &str) -> usize {
+    split_frontmatter(content, Path::new(""))
+        .ok()
+        .and_then(|split| split.frontmatter)
+        .map(|frontmatter| frontmatter.first_line + frontmatter.raw.lines().count())
+        .unwrap_or(0)
+}
+
+/// Checks that the frontmatter of `content`, if any, is well-formed YAML or TOML.
+pub fn check_frontmatter_syntax(content: &str, path: &Path) -> Result<()> {
+    if let Some(frontmatter) = split_frontmatter(content, path)?.frontmatter {
+        frontmatter.deserialize::<IgnoredAny>(path)?;
+    }
+    Ok(())
+}
+
+impl Frontmatter {
+    /// Deserializes the frontmatter, reporting errors as `file:line:column: message`.
+    pub fn deserialize<T: DeserializeOwned>(&self, path: &Path) -> Result<T> {
+        self.deserialize_reporting_unknown(path)
+            .map(|(value, _)| value)
+    }
+
+    /// Like [`deserialize`](Self::deserialize), also returning the keys `T` doesn't know.
+    pub fn deserialize_reporting_unknown<T: DeserializeOwned>(
+        &self,
+        path: &Path,
+    ) -> Result<(T, Vec<KeyPath>)> {
+        match self.format {
+            FrontmatterFormat::Yaml => {
+                deserialize_reporting_unknown(serde_yaml::Deserializer::from_str(&self.raw))
+                    .map_err(|error| {
+                        let (line, column) = error
+                            .location()
+                            .map(|location| (location.line(), location.column()))
+                            .unwrap_or((1, 1));
+                        let message = error.to_string();
+                        let message = YAML_LOCATION_SUFFIX.replace(&message, "");
+                        self.error_at(path, line, column, "YAML", &message)
+                    })
+            }
+            FrontmatterFormat::Toml => {
+                deserialize_reporting_unknown(toml::Deserializer::new(&self.raw)).map_err(|error| {
+                    let (line, column) = error
+                        .span()
+                        .map(|span| line_and_column(&self.raw, span.start))
+                        .unwrap_or((1, 1));
+                    self.error_at(path, line, column, "TOML", error.message())
+                })
+            }
+        }
+    }
+
+    fn error_at(
+        &self,
+        path: &Path,
+        line: usize,
+        column: usize,
+        format_name: &str,
+        message: &str,
+    ) -> anyhow::Error {
+        anyhow!(
+            "{}:{}:{}: invalid {} frontmatter: {}",
+            path.display(),
+            self.first_line + line.saturating_sub(1),
+            column,
+            format_name,
+            message
+        )
+    }
+}
+
+fn normalize_content(content: &str) -> String {
+    content
+        .strip_prefix(UTF8_BOM)
+        .unwrap_or(content)
+        .replace("\r\n", "\n")
+}
+
+/// Converts a byte offset into a 1-based line and column.
+fn line_and_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |index| index + 1) + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Meta {
+        description: String,
+    }
+
+    fn split(content: &str) -> SplitContent {
+        split_frontmatter(content, Path::new("rule.md")).unwrap()
+    }
+
+    #[test]
+    fn test_split_ignores_dashes_inside_values_and_body() {
+        let result = split("---\ndescription: a---b\n---\nIntro\n---\nMore\n");
+
+        let frontmatter = result.frontmatter.unwrap();
+        assert_eq!(frontmatter.raw, "description: a---b\n");
+        assert_eq!(frontmatter.first_line, 2);
+        assert_eq!(result.body, "Intro\n---\nMore\n");
+    }
+
+    #[test]
+    fn test_split_handles_crlf_and_bom() {
+        let result = split("\u{feff}---\r\ndescription: Test\r\n---\r\nBody\r\n");
+
+        assert_eq!(result.frontmatter.unwrap().raw, "description: Test\n");
+        assert_eq!(result.body, "Body\n");
+        assert!(has_frontmatter("\u{feff}---\r\n"));
+    }
+
+    #[test]
+    fn test_split_without_frontmatter() {
+        let result = split("\n# Title\n---\n");
+
+        assert!(result.frontmatter.is_none());
+        assert_eq!(result.body, "# Title\n---\n");
+        assert_eq!(frontmatter_line_count("# Title\n"), 0);
+    }
+
+    #[test]
+    fn test_split_unclosed_frontmatter_errors() {
+        let error = split_frontmatter("\n---\ndescription: x\n", Path::new("rule.md"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("rule.md:2:1:"), "{error}");
+    }
+
+    #[test]
+    fn test_toml_frontmatter() {
+        let result = split("+++\ndescription = \"Toml rule\"\n+++\nBody\n");
+        let frontmatter = result.frontmatter.unwrap();
+
+        assert_eq!(frontmatter.format, FrontmatterFormat::Toml);
+        let meta: Meta = frontmatter.deserialize(Path::new("rule.md")).unwrap();
+        assert_eq!(meta.description, "Toml rule");
+        assert_eq!(frontmatter_line_count("+++\na = 1\n+++\nBody\n"), 3);
+    }
+
+    #[test]
+    fn test_deserialize_errors_report_file_line_and_column() {
+        let yaml = split("\n---\nname: x\ndescription: [unclosed\n---\n")
+            .frontmatter
+            .unwrap();
+        let error = yaml
+            .deserialize::<Meta>(Path::new("rule.md"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("rule.md:"), "{error}");
+        assert!(!error.contains(" at line "), "{error}");
+
+        let toml = split("+++\ntitle = \"x\"\ndescription = \n+++\n")
+            .frontmatter
+            .unwrap();
+        let error = toml
+            .deserialize::<Meta>(Path::new("rule.md"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("rule.md:3:"), "{error}");
+        assert!(error.contains("invalid TOML frontmatter"), "{error}");
+
+        assert!(check_frontmatter_syntax("---\n: :\n  - [\n---\n", Path::new("c.md")).is_err());
+        assert!(check_frontmatter_syntax("No frontmatter", Path::new("c.md")).is_ok());
+    }
+}
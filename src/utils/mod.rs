@@ -1,7 +1,14 @@
+pub mod annotations;
+pub mod backup;
+pub mod diff_utils;
 pub mod dir_filter;
 pub mod file_utils;
+pub mod frontmatter;
 pub mod git_utils;
 pub mod goose_utils;
+pub mod plan;
 pub mod print_utils;
 pub mod prompt_utils;
+pub mod schema;
 pub mod test_utils;
+pub mod transaction;
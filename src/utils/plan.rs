@@ -0,0 +1,725 @@
+//! Declarative changes to a project.
+//!
+//! Generators don't write anything themselves: they return [`Operation`]s, which a
+//! [`Plan`] applies in order to an in-memory view of the disk. Reading through the
+//! plan sees the project as it will be, so `status`, `diff` and `generate --dry-run`
+//! compare that with the disk without touching it, and
+//! [`transaction::apply`](crate::utils::transaction::apply) writes only what differs.
+
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsString;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, Path, PathBuf};
+
+/// One change to a file, symlink or folder. Paths are absolute.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// Writes a file. An existing symlink is written through, unless an earlier
+    /// operation deleted it
+    Write {
+        path: PathBuf,
+        contents: Vec<u8>,
+    },
+    Symlink {
+        path: PathBuf,
+        target: PathBuf,
+    },
+    /// A hard link to `source`, for `link_mode: hardlink`
+    HardLink {
+        path: PathBuf,
+        source: PathBuf,
+    },
+    /// Removes the keys at the `remove` JSON pointers, then sets the `set` ones,
+    /// keeping the rest of a JSON file the user also edits
+    MergeJson {
+        path: PathBuf,
+        remove: Vec<String>,
+        set: Vec<(String, Value)>,
+    },
+    /// Removes a file, symlink or folder
+    Delete {
+        path: PathBuf,
+    },
+}
+
+impl Operation {
+    pub fn write(path: impl Into<PathBuf>, contents: impl Into<Vec<u8>>) -> Self {
+        Operation::Write {
+            path: path.into(),
+            contents: contents.into(),
+        }
+    }
+
+    pub fn symlink(path: impl Into<PathBuf>, target: impl Into<PathBuf>) -> Self {
+        Operation::Symlink {
+            path: path.into(),
+            target: target.into(),
+        }
+    }
+
+    pub fn delete(path: impl Into<PathBuf>) -> Self {
+        Operation::Delete { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            Operation::Write { path, .. }
+            | Operation::Symlink { path, .. }
+            | Operation::HardLink { path, .. }
+            | Operation::MergeJson { path, .. }
+            | Operation::Delete { path } => path,
+        }
+    }
+}
+
+/// A file, symlink or folder as it is on disk or will be once a plan is applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Snapshot {
+    File(Vec<u8>),
+    Symlink(PathBuf),
+    /// Everything inside, keyed by path relative to the folder
+    Directory(BTreeMap<PathBuf, Snapshot>),
+}
+
+impl Snapshot {
+    /// What is at `path` on disk, without following a symlink there.
+    pub fn read(path: &Path) -> Result<Option<Snapshot>> {
+        let Ok(metadata) = path.symlink_metadata() else {
+            return Ok(None);
+        };
+        let snapshot = if metadata.is_symlink() {
+            Snapshot::Symlink(fs::read_link(path)?)
+        } else if metadata.is_dir() {
+            let mut entries = BTreeMap::new();
+            for entry in fs::read_dir(path)? {
+                let entry = entry?;
+                if let Some(snapshot) = Snapshot::read(&entry.path())? {
+                    entries.insert(PathBuf::from(entry.file_name()), snapshot);
+                }
+            }
+            Snapshot::Directory(entries)
+        } else {
+            Snapshot::File(fs::read(path)?)
+        };
+        Ok(Some(snapshot))
+    }
+}
+
+/// A path the plan changes. `before` is `None` for a path that is created and
+/// `after` for one that is removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedChange {
+    pub path: PathBuf,
+    pub before: Option<Snapshot>,
+    pub after: Option<Snapshot>,
+}
+
+/// What the plan puts at a path.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    File(Vec<u8>),
+    Symlink(PathBuf),
+    HardLink(PathBuf),
+    Removed,
+}
+
+/// One entry to replace when applying a plan: whatever is at `path` on disk is
+/// removed, then `after` is put there if it is set.
+pub(crate) struct Step {
+    pub path: PathBuf,
+    pub after: Option<Node>,
+}
+
+/// Operations applied to an in-memory view of the disk.
+#[derive(Debug, Default)]
+pub struct Plan {
+    nodes: BTreeMap<PathBuf, Node>,
+    /// Outputs generate can recreate exactly, so they aren't backed up
+    regenerable: BTreeSet<PathBuf>,
+}
+
+struct Comparison<'a> {
+    keep: Option<&'a Path>,
+    steps: Vec<Step>,
+    emptied: Vec<PathBuf>,
+}
+
+/// Symlinks followed before giving up, as in most kernels.
+const MAX_SYMLINKS: usize = 40;
+
+impl Plan {
+    pub fn push(&mut self, operation: Operation) -> Result<()> {
+        match operation {
+            Operation::Write { path, contents } => {
+                let path = self.write_target(&path)?;
+                self.insert(path, Node::File(contents));
+            }
+            Operation::Symlink { path, target } => self.insert(path, Node::Symlink(target)),
+            Operation::HardLink { path, source } => {
+                // A link to a planned link shares its source
+                let source = self.resolve(&source)?;
+                let source = match self.nodes.get(&source) {
+                    Some(Node::HardLink(original)) => original.clone(),
+                    _ => source,
+                };
+                self.insert(path, Node::HardLink(source));
+            }
+            Operation::MergeJson { path, remove, set } => self.merge_json(&path, remove, set)?,
+            Operation::Delete { path } => self.insert(path, Node::Removed),
+        }
+        Ok(())
+    }
+
+    pub fn extend(&mut self, operations: impl IntoIterator<Item = Operation>) -> Result<()> {
+        for operation in operations {
+            self.push(operation)?;
+        }
+        Ok(())
+    }
+
+    /// Leaves `path` out of backups, as generate recreates it exactly.
+    pub fn mark_regenerable(&mut self, path: PathBuf) {
+        self.regenerable.insert(path);
+    }
+
+    pub fn is_regenerable(&self, path: &Path) -> bool {
+        self.regenerable.contains(path)
+    }
+
+    fn insert(&mut self, path: PathBuf, node: Node) {
+        let descendants: Vec<PathBuf> = self
+            .nodes
+            .range(path.clone()..)
+            .skip_while(|(planned, _)| **planned == path)
+            .take_while(|(planned, _)| planned.starts_with(&path))
+            .map(|(planned, _)| planned.clone())
+            .collect();
+        for descendant in descendants {
+            self.nodes.remove(&descendant);
+        }
+        self.nodes.insert(path, node);
+    }
+
+    /// Where a write to `path` lands: the file a symlink on disk points to, unless
+    /// the plan replaced the symlink.
+    fn write_target(&self, path: &Path) -> Result<PathBuf> {
+        if self.nodes.contains_key(path) || !self.is_on_disk(path) || !path.is_symlink() {
+            return Ok(path.to_path_buf());
+        }
+        Ok(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()))
+    }
+
+    fn merge_json(
+        &mut self,
+        path: &Path,
+        remove: Vec<String>,
+        set: Vec<(String, Value)>,
+    ) -> Result<()> {
+        let original: Option<Value> = self
+            .read(path)?
+            .as_deref()
+            .and_then(|contents| serde_json::from_slice(contents).ok())
+            .filter(Value::is_object);
+        // Nothing to remove from a missing file or one that isn't a JSON object
+        if original.is_none() && set.is_empty() {
+            return Ok(());
+        }
+        // A file that isn't a JSON object can't be merged into, so it is replaced
+        let mut value = original
+            .clone()
+            .unwrap_or_else(|| Value::Object(Map::new()));
+        for pointer in &remove {
+            remove_json_pointer(&mut value, pointer);
+        }
+        for (pointer, new_value) in set {
+            set_json_pointer(&mut value, &pointer, new_value);
+        }
+        if original.as_ref() == Some(&value) {
+            return Ok(());
+        }
+        // Merging back to what is on disk keeps the file's own formatting
+        let target = self.resolve(path)?;
+        let replaced = matches!(self.nodes.get(&target), Some(Node::File(_)));
+        if replaced && target.parent().is_none_or(|parent| self.is_on_disk(parent)) {
+            let on_disk: Option<Value> = fs::read(&target)
+                .ok()
+                .and_then(|contents| serde_json::from_slice(&contents).ok());
+            if on_disk.as_ref() == Some(&value) {
+                self.nodes.remove(&target);
+                return Ok(());
+            }
+        }
+        self.push(Operation::write(
+            path,
+            serde_json::to_string_pretty(&value)?,
+        ))
+    }
+
+    /// Whether the disk entry at `path` is still there once the plan is applied,
+    /// ignoring what the plan puts inside it.
+    fn is_on_disk(&self, path: &Path) -> bool {
+        !path
+            .ancestors()
+            .any(|ancestor| self.nodes.contains_key(ancestor))
+    }
+
+    /// `path` with a planned symlink among its parents replaced by its target.
+    fn redirect(&self, path: &Path) -> Option<PathBuf> {
+        for ancestor in path.ancestors().skip(1) {
+            if let Some(Node::Symlink(target)) = self.nodes.get(ancestor) {
+                let rest = path.strip_prefix(ancestor).ok()?;
+                return Some(normalize(&link_target(ancestor, target)).join(rest));
+            }
+        }
+        None
+    }
+
+    /// `path` with the symlinks at the end followed, planned or on disk.
+    pub fn resolve(&self, path: &Path) -> Result<PathBuf> {
+        let mut path = path.to_path_buf();
+        for _ in 0..MAX_SYMLINKS {
+            if let Some(redirected) = self.redirect(&path) {
+                path = redirected;
+                continue;
+            }
+            let target = match self.nodes.get(&path) {
+                Some(Node::Symlink(target)) => target.clone(),
+                Some(_) => return Ok(path),
+                None if self.is_on_disk(&path) && path.is_symlink() => fs::read_link(&path)?,
+                None => return Ok(path),
+            };
+            path = normalize(&link_target(&path, &target));
+        }
+        anyhow::bail!("Too many levels of symlinks at {}", path.display())
+    }
+
+    /// Names of the planned paths directly inside `path`.
+    fn planned_children(&self, path: &Path) -> BTreeSet<OsString> {
+        self.nodes
+            .range(path.to_path_buf()..)
+            .take_while(|(planned, _)| planned.starts_with(path))
+            .filter_map(|(planned, _)| {
+                match planned.strip_prefix(path).ok()?.components().next()? {
+                    Component::Normal(name) => Some(name.to_os_string()),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Names inside the folder at `path` once the plan is applied, or `None` if it
+    /// isn't a folder. Symlinks are followed.
+    pub fn children(&self, path: &Path) -> Result<Option<BTreeSet<OsString>>> {
+        let path = self.resolve(path)?;
+        if matches!(self.nodes.get(&path), Some(node) if *node != Node::Removed) {
+            return Ok(None);
+        }
+        let on_disk = self.is_on_disk(&path) && path.is_dir();
+        let mut candidates = self.planned_children(&path);
+        if on_disk {
+            for entry in fs::read_dir(&path)? {
+                candidates.insert(entry?.file_name());
+            }
+        } else if candidates.is_empty() {
+            return Ok(None);
+        }
+        let mut names = BTreeSet::new();
+        for name in candidates {
+            if self.exists(&path.join(&name))? {
+                names.insert(name);
+            }
+        }
+        // A removed folder only comes back if something is planned inside it
+        if names.is_empty() && !on_disk {
+            return Ok(None);
+        }
+        Ok(Some(names))
+    }
+
+    /// Whether anything is at `path` once the plan is applied, without following a
+    /// symlink there.
+    pub fn exists(&self, path: &Path) -> Result<bool> {
+        if let Some(redirected) = self.redirect(path) {
+            return self.exists(&redirected);
+        }
+        match self.nodes.get(path) {
+            Some(Node::Removed) => {}
+            Some(_) => return Ok(true),
+            None if self.is_on_disk(path) && path.symlink_metadata().is_ok() => return Ok(true),
+            None => {}
+        }
+        for name in self.planned_children(path) {
+            if self.exists(&path.join(name))? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// The contents of the file at `path` once the plan is applied, following
+    /// symlinks. `None` if there is no file there.
+    pub fn read(&self, path: &Path) -> Result<Option<Vec<u8>>> {
+        let path = self.resolve(path)?;
+        match self.nodes.get(&path) {
+            Some(Node::File(contents)) => return Ok(Some(contents.clone())),
+            Some(Node::HardLink(source)) => return self.read(source),
+            _ => {}
+        }
+        if !self.is_on_disk(&path) || !path.is_file() {
+            return Ok(None);
+        }
+        let contents =
+            fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Some(contents))
+    }
+
+    /// What is at `path` once the plan is applied, without following a symlink there.
+    pub fn snapshot(&self, path: &Path) -> Result<Option<Snapshot>> {
+        if let Some(redirected) = self.redirect(path) {
+            return self.snapshot(&redirected);
+        }
+        match self.nodes.get(path) {
+            Some(Node::File(contents)) => return Ok(Some(Snapshot::File(contents.clone()))),
+            Some(Node::Symlink(target)) => return Ok(Some(Snapshot::Symlink(target.clone()))),
+            Some(Node::HardLink(source)) => return Ok(self.read(source)?.map(Snapshot::File)),
+            Some(Node::Removed) | None => {}
+        }
+        let planned = self.planned_children(path);
+        let metadata = match self.is_on_disk(path) {
+            true => path.symlink_metadata().ok(),
+            false => None,
+        };
+        if planned.is_empty() {
+            return match metadata {
+                Some(_) => Snapshot::read(path),
+                None => Ok(None),
+            };
+        }
+        let mut names = planned;
+        if metadata.as_ref().is_some_and(|metadata| metadata.is_dir()) {
+            for entry in fs::read_dir(path)? {
+                names.insert(entry?.file_name());
+            }
+        }
+        let mut entries = BTreeMap::new();
+        for name in names {
+            if let Some(snapshot) = self.snapshot(&path.join(&name))? {
+                entries.insert(PathBuf::from(name), snapshot);
+            }
+        }
+        if entries.is_empty() && metadata.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Snapshot::Directory(entries)))
+    }
+
+    /// Every path whose entry on disk differs from the plan, with both versions.
+    pub fn changes(&self) -> Result<Vec<PlannedChange>> {
+        self.steps()?
+            .into_iter()
+            .map(|step| {
+                let after = match &step.after {
+                    Some(Node::File(contents)) => Some(Snapshot::File(contents.clone())),
+                    Some(Node::Symlink(target)) => Some(Snapshot::Symlink(target.clone())),
+                    Some(Node::HardLink(source)) => self.read(source)?.map(Snapshot::File),
+                    Some(Node::Removed) | None => None,
+                };
+                Ok(PlannedChange {
+                    before: Snapshot::read(&step.path)?,
+                    path: step.path,
+                    after,
+                })
+            })
+            .collect()
+    }
+
+    /// The entries to replace on disk, parents before what is inside them.
+    pub(crate) fn steps(&self) -> Result<Vec<Step>> {
+        Ok(self.steps_keeping(None)?.0)
+    }
+
+    /// Like [`steps`](Self::steps), but folders holding `keep` are emptied instead of
+    /// removed in one go. They are returned to be removed once `keep` is gone.
+    pub(crate) fn steps_keeping(&self, keep: Option<&Path>) -> Result<(Vec<Step>, Vec<PathBuf>)> {
+        let mut roots: Vec<&PathBuf> = Vec::new();
+        for path in self.nodes.keys() {
+            if !roots.last().is_some_and(|root| path.starts_with(root)) {
+                roots.push(path);
+            }
+        }
+        let mut comparison = Comparison {
+            keep,
+            steps: Vec::new(),
+            emptied: Vec::new(),
+        };
+        for root in roots {
+            self.compare(root, false, &mut comparison)?;
+        }
+        Ok((comparison.steps, comparison.emptied))
+    }
+
+    fn compare(&self, path: &Path, removed_above: bool, comparison: &mut Comparison) -> Result<()> {
+        let node = self.nodes.get(path);
+        if let Some(node) = node.filter(|node| **node != Node::Removed) {
+            if !self.is_current(path, node)? {
+                comparison.steps.push(Step {
+                    path: path.to_path_buf(),
+                    after: Some(node.clone()),
+                });
+            }
+            return Ok(());
+        }
+
+        let removed = removed_above || node.is_some();
+        let metadata = path.symlink_metadata().ok();
+        let is_dir = metadata
+            .as_ref()
+            .is_some_and(|metadata| metadata.is_dir() && !metadata.is_symlink());
+        let mut names = self.planned_children(path);
+        let holds_kept = comparison
+            .keep
+            .is_some_and(|keep| keep != path && keep.starts_with(path));
+        if removed && is_dir && holds_kept {
+            comparison.emptied.push(path.to_path_buf());
+        } else if names.is_empty() {
+            if removed && metadata.is_some() {
+                comparison.steps.push(Step {
+                    path: path.to_path_buf(),
+                    after: None,
+                });
+            }
+            return Ok(());
+        }
+        match metadata {
+            // Untouched entries of a folder that stays are left alone
+            Some(_) if is_dir => {
+                if removed {
+                    for entry in fs::read_dir(path)? {
+                        names.insert(entry?.file_name());
+                    }
+                }
+            }
+            // A file or symlink in the way of planned paths goes first
+            Some(_) => comparison.steps.push(Step {
+                path: path.to_path_buf(),
+                after: None,
+            }),
+            None => {}
+        }
+        for name in names {
+            let child = path.join(name);
+            if comparison.keep != Some(child.as_path()) {
+                self.compare(&child, removed, comparison)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the disk already has `node` at `path`.
+    fn is_current(&self, path: &Path, node: &Node) -> Result<bool> {
+        let Ok(metadata) = path.symlink_metadata() else {
+            return Ok(false);
+        };
+        match node {
+            Node::File(contents) => Ok(metadata.is_file() && fs::read(path)? == *contents),
+            Node::Symlink(target) => Ok(metadata.is_symlink() && fs::read_link(path)? == *target),
+            Node::HardLink(source) => {
+                let source_is_current = match self.nodes.get(source) {
+                    Some(node) => self.is_current(source, node)?,
+                    None => true,
+                };
+                let same_inode = source.symlink_metadata().is_ok_and(|source| {
+                    source.ino() == metadata.ino() && source.dev() == metadata.dev()
+                });
+                Ok(metadata.is_file() && source_is_current && same_inode)
+            }
+            Node::Removed => Ok(false),
+        }
+    }
+}
+
+/// Where a symlink at `link` pointing at `target` leads.
+fn link_target(link: &Path, target: &Path) -> PathBuf {
+    match link.parent() {
+        Some(parent) => parent.join(target),
+        None => target.to_path_buf(),
+    }
+}
+
+/// `path` with `.` and `..` components removed, without touching the disk.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Removes the value at a JSON pointer such as `/mcpServers/db`, if it is there.
+pub fn remove_json_pointer(value: &mut Value, pointer: &str) {
+    let Some((parent, key)) = pointer.rsplit_once('/') else {
+        return;
+    };
+    let key = key.replace("~1", "/").replace("~0", "~");
+    if let Some(Value::Object(map)) = value.pointer_mut(parent) {
+        map.remove(&key);
+    }
+}
+
+/// Sets the value at a JSON pointer, creating the objects on the way.
+fn set_json_pointer(value: &mut Value, pointer: &str, new_value: Value) {
+    let mut current = value;
+    let keys: Vec<String> = pointer
+        .split('/')
+        .skip(1)
+        .map(|key| key.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    let Some((last, parents)) = keys.split_last() else {
+        *current = new_value;
+        return;
+    };
+    for key in parents {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        current = current
+            .as_object_mut()
+            .expect("just made an object")
+            .entry(key.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+    if !current.is_object() {
+        *current = Value::Object(Map::new());
+    }
+    if let Value::Object(map) = current {
+        map.insert(last.clone(), new_value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::helpers::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_plan_lists_changes_without_writing() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        create_file(root, "CLAUDE.md", "Old");
+        create_file(root, "GEMINI.md", "Same");
+        create_file(root, ".cursor/rules/old.mdc", "Old rule");
+
+        let mut plan = Plan::default();
+        plan.extend([
+            Operation::delete(root.join("CLAUDE.md")),
+            Operation::symlink(root.join("CLAUDE.md"), "AGENTS.md"),
+            Operation::write(root.join("GEMINI.md"), "Same"),
+            Operation::delete(root.join(".cursor")),
+            Operation::write(root.join(".claude/commands/commit.md"), "Commit"),
+        ])
+        .unwrap();
+
+        let summary: Vec<_> = plan
+            .changes()
+            .unwrap()
+            .into_iter()
+            .map(|change| {
+                let path = change.path.strip_prefix(root).unwrap().to_path_buf();
+                (path, change.before, change.after)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    PathBuf::from(".claude/commands/commit.md"),
+                    None,
+                    Some(Snapshot::File(b"Commit".to_vec()))
+                ),
+                (
+                    PathBuf::from(".cursor"),
+                    Some(Snapshot::Directory(BTreeMap::from([(
+                        PathBuf::from("rules"),
+                        Snapshot::Directory(BTreeMap::from([(
+                            PathBuf::from("old.mdc"),
+                            Snapshot::File(b"Old rule".to_vec())
+                        )]))
+                    )]))),
+                    None
+                ),
+                (
+                    PathBuf::from("CLAUDE.md"),
+                    Some(Snapshot::File(b"Old".to_vec())),
+                    Some(Snapshot::Symlink(PathBuf::from("AGENTS.md")))
+                ),
+            ]
+        );
+        assert_file_content(root, "CLAUDE.md", "Old");
+        assert_file_content(root, ".cursor/rules/old.mdc", "Old rule");
+        assert_file_not_exists(root, ".claude");
+    }
+
+    #[test]
+    fn test_plan_reads_through_planned_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        create_file(root, "ai-rules/skills/deploy/SKILL.md", "Deploy");
+        create_file(root, ".gemini/settings.json", r#"{"theme": "dark"}"#);
+
+        let mut plan = Plan::default();
+        plan.extend([
+            Operation::symlink(
+                root.join(".claude/skills/deploy"),
+                "../../ai-rules/skills/deploy",
+            ),
+            Operation::MergeJson {
+                path: root.join(".gemini/settings.json"),
+                remove: vec![],
+                set: vec![("/mcpServers/db".into(), json!({"command": "db"}))],
+            },
+        ])
+        .unwrap();
+
+        assert_eq!(
+            plan.read(&root.join(".claude/skills/deploy/SKILL.md"))
+                .unwrap(),
+            Some(b"Deploy".to_vec())
+        );
+        let settings: Value = serde_json::from_slice(
+            &plan
+                .read(&root.join(".gemini/settings.json"))
+                .unwrap()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            settings,
+            json!({"theme": "dark", "mcpServers": {"db": {"command": "db"}}})
+        );
+
+        // Removing what was just set gives the file back unchanged
+        plan.push(Operation::MergeJson {
+            path: root.join(".gemini/settings.json"),
+            remove: vec!["/mcpServers".into()],
+            set: vec![],
+        })
+        .unwrap();
+        let change = plan
+            .changes()
+            .unwrap()
+            .into_iter()
+            .find(|change| change.path.ends_with("settings.json"));
+        assert!(change.is_none());
+    }
+}
@@ -4,3 +4,6 @@ pub fn print_success(text: &str) {
 pub fn print_info(text: &str) {
     println!("ℹ️  {text}");
 }
+pub fn print_warning(text: &str) {
+    eprintln!("⚠️  {text}");
+}
@@ -0,0 +1,186 @@
+//! Helpers for checking source files against the types they deserialize into:
+//! finding unknown keys, suggesting the key that was meant, and locating keys in text.
+
+use serde::de::{self, DeserializeOwned, Deserializer, Visitor};
+
+/// A key as the path of map keys and list indices leading to it, like `["profiles", "web", "agents"]`.
+pub type KeyPath = Vec<String>;
+
+/// Deserializes `T`, also returning the keys `T` doesn't have a field for.
+pub fn deserialize_reporting_unknown<'de, D, T>(
+    deserializer: D,
+) -> Result<(T, Vec<KeyPath>), D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let mut unknown = Vec::new();
+    let value = serde_ignored::deserialize(deserializer, |path| unknown.push(key_path(&path)))?;
+    Ok((value, unknown))
+}
+
+fn key_path(path: &serde_ignored::Path) -> KeyPath {
+    let mut keys = match path {
+        serde_ignored::Path::Root => return Vec::new(),
+        serde_ignored::Path::Seq { parent, .. }
+        | serde_ignored::Path::Map { parent, .. }
+        | serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => key_path(parent),
+    };
+    match path {
+        serde_ignored::Path::Seq { index, .. } => keys.push(index.to_string()),
+        serde_ignored::Path::Map { key, .. } => keys.push(key.clone()),
+        _ => {}
+    }
+    keys
+}
+
+/// The field names of the struct `T`, as its derived `Deserialize` asks for them.
+pub fn struct_fields<T: DeserializeOwned>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+/// A deserializer that only records the fields a struct asks for.
+struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+impl<'de> Deserializer<'de> for FieldNames<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = fields;
+        Err(de::Error::custom("only the fields are read"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+/// The candidate closest to `word`, if it is close enough to be a typo of it.
+pub fn did_you_mean<'a>(
+    word: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let word = word.to_lowercase();
+    let max_distance = (word.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(&word, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The 1-based line and column of the key at `path` in YAML, TOML or JSON `text`.
+///
+/// Each key is looked for after the line of its parent, so this is a best effort for
+/// the block styles people write by hand. List indices are skipped.
+pub fn locate_key(text: &str, path: &[String]) -> Option<(usize, usize)> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut start = 0;
+    let mut found = None;
+    for key in path {
+        if key.parse::<usize>().is_ok() {
+            continue;
+        }
+        found = (start..lines.len()).find_map(|index| {
+            let line = lines[index];
+            let indent = line.len() - line.trim_start().len();
+            let rest = line.trim_start().trim_start_matches("- ");
+            let rest = rest
+                .strip_prefix(&format!("\"{key}\""))
+                .or_else(|| rest.strip_prefix(&format!("'{key}'")))
+                .or_else(|| rest.strip_prefix(key.as_str()))?;
+            let separator = rest.trim_start().chars().next();
+            matches!(separator, Some(':') | Some('=')).then_some((index, indent + 1))
+        });
+        if let Some((index, _)) = found {
+            start = index + 1;
+        }
+    }
+    found.map(|(index, column)| (index + 1, column))
+}
+
+/// The 1-based line and column of the first whole-word `word` at or after `line`.
+pub fn locate_word(text: &str, word: &str, line: usize) -> Option<(usize, usize)> {
+    text.lines()
+        .enumerate()
+        .skip(line.saturating_sub(1))
+        .find_map(|(index, text)| {
+            text.match_indices(word)
+                .find(|(offset, _)| {
+                    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+                    let before = text[..*offset].chars().next_back();
+                    let after = text[offset + word.len()..].chars().next();
+                    !before.is_some_and(is_word) && !after.is_some_and(is_word)
+                })
+                .map(|(offset, _)| (index + 1, offset + 1))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Settings {
+        agents: Option<Vec<String>>,
+        #[serde(rename = "nestedDepth")]
+        nested_depth: Option<usize>,
+    }
+
+    #[test]
+    fn test_unknown_keys_are_found_located_and_suggested() {
+        let yaml = "agents:\n  - claude\nnestedDepht: 2\nextra:\n  deep: true\n";
+        let (_, unknown): (Settings, _) =
+            deserialize_reporting_unknown(serde_yaml::Deserializer::from_str(yaml)).unwrap();
+        assert_eq!(unknown, vec![vec!["nestedDepht"], vec!["extra"]]);
+
+        assert_eq!(struct_fields::<Settings>(), ["agents", "nestedDepth"]);
+        assert_eq!(
+            did_you_mean("nestedDepht", struct_fields::<Settings>().iter().copied()),
+            Some("nestedDepth")
+        );
+        assert_eq!(did_you_mean("extra", ["agents", "nestedDepth"]), None);
+
+        assert_eq!(locate_key(yaml, &unknown[0]), Some((3, 1)));
+        let json = "{\n  \"servers\": {\n    \"db\": {\n      \"comand\": \"db\"\n    }\n  }\n}\n";
+        let path = ["servers", "db", "comand"].map(String::from);
+        assert_eq!(locate_key(json, &path), Some((4, 7)));
+        assert_eq!(
+            locate_word("agents: [claude, cursr]", "cursr", 1),
+            Some((1, 18))
+        );
+    }
+}
@@ -11,6 +11,26 @@ pub mod helpers {
         fs::write(full_path, content).unwrap();
     }
 
+    /// Applies `operations` to the project at `root`, as generate would.
+    pub fn apply_operations(
+        root: &Path,
+        operations: impl IntoIterator<Item = crate::utils::plan::Operation>,
+    ) {
+        let mut plan = crate::utils::plan::Plan::default();
+        plan.extend(operations).unwrap();
+        crate::utils::transaction::apply(root, &plan, None).unwrap();
+    }
+
+    /// What applying `operations` would change on disk. Status plans an agent's cleanup
+    /// followed by its outputs, so an empty result means the agent is in sync.
+    pub fn planned_changes(
+        operations: impl IntoIterator<Item = crate::utils::plan::Operation>,
+    ) -> Vec<crate::utils::plan::PlannedChange> {
+        let mut plan = crate::utils::plan::Plan::default();
+        plan.extend(operations).unwrap();
+        plan.changes().unwrap()
+    }
+
     pub fn assert_file_exists(base: &Path, path: &str) {
         assert!(base.join(path).exists(), "Expected {path} to exist");
     }
@@ -55,6 +75,9 @@ pub mod helpers {
                 description: description.to_string(),
                 always_apply,
                 file_matching_patterns: Some(file_patterns),
+                order: None,
+                tags: None,
+                inherit: None,
             },
             body: body.to_string(),
         }
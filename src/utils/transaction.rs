@@ -0,0 +1,340 @@
+//! All-or-nothing writes.
+//!
+//! [`apply`] first writes every new file and symlink of a [`Plan`] into a staging
+//! folder, so a failure there leaves the project untouched. It then commits them by
+//! renaming: the entry each one replaces is moved aside into the staging folder, and
+//! the new one renamed into place. If a rename fails, the entries already committed
+//! are taken out and the ones moved aside are renamed back. The staging folder is
+//! deleted at the end.
+
+use crate::constants::{AI_RULE_SOURCE_DIR, GENERATED_RULE_BODY_DIR};
+use crate::utils::backup::{copy_preserving_links, Backup};
+use crate::utils::plan::{Node, Plan, Step};
+use crate::utils::print_utils::print_info;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs as unix_fs;
+use std::path::{Path, PathBuf};
+
+pub const STAGING_DIR: &str = "staging";
+/// Inside the staging folder, the entries to commit
+const NEW_DIR: &str = "new";
+/// Inside the staging folder, the entries they replace
+const OLD_DIR: &str = "old";
+
+enum Undo {
+    Created(PathBuf),
+    MovedAside { original: PathBuf, aside: PathBuf },
+}
+
+/// `ai-rules/.generated-ai-rules/staging` under the project root.
+pub fn staging_dir(root: &Path) -> PathBuf {
+    root.join(AI_RULE_SOURCE_DIR)
+        .join(GENERATED_RULE_BODY_DIR)
+        .join(STAGING_DIR)
+}
+
+/// Makes the disk match `plan`, or changes nothing if that fails. Every entry that
+/// is replaced is saved to `backup` first, unless the plan can recreate it.
+pub fn apply(root: &Path, plan: &Plan, mut backup: Option<&mut Backup>) -> Result<()> {
+    let staging = staging_dir(root);
+    let (steps, emptied) = plan.steps_keeping(Some(&staging))?;
+    if steps.is_empty() && emptied.is_empty() {
+        return Ok(());
+    }
+    // Left over from a run that was killed before it could clean up
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    let missing_parents: Vec<PathBuf> = staging
+        .ancestors()
+        .skip(1)
+        .take(2)
+        .filter(|dir| !dir.exists())
+        .map(Path::to_path_buf)
+        .collect();
+
+    let staged = match stage(&staging, &steps) {
+        Ok(staged) => staged,
+        Err(error) => {
+            remove_staging(&staging, &missing_parents)?;
+            return Err(error);
+        }
+    };
+    let mut journal = Vec::new();
+    let committed = commit(&staging, &steps, staged, plan, &mut backup, &mut journal);
+    if let Err(error) = committed {
+        return match roll_back(journal) {
+            Ok(()) => {
+                remove_staging(&staging, &missing_parents)?;
+                print_info("Rolled back the files changed before the error");
+                Err(error)
+            }
+            Err(rollback_error) => Err(error.context(format!(
+                "Rolling back failed ({rollback_error:#}). The replaced files are in {}",
+                staging.join(OLD_DIR).display()
+            ))),
+        };
+    }
+    remove_staging(&staging, &missing_parents)?;
+    // Folders the plan removes while they held the staging folder
+    for dir in emptied.iter().rev() {
+        if fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_none()) {
+            fs::remove_dir(dir)?;
+        }
+    }
+    Ok(())
+}
+
+/// Applies `plan`, backing up what it replaces for `ai-rules restore`. Prints the
+/// backup id even if applying fails, as the backup lists what it saved so far.
+pub fn apply_with_backup(root: &Path, plan: &Plan, command: &str) -> Result<()> {
+    apply_with_backup_in(root, root, plan, command)
+}
+
+/// Like [`apply_with_backup`] for a plan changing files under `root`, outside the
+/// project at `store`, which holds the staging folder and the backup.
+pub fn apply_with_backup_in(store: &Path, root: &Path, plan: &Plan, command: &str) -> Result<()> {
+    let mut backup = Backup::start(store, root, command);
+    let applied = apply(store, plan, backup.as_mut());
+    if let Some(backup) = backup {
+        backup.finish();
+    }
+    applied
+}
+
+/// Writes the new entries into the staging folder, in the order of `steps`. Hard
+/// links come last, as they may link to a file staged here.
+fn stage(staging: &Path, steps: &[Step]) -> Result<Vec<Option<PathBuf>>> {
+    let new_dir = staging.join(NEW_DIR);
+    fs::create_dir_all(&new_dir)?;
+    fs::create_dir_all(staging.join(OLD_DIR))?;
+    let mut staged = vec![None; steps.len()];
+    let mut staged_files: HashMap<&Path, PathBuf> = HashMap::new();
+    for (index, step) in steps.iter().enumerate() {
+        let staged_path = new_dir.join(index.to_string());
+        match &step.after {
+            Some(Node::File(contents)) => {
+                fs::write(&staged_path, contents)
+                    .with_context(|| format!("Failed to write {}", step.path.display()))?;
+                staged_files.insert(&step.path, staged_path.clone());
+            }
+            Some(Node::Symlink(target)) => unix_fs::symlink(target, &staged_path)
+                .with_context(|| format!("Failed to create {}", step.path.display()))?,
+            _ => continue,
+        }
+        staged[index] = Some(staged_path);
+    }
+    for (index, step) in steps.iter().enumerate() {
+        let Some(Node::HardLink(source)) = &step.after else {
+            continue;
+        };
+        let staged_path = new_dir.join(index.to_string());
+        let linked = staged_files.get(source.as_path()).unwrap_or(source);
+        fs::hard_link(linked, &staged_path).with_context(|| {
+            format!(
+                "Failed to hard link {} to {}. Hard links need both on the same filesystem",
+                step.path.display(),
+                source.display()
+            )
+        })?;
+        staged[index] = Some(staged_path);
+    }
+    Ok(staged)
+}
+
+/// Moves each replaced entry aside and renames the staged one into place.
+fn commit(
+    staging: &Path,
+    steps: &[Step],
+    staged: Vec<Option<PathBuf>>,
+    plan: &Plan,
+    backup: &mut Option<&mut Backup>,
+    journal: &mut Vec<Undo>,
+) -> Result<()> {
+    for (index, (step, staged)) in steps.iter().zip(staged).enumerate() {
+        let path = &step.path;
+        if path.symlink_metadata().is_ok() {
+            if let Some(backup) = backup.as_deref_mut() {
+                if !plan.is_regenerable(path) {
+                    backup.save(path)?;
+                }
+            }
+            let aside = staging.join(OLD_DIR).join(index.to_string());
+            move_entry(path, &aside)?;
+            journal.push(Undo::MovedAside {
+                original: path.clone(),
+                aside,
+            });
+        }
+        let Some(staged) = staged else {
+            continue;
+        };
+        if let Some(parent) = path.parent() {
+            if let Some(first_missing) = parent
+                .ancestors()
+                .take_while(|ancestor| ancestor.symlink_metadata().is_err())
+                .last()
+            {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+                journal.push(Undo::Created(first_missing.to_path_buf()));
+            }
+        }
+        move_entry(&staged, path)?;
+        journal.push(Undo::Created(path.clone()));
+    }
+    Ok(())
+}
+
+/// Renames `from` to `to`, copying where a rename can't, e.g. across filesystems.
+fn move_entry(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_err() {
+        copy_preserving_links(from, to)
+            .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))?;
+        remove_now(from)?;
+    }
+    Ok(())
+}
+
+fn roll_back(journal: Vec<Undo>) -> Result<()> {
+    for undo in journal.into_iter().rev() {
+        match undo {
+            Undo::Created(path) => remove_now(&path)?,
+            Undo::MovedAside { original, aside } => {
+                remove_now(&original)?;
+                move_entry(&aside, &original)
+                    .with_context(|| format!("Failed to restore {}", original.display()))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the staging folder, and the `parents` created for it if nothing else is there.
+fn remove_staging(staging: &Path, parents: &[PathBuf]) -> Result<()> {
+    if staging.exists() {
+        fs::remove_dir_all(staging)?;
+    }
+    for dir in parents {
+        if fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_none()) {
+            fs::remove_dir(dir)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove_now(path: &Path) -> Result<()> {
+    match path.symlink_metadata() {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path)?,
+        Ok(_) => fs::remove_file(path)?,
+        Err(_) => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::plan::Operation;
+    use crate::utils::test_utils::helpers::*;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_apply_commits_the_plan() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        create_file(root, "ai-rules/AGENTS.md", "Rules");
+        create_file(root, "CLAUDE.md", "Old");
+        create_file(root, ".cursor/rules/old.mdc", "Old rule");
+
+        let mut plan = Plan::default();
+        plan.extend([
+            Operation::delete(root.join("CLAUDE.md")),
+            Operation::symlink(root.join("CLAUDE.md"), "ai-rules/AGENTS.md"),
+            Operation::delete(root.join(".cursor")),
+            Operation::write(root.join(".claude/commands/commit.md"), "Commit"),
+        ])
+        .unwrap();
+        apply(root, &plan, None).unwrap();
+
+        assert_eq!(
+            fs::read_link(root.join("CLAUDE.md")).unwrap(),
+            PathBuf::from("ai-rules/AGENTS.md")
+        );
+        assert_file_not_exists(root, ".cursor");
+        assert_file_content(root, ".claude/commands/commit.md", "Commit");
+        assert_file_not_exists(root, "ai-rules/.generated-ai-rules");
+    }
+
+    #[test]
+    fn test_atomically_rolls_back_on_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        create_file(root, "ai-rules/AGENTS.md", "Rules");
+        create_file(root, "CLAUDE.md", "Old");
+        create_file(root, ".cursor/rules/old.mdc", "Old rule");
+        // A file where the plan needs a folder, so renaming into it fails
+        create_file(root, "docs", "Not a folder");
+
+        let mut plan = Plan::default();
+        plan.extend([
+            Operation::write(root.join("CLAUDE.md"), "New"),
+            Operation::delete(root.join(".cursor")),
+            Operation::write(root.join(".claude/commands/commit.md"), "Commit"),
+            Operation::write(root.join("docs/AGENTS.md"), "Rules"),
+        ])
+        .unwrap();
+        assert!(apply(root, &plan, None).is_err());
+
+        assert_file_content(root, "CLAUDE.md", "Old");
+        assert_file_content(root, ".cursor/rules/old.mdc", "Old rule");
+        assert_file_not_exists(root, ".claude");
+        assert_file_content(root, "docs", "Not a folder");
+        assert_file_not_exists(root, "ai-rules/.generated-ai-rules");
+
+        let mut plan = Plan::default();
+        plan.push(Operation::write(root.join("CLAUDE.md"), "New"))
+            .unwrap();
+        apply(root, &plan, None).unwrap();
+        assert_file_content(root, "CLAUDE.md", "New");
+        assert_file_not_exists(root, "ai-rules/.generated-ai-rules");
+    }
+
+    #[test]
+    fn test_held_back_changes_skip_rewritten_outputs() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        create_file(root, "ai-rules/AGENTS.md", "Rules");
+        create_file(root, "rules/kept.md", "Same");
+        create_file(root, "rules/stale.md", "Old");
+        create_file(root, "notes.md", "Notes\n<!-- block -->");
+        let old_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let kept = fs::File::options()
+            .write(true)
+            .open(root.join("rules/kept.md"))
+            .unwrap();
+        kept.set_modified(old_time).unwrap();
+
+        let mut plan = Plan::default();
+        plan.extend([
+            Operation::delete(root.join("rules")),
+            Operation::write(root.join("notes.md"), "Notes"),
+            Operation::write(root.join("rules/kept.md"), "Same"),
+        ])
+        .unwrap();
+        apply(root, &plan, None).unwrap();
+
+        assert_eq!(
+            fs::metadata(root.join("rules/kept.md"))
+                .unwrap()
+                .modified()
+                .unwrap(),
+            old_time
+        );
+        assert_file_not_exists(root, "rules/stale.md");
+        assert_file_content(root, "notes.md", "Notes");
+    }
+}